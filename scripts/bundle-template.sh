@@ -66,6 +66,9 @@ images:
     newTag: "${VERSION}"
 KUSTEOF
 mkdir -p "$tmpdir/install-template/k8s/overlays/remote"
+# Namespace is created by the installer (name chosen at install time), so it
+# is bundled separately rather than rendered into the overlay.
+cp "$REPO_ROOT/k8s/namespace.yaml" "$tmpdir/install-template/k8s/namespace.yaml"
 kubectl kustomize "$wrapper" \
   > "$tmpdir/install-template/k8s/overlays/remote/all.yaml"
 rm -rf "$wrapper"
@@ -101,6 +101,33 @@ for service in $SERVICES; do
     }]')
 done
 
+# Third-party images for the installer's optional telemetry stack
+# (--telemetry), pinned by digest so the installer can verify them
+TELEMETRY_IMAGES="prometheus=prom/prometheus:v2.53.0 grafana=grafana/grafana:11.1.0"
+for entry in $TELEMETRY_IMAGES; do
+  name="${entry%%=*}"
+  ref="${entry#*=}"
+  digest=$(docker buildx imagetools inspect "$ref" --format '{{json .Manifest}}' | jq -r .digest)
+  if [[ -z "$digest" || "$digest" == "null" ]]; then
+    echo "error: could not resolve the digest of $ref" >&2
+    exit 1
+  fi
+
+  IMAGES_JSON=$(echo "$IMAGES_JSON" | jq \
+    --arg name "$name" \
+    --arg image "docker.io/${ref%:*}" \
+    --arg tag "${ref##*:}" \
+    --arg digest "$digest" \
+    '. + [{
+      name: $name,
+      image: $image,
+      tag: $tag,
+      required: false,
+      architectures: ["linux/amd64", "linux/arm64"],
+      digest: $digest
+    }]')
+done
+
 # Build installers array
 INSTALLERS_JSON="[]"
 
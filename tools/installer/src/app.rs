@@ -1,8 +1,14 @@
 //! Application state for the TUI installer.
 
+use crate::health::{HealthEvent, PodHealth};
 use crate::manifest::Manifest;
 use crate::interview::InterviewResult;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How long a flash message stays in the status bar.
+pub const FLASH_DURATION: Duration = Duration::from_secs(3);
 
 #[derive(Debug, Default)]
 pub enum Phase {
@@ -29,9 +35,48 @@ pub struct App {
     pub errors: Vec<String>,
     pub dry_run: bool,
     pub auth_token: Option<String>,
+    /// `--auth-token-out` file the token was written to.
+    pub auth_token_path: Option<PathBuf>,
+    /// Where the installed UI is reached, shown on the Complete screen.
+    pub access_url: Option<String>,
     pub status_message: Option<String>,
+    /// Short-lived confirmation (e.g. "copied!") and when it was shown.
+    pub flash: Option<(String, Instant)>,
+    /// Pause at every phase boundary (--step, toggled with Space).
+    pub step_mode: bool,
+    /// Held at a phase boundary until the user presses Enter.
+    pub paused: bool,
+    /// Latest health of each pod, in first-seen order.
+    pub pods: Vec<PodHealth>,
+    /// Index into `pods` of the highlighted pod.
+    pub selected_pod: usize,
+    /// Pod awaiting a y/n before it's restarted.
+    pub confirm_restart: Option<String>,
+    /// Confirmed restarts for the driver to carry out (not counted against
+    /// the automatic recovery budget).
+    pub restart_requests: Vec<String>,
+    /// Latest streamed log lines per pod, at most [`MAX_LOG_LINES`] each.
+    pub pod_logs: HashMap<String, VecDeque<String>>,
+    /// `--no-wait`: the Verify phase is shown as skipped, not passed.
+    pub health_skipped: bool,
+    /// Where the time went, shown on the Complete screen.
+    pub timing: Option<crate::output::Timing>,
+    /// First line shown in the main pane once it outgrows the terminal.
+    pub scroll: usize,
+    /// Keep the highlighted pod in view; scrolling by hand turns it off
+    /// until another pod is selected.
+    pub follow_selection: bool,
+    /// The '?' overlay listing every key, drawn over the current phase.
+    pub show_help: bool,
+    /// Automatic pod restarts so far, per deployment.
+    pub recovery_attempts: HashMap<String, u32>,
+    /// Why automatic recovery gave up on a deployment, once it has.
+    pub recovery_gave_up: HashMap<String, String>,
 }
 
+/// Log lines kept per pod for the health view's log pane.
+pub const MAX_LOG_LINES: usize = 200;
+
 impl App {
     pub fn new(namespace: &str) -> Self {
         Self {
@@ -45,7 +90,387 @@ impl App {
             errors: Vec::new(),
             dry_run: false,
             auth_token: None,
+            auth_token_path: None,
+            access_url: None,
             status_message: None,
+            flash: None,
+            step_mode: false,
+            paused: false,
+            pods: Vec::new(),
+            selected_pod: 0,
+            confirm_restart: None,
+            restart_requests: Vec::new(),
+            pod_logs: HashMap::new(),
+            health_skipped: false,
+            timing: None,
+            scroll: 0,
+            follow_selection: true,
+            show_help: false,
+            recovery_attempts: HashMap::new(),
+            recovery_gave_up: HashMap::new(),
+        }
+    }
+
+    /// Called when a phase finishes. In step mode the next phase is held
+    /// until `resume` is called; background work keeps running meanwhile.
+    pub fn reach_boundary(&mut self) {
+        if self.step_mode {
+            self.paused = true;
+        }
+    }
+
+    /// Whether the installer may move on to the next phase.
+    pub fn can_advance(&self) -> bool {
+        !self.paused
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Space toggles step mode; turning it off also releases a held boundary.
+    pub fn toggle_step_mode(&mut self) {
+        self.step_mode = !self.step_mode;
+        if !self.step_mode {
+            self.paused = false;
         }
     }
+
+    /// Record a pod health update, replacing the previous one for that pod.
+    pub fn update_pod(&mut self, health: PodHealth) {
+        match self.pods.iter_mut().find(|p| p.name == health.name) {
+            Some(existing) => *existing = health,
+            None => self.pods.push(health),
+        }
+    }
+
+    /// Record what health polling reported. `AllHealthy` and `Failed` end
+    /// the phase, which is the driver's to do; their pods are still kept.
+    pub fn record_health_event(&mut self, event: HealthEvent) {
+        match event {
+            HealthEvent::PodUpdate(health) => self.update_pod(health),
+            HealthEvent::LogLine { pod, line } => self.push_log_line(&pod, line),
+            HealthEvent::RecoveryAttempt { deployment, attempt } => {
+                self.log_entries.push(format!("Restarted a crashing {} pod (attempt {})", deployment, attempt));
+                self.recovery_attempts.insert(deployment, attempt);
+            }
+            HealthEvent::RecoveryGaveUp { deployment, attempts, reason } => {
+                self.log_entries.push(format!("Gave up recovering {}: {}", deployment, reason));
+                self.recovery_attempts.insert(deployment.clone(), attempts);
+                self.recovery_gave_up.insert(deployment, reason);
+            }
+            HealthEvent::WorkloadReady { workload, ready } => {
+                let state = if ready { "ready" } else { "no longer ready" };
+                self.log_entries.push(format!("{} {}", workload, state));
+            }
+            HealthEvent::Failed { reason, unhealthy, .. } => {
+                for pod in unhealthy {
+                    self.update_pod(pod);
+                }
+                self.errors.push(reason);
+            }
+            HealthEvent::AllHealthy => {}
+        }
+    }
+
+    /// Automatic recovery of `deployment` so far, for its summary line.
+    pub fn recovery_note(&self, deployment: &str) -> Option<String> {
+        let attempts = self.recovery_attempts.get(deployment).copied().unwrap_or(0);
+        match (self.recovery_gave_up.contains_key(deployment), attempts) {
+            (true, 0) => Some("auto-recovery off".into()),
+            (true, n) => Some(format!("auto-recovery gave up after {} restart{}", n, if n == 1 { "" } else { "s" })),
+            (false, 0) => None,
+            (false, n) => Some(format!("{} auto-restart{}", n, if n == 1 { "" } else { "s" })),
+        }
+    }
+
+    /// Highlight the pod at `index`, e.g. a clicked row; out of range is
+    /// ignored.
+    pub fn select_pod(&mut self, index: usize) {
+        if index < self.pods.len() {
+            self.selected_pod = index;
+            self.follow_selection = true;
+        }
+    }
+
+    pub fn select_next_pod(&mut self) {
+        if !self.pods.is_empty() {
+            self.selected_pod = (self.selected_pod + 1) % self.pods.len();
+            self.follow_selection = true;
+        }
+    }
+
+    pub fn select_prev_pod(&mut self) {
+        if !self.pods.is_empty() {
+            self.selected_pod = (self.selected_pod + self.pods.len() - 1) % self.pods.len();
+            self.follow_selection = true;
+        }
+    }
+
+    /// Scroll the main pane so `top` is its first line (PageUp/PageDown,
+    /// or Up/Down when there are no pods to select).
+    pub fn scroll_to(&mut self, top: usize) {
+        self.scroll = top;
+        self.follow_selection = false;
+    }
+
+    /// '?' opens and closes the help overlay; Esc also closes it.
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    /// Keys the help overlay lists: those that do something in the current
+    /// phase, then the ones that work everywhere.
+    pub fn help_keys(&self) -> Vec<(&'static str, &'static str)> {
+        let mut keys = Vec::new();
+        if !self.pods.is_empty() {
+            keys.push(("\u{2191}/\u{2193}", "Select a pod"));
+            keys.push(("R", "Restart the selected pod"));
+            keys.push(("Click", "Select a pod, or answer Yes/No"));
+        } else {
+            keys.push(("\u{2191}/\u{2193}", "Scroll one line"));
+        }
+        keys.push(("PgUp/PgDn", "Scroll one page"));
+        keys.push(("Wheel", "Scroll a few lines"));
+        match self.phase {
+            Phase::Complete => {
+                if self.access_url.is_some() {
+                    keys.push(("u", "Copy the URL"));
+                }
+                if self.auth_token.is_some() {
+                    keys.push(("t / c", "Copy the auth token"));
+                }
+            }
+            Phase::Failed => {}
+            _ => {
+                keys.push(("Space", "Toggle step mode"));
+                if self.paused {
+                    keys.push(("Enter", "Continue to the next phase"));
+                }
+            }
+        }
+        keys.push(("?", "Show or hide this help"));
+        keys.push(("q", "Quit"));
+        keys.push(("Ctrl+C", "Abort the install"));
+        keys
+    }
+
+    /// 'R' on the selected pod: ask for confirmation first.
+    pub fn request_restart(&mut self) {
+        if let Some(pod) = self.pods.get(self.selected_pod) {
+            self.confirm_restart = Some(pod.name.clone());
+        }
+    }
+
+    /// Answer the pending restart confirmation.
+    pub fn answer_restart(&mut self, yes: bool) {
+        if let Some(pod) = self.confirm_restart.take() {
+            if yes {
+                self.restart_requests.push(pod);
+            }
+        }
+    }
+
+    /// Confirmed restarts since the last call.
+    pub fn take_restart_requests(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.restart_requests)
+    }
+
+    /// Show `message` in the status bar for [`FLASH_DURATION`].
+    pub fn flash(&mut self, message: impl Into<String>) {
+        self.flash = Some((message.into(), Instant::now()));
+    }
+
+    /// The flash message, while it is still current.
+    pub fn current_flash(&self) -> Option<&str> {
+        self.flash
+            .as_ref()
+            .filter(|(_, shown)| shown.elapsed() < FLASH_DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Record a streamed log line, dropping the oldest past the cap.
+    pub fn push_log_line(&mut self, pod: &str, line: String) {
+        let lines = self.pod_logs.entry(pod.to_string()).or_default();
+        if lines.len() == MAX_LOG_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Streamed log lines of the highlighted pod, if any arrived.
+    pub fn selected_pod_logs(&self) -> Option<(&str, &VecDeque<String>)> {
+        let pod = self.pods.get(self.selected_pod)?;
+        let lines = self.pod_logs.get(&pod.name).filter(|l| !l.is_empty())?;
+        Some((&pod.name, lines))
+    }
+}
+
+/// First line to show of `total` lines in a pane `height` lines tall:
+/// `scroll`, moved just enough to bring line `keep` into view if given, and
+/// never past the last full page.
+pub fn scroll_top(total: usize, height: usize, scroll: usize, keep: Option<usize>) -> usize {
+    let mut top = scroll;
+    if let Some(line) = keep {
+        if line < top {
+            top = line;
+        } else if height > 0 && line >= top + height {
+            top = line + 1 - height;
+        }
+    }
+    top.min(total.saturating_sub(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(name: &str) -> PodHealth {
+        PodHealth {
+            name: name.into(),
+            deployment: "brain".into(),
+            ready: false,
+            phase: "Running".into(),
+            image: String::new(),
+            restarts: 0,
+            error: None,
+            logs_tail: None,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn scrolling_clamps_to_the_content_and_follows_the_selection() {
+        // Fits: never scrolled
+        assert_eq!(scroll_top(10, 20, 5, None), 0);
+        // Paged past the end: the last full page
+        assert_eq!(scroll_top(50, 20, 45, None), 30);
+        // The highlighted line below the window pulls it down, just enough
+        assert_eq!(scroll_top(50, 20, 0, Some(25)), 6);
+        // ...and above it pulls it up
+        assert_eq!(scroll_top(50, 20, 30, Some(12)), 12);
+
+        let mut app = App::new("bakerst");
+        app.scroll_to(40);
+        assert!(!app.follow_selection);
+        app.update_pod(pod("brain-1"));
+        app.select_next_pod();
+        assert!(app.follow_selection);
+    }
+
+    #[test]
+    fn clicking_a_pod_row_selects_it() {
+        let mut app = App::new("bakerst");
+        app.update_pod(pod("brain-1"));
+        app.update_pod(pod("worker-1"));
+        app.scroll_to(10);
+        app.select_pod(1);
+        assert_eq!((app.selected_pod, app.follow_selection), (1, true));
+        // A click below the last pod leaves the selection alone
+        app.select_pod(2);
+        assert_eq!(app.selected_pod, 1);
+    }
+
+    #[test]
+    fn help_lists_the_keys_for_the_current_phase() {
+        let mut app = App::new("bakerst");
+        app.toggle_help();
+        assert!(app.show_help);
+        let keys: Vec<&str> = app.help_keys().iter().map(|(k, _)| *k).collect();
+        assert!(keys.contains(&"Space"));
+        assert!(!keys.contains(&"R") && !keys.contains(&"u"));
+        assert_eq!(keys[keys.len() - 3..], ["?", "q", "Ctrl+C"]);
+
+        app.update_pod(pod("brain-1"));
+        app.phase = Phase::Complete;
+        app.access_url = Some("http://localhost:30080".into());
+        let keys: Vec<&str> = app.help_keys().iter().map(|(k, _)| *k).collect();
+        assert!(keys.contains(&"R") && keys.contains(&"u"));
+        assert!(!keys.contains(&"Space") && !keys.contains(&"t / c"));
+        app.toggle_help();
+        assert!(!app.show_help);
+    }
+
+    #[test]
+    fn restart_needs_confirmation() {
+        let mut app = App::new("bakerst");
+        app.update_pod(pod("brain-1"));
+        app.update_pod(pod("worker-1"));
+        app.select_next_pod();
+
+        app.request_restart();
+        assert_eq!(app.confirm_restart.as_deref(), Some("worker-1"));
+        app.answer_restart(false);
+        assert!(app.take_restart_requests().is_empty());
+
+        app.request_restart();
+        app.answer_restart(true);
+        assert_eq!(app.take_restart_requests(), vec!["worker-1".to_string()]);
+        assert!(app.take_restart_requests().is_empty());
+    }
+
+    #[test]
+    fn pod_updates_replace_by_name() {
+        let mut app = App::new("bakerst");
+        app.update_pod(pod("brain-1"));
+        let mut ready = pod("brain-1");
+        ready.ready = true;
+        app.update_pod(ready);
+        assert_eq!(app.pods.len(), 1);
+        assert!(app.pods[0].ready);
+    }
+
+    #[test]
+    fn recovery_is_tracked_per_deployment() {
+        let mut app = App::new("bakerst");
+        assert_eq!(app.recovery_note("brain"), None);
+        app.record_health_event(HealthEvent::RecoveryAttempt { deployment: "brain".into(), attempt: 1 });
+        assert_eq!(app.recovery_note("brain").as_deref(), Some("1 auto-restart"));
+        app.record_health_event(HealthEvent::RecoveryGaveUp {
+            deployment: "brain".into(),
+            attempts: 3,
+            reason: crate::health::recovery_gave_up_reason(3),
+        });
+        assert_eq!(app.recovery_note("brain").as_deref(), Some("auto-recovery gave up after 3 restarts"));
+        assert!(app.log_entries.last().unwrap().starts_with("Gave up recovering brain: still crash-looping"));
+
+        app.record_health_event(HealthEvent::RecoveryGaveUp {
+            deployment: "worker".into(),
+            attempts: 0,
+            reason: crate::health::recovery_gave_up_reason(0),
+        });
+        assert_eq!(app.recovery_note("worker").as_deref(), Some("auto-recovery off"));
+    }
+
+    #[test]
+    fn flash_messages_expire() {
+        let mut app = App::new("bakerst");
+        assert!(app.current_flash().is_none());
+        app.flash("URL copied!");
+        assert_eq!(app.current_flash(), Some("URL copied!"));
+        app.flash = Some(("old".into(), Instant::now() - FLASH_DURATION));
+        assert!(app.current_flash().is_none());
+    }
+
+    #[test]
+    fn log_pane_follows_the_selected_pod() {
+        let mut app = App::new("bakerst");
+        app.update_pod(pod("brain-1"));
+        app.update_pod(pod("worker-1"));
+        assert!(app.selected_pod_logs().is_none());
+
+        for i in 0..MAX_LOG_LINES + 5 {
+            app.push_log_line("brain-1", format!("line {}", i));
+        }
+        app.push_log_line("worker-1", "missing NATS_URL".into());
+
+        let (name, lines) = app.selected_pod_logs().unwrap();
+        assert_eq!(name, "brain-1");
+        assert_eq!(lines.len(), MAX_LOG_LINES);
+        assert_eq!(lines.front().map(String::as_str), Some("line 5"));
+
+        app.select_next_pod();
+        let (name, lines) = app.selected_pod_logs().unwrap();
+        assert_eq!((name, lines.len()), ("worker-1", 1));
+    }
 }
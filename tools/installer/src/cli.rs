@@ -1,6 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 
+use crate::deploy::ResourceProfile;
+use crate::k8s::{ApplyStrategy, PullPolicy};
+
 #[derive(Parser)]
 #[command(name = "bakerst-install", version, about = "Baker Street Installer")]
 pub struct Cli {
@@ -11,29 +15,112 @@ pub struct Cli {
     #[arg(long, default_value = "bakerst")]
     pub namespace: String,
 
-    /// Enable verbose logging
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// Write a debug trace to the log: each docker command, each applied
+    /// resource and each deployment readiness change. -vv also prints it
+    /// to stderr
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Write the log to this file instead (install's --log is replaced by
+    /// the verification results once the install passes)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub trace_file: Option<PathBuf>,
+
+    /// Allow installing into the 'default' namespace
+    #[arg(long = "i-know-what-im-doing", global = true)]
+    pub i_know_what_im_doing: bool,
+
+    /// Named instance: prefixes resource names so several agents can share a namespace
+    #[arg(long, global = true, value_name = "NAME", value_parser = parse_instance)]
+    pub instance: Option<String>,
+
+    /// Kubeconfig context to use instead of the current one
+    #[arg(long, global = true, value_name = "NAME")]
+    pub kube_context: Option<String>,
+}
+
+/// Parse the command line, exiting on a usage error, and note which
+/// install flags it gave.
+pub fn parse() -> Cli {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let (Some(Commands::Install(args)), Some(("install", install))) = (&mut cli.command, matches.subcommand()) {
+        args.from_command_line = install
+            .ids()
+            .filter(|id| install.value_source(id.as_str()) == Some(clap::parser::ValueSource::CommandLine))
+            .map(|id| id.to_string())
+            .collect();
+    }
+    cli
+}
+
+/// Re-run clap's `requires` and `conflicts_with` checks between the
+/// install flags in `present` (ids, as in [`InstallArgs::from_command_line`]),
+/// for when some were set by the `--config` file rather than the command
+/// line. The error is clap's, e.g. "the argument '--dry-run' cannot be
+/// used with '--prune'".
+pub fn check_install_relations<'a>(present: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+    // Every flag as a bare switch, so only the relations between them are checked
+    let command = <InstallArgs as clap::Args>::augment_args(clap::Command::new("install").no_binary_name(true))
+        .mut_args(|arg| {
+            arg.action(clap::ArgAction::SetTrue)
+                .num_args(0)
+                .value_parser(clap::value_parser!(bool))
+                .default_value(None::<&str>)
+                .default_missing_value(None::<&str>)
+        });
+    let flags: Vec<String> = present
+        .into_iter()
+        .filter_map(|id| command.get_arguments().find(|arg| arg.get_id() == id)?.get_long())
+        .map(|long| format!("--{}", long))
+        .collect();
+    command.try_get_matches_from(flags).map(drop).map_err(|e| {
+        // Without the usage that follows, on one line
+        let message = e.render().to_string();
+        let words: Vec<&str> = message.split("\n\n").next().unwrap_or_default().split_whitespace().collect();
+        words.join(" ").trim_start_matches("error: ").to_string()
+    })
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Install Baker Street (default)
-    Install(InstallArgs),
+    Install(Box<InstallArgs>),
     /// Check deployment status
+    ///
+    /// Read-only: needs `list` on deployments (and optionally secrets and
+    /// pods.metrics.k8s.io) in the namespace, so a viewer kubeconfig works.
     Status(StatusArgs),
+    /// List every resource created by a previous install
+    ///
+    /// Read-only: needs `list` on each managed kind; kinds that can't be
+    /// listed are skipped.
+    Resources(ResourcesArgs),
     /// Update to latest version
     Update(UpdateArgs),
     /// Remove Baker Street
     Uninstall(UninstallArgs),
 }
 
-#[derive(clap::Args, Default)]
+#[derive(clap::Args)]
 pub struct InstallArgs {
-    /// Path to config file (skip interactive interview)
-    #[arg(long)]
+    /// Path to config file, YAML or TOML (skips interactive interview; may also set install flags)
+    #[arg(long, visible_alias = "profile")]
     pub config: Option<PathBuf>,
 
+    /// Save the collected configuration as a profile for `--profile`
+    #[arg(long, value_name = "PATH")]
+    pub save_profile: Option<PathBuf>,
+
+    /// With --save-profile, store secret values in the profile instead of ${ENV} references
+    #[arg(long, requires = "save_profile")]
+    pub save_profile_secrets: bool,
+
+    /// Read secrets and settings (API keys, AGENT_NAME, AUTH_TOKEN, feature
+    /// secrets) from a .env file; environment variables win over it
+    #[arg(long, value_name = "PATH")]
+    pub env_file: Option<PathBuf>,
+
     /// Path to local manifest file (skip GitHub fetch)
     #[arg(long)]
     pub manifest: Option<PathBuf>,
@@ -46,21 +133,304 @@ pub struct InstallArgs {
     #[arg(long)]
     pub version: Option<String>,
 
+    /// File holding the base64 Ed25519 key release manifests are signed with
+    /// (default: the key built into this installer)
+    #[arg(long, value_name = "PATH")]
+    pub manifest_pubkey: Option<PathBuf>,
+
+    /// Use the manifest and template cached for --version by an earlier run; no network access
+    #[arg(long, requires = "version", conflicts_with = "manifest")]
+    pub assume_cached_manifest: bool,
+
+    /// Don't read or write the local manifest cache (by default a failed
+    /// download falls back to the manifest cached by an earlier run)
+    #[arg(long, conflicts_with = "assume_cached_manifest")]
+    pub no_cache: bool,
+
+    /// Load images from a `docker save` tarball instead of pulling them. No
+    /// network access: needs --manifest, and the template from --template,
+    /// beside the manifest, or the cache
+    #[arg(long, value_name = "PATH.tar", requires = "manifest", conflicts_with = "registry_mirror")]
+    pub image_bundle: Option<PathBuf>,
+
     /// Path for structured JSON log
     #[arg(long, default_value = "bakerst-install.log")]
     pub log: PathBuf,
 
+    /// Write Prometheus text-format metrics about the run to this file
+    #[arg(long)]
+    pub metrics_file: Option<PathBuf>,
+
     /// Fail on missing required values instead of prompting
     #[arg(long)]
     pub non_interactive: bool,
 
-    /// Show what would be applied without applying
+    /// Abort the whole run if it takes longer than this many seconds
+    #[arg(long, value_name = "SECS", requires = "non_interactive")]
+    pub timeout_total: Option<u64>,
+
+    /// Output format; `json` streams one JSON object per line on stdout (progress
+    /// events, then the result) and moves the human-readable text to stderr
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, requires = "non_interactive")]
+    pub output: OutputFormat,
+
+    /// Print every manifest and secret that would be applied, without touching the cluster
     #[arg(long)]
     pub dry_run: bool,
 
+    /// Debugging aid: show entered secret values on the review screen
+    /// (also requires BAKERST_REVEAL_SECRETS=1)
+    #[arg(long, hide = true)]
+    pub reveal_secrets_in_confirm: bool,
+
+    /// With --dry-run, print real secret values instead of masking them
+    #[arg(long, requires = "dry_run")]
+    pub show_secrets: bool,
+
+    /// Print a diff of the target manifests against the live install, then exit
+    #[arg(long, conflicts_with = "dry_run")]
+    pub diff: bool,
+
+    /// After applying, delete managed resources no longer in the manifest (asks first unless --non-interactive)
+    #[arg(long, conflicts_with_all = ["dry_run", "diff"])]
+    pub prune: bool,
+
+    /// Send every object to the API server as a server-side dry run: admission
+    /// and validation run, nothing is persisted and no config is saved
+    #[arg(long, conflicts_with_all = ["dry_run", "diff", "prune"])]
+    pub server_dry_run: bool,
+
+    /// Keep trailing whitespace/newlines in secret values (trimmed by default)
+    #[arg(long)]
+    pub no_trim_secrets: bool,
+
+    /// Deploy even if a pulled image's digest differs from the one the manifest pins
+    #[arg(long)]
+    pub skip_digest_check: bool,
+
+    /// Images to pull at once (1 for a metered connection)
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = crate::images::DEFAULT_PULL_CONCURRENCY as u64,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub pull_concurrency: u64,
+
     /// Apply manifests but skip waiting for pods and verification
     #[arg(long)]
     pub no_wait: bool,
+
+    /// Once healthy, stream all components' logs for SECS seconds (default 60) or until Ctrl+C
+    #[arg(
+        long,
+        value_name = "SECS",
+        num_args = 0..=1,
+        default_missing_value = "60",
+        requires = "non_interactive",
+        conflicts_with = "no_wait"
+    )]
+    pub follow_logs: Option<u64>,
+
+    /// Once verified, request the UI (and through it the brain, with the
+    /// auth token) to check the app responds. Uses the access URL, or URL
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = "",
+        conflicts_with = "no_wait"
+    )]
+    pub smoke_test: Option<String>,
+
+    /// Force how resources are applied (default: server-side apply, falling
+    /// back to create/replace if the cluster doesn't support it)
+    #[arg(long, value_enum)]
+    pub apply_strategy: Option<ApplyStrategy>,
+
+    /// Server-side apply field manager name (default: bakerst-install/<version>)
+    #[arg(long, value_name = "NAME", value_parser = parse_field_manager)]
+    pub field_manager: Option<String>,
+
+    /// Times to retry a resource whose apply fails transiently (conflict,
+    /// server error, dropped connection), backing off between attempts
+    #[arg(long, value_name = "N", default_value_t = crate::k8s::DEFAULT_APPLY_RETRIES,
+          value_parser = clap::value_parser!(u32).range(0..=crate::k8s::MAX_APPLY_RETRIES as i64))]
+    pub apply_retries: u32,
+
+    /// Fill a {{KEY}} placeholder in the release's manifests (repeatable).
+    /// An escape hatch for knobs without a flag of their own; saved, so
+    /// updates keep it
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = crate::templates::parse_var)]
+    pub set: Vec<(String, String)>,
+
+    /// Container image pull policy for all workloads (default: IfNotPresent for
+    /// pre-pulled :latest images, Always for pinned release tags)
+    #[arg(long, value_enum)]
+    pub pull_policy: Option<PullPolicy>,
+
+    /// Storage class for the install's PersistentVolumeClaims (default: the cluster's default class)
+    #[arg(long, value_name = "NAME")]
+    pub storage_class: Option<String>,
+
+    /// Store component data in hostPath directories under this node path
+    /// instead of PersistentVolumeClaims (single-node clusters without a
+    /// storage provisioner)
+    #[arg(long, value_name = "PATH", value_parser = parse_data_dir, conflicts_with = "storage_class")]
+    pub data_dir: Option<String>,
+
+    /// Run Qdrant and NATS as StatefulSets with a volume per replica
+    /// instead of Deployments sharing one PVC (experimental)
+    #[arg(long, conflicts_with = "data_dir")]
+    pub stateful_storage: bool,
+
+    /// Replica counts and CPU/memory for the cluster's size (default: the
+    /// release's default profile, usually standard)
+    #[arg(long, value_enum, value_name = "PROFILE")]
+    pub resource_profile: Option<ResourceProfile>,
+
+    /// Also expose the UI through an Ingress for this host, reached at
+    /// http://<HOST> (needs an ingress controller; default: NodePort only)
+    #[arg(long, value_name = "HOST", value_parser = parse_ingress_host)]
+    pub ingress_host: Option<String>,
+
+    /// Serve the Ingress host over TLS with the certificate in this Secret
+    /// (kubernetes.io/tls, in the install's namespace), at https://<HOST>
+    #[arg(long, value_name = "SECRET", requires = "ingress_host")]
+    pub ingress_tls_secret: Option<String>,
+
+    /// IngressClass for the Ingress (default: the cluster's default class)
+    #[arg(long, value_name = "CLASS", requires = "ingress_host")]
+    pub ingress_class: Option<String>,
+
+    /// Pull every image from this registry prefix instead of its upstream
+    /// registry, e.g. harbor.internal/baker for an air-gapped mirror (the
+    /// manifest's digests are still checked)
+    #[arg(long, value_name = "PREFIX", value_parser = parse_registry_mirror)]
+    pub registry_mirror: Option<String>,
+
+    /// NodePort to expose the UI on, reached at http://localhost:<PORT>
+    /// (default: 30080; a named instance gets none unless given)
+    #[arg(long, value_name = "PORT", value_parser = clap::value_parser!(u16).range(30000..=32767))]
+    pub ui_node_port: Option<u16>,
+
+    /// Seconds to wait for deployments to become ready (default: 600; 120 in the TUI)
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub health_timeout: Option<u64>,
+
+    /// Seconds between readiness polls while waiting (default: 5; 2 in the TUI)
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub poll_interval: Option<u64>,
+
+    /// Initial delay for a component's readiness and liveness probes, as
+    /// component=seconds (repeatable; overrides the manifest)
+    #[arg(long, value_name = "COMPONENT=SECS", value_parser = parse_component_secs)]
+    pub probe_initial_delay: Vec<(String, u32)>,
+
+    /// Period of a component's readiness and liveness probes, as
+    /// component=seconds (repeatable; overrides the manifest)
+    #[arg(long, value_name = "COMPONENT=SECS", value_parser = parse_component_period)]
+    pub probe_period: Vec<(String, u32)>,
+
+    /// Consecutive all-ready polls required before pods count as healthy (default: 2)
+    #[arg(long, value_name = "N")]
+    pub health_stable_cycles: Option<u32>,
+
+    /// Times a crash-looping component's pods are deleted to be recreated
+    /// before giving up; 0 never deletes them (default: 3)
+    #[arg(long, value_name = "N")]
+    pub max_recovery_attempts: Option<u32>,
+
+    /// Load agent definition files (BRAIN.md, SOUL.md, ...) for the bakerst-os
+    /// ConfigMap from this directory instead of the release defaults
+    #[arg(long, value_name = "PATH")]
+    pub os_dir: Option<PathBuf>,
+
+    /// Leave an existing bakerst-os ConfigMap untouched
+    #[arg(long, conflicts_with = "os_dir")]
+    pub skip_os_configmap: bool,
+
+    /// Also deploy a Prometheus/Grafana telemetry stack in its own namespace
+    #[arg(long)]
+    pub telemetry: bool,
+
+    /// Seconds to wait for the telemetry stack to become ready; it only
+    /// warns if it doesn't (default: 120)
+    #[arg(long, value_name = "SECONDS", requires = "telemetry", value_parser = clap::value_parser!(u64).range(1..))]
+    pub telemetry_timeout: Option<u64>,
+
+    /// If applying the manifests fails, delete what this run created (and
+    /// the namespace, if it created that too) instead of leaving a partial install
+    #[arg(long, conflicts_with = "server_dry_run")]
+    pub rollback_on_failure: bool,
+
+    /// Write the install's auth token to this file (mode 0600) instead of
+    /// leaving it only in the cluster
+    #[arg(long, value_name = "PATH")]
+    pub auth_token_out: Option<PathBuf>,
+
+    /// Overwrite an existing --auth-token-out file
+    #[arg(long, requires = "auth_token_out")]
+    pub force: bool,
+
+    /// Annotation to set on the namespace, as key=value (repeatable)
+    #[arg(long = "namespace-annotations", value_name = "KEY=VALUE", value_parser = crate::k8s::parse_annotation)]
+    pub namespace_annotations: Vec<(String, String)>,
+
+    /// Label to set on the namespace, as key=value (repeatable), e.g. for
+    /// policies that require team or cost-center labels
+    #[arg(long = "namespace-label", value_name = "KEY=VALUE", value_parser = crate::k8s::parse_label)]
+    pub namespace_labels: Vec<(String, String)>,
+
+    /// Install into an existing namespace that bakerst-install didn't create
+    /// (it is labeled as managed from then on)
+    #[arg(long)]
+    pub adopt_namespace: bool,
+
+    /// Pause at each phase boundary until Enter is pressed
+    #[arg(long, conflicts_with = "non_interactive")]
+    pub step: bool,
+
+    /// Interactive display colors (the health-wait TUI and the prompts);
+    /// `mono` uses the terminal's own colors only, and is used whenever
+    /// NO_COLOR is set
+    #[arg(long, value_enum, default_value_t = Theme::Dark, conflicts_with = "non_interactive")]
+    pub theme: Theme,
+
+    /// Print which K8s Secret each configured key is routed to, then exit (no cluster access)
+    #[arg(long)]
+    pub explain_secrets: bool,
+
+    /// List the release's images, whether each is required and already pulled locally, then exit
+    #[arg(long, conflicts_with = "explain_secrets")]
+    pub print_images: bool,
+
+    /// Ids of the flags given on the command line, which win over the
+    /// `--config` file's (see [`parse`])
+    #[arg(skip)]
+    pub from_command_line: BTreeSet<String>,
+}
+
+/// The args of a bare `install`, with every `default_value` applied, as
+/// when no subcommand is given.
+impl Default for InstallArgs {
+    fn default() -> Self {
+        let command = <InstallArgs as clap::Args>::augment_args(clap::Command::new("install").no_binary_name(true));
+        InstallArgs::from_arg_matches(&command.get_matches_from(std::iter::empty::<&str>()))
+            .expect("a bare install always parses")
+    }
+}
+
+impl InstallArgs {
+    /// The health-wait flags, with `defaults` for those not given.
+    pub fn poll_options(&self, defaults: crate::health::PollOptions) -> crate::health::PollOptions {
+        crate::health::PollOptions {
+            stable_cycles: self.health_stable_cycles.unwrap_or(defaults.stable_cycles),
+            max_recovery_attempts: self.max_recovery_attempts.unwrap_or(defaults.max_recovery_attempts),
+            timeout: self.health_timeout.map(std::time::Duration::from_secs).unwrap_or(defaults.timeout),
+            interval: self.poll_interval.map(std::time::Duration::from_secs).unwrap_or(defaults.interval),
+        }
+    }
 }
 
 #[derive(clap::Args)]
@@ -69,9 +439,75 @@ pub struct StatusArgs {
     #[arg(long)]
     pub json: bool,
 
-    /// Watch mode (poll every 5s)
+    /// Watch mode: refresh until Ctrl+C, marking deployments whose ready
+    /// count went up or down (with --json, one JSON object per line)
     #[arg(long)]
     pub watch: bool,
+
+    /// Also list each deployment's pods: phase, restarts and latest Warning event
+    #[arg(long)]
+    pub pods: bool,
+
+    /// Seconds between --watch refreshes
+    #[arg(long, value_name = "SECONDS", default_value_t = 5, requires = "watch",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval: u64,
+
+    /// When to use colors
+    #[arg(long, value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+}
+
+/// `--theme` setting for the interactive display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// For dark terminal backgrounds
+    #[default]
+    Dark,
+    /// For light terminal backgrounds
+    Light,
+    /// No colors; states are told apart by their glyphs
+    Mono,
+}
+
+/// `--color` setting. `auto` colors only when stdout is a terminal and
+/// `NO_COLOR` is unset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn enabled(self) -> bool {
+        use std::io::IsTerminal;
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// `--output` setting for non-interactive installs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct ResourcesArgs {
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(clap::Args)]
@@ -80,9 +516,75 @@ pub struct UpdateArgs {
     #[arg(long, short = 'y')]
     pub non_interactive: bool,
 
+    /// File holding the base64 Ed25519 key release manifests are signed with
+    /// (default: the key built into this installer)
+    #[arg(long, value_name = "PATH")]
+    pub manifest_pubkey: Option<PathBuf>,
+
     /// Reconfigure secrets (re-run interview)
     #[arg(long)]
     pub reconfigure: bool,
+
+    /// With --reconfigure, read secrets from a .env file as well as the
+    /// environment (which wins)
+    #[arg(long, value_name = "PATH", requires = "reconfigure")]
+    pub env_file: Option<PathBuf>,
+
+    /// Change the install's resource profile (default: keep the one it was installed with)
+    #[arg(long, value_enum, value_name = "PROFILE")]
+    pub resource_profile: Option<ResourceProfile>,
+
+    /// Apply (or update) the UI's Ingress for this host, as `install --ingress-host`
+    #[arg(long, value_name = "HOST", value_parser = parse_ingress_host)]
+    pub ingress_host: Option<String>,
+
+    /// TLS Secret for the Ingress host, as `install --ingress-tls-secret`
+    #[arg(long, value_name = "SECRET", requires = "ingress_host")]
+    pub ingress_tls_secret: Option<String>,
+
+    /// IngressClass for the Ingress, as `install --ingress-class`
+    #[arg(long, value_name = "CLASS", requires = "ingress_host")]
+    pub ingress_class: Option<String>,
+
+    /// Pull images from this registry prefix (default: the one the install used)
+    #[arg(long, value_name = "PREFIX", value_parser = parse_registry_mirror)]
+    pub registry_mirror: Option<String>,
+
+    /// Move the UI to this NodePort (default: the one the install used)
+    #[arg(long, value_name = "PORT", value_parser = clap::value_parser!(u16).range(30000..=32767))]
+    pub ui_node_port: Option<u16>,
+
+    /// Print a diff of the new release's manifests against the live install, then exit
+    #[arg(long)]
+    pub diff: bool,
+
+    /// After applying, delete managed resources no longer in the manifest (asks first unless --non-interactive)
+    #[arg(long, conflicts_with = "diff")]
+    pub prune: bool,
+
+    /// Keep trailing whitespace/newlines in secret values (trimmed by default)
+    #[arg(long)]
+    pub no_trim_secrets: bool,
+
+    /// Force how resources are applied (default: server-side apply, falling
+    /// back to create/replace if the cluster doesn't support it)
+    #[arg(long, value_enum)]
+    pub apply_strategy: Option<ApplyStrategy>,
+
+    /// Server-side apply field manager name (default: bakerst-install/<version>)
+    #[arg(long, value_name = "NAME", value_parser = parse_field_manager)]
+    pub field_manager: Option<String>,
+
+    /// Times to retry a resource whose apply fails transiently (conflict,
+    /// server error, dropped connection), backing off between attempts
+    #[arg(long, value_name = "N", default_value_t = crate::k8s::DEFAULT_APPLY_RETRIES,
+          value_parser = clap::value_parser!(u32).range(0..=crate::k8s::MAX_APPLY_RETRIES as i64))]
+    pub apply_retries: u32,
+
+    /// Fill a {{KEY}} placeholder in the release's manifests (repeatable),
+    /// over the values the install saved
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = crate::templates::parse_var)]
+    pub set: Vec<(String, String)>,
 }
 
 #[derive(clap::Args)]
@@ -90,4 +592,93 @@ pub struct UninstallArgs {
     /// Skip confirmation prompt
     #[arg(long, short = 'y')]
     pub non_interactive: bool,
+
+    /// Also delete the PersistentVolumes the install's claims were bound
+    /// to (kept by default, with the data on them)
+    #[arg(long)]
+    pub prune: bool,
+
+    /// Seconds to wait for the deleted namespaces to finish terminating
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Return once the deletes are accepted, without waiting for the
+    /// namespaces to terminate
+    #[arg(long, conflicts_with = "timeout")]
+    pub no_wait: bool,
+
+    /// Remove only this component (repeatable): its Deployments, their
+    /// Services and the Secrets nothing else uses. The namespace stays
+    #[arg(long, value_name = "NAME", conflicts_with = "prune")]
+    pub component: Vec<String>,
+
+    /// With --component, allow removing a core component (brain, worker, ...)
+    #[arg(long, requires = "component")]
+    pub force: bool,
+}
+
+/// The API server caps field manager names at 128 characters.
+pub(crate) fn parse_field_manager(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.len() > 128 {
+        return Err("field manager must be 1-128 characters".into());
+    }
+    Ok(s.to_string())
+}
+
+pub(crate) fn parse_data_dir(s: &str) -> Result<String, String> {
+    if !s.starts_with('/') {
+        return Err("data directory must be an absolute path on the node".into());
+    }
+    Ok(s.trim_end_matches('/').to_string())
+}
+
+/// Ingress hosts are DNS names; TLS, if any, is `--ingress-tls-secret`.
+pub(crate) fn parse_ingress_host(s: &str) -> Result<String, String> {
+    let valid = !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        });
+    if !valid {
+        return Err(format!("'{}' is not a lowercase DNS host name (e.g. bakerst.dev.example.com)", s));
+    }
+    Ok(s.to_string())
+}
+
+/// A registry host with an optional repository path, no scheme.
+pub(crate) fn parse_registry_mirror(s: &str) -> Result<String, String> {
+    let s = s.trim_end_matches('/');
+    if s.is_empty() || s.contains("://") || s.contains(char::is_whitespace) {
+        return Err(format!("'{}' is not a registry prefix (e.g. harbor.internal/baker)", s));
+    }
+    Ok(s.to_string())
+}
+
+fn parse_component_secs(s: &str) -> Result<(String, u32), String> {
+    let (component, secs) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected component=seconds, got '{}'", s))?;
+    let secs = secs
+        .parse()
+        .map_err(|_| format!("'{}' is not a non-negative number of seconds", secs))?;
+    Ok((component.to_string(), secs))
+}
+
+/// Like [`parse_component_secs`], but at least 1: the API server rejects a
+/// probe period of 0.
+fn parse_component_period(s: &str) -> Result<(String, u32), String> {
+    let (component, secs) = parse_component_secs(s)?;
+    if secs == 0 {
+        return Err("a probe period must be at least 1 second".into());
+    }
+    Ok((component, secs))
+}
+
+fn parse_instance(s: &str) -> Result<String, String> {
+    crate::instance::check_name(s).map_err(|e| e.to_string())?;
+    Ok(s.to_string())
 }
@@ -3,60 +3,162 @@
 
 use anyhow::{bail, Context, Result};
 use std::collections::BTreeMap;
+use tokio::sync::mpsc;
 
-use crate::cli::{Cli, InstallArgs};
+use crate::app::App;
+use crate::cli::{Cli, InstallArgs, OutputFormat};
 use crate::config_file;
 use crate::config_schema::ConfigSchema;
-use crate::{deploy, fetcher, interview, k8s, verify};
+use crate::images::{PullEvent, PullProgress};
+use crate::manifest::{Manifest, ManifestImage};
+use crate::metrics::InstallMetrics;
+use crate::{deploy, diagnose, fetcher, health, images, instance, interview, k8s, output, signals, telemetry, tui_driver, verify};
 
-/// Entry point for the `install` subcommand.
-pub async fn run(_cli: &Cli, args: InstallArgs) -> Result<()> {
+/// What [`read_inputs`] read from `--env-file` and `--config`.
+#[derive(Default)]
+pub struct Inputs {
+    /// How many values the env file had.
+    env_values: Option<usize>,
+    config_file: Option<config_file::ConfigFile>,
+}
+
+/// Read `--env-file` and merge the `--config` file's options into `args`,
+/// command-line flags winning. Done before anything reads `args`, so the
+/// file can set every flag (`--log`, `--output` and `--metrics-file`
+/// included) and its flags are checked against the command line's.
+pub fn read_inputs(args: &mut InstallArgs) -> Result<Inputs> {
+    // Before the config file, whose ${VAR} references may come from it
+    let env_values = args.env_file.as_deref().map(crate::env_file::load).transpose()?;
+    let config_file = match args.config.clone() {
+        Some(path) => {
+            let file = config_file::load_config(&path)?;
+            file.apply_to(args).with_context(|| format!("Invalid options in {}", path.display()))?;
+            Some(file)
+        }
+        None => None,
+    };
+    Ok(Inputs { env_values, config_file })
+}
+
+/// Entry point for the `install` subcommand, with `args` already merged
+/// with the config file by [`read_inputs`].
+pub async fn run(cli: &Cli, args: InstallArgs, inputs: Inputs) -> Result<()> {
+    let metrics_file = args.metrics_file.clone();
+    let output = args.output;
+    let budget = args.timeout_total.map(std::time::Duration::from_secs);
+    if output == OutputFormat::Json {
+        output::enable_json()?;
+    }
+    let mut metrics = InstallMetrics::new();
+    let run = signals::or_terminated(install(cli, args, inputs, &mut metrics));
+    let result = match budget {
+        Some(budget) => match tokio::time::timeout(budget, run).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!(
+                "Install exceeded --timeout-total of {}s during the {} phase",
+                budget.as_secs(),
+                metrics.current_phase().unwrap_or("startup")
+            )),
+        },
+        None => run.await,
+    };
+    if let (Err(e), OutputFormat::Json) = (&result, output) {
+        output::emit(&output::ErrorReport::new(metrics.current_phase(), e));
+    }
+    metrics.finish_phase();
+
+    // Written on failure too: a failed run is what the metrics are for
+    if let Some(path) = metrics_file {
+        if let Err(e) = metrics.write(&path, result.is_ok()) {
+            eprintln!("  Warning: {:#}", e);
+        }
+    }
+    result
+}
+
+async fn install(cli: &Cli, args: InstallArgs, inputs: Inputs, metrics: &mut InstallMetrics) -> Result<()> {
     println!("Baker Street Installer v{}", env!("CARGO_PKG_VERSION"));
     println!();
 
-    // 1. Preflight: detect kubectl, K8s contexts
-    println!("[1/9] Preflight checks...");
-    let server_version = k8s::check_cluster()
-        .await
-        .context("Kubernetes cluster not reachable. Ensure kubectl is installed and a cluster is running.")?;
-    println!("  K8s server version: {}", server_version);
+    if let (Some(path), Some(count)) = (&args.env_file, inputs.env_values) {
+        println!("Read {} value(s) from {} (environment variables take precedence)", count, path.display());
+    }
+    let config_file = inputs.config_file;
+    if let (Some(path), Some(file)) = (&args.config, &config_file) {
+        for key in file.unknown_keys() {
+            println!("  Warning: unknown key '{}' in {}", key, path.display());
+        }
+    }
 
-    let contexts = k8s::detect_contexts().await?;
-    if contexts.is_empty() {
-        bail!("No Kubernetes contexts found. Install Docker Desktop or OrbStack with Kubernetes enabled.");
+    // Plain-text secrets on screen need a second, deliberate opt-in
+    if args.reveal_secrets_in_confirm && std::env::var("BAKERST_REVEAL_SECRETS").as_deref() != Ok("1") {
+        bail!("--reveal-secrets-in-confirm prints secret values in plain text; set BAKERST_REVEAL_SECRETS=1 to confirm");
     }
-    if contexts.len() == 1 {
-        println!(
-            "  Using K8s context: {} ({})",
-            contexts[0].name, contexts[0].cluster_type
-        );
+
+    // The namespace as far as it's known before the interview, so a
+    // foreign one is caught before any secrets are typed
+    let preflight_namespace = config_file
+        .as_ref()
+        .and_then(|file| file.namespace.clone())
+        .unwrap_or_else(|| cli.namespace.clone());
+
+    // 1. Preflight: detect kubectl, K8s contexts
+    // --explain-secrets and --print-images never touch the cluster, so skip it entirely.
+    if args.explain_secrets || args.print_images {
+        let flag = if args.print_images { "--print-images" } else { "--explain-secrets" };
+        println!("[1/10] Preflight checks... skipped ({})", flag);
     } else {
-        println!("  Available K8s contexts:");
-        for (i, ctx) in contexts.iter().enumerate() {
-            println!("    {}) {} ({})", i + 1, ctx.name, ctx.cluster_type);
-        }
-        // For non-interactive, use first context; for interactive, prompt
-        if !args.non_interactive {
-            // TODO: Prompt user for context selection (Task 15 TUI)
-            println!("  Using first context: {}", contexts[0].name);
-        }
-        k8s::use_context(&contexts[0].name).await?;
+        metrics.phase("preflight");
+        let docker = preflight(cli, &args, &preflight_namespace).await?;
+        output::emit(&output::Event::Preflight { context: cli.kube_context.as_deref(), docker });
     }
 
     // 2. Fetch manifest
-    println!("[2/9] Fetching manifest...");
-    let manifest = fetcher::fetch_manifest(
-        args.manifest.as_deref(),
-        args.version.as_deref(),
-    )
-    .await?;
+    step_boundary(args.step, "Fetch manifest")?;
+    println!("[2/10] Fetching manifest...");
+    metrics.phase("manifest");
+    let (mut manifest, source) = match (&args.manifest, &args.version, args.assume_cached_manifest) {
+        (Some(path), ..) => fetcher::load_local_manifest(path)?,
+        (None, Some(version), true) => {
+            let manifest = fetcher::load_cached_manifest(version, args.manifest_pubkey.as_deref())?;
+            println!("  Using cached manifest (--assume-cached-manifest)");
+            (manifest, fetcher::ManifestSource::Cache)
+        }
+        _ => {
+            let public_key = crate::manifest::release_public_key(args.manifest_pubkey.as_deref())?;
+            fetcher::fetch_manifest(args.version.as_deref(), &public_key, !args.no_cache).await?
+        }
+    };
     println!(
-        "  Version: {} (schema v{})",
-        manifest.version, manifest.schema_version
+        "  Version: {} (schema v{}, from {})",
+        manifest.version, manifest.schema_version, source
     );
+    // Pulls go through the mirror; the workloads are pointed at it once
+    // rendered, after their digests are pinned
+    if let Some(mirror) = &args.registry_mirror {
+        println!("  Registry mirror: {}", mirror);
+    }
+    // Only a stack that's deployed needs its images pulled
+    if !args.telemetry {
+        manifest.images.retain(|i| !telemetry::IMAGES.contains(&i.name.as_str()));
+    }
+    let probes = deploy::probe_tuning(&manifest, &args.probe_initial_delay, &args.probe_period)?;
+
+    if args.print_images {
+        println!();
+        return print_images(&manifest, args.registry_mirror.as_deref()).await;
+    }
+    // What this replaces, confirmed before any questions are asked
+    let installed = match args.explain_secrets {
+        true => None,
+        false => detect_installed(cli, &preflight_namespace, &manifest, args.non_interactive).await?,
+    };
+    let version_confirmed = installed.as_ref().is_some_and(|v| *v != manifest.version.trim_start_matches('v'));
 
     // 3. Download and extract template
-    println!("[3/9] Downloading install template...");
+    step_boundary(args.step, "Download install template")?;
+    println!("[3/10] Downloading install template...");
+    metrics.phase("template");
     let work_dir = tempfile::tempdir()?;
     let template_dir = if let Some(template_path) = &args.template {
         // Local template tarball provided — extract it directly
@@ -66,112 +168,502 @@ pub async fn run(_cli: &Cli, args: InstallArgs) -> Result<()> {
             &manifest,
             args.manifest.as_deref(),
             work_dir.path(),
+            // An image bundle means an air-gapped install: cache, not network
+            args.assume_cached_manifest || args.image_bundle.is_some(),
         )
         .await?
     };
     println!("  Template extracted to: {}", template_dir.display());
 
+    // Agent definition files: a custom --os-dir must provide every file the
+    // release ships, so a typo'd directory fails before touching the cluster
+    let template_os_dir = template_dir.join("operating_system");
+    let os_files = match &args.os_dir {
+        Some(dir) => {
+            let files = load_os_files(dir)
+                .with_context(|| format!("Cannot read --os-dir {}", dir.display()))?;
+            let expected = if template_os_dir.exists() {
+                load_os_files(&template_os_dir)?.into_keys().collect()
+            } else {
+                Vec::new()
+            };
+            let missing = missing_os_files(&files, &expected);
+            if !missing.is_empty() {
+                bail!("--os-dir {} is missing: {}", dir.display(), missing.join(", "));
+            }
+            println!("  Using OS files from {}", dir.display());
+            Some(files)
+        }
+        None if template_os_dir.exists() => Some(load_os_files(&template_os_dir)?),
+        None => None,
+    };
+
+    // Catch manifest/installer mismatches: a release component with no
+    // deploy template would otherwise be silently skipped
+    let template_resources = deploy::load_resources_recursive(&template_dir.join("k8s"))?;
+    for name in deploy::components_without_templates(&manifest, &template_resources) {
+        println!(
+            "  Warning: manifest component '{}' has no deploy template in this release and won't be deployed",
+            name
+        );
+    }
+
     // 4. Load config schema from template
     let schema_path = template_dir.join("config-schema.json");
     let schema = ConfigSchema::from_file(&schema_path)?;
 
     // 5. Configure (interview or config file)
-    println!("[4/9] Configuring...");
-    let config = if let Some(config_path) = &args.config {
-        let file = config_file::load_config(config_path)?;
-        interview::from_config_file(&schema, &file)?
+    step_boundary(args.step, "Configure")?;
+    println!("[4/10] Configuring...");
+    metrics.phase("configure");
+    let mut config = if let Some(file) = &config_file {
+        for feature in file.unknown_features(&schema) {
+            println!("  Warning: feature '{}' is not in this release; ignoring it", feature);
+        }
+        interview::from_config_file(&schema, file)?
     } else if args.non_interactive {
         interview::from_env(&schema)?
     } else {
-        interview::run_interactive(&schema).await?
+        let upgrade = installed.as_deref().map(|v| (v, manifest.version.trim_start_matches('v')));
+        interview::run_interactive(&schema, args.reveal_secrets_in_confirm, upgrade, args.theme).await?
     };
+    config.instance = cli.instance.clone();
+    config.storage_class = args.storage_class.clone();
+    config.data_dir = args.data_dir.clone();
+    config.registry_mirror = args.registry_mirror.clone();
+    config.ui_node_port = args.ui_node_port;
+    config.stateful_storage = args.stateful_storage;
+    config.set = args.set.clone();
+    config.resource_profile = resource_profile(args.resource_profile, &schema);
     println!("  Namespace: {}", config.namespace);
+    if let Some(instance) = &config.instance {
+        println!("  Instance: {} (resources prefixed '{}-')", instance, instance);
+    }
+    let os_configmap = instance::prefixed(config.instance.as_deref(), k8s::OS_CONFIGMAP);
     println!("  Features: {:?}", config.enabled_features);
+    if let Some(dir) = &config.data_dir {
+        println!("  Data directory: {} (hostPath volumes in place of PVCs; pods are tied to one node)", dir);
+    }
+    if config.stateful_storage {
+        println!("  Stateful storage: {} as StatefulSets with per-replica volumes", deploy::STATEFUL_COMPONENTS.join(" and "));
+    }
+    let telemetry = match args.telemetry {
+        true => Some(telemetry::render(&config.namespace, config.instance.as_deref(), &manifest, config.registry_mirror.as_deref())?),
+        false => None,
+    };
+    match &telemetry {
+        Some(t) => println!("  Telemetry: Prometheus and Grafana in namespace {}", t.namespace),
+        None => println!("  Telemetry: not deployed (--telemetry to add Prometheus and Grafana)"),
+    }
+    println!("  Resource profile: {}", config.resource_profile.name());
+    for knob in config.resource_profile.describe() {
+        println!("    {}", knob);
+    }
+    if let Some(note) = manifest.staleness_note(std::time::SystemTime::now()) {
+        println!("  Note: {}", note);
+    }
+    if !config.set.is_empty() {
+        let keys = deploy::template_keys(&template_dir.join("k8s"), &config.enabled_features)?;
+        // Only the keys: a value may well be a credential
+        for (key, _) in &config.set {
+            match keys.contains(key) {
+                true => println!("  Set: {}", key),
+                false => println!("  Warning: --set {} matches no placeholder in this release's manifests", key),
+            }
+        }
+    }
+    if let Some(port) = config.ui_node_port {
+        println!("  UI NodePort: {}", port);
+    }
+    // What every render and apply below shares; each overrides what differs
+    let mut deploy_base = deploy::DeployOptions {
+        ingress_host: args.ingress_host.clone(),
+        ingress_tls_secret: args.ingress_tls_secret.clone(),
+        ingress_class: args.ingress_class.clone(),
+        ..deploy::DeployOptions::from_config(&manifest, &config, probes)
+    };
+    if let Some(policy) = args.pull_policy {
+        deploy_base.pull_policy = Some(policy);
+    }
+    let ingress_url = args
+        .ingress_host
+        .as_deref()
+        .map(|host| deploy::ingress_url(host, args.ingress_tls_secret.as_deref()));
+    // The namespace may come from the config file or interview, not just --namespace
+    k8s::check_namespace(&config.namespace, cli.i_know_what_im_doing)?;
+    // Refuse before anything is deployed rather than after
+    if let Some(path) = &args.auth_token_out {
+        if path.exists() && !args.force {
+            bail!("{} already exists; pass --force to overwrite it", path.display());
+        }
+    }
+    if let Some(path) = &args.save_profile {
+        config.save_profile(path, &schema, args.save_profile_secrets)?;
+        println!("  Profile saved to {}", path.display());
+    }
 
-    // 6. Save config for future updates (NON-SECRET data only)
-    let config_save_path = dirs::home_dir()
-        .context("Cannot determine home directory")?
-        .join(".bakerst/config.json");
-    config.save_non_secret(&config_save_path)?;
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(
-            &config_save_path,
-            std::fs::Permissions::from_mode(0o600),
-        )?;
+    if args.explain_secrets {
+        println!();
+        deploy::explain_secrets(&schema, &config);
+        return Ok(());
     }
 
+    // --dry-run: print everything that would be applied; nothing touches
+    // the cluster and no config is saved
     if args.dry_run {
-        println!(
-            "\nDry run complete. Would apply manifests from: {}",
-            template_dir.display()
+        let namespace = namespace_object(&args, &template_dir, &config.namespace, &manifest)?;
+        let os_files = if args.skip_os_configmap { None } else { os_files.as_ref() };
+        let mut objects = deploy::render_install(
+            namespace,
+            &schema,
+            &config,
+            !args.no_trim_secrets,
+            os_files,
+            &template_dir.join("k8s"),
+            &deploy_base,
+        )?;
+        if let Some(t) = &telemetry {
+            objects.extend(telemetry::objects(t, &manifest.version)?);
+        }
+        if !args.show_secrets {
+            deploy::mask_secret_values(&mut objects);
+        }
+        println!();
+        print!("{}", deploy::to_yaml_stream(&objects)?);
+        return Ok(());
+    }
+
+    // Obtain a K8s client for all cluster operations
+    let client = k8s::connect(cli.kube_context.as_deref()).await?;
+    // A namespace chosen in the interview wasn't checked in preflight
+    if config.namespace != preflight_namespace {
+        check_namespace_owner(&client, &config.namespace, config.instance.as_deref(), args.adopt_namespace).await?;
+    }
+    let apply = k8s::ApplyParams {
+        strategy: args.apply_strategy.unwrap_or_default(),
+        field_manager: args
+            .field_manager
+            .clone()
+            .unwrap_or_else(k8s::default_field_manager),
+        dry_run: args.server_dry_run,
+        labels: instance::labels(config.instance.as_deref()),
+        retries: args.apply_retries,
+    };
+
+    // --server-dry-run: the API server validates each object, nothing is kept
+    if args.server_dry_run {
+        let namespace = namespace_object(&args, &template_dir, &config.namespace, &manifest)?;
+        if !k8s::namespace_exists(&client, &config.namespace).await? {
+            bail!(
+                "Namespace '{}' does not exist yet, so the API server cannot validate the resources in it. \
+                 Use --dry-run to review the rendered manifests instead.",
+                config.namespace
+            );
+        }
+        println!("\nValidating against the API server (--server-dry-run)...");
+        k8s::apply_namespace(&client, &namespace, &apply).await?;
+        deploy::apply_secrets(&client, &schema, &config, k8s::SecretMode::Replace, !args.no_trim_secrets, &apply)
+            .await?;
+        if let (false, Some(os_files)) = (args.skip_os_configmap, &os_files) {
+            k8s::create_os_configmap(&client, &config.namespace, &os_configmap, os_files, &apply).await?;
+        }
+        let opts = deploy::DeployOptions { apply: apply.clone(), ..deploy_base.clone() };
+        let k8s_dir = template_dir.join("k8s");
+        let mut validated =
+            deploy::apply_manifests_from_dir(&client, &config.namespace, &deploy::manifest_dir(&k8s_dir), &opts)
+                .await?;
+        validated.extend(deploy::apply_ingress(&client, &config.namespace, &opts).await?);
+        validated.extend(
+            deploy::apply_extensions(&client, &config.namespace, &k8s_dir.join("extensions"), &config.enabled_features, &opts)
+                .await?,
         );
+        println!("\n{} resource(s) validated. Nothing was persisted (--server-dry-run).", validated.len());
         return Ok(());
     }
 
+    // --diff: show what would change against the live install, then stop
+    if args.diff {
+        let opts = deploy::DeployOptions { apply: apply.clone(), ..deploy_base.clone() };
+        let resources = deploy::render_target(&template_dir.join("k8s"), &config.enabled_features, &opts)?;
+        let changed = deploy::diff_against_cluster(&client, &config.namespace, &resources, &apply).await?;
+        println!("\n{} resource(s) would change. Nothing was applied (--diff).", changed);
+        return Ok(());
+    }
+
+    // Fail now, not at apply time, if a NodePort we need is taken. A named
+    // instance requests none (see instance::prefix_resources) unless given
+    // a --ui-node-port.
+    let mut exposed = template_resources.clone();
+    deploy::customize_resources(&mut exposed, &deploy_base);
+    check_node_ports(&client, &exposed, &config.namespace).await?;
+    check_upgrade(&client, &manifest, &config, args.non_interactive || version_confirmed).await?;
+    // Sized as it will be deployed, so the profile and feature hints count
+    let sized = deploy::render_target(&template_dir.join("k8s"), &config.enabled_features, &deploy_base)?;
+    check_capacity(&client, &sized, &config.namespace).await;
+
+    // 6. Save config for future updates (NON-SECRET data only)
+    let config_save_path = instance::config_path(config.instance.as_deref())?;
+    config.save_non_secret(&config_save_path)?;
+
     let skip_verify = args.no_wait;
 
-    // Obtain a K8s client for all cluster operations
-    let client = kube::Client::try_default().await?;
+    // 7. Pre-pull images into the local Docker cache
+    step_boundary(args.step, "Pull images")?;
+    match &args.image_bundle {
+        Some(bundle) => println!("[5/10] Loading images from {}...", bundle.display()),
+        None => println!("[5/10] Pulling images ({} at a time)...", args.pull_concurrency),
+    }
+    metrics.phase("pull");
+    let prepulled = pull_images(
+        &manifest,
+        config.registry_mirror.as_deref(),
+        metrics,
+        args.skip_digest_check,
+        args.pull_concurrency as usize,
+        args.image_bundle.as_deref(),
+    )
+    .await?;
+
+    let deploy_opts = deploy::DeployOptions {
+        apply: apply.clone(),
+        pull_policy: Some(
+            args.pull_policy.unwrap_or_else(|| match args.image_bundle {
+                // The cluster has nowhere to pull from; use the loaded images
+                Some(_) => k8s::PullPolicy::IfNotPresent,
+                None => deploy::default_pull_policy(&manifest, prepulled.all),
+            }),
+        ),
+        journal: args.rollback_on_failure.then(deploy::ApplyJournal::default),
+        // What wasn't checked locally, the cluster checks by digest
+        image_digests: match args.skip_digest_check {
+            true => BTreeMap::new(),
+            false => deploy::image_digests(&manifest, config.registry_mirror.as_deref(), &prepulled.unchecked),
+        },
+        ..deploy_base
+    };
+
+    // Before anything is applied: qdrant and nats can't switch kind in place
+    let target = deploy::render_target(&template_dir.join("k8s"), &config.enabled_features, &deploy_opts)?;
+    deploy::check_workload_kinds(&client, &config.namespace, &target).await?;
+
+    // 8. Create namespace and secrets
+    step_boundary(args.step, "Create namespace and secrets")?;
+    println!("[6/10] Creating namespace and secrets...");
+    metrics.phase("namespace");
+    let namespace = namespace_object(&args, &template_dir, &config.namespace, &manifest)?;
+    // What existed before, so a rollback only removes what this run created
+    let preexisting = match args.rollback_on_failure {
+        true => Some(preexisting_resources(&client, &config.namespace, config.instance.as_deref()).await?),
+        false => None,
+    };
+    if let Some(unlisted) = preexisting.as_ref().map(|p| &p.unlisted).filter(|u| !u.is_empty()) {
+        let kinds: Vec<&str> = unlisted.iter().map(String::as_str).collect();
+        println!(
+            "  Note: not allowed to list {}; a rollback will leave those kinds in place",
+            kinds.join(", ")
+        );
+    }
+    k8s::apply_namespace(&client, &namespace, &apply).await?;
+    deploy::apply_secrets(&client, &schema, &config, k8s::SecretMode::Replace, !args.no_trim_secrets, &apply).await?;
 
-    // 7. Create namespace and secrets
-    println!("[5/9] Creating namespace and secrets...");
-    k8s::create_namespace(&client, &config.namespace).await?;
-    deploy::apply_secrets(&client, &schema, &config).await?;
+    if let (Some(path), Some(token)) = (&args.auth_token_out, config.secrets.get("AUTH_TOKEN")) {
+        write_auth_token(path, token, args.force)?;
+        println!("  Auth token written to {}", path.display());
+    }
 
-    // Create ConfigMap from operating_system/ files
-    let os_dir = template_dir.join("operating_system");
-    if os_dir.exists() {
-        let os_files = load_os_files(&os_dir)?;
-        k8s::create_os_configmap(&client, &config.namespace, &os_files).await?;
+    // Create ConfigMap from operating_system/ files (or --os-dir)
+    if args.skip_os_configmap {
+        println!("  Skipping bakerst-os ConfigMap (--skip-os-configmap)");
+    } else if let Some(os_files) = &os_files {
+        k8s::create_os_configmap(&client, &config.namespace, &os_configmap, os_files, &apply).await?;
     }
 
-    // 8. Apply K8s manifests
-    println!("[6/9] Applying manifests...");
+    // 9. Apply K8s manifests
+    step_boundary(args.step, "Apply manifests")?;
+    println!("[7/10] Applying manifests...");
+    metrics.phase("apply");
     let k8s_dir = template_dir.join("k8s");
-    // The template always bundles pre-rendered YAML in overlays/remote/
-    let remote_overlay = k8s_dir.join("overlays/remote");
-    let manifest_dir = if remote_overlay.exists() {
-        remote_overlay
-    } else {
-        k8s_dir.clone()
+    // Interactive runs can fix a failed step (e.g. a flaky API server) and
+    // re-run it and the steps after it; the steps before stay applied
+    let mut progress = deploy::DeployProgress::new(&k8s_dir, &config.enabled_features, &deploy_opts);
+    let applying = loop {
+        match deploy::apply_steps(&client, &config.namespace, &k8s_dir, &mut progress, &deploy_opts).await {
+            Ok(()) => break Ok(progress.applied),
+            Err(e) if !args.non_interactive => {
+                let step = progress.failed().map(ToString::to_string).unwrap_or_default();
+                println!("  \u{2717} {}: {:#}", step, e);
+                if !prompt_retry()? {
+                    break Err(e);
+                }
+                println!("  Re-running {} and the steps after it...", step);
+            }
+            Err(e) => break Err(e),
+        }
     };
-    deploy::apply_manifests_from_dir(&client, &config.namespace, &manifest_dir).await?;
+    let applied = match (applying, &deploy_opts.journal, &preexisting) {
+        (Ok(applied), _, _) => applied,
+        (Err(e), Some(journal), Some(preexisting)) => {
+            println!("  Apply failed: {:#}", e);
+            println!("  Rolling back (--rollback-on-failure)...");
+            let plan = deploy::rollback_plan(&journal.lock().unwrap(), &preexisting.resources, &preexisting.unlisted);
+            deploy::rollback(&client, &config.namespace, &plan).await;
+            if !preexisting.namespace_existed {
+                k8s::delete_namespace(&client, &config.namespace).await?;
+                println!("    \u{21b6} rolled back Namespace/{}", config.namespace);
+            }
+            return Err(e.context("Applying manifests failed; the partial install was rolled back"));
+        }
+        (Err(e), _, _) => return Err(e),
+    };
+
+    // The stack is an add-on: failing to deploy it doesn't fail the install
+    let mut telemetry_applied = false;
+    if let Some(t) = &telemetry {
+        println!("  telemetry ({})", t.namespace);
+        match telemetry::apply(&client, t, &manifest.version, &apply).await {
+            Ok(_) => telemetry_applied = true,
+            Err(e) => println!("  WARNING: telemetry stack not applied: {:#}", e),
+        }
+    }
 
-    // Apply extension manifests for enabled features
-    let extensions_dir = k8s_dir.join("extensions");
-    deploy::apply_extensions(&client, &config.namespace, &extensions_dir, &config.enabled_features).await?;
+    if args.prune {
+        println!("  Pruning resources no longer in the manifest...");
+        let rendered = deploy::render_target(&k8s_dir, &config.enabled_features, &deploy_opts)?;
+        let owned = deploy::owned_resources(&rendered, &schema, &config);
+        deploy::prune(&client, &config.namespace, config.instance.as_deref(), &owned, args.non_interactive)
+            .await?;
+    }
 
     if skip_verify {
-        println!("\nManifests applied (--no-wait: skipping pod wait and verification).");
-        println!("   Access Baker Street at http://localhost:30080");
+        println!("[8/10] Waiting for pods to start... skipped (--no-wait)");
+        println!("[9/10] Verifying deployment... skipped (--no-wait)");
+        println!("\nManifests applied. Health was not verified (--no-wait); check the pods with:");
+        println!("   kubectl get pods -n {}", config.namespace);
+        print_access(
+            &config.namespace,
+            config.instance.as_deref(),
+            ingress_url.as_deref(),
+            config.ui_node_port,
+            telemetry.as_ref(),
+        );
+        let timing = output::Timing::new(metrics.elapsed(), metrics.phase_times(), Vec::new());
+        print_timing(&timing);
+        output::emit(&summary(
+            &manifest,
+            &schema,
+            &config,
+            args.auth_token_out.as_deref(),
+            ingress_url.as_deref(),
+            false,
+            timing,
+        ));
         return Ok(());
     }
 
-    // 9. Wait for pods to start
-    println!("[7/9] Waiting for pods to start...");
-    k8s::wait_for_deployments(
-        &client,
-        &config.namespace,
-        std::time::Duration::from_secs(600),
-    )
-    .await
-    .context("Pods did not become ready within 10 minutes")?;
+    // 10. Wait for pods to start
+    step_boundary(args.step, "Wait for pods")?;
+    println!("[8/10] Waiting for pods to start...");
+    metrics.phase("wait");
+    // Watch exactly what was deployed, so a new component can't be skipped
+    let workloads: Vec<health::WorkloadRef> =
+        applied.iter().filter_map(|label| health::WorkloadRef::from_label(label)).collect();
+    // Watched in the TUI when someone is at the terminal, which keeps its
+    // own quicker defaults
+    let mut tui_app = tui_driver::enabled(&args).then(|| App::new(&config.namespace));
+    let poll = args.poll_options(match tui_app {
+        Some(_) => health::PollOptions::default(),
+        None => health::PollOptions::deployment_wait(),
+    });
+    let waited = match &mut tui_app {
+        Some(app) => {
+            tui_driver::wait_for_workloads(&client, &config.namespace, &workloads, &poll, args.theme, app).await
+        }
+        None => k8s::wait_for_deployments(&client, &config.namespace, &workloads, &poll).await,
+    };
+    if waited.is_err() {
+        // Best effort: the timeout is reported either way
+        if let Ok(stuck) = health::unready_pods(&client, &config.namespace, &workloads).await {
+            diagnose::print_diagnosis(&stuck, &config.namespace);
+        }
+    }
+    // The error says whether it timed out or hit a problem that won't clear
+    let ready_after = waited.context("Deployment health check failed")?;
     println!("  All deployments ready");
+    if let (true, Some(t)) = (telemetry_applied, &telemetry) {
+        // An add-on: a short wait of its own, not the install's
+        let timeout = args
+            .telemetry_timeout
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(telemetry::DEFAULT_TELEMETRY_TIMEOUT);
+        let deployments: Vec<health::WorkloadRef> = telemetry::DEPLOYMENTS
+            .iter()
+            .map(|d| health::WorkloadRef::new(health::WorkloadKind::Deployment, *d))
+            .collect();
+        let opts = health::PollOptions { timeout, stable_cycles: 1, ..poll };
+        if let Err(e) = k8s::wait_for_deployments(&client, &t.namespace, &deployments, &opts).await {
+            println!("  WARNING: telemetry not ready: {:#}", e);
+        }
+    }
 
-    // 10. Verify
-    println!("[8/9] Verifying deployment...");
+    // 11. Verify
+    step_boundary(args.step, "Verify deployment")?;
+    println!("[9/10] Verifying deployment...");
+    metrics.phase("verify");
     let result = verify::run_checks(&client, &config.namespace, &config).await?;
 
-    // 11. Report
-    println!("[9/9] Writing log...");
+    // 12. Report
+    println!("[10/10] Writing log...");
     result.write_log(&args.log)?;
 
     if result.all_passed() {
+        let smoke_test = match &args.smoke_test {
+            Some(url) => smoke_test(url, &config, ingress_url.as_deref()).await?,
+            None => Vec::new(),
+        };
         println!("\nInstallation complete!");
-        println!("   Access Baker Street at http://localhost:30080");
-        println!("   Auth token saved to ~/.bakerst/config.json");
+        match &args.auth_token_out {
+            Some(path) => println!("   Auth token saved to {}", path.display()),
+            None => println!("   Auth token stored in the cluster only (use --auth-token-out to save a copy)"),
+        }
+        print_access(
+            &config.namespace,
+            config.instance.as_deref(),
+            ingress_url.as_deref(),
+            config.ui_node_port,
+            telemetry.as_ref(),
+        );
+        let timing = output::Timing::new(metrics.elapsed(), metrics.phase_times(), ready_after);
+        print_timing(&timing);
+        let summary = output::Summary {
+            smoke_test,
+            ..summary(
+                &manifest,
+                &schema,
+                &config,
+                args.auth_token_out.as_deref(),
+                ingress_url.as_deref(),
+                true,
+                timing,
+            )
+        };
+        output::emit(&summary);
+        if let Some(app) = &mut tui_app {
+            app.access_url = Some(summary.url.clone());
+            app.auth_token = config.secrets.get("AUTH_TOKEN").cloned();
+            app.auth_token_path = args.auth_token_out.clone();
+            app.timing = Some(summary.timing.clone());
+            tui_driver::show_complete(args.theme, app)?;
+        }
+        if let Some(secs) = args.follow_logs {
+            println!("\nFollowing logs for {}s (Ctrl+C to stop)...", secs);
+            k8s::follow_logs(
+                &client,
+                &config.namespace,
+                &deploy::applied_deployments(&applied),
+                std::time::Duration::from_secs(secs),
+            )
+            .await?;
+        }
         Ok(())
     } else {
         println!("\nInstallation completed but verification failed.");
@@ -185,6 +677,794 @@ pub async fn run(_cli: &Cli, args: InstallArgs) -> Result<()> {
     }
 }
 
+/// `--smoke-test`: check the app answers at `url` (or where the install
+/// says it's reached), failing the install if it doesn't. A named instance
+/// without an Ingress or NodePort is only reachable by port-forward, so it
+/// needs a URL.
+async fn smoke_test(url: &str, config: &interview::InterviewResult, ingress_url: Option<&str>) -> Result<Vec<verify::Check>> {
+    println!("  Smoke test...");
+    let url = match (url, &config.instance, ingress_url) {
+        ("", Some(_), None) if config.ui_node_port.is_none() => {
+            println!("    skipped: a named instance needs --ingress-host, --ui-node-port or --smoke-test=URL");
+            return Ok(Vec::new());
+        }
+        ("", ..) => access_url(config.instance.as_deref(), ingress_url, config.ui_node_port),
+        (url, ..) => url.to_string(),
+    };
+    let token = config.secrets.get("AUTH_TOKEN").map(String::as_str).unwrap_or_default();
+    let checks = verify::smoke_test(&url, token).await;
+    for check in &checks {
+        let mark = if check.passed { "\u{2713}" } else { "\u{2717}" };
+        println!("    {} {}", mark, check.message);
+    }
+    if checks.iter().any(|c| !c.passed) {
+        bail!("Smoke test failed: the pods are ready but the app at {} doesn't respond as expected", url);
+    }
+    Ok(checks)
+}
+
+/// Handy follow-up commands, filled in for this install (and instance) so
+/// they can be copy-pasted as-is.
+pub fn next_steps(namespace: &str, instance: Option<&str>) -> Vec<(&'static str, String)> {
+    let pods = match instance {
+        Some(instance) => format!(" -l {}", instance::selector(Some(instance))),
+        None => String::new(),
+    };
+    let flag = instance.map(|i| format!(" --instance {}", i)).unwrap_or_default();
+    vec![
+        ("Pods", format!("kubectl get pods -n {}{}", namespace, pods)),
+        (
+            "Brain logs",
+            format!(
+                "kubectl logs -n {} -l app=brain{} --tail=100 -f",
+                namespace,
+                instance.map(|i| format!(",{}", instance::selector(Some(i)))).unwrap_or_default()
+            ),
+        ),
+        (
+            "Port-forward UI",
+            format!("kubectl port-forward -n {} svc/{} 8080:8080", namespace, instance::prefixed(instance, "ui")),
+        ),
+        ("Status", format!("bakerst-install --namespace {} status{}", namespace, flag)),
+        ("Uninstall", format!("bakerst-install --namespace {} uninstall{}", namespace, flag)),
+    ]
+}
+
+/// The UI's address: the `--ingress-host`'s (see [`deploy::ingress_url`]),
+/// the UI's NodePort, or the port-forward for a named instance without a
+/// `--ui-node-port`.
+fn access_url(instance: Option<&str>, ingress_url: Option<&str>, ui_node_port: Option<u16>) -> String {
+    match (ingress_url, instance, ui_node_port) {
+        (Some(url), ..) => url.to_string(),
+        (None, None, port) => format!("http://localhost:{}", port.unwrap_or(deploy::DEFAULT_UI_NODE_PORT)),
+        (None, Some(_), Some(port)) => format!("http://localhost:{}", port),
+        (None, Some(_), None) => "http://localhost:8080".into(),
+    }
+}
+
+/// The final `--output json` object for a successful install.
+fn summary(
+    manifest: &Manifest,
+    schema: &ConfigSchema,
+    config: &interview::InterviewResult,
+    token_file: Option<&std::path::Path>,
+    ingress_url: Option<&str>,
+    health_verified: bool,
+    timing: output::Timing,
+) -> output::Summary {
+    let routes = deploy::route_secrets(schema, config);
+    output::Summary {
+        result: "ok",
+        version: manifest.version.clone(),
+        namespace: config.namespace.clone(),
+        agent_name: config.agent_name.clone(),
+        url: access_url(config.instance.as_deref(), ingress_url, config.ui_node_port),
+        auth_token: output::TokenLocation {
+            file: token_file.map(std::path::PathBuf::from),
+            secret: deploy::secret_key_targets(&routes)
+                .remove("AUTH_TOKEN")
+                .and_then(|targets| targets.into_iter().next()),
+        },
+        health_verified,
+        timing,
+        smoke_test: Vec::new(),
+    }
+}
+
+/// Where the time went: each phase, then how long each deployment took to
+/// become ready, so a slow component stands out.
+fn print_timing(timing: &output::Timing) {
+    println!("\nTiming ({:.1}s total):", timing.total.as_secs_f64());
+    for phase in &timing.phases {
+        println!("   {:<16} {:>7.1}s", phase.name, phase.seconds.as_secs_f64());
+    }
+    if !timing.ready_after.is_empty() {
+        println!("   Ready after:");
+        for deployment in &timing.ready_after {
+            println!("     {:<14} {:>7.1}s", deployment.name, deployment.seconds.as_secs_f64());
+        }
+    }
+}
+
+/// Where to reach the UI, then the follow-up commands. A named instance
+/// has no fixed NodePort, so without an Ingress or a `--ui-node-port` it's
+/// reached through the port-forward.
+fn print_access(
+    namespace: &str,
+    instance: Option<&str>,
+    ingress_url: Option<&str>,
+    ui_node_port: Option<u16>,
+    telemetry: Option<&telemetry::Telemetry>,
+) {
+    let url = access_url(instance, ingress_url, ui_node_port);
+    match (ingress_url, instance, ui_node_port) {
+        (None, Some(_), None) => println!("   Access Baker Street with the port-forward below, at {}", url),
+        _ => println!("   Access Baker Street at {}", url),
+    }
+    println!("\nUseful commands:");
+    for (label, cmd) in next_steps(namespace, instance) {
+        println!("   {:<16} {}", label, cmd);
+    }
+    if let Some(t) = telemetry {
+        println!("   {:<16} kubectl port-forward -n {} svc/grafana 3000:3000", "Grafana", t.namespace);
+        println!(
+            "   {:<16} kubectl get secret -n {} {} -o jsonpath='{{.data.admin-password}}' | base64 -d",
+            "Grafana login",
+            t.namespace,
+            telemetry::GRAFANA_ADMIN_SECRET
+        );
+    }
+}
+
+/// Preflight: verify the cluster is reachable, select a K8s context, and
+/// check that an existing `namespace` is the installer's own.
+/// Interactive runs can fix a failed critical check (e.g. start Docker
+/// Desktop) and re-run the checks instead of starting over. Returns whether
+/// Docker is available.
+async fn preflight(cli: &Cli, args: &InstallArgs, namespace: &str) -> Result<bool> {
+    println!("[1/10] Preflight checks...");
+    loop {
+        let checks = async {
+            run_preflight_checks(cli, args).await?;
+            let client = k8s::connect(cli.kube_context.as_deref()).await?;
+            check_namespace_owner(&client, namespace, cli.instance.as_deref(), args.adopt_namespace).await
+        };
+        match checks.await {
+            Ok(()) => break,
+            Err(e) if !args.non_interactive => {
+                println!("  \u{2717} {:#}", e);
+                if !prompt_retry()? {
+                    return Err(e);
+                }
+                println!("  Re-running preflight checks...");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Non-critical: without Docker we skip the pre-pull and let the cluster pull
+    let docker = images::docker_available(&images::Docker).await;
+    if docker {
+        println!("  Docker: running");
+    } else {
+        println!("  Docker: not available (images will be pulled by the cluster)");
+    }
+
+    // Non-critical: the PVC templates rely on a default storage class
+    // With --data-dir no claims are made, so no class is needed
+    if args.storage_class.is_none() && args.data_dir.is_none() {
+        check_default_storage_class(cli).await;
+    }
+    Ok(docker)
+}
+
+/// Write the auth token to `path`, readable only by the owner. Fails if the
+/// file exists unless `force`.
+pub fn write_auth_token(path: &std::path::Path, token: &str, force: bool) -> Result<()> {
+    let contents = format!("{}\n", token);
+    let written = if force {
+        // Tightened before the token goes into an existing file
+        crate::instance::write_private(path, &contents)
+    } else {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(path).and_then(|mut file| std::io::Write::write_all(&mut file, contents.as_bytes()))
+    };
+    written.with_context(|| format!("write auth token to {}", path.display()))
+}
+
+/// Fail if `namespace` exists but bakerst-install didn't create it, unless
+/// `--adopt-namespace` was passed. An unlabeled namespace holding an earlier
+/// install (or the one this instance's saved config names) is the
+/// installer's from before the label; applying adds it.
+async fn check_namespace_owner(
+    client: &kube::Client,
+    namespace: &str,
+    instance: Option<&str>,
+    adopt: bool,
+) -> Result<()> {
+    let labels = match k8s::namespace_labels(client, namespace).await {
+        Ok(labels) => labels,
+        Err(e) if k8s::is_forbidden(&e) => {
+            println!("  Namespace ownership: not allowed to read namespaces (check skipped)");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let Err(e) = k8s::check_namespace_owner(namespace, labels.as_ref()) else {
+        return Ok(());
+    };
+    let installed = match saved_namespace(instance).as_deref() == Some(namespace) {
+        true => true,
+        false => match k8s::has_installer_resources(client, namespace).await {
+            Ok(found) => found,
+            Err(e) if k8s::is_forbidden(&e) => false,
+            Err(e) => return Err(e),
+        },
+    };
+    if installed {
+        println!("  Namespace '{}' holds an earlier install; it will be labelled as the installer's", namespace);
+        return Ok(());
+    }
+    match adopt {
+        true => {
+            println!("  Namespace '{}' was not created by bakerst-install; adopting it (--adopt-namespace)", namespace);
+            Ok(())
+        }
+        false => Err(e),
+    }
+}
+
+/// The namespace in the instance's saved config, if there is one.
+fn saved_namespace(instance: Option<&str>) -> Option<String> {
+    let content = std::fs::read_to_string(instance::config_path(instance).ok()?).ok()?;
+    let saved: serde_json::Value = serde_json::from_str(&content).ok()?;
+    saved["namespace"].as_str().map(String::from)
+}
+
+/// What a rollback must leave alone, from before anything is applied.
+struct Preexisting {
+    namespace_existed: bool,
+    /// The instance's managed resources, as (kind, name)
+    resources: std::collections::BTreeSet<(String, String)>,
+    /// Kinds the kubeconfig may not list, so what existed is unknown
+    unlisted: std::collections::BTreeSet<String>,
+}
+
+/// Whether the namespace exists yet, and the instance's managed resources
+/// in it, before anything is applied.
+async fn preexisting_resources(client: &kube::Client, namespace: &str, instance: Option<&str>) -> Result<Preexisting> {
+    if !k8s::namespace_exists(client, namespace).await? {
+        return Ok(Preexisting { namespace_existed: false, resources: Default::default(), unlisted: Default::default() });
+    }
+    let (live, denied) = k8s::list_managed_resources(client, namespace, instance).await?;
+    let unlisted = k8s::MANAGED_KINDS
+        .iter()
+        .filter(|(.., plural, _)| denied.iter().any(|d| d == plural))
+        .map(|(kind, ..)| kind.to_string())
+        .collect();
+    Ok(Preexisting {
+        namespace_existed: true,
+        resources: live.into_iter().map(|r| (r.kind, r.name)).collect(),
+        unlisted,
+    })
+}
+
+/// `--resource-profile`, else the release's default. An unknown default
+/// falls back to standard (the templates as written).
+fn resource_profile(flag: Option<deploy::ResourceProfile>, schema: &ConfigSchema) -> deploy::ResourceProfile {
+    flag.or_else(|| {
+        let name = schema.defaults.resource_profile.as_deref()?;
+        let profile = deploy::ResourceProfile::from_name(name);
+        if profile.is_none() {
+            println!("  WARNING: unknown default resource profile '{}', using standard", name);
+        }
+        profile
+    })
+    .unwrap_or_default()
+}
+
+/// Warn when the cluster has no default storage class: the install's PVCs
+/// would stay Pending and their pods would never start.
+async fn check_default_storage_class(cli: &Cli) {
+    let found = match k8s::connect(cli.kube_context.as_deref()).await {
+        Ok(client) => k8s::default_storage_class(&client).await,
+        Err(e) => Err(e),
+    };
+    match found {
+        Ok(Some(name)) => println!("  Storage class: {} (default)", name),
+        Ok(None) => {
+            println!("  Warning: the cluster has no default storage class, so PersistentVolumeClaims will stay Pending.");
+            println!("           Install a storage provisioner, or pass --storage-class <NAME>.");
+        }
+        Err(e) if k8s::is_forbidden(&e) => {
+            println!("  Storage class: not allowed to list storage classes (check skipped)");
+        }
+        Err(e) => println!("  Storage class: could not check ({:#})", e),
+    }
+}
+
+/// Ask whether to re-run a failed check or deploy step. Returns true on 'r'.
+fn prompt_retry() -> Result<bool> {
+    use std::io::Write;
+    print!("  Fix the problem, then press 'r' + Enter to retry (Enter to abort): ");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("r"))
+}
+
+/// Critical preflight checks; any error here blocks the install.
+async fn run_preflight_checks(cli: &Cli, args: &InstallArgs) -> Result<()> {
+    let kube_context = cli.kube_context.as_deref();
+    if let Some(name) = kube_context {
+        k8s::require_context(name)?;
+    }
+    let server_version = match k8s::check_cluster(kube_context).await {
+        Ok(version) => version,
+        Err(e) if k8s::is_expired_credentials(&e) => {
+            return Err(e.context(k8s::EXPIRED_CREDENTIALS_HINT));
+        }
+        Err(e) => {
+            return Err(e.context(
+                "Kubernetes cluster not reachable. Ensure kubectl is installed and a cluster is running.",
+            ));
+        }
+    };
+    println!("  K8s server version: {}", server_version);
+    if let Some(name) = k8s::context_name(kube_context) {
+        println!("  Connected via context: {}", name);
+    }
+
+    // An explicit --kube-context is used as-is; never switch the user's
+    // current context behind their back
+    if kube_context.is_some() {
+        return Ok(());
+    }
+
+    let contexts = k8s::detect_contexts().await?;
+    if contexts.is_empty() {
+        bail!("No Kubernetes contexts found. Install Docker Desktop or OrbStack with Kubernetes enabled.");
+    }
+    if contexts.len() == 1 {
+        println!(
+            "  Using K8s context: {} ({})",
+            contexts[0].name, contexts[0].cluster_type
+        );
+    } else {
+        println!("  Available K8s contexts:");
+        for (i, ctx) in contexts.iter().enumerate() {
+            println!("    {}) {} ({})", i + 1, ctx.name, ctx.cluster_type);
+        }
+        // For non-interactive, use first context; for interactive, prompt
+        if !args.non_interactive {
+            // TODO: Prompt user for context selection (Task 15 TUI)
+            println!("  Using first context: {}", contexts[0].name);
+        }
+        k8s::use_context(&contexts[0].name).await?;
+    }
+    Ok(())
+}
+
+/// What pre-pulling left in the local Docker cache.
+struct Prepulled {
+    /// Every manifest image is there.
+    all: bool,
+    /// Images the cluster will pull itself, their digests unchecked here
+    /// (all of them without Docker), as pulled through any mirror.
+    unchecked: Vec<String>,
+}
+
+/// Pull every manifest image with live progress. Failed optional images are
+/// reported but don't block; a failed required image aborts the install, as
+/// does a pulled image whose digest isn't the one the manifest pins (unless
+/// `skip_digest_check`).
+async fn pull_images(
+    manifest: &Manifest,
+    mirror: Option<&str>,
+    metrics: &mut InstallMetrics,
+    skip_digest_check: bool,
+    concurrency: usize,
+    bundle: Option<&std::path::Path>,
+) -> Result<Prepulled> {
+    let docker = std::sync::Arc::new(images::Docker);
+    if !images::docker_available(docker.as_ref()).await {
+        if let Some(bundle) = bundle {
+            bail!("Docker is needed to load the image bundle {}", bundle.display());
+        }
+        println!("  Docker not available — skipping pre-pull (the cluster will pull images)");
+        if manifest.images.iter().any(|i| i.digest.is_some()) && !skip_digest_check {
+            println!("  Workloads are pinned to the manifest's image digests instead");
+        }
+        let unchecked = manifest.images.iter().map(|i| i.pull_reference(mirror)).collect();
+        return Ok(Prepulled { all: false, unchecked });
+    }
+
+    let refs: Vec<String> = manifest.images.iter().map(|i| i.pull_reference(mirror)).collect();
+    let mut progress = PullProgress::new(refs.len());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut bundle_ids = BTreeMap::new();
+    let pulls = match bundle {
+        Some(bundle) => {
+            bundle_ids = images::bundle_image_ids(bundle).map_err(anyhow::Error::msg)?;
+            let loaded = images::load_bundle(docker.as_ref(), bundle).await.map_err(anyhow::Error::msg)?;
+            println!("  Loaded {} image(s) from the bundle", loaded.len());
+            for image in loaded.iter().filter(|i| !refs.contains(i)) {
+                println!("  Warning: {} is in the bundle but not in the manifest", image);
+            }
+            tokio::spawn(images::inspect_all(docker, refs, tx))
+        }
+        None => tokio::spawn(images::pull_all(docker, refs, concurrency, tx)),
+    };
+
+    // A bar per image in flight on a terminal; plain lines otherwise
+    let bars = std::io::IsTerminal::is_terminal(&std::io::stdout()).then(indicatif::MultiProgress::new);
+    let mut in_flight: BTreeMap<usize, indicatif::ProgressBar> = BTreeMap::new();
+    let say = |line: String| match &bars {
+        Some(bars) => {
+            let _ = bars.println(line);
+        }
+        None => println!("{}", line),
+    };
+    while let Some(event) = rx.recv().await {
+        progress.record(&event);
+        if !matches!(event, PullEvent::Started { .. } | PullEvent::Retrying { .. } | PullEvent::Progress { .. }) {
+            output::emit(&output::Event::Pull(&event));
+        }
+        match &event {
+            PullEvent::Completed { index, .. } | PullEvent::Failed { index, .. } | PullEvent::AuthRequired { index, .. } => {
+                if let Some(bar) = in_flight.remove(index) {
+                    bar.finish_and_clear();
+                }
+            }
+            _ => {}
+        }
+        match &event {
+            PullEvent::Completed { image, elapsed, .. } => {
+                say(format!("  \u{2713} {} ({:.1}s) [{}]", image, elapsed.as_secs_f64(), progress));
+            }
+            PullEvent::Failed { image, error, .. } => {
+                say(format!("  \u{2717} {}: {} [{}]", image, error, progress));
+            }
+            PullEvent::AuthRequired { image, registry, .. } => {
+                let hint = images::PullError::AuthRequired { registry: registry.clone() };
+                say(format!("  \u{2717} {}: {} [{}]", image, hint, progress));
+            }
+            PullEvent::Started { index, image } => {
+                if let Some(bars) = &bars {
+                    let bar = bars.add(indicatif::ProgressBar::new(100));
+                    bar.set_style(
+                        indicatif::ProgressStyle::with_template("  {msg} [{bar:30}] {pos:>3}%")
+                            .expect("valid template")
+                            .progress_chars("=> "),
+                    );
+                    bar.set_message(image.clone());
+                    in_flight.insert(*index, bar);
+                }
+            }
+            PullEvent::Progress { index, percent } => {
+                if let Some(bar) = in_flight.get(index) {
+                    bar.set_position(*percent as u64);
+                }
+            }
+            PullEvent::Retrying { .. } => {}
+        }
+    }
+    pulls.await?;
+    metrics.images_total = progress.total;
+    metrics.images_failed = progress.failed.len();
+
+    if skip_digest_check {
+        println!("  Image digests not verified (--skip-digest-check)");
+    } else {
+        verify_digests(manifest, mirror, &progress.failed, &bundle_ids).await?;
+    }
+
+    if progress.all_succeeded() {
+        println!("  All images pulled ({})", progress);
+        return Ok(Prepulled { all: true, unchecked: Vec::new() });
+    }
+
+    let failed_required: Vec<&ManifestImage> = manifest
+        .required_images()
+        .filter(|i| progress.failed.contains(&i.pull_reference(mirror)))
+        .collect();
+    if let Some(first) = failed_required.first() {
+        let references: Vec<String> = failed_required.iter().map(|i| i.pull_reference(mirror)).collect();
+        return Err(output::ComponentError::new(
+            &first.name,
+            format!("Failed to pull required images: {}", references.join(", ")),
+        )
+        .into());
+    }
+    println!("  Pulled {} — optional images will be pulled by the cluster if needed", progress);
+    Ok(Prepulled { all: false, unchecked: progress.failed })
+}
+
+/// `--print-images`: every manifest image with whether it is required and
+/// already in the local Docker cache.
+async fn print_images(manifest: &Manifest, mirror: Option<&str>) -> Result<()> {
+    let docker = images::docker_available(&images::Docker).await;
+    let width = manifest.images.iter().map(|i| i.pull_reference(mirror).len()).max().unwrap_or(0).max(5);
+    println!("  {:<width$}  {:<8}  LOCAL", "IMAGE", "NEEDED", width = width);
+    for image in &manifest.images {
+        let reference = image.pull_reference(mirror);
+        let local = if !docker {
+            "unknown"
+        } else if images::image_present(&images::Docker, &reference).await {
+            "yes"
+        } else {
+            "no"
+        };
+        let needed = if image.required { "required" } else { "optional" };
+        println!("  {:<width$}  {:<8}  {}", reference, needed, local, width = width);
+    }
+    if !docker {
+        println!("\n  Docker not available \u{2014} can't tell which images are already pulled");
+    }
+    Ok(())
+}
+
+/// Compare each pulled image against the digest the manifest pins. An image
+/// loaded from a bundle has no registry digest, so it's checked against the
+/// image ID its bundle records (`bundle_ids`) instead. Images that failed to
+/// pull, or that have neither, are skipped.
+async fn verify_digests(
+    manifest: &Manifest,
+    mirror: Option<&str>,
+    failed: &[String],
+    bundle_ids: &BTreeMap<String, String>,
+) -> Result<()> {
+    let mut mismatched = Vec::new();
+    for image in &manifest.images {
+        let reference = image.pull_reference(mirror);
+        if failed.contains(&reference) {
+            continue;
+        }
+        let verified = match (bundle_ids.get(&reference), &image.digest) {
+            (Some(id), _) => images::verify_image_id(&images::Docker, &reference, id).await,
+            (None, Some(expected)) => images::verify_digest(&images::Docker, &reference, expected).await,
+            (None, None) => continue,
+        };
+        if let Err(e) = verified {
+            println!("  \u{2717} {}: {}", reference, e);
+            mismatched.push(reference);
+        }
+    }
+    if !mismatched.is_empty() {
+        bail!(
+            "Digest verification failed for {}. Refusing to deploy; pass --skip-digest-check to override.",
+            mismatched.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The Namespace to apply. Uses the template's namespace.yaml (if bundled)
+/// so its labels apply, plus any --namespace-annotations.
+fn namespace_object(
+    args: &InstallArgs,
+    template_dir: &std::path::Path,
+    name: &str,
+    manifest: &Manifest,
+) -> Result<serde_json::Value> {
+    let ns_template = std::fs::read_to_string(template_dir.join("k8s/namespace.yaml")).ok();
+    let mut namespace = k8s::render_namespace(ns_template.as_deref(), name, Some(&manifest.version))?;
+    k8s::label_namespace(&mut namespace, &args.namespace_labels);
+    k8s::annotate_namespace(&mut namespace, &args.namespace_annotations);
+    Ok(namespace)
+}
+
+/// In --step mode, hold at a phase boundary until the user presses Enter.
+fn step_boundary(step: bool, next: &str) -> Result<()> {
+    if !step {
+        return Ok(());
+    }
+    use std::io::Write;
+    print!("  -- Next: {}. Press Enter to continue... ", next);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(())
+}
+
+/// The version installed in `namespace`, if any. A different one than
+/// the manifest's is pointed out, and an interactive run must confirm
+/// replacing it.
+async fn detect_installed(
+    cli: &Cli,
+    namespace: &str,
+    manifest: &Manifest,
+    non_interactive: bool,
+) -> Result<Option<String>> {
+    let found = async {
+        let client = k8s::connect(cli.kube_context.as_deref()).await?;
+        let live = k8s::deployment_images(&client, namespace, cli.instance.as_deref()).await?;
+        // Shared by every instance in the namespace, so only the default one's
+        let label = match cli.instance {
+            Some(_) => None,
+            None => k8s::namespace_labels(&client, namespace)
+                .await?
+                .and_then(|mut labels| labels.remove("app.kubernetes.io/version")),
+        };
+        anyhow::Ok(deploy::installed_version(manifest, &live, label.as_deref()))
+    };
+    let installed = match found.await {
+        Ok(Some(installed)) => installed,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            println!("  Installed version: could not check ({:#})", e);
+            return Ok(None);
+        }
+    };
+    let version = manifest.version.trim_start_matches('v');
+    if installed == version {
+        println!("  Installed: v{} in '{}' (reinstalling the same version)", installed, namespace);
+        return Ok(Some(installed));
+    }
+    println!(
+        "  \u{26a0} v{} is already installed in '{}'; this installer deploys v{}",
+        installed, namespace, version
+    );
+    if !non_interactive && !confirm(&format!("  Replace v{} with v{}?", installed, version))? {
+        bail!("Install cancelled; v{} is still installed", installed);
+    }
+    Ok(Some(installed))
+}
+
+/// Ask a yes/no question, defaulting to no.
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Show how a re-install changes each component's image, and ask before
+/// going ahead with a downgrade unless `confirmed` (--non-interactive runs
+/// only warn, and an accepted version change isn't asked about twice).
+async fn check_upgrade(
+    client: &kube::Client,
+    manifest: &Manifest,
+    config: &interview::InterviewResult,
+    confirmed: bool,
+) -> Result<()> {
+    let live = match k8s::deployment_images(client, &config.namespace, config.instance.as_deref()).await {
+        Ok(live) => live,
+        Err(e) if k8s::is_forbidden(&e) => {
+            println!("  Existing install: not allowed to list Deployments (check skipped)");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let Some(plan) = deploy::upgrade_plan(manifest, &live) else {
+        println!("  Existing install: none (new install)");
+        return Ok(());
+    };
+    println!("  Upgrading the existing install:");
+    let width = plan.iter().map(|c| c.component.len()).max().unwrap_or(0);
+    let mut downgrades = Vec::new();
+    for c in &plan {
+        let change = match &c.change {
+            deploy::ImageChange::New => format!("new -> {}", c.to),
+            deploy::ImageChange::Unchanged => format!("{} (unchanged)", c.to),
+            deploy::ImageChange::Upgrade { from } | deploy::ImageChange::Changed { from } => {
+                format!("{} -> {}", from, c.to)
+            }
+            deploy::ImageChange::Downgrade { from } => {
+                downgrades.push(c.component.as_str());
+                format!("{} -> {} (downgrade)", from, c.to)
+            }
+        };
+        println!("    {:<width$}  {}", c.component, change, width = width);
+    }
+    if downgrades.is_empty() {
+        return Ok(());
+    }
+    if confirmed {
+        println!("  Warning: this downgrades {}", downgrades.join(", "));
+        return Ok(());
+    }
+    if !confirm(&format!("  This downgrades {}. Continue?", downgrades.join(", ")))? {
+        bail!("Install cancelled rather than downgrade {}", downgrades.join(", "));
+    }
+    Ok(())
+}
+
+/// Warn (without failing) if the cluster likely can't schedule the
+/// install: its pods would otherwise sit Pending until the health wait
+/// times out.
+async fn check_capacity(client: &kube::Client, resources: &[serde_json::Value], namespace: &str) {
+    let needed = deploy::requested_resources(resources);
+    let allocatable = match k8s::node_allocatable(client).await {
+        Ok(allocatable) => allocatable,
+        Err(e) if k8s::is_forbidden(&e) => {
+            println!("  Capacity: not allowed to list nodes (check skipped)");
+            return;
+        }
+        Err(e) => {
+            println!("  Capacity: could not check ({:#})", e);
+            return;
+        }
+    };
+    // Without access to other namespaces' pods, compare against the whole cluster
+    let in_use = k8s::requested_outside(client, namespace).await.unwrap_or_default();
+    let free = k8s::ResourceUsage {
+        cpu_millicores: allocatable.cpu_millicores.saturating_sub(in_use.cpu_millicores),
+        memory_bytes: allocatable.memory_bytes.saturating_sub(in_use.memory_bytes),
+    };
+    let shortfall = deploy::capacity_shortfall(needed, free);
+    if shortfall.is_empty() {
+        println!(
+            "  Capacity: {}m CPU and {}Mi memory requested; the cluster has room",
+            needed.cpu_millicores,
+            needed.memory_bytes / (1024 * 1024)
+        );
+        return;
+    }
+    for line in &shortfall {
+        println!("  \u{26a0} Capacity: {}", line);
+    }
+    println!("    Pods may stay Pending. Free up capacity, add a node, or use --resource-profile small.");
+}
+
+/// Make sure no other Service already holds a NodePort the template needs.
+async fn check_node_ports(
+    client: &kube::Client,
+    resources: &[serde_json::Value],
+    namespace: &str,
+) -> Result<()> {
+    let requested = deploy::requested_node_ports(resources);
+    if requested.is_empty() {
+        return Ok(());
+    }
+    let in_use = match k8s::node_ports_in_use(client).await {
+        Ok(ports) => ports,
+        Err(e) if k8s::is_forbidden(&e) => {
+            println!("  NodePort check skipped: no permission to list Services cluster-wide");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let conflicts = deploy::node_port_conflicts(&requested, &in_use, namespace);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    for c in &conflicts {
+        println!(
+            "  \u{2717} NodePort {} (service '{}') is already allocated by {}",
+            c.port, c.service, c.held_by
+        );
+    }
+    if let Some(free) = deploy::suggest_free_node_port(&in_use, &requested) {
+        println!("  NodePort {} is free", free);
+    }
+    bail!(
+        "NodePort conflict: {}",
+        conflicts
+            .iter()
+            .map(|c| format!("{} held by {}", c.port, c.held_by))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+}
+
+/// Expected OS filenames that `files` doesn't provide.
+fn missing_os_files(files: &BTreeMap<String, String>, expected: &[String]) -> Vec<String> {
+    expected
+        .iter()
+        .filter(|name| !files.contains_key(*name))
+        .cloned()
+        .collect()
+}
+
 fn load_os_files(dir: &std::path::Path) -> Result<BTreeMap<String, String>> {
     let mut files = BTreeMap::new();
     for entry in std::fs::read_dir(dir)? {
@@ -197,3 +1477,66 @@ fn load_os_files(dir: &std::path::Path) -> Result<BTreeMap<String, String>> {
     }
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_os_dir_must_cover_release_files() {
+        let files = BTreeMap::from([("BRAIN.md".to_string(), "# Brain".to_string())]);
+        let expected = vec!["BRAIN.md".to_string(), "SOUL.md".to_string()];
+        assert_eq!(missing_os_files(&files, &expected), vec!["SOUL.md"]);
+        assert!(missing_os_files(&files, &expected[..1]).is_empty());
+    }
+
+    #[test]
+    fn auth_token_file_is_private_and_not_overwritten_without_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token");
+        write_auth_token(&path, "abc123", false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "abc123\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        assert!(write_auth_token(&path, "def456", false).is_err());
+        write_auth_token(&path, "def456", true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "def456\n");
+
+        // An existing world-readable file is tightened by --force
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            write_auth_token(&path, "ghi789", true).unwrap();
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn next_steps_use_the_install_namespace() {
+        let steps = next_steps("team-a", None);
+        assert!(steps.iter().any(|(_, c)| c == "kubectl get pods -n team-a"));
+        assert!(steps
+            .iter()
+            .filter(|(_, c)| c.starts_with("kubectl"))
+            .all(|(_, c)| c.contains("-n team-a")));
+        assert!(steps
+            .iter()
+            .filter(|(_, c)| c.starts_with("bakerst-install"))
+            .all(|(_, c)| c.contains("--namespace team-a")));
+        let steps = next_steps("team-a", Some("staging"));
+        assert!(steps.contains(&("Status", "bakerst-install --namespace team-a status --instance staging".into())));
+    }
+
+    #[test]
+    fn access_url_follows_the_ui_node_port() {
+        assert_eq!(access_url(None, None, None), "http://localhost:30080");
+        assert_eq!(access_url(None, None, Some(31080)), "http://localhost:31080");
+        assert_eq!(access_url(Some("staging"), None, None), "http://localhost:8080");
+        assert_eq!(access_url(Some("staging"), None, Some(31081)), "http://localhost:31081");
+        assert_eq!(access_url(None, Some("https://bakerst.example.com"), Some(31080)), "https://bakerst.example.com");
+    }
+}
@@ -0,0 +1,83 @@
+//! Resources command — lists everything a previous install created.
+//!
+//! Enumerates every resource carrying the installer's ownership label across
+//! all kinds the installer applies, for audits and cleanup verification.
+//!
+//! Read-only. Needs `list` on each managed kind in the namespace and on
+//! namespaces; kinds it may not list are skipped with a note.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::cli::{Cli, ResourcesArgs};
+use crate::{instance, k8s};
+
+#[derive(Serialize)]
+struct ResourceInfo {
+    kind: String,
+    name: String,
+    age: String,
+    status: String,
+}
+
+/// Entry point for the `resources` subcommand.
+pub async fn run(cli: &Cli, args: ResourcesArgs) -> Result<()> {
+    let config_path = instance::config_path(cli.instance.as_deref())?;
+
+    let saved_config: Option<serde_json::Value> = if config_path.exists() {
+        let content = std::fs::read_to_string(&config_path)?;
+        Some(serde_json::from_str(&content)?)
+    } else {
+        None
+    };
+
+    let namespace = saved_config
+        .as_ref()
+        .and_then(|c| c["namespace"].as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| cli.namespace.clone());
+
+    let client = k8s::connect(cli.kube_context.as_deref())
+        .await
+        .context("Cannot connect to Kubernetes cluster")?;
+
+    let (resources, denied) = k8s::list_managed_resources(&client, &namespace, cli.instance.as_deref())
+        .await
+        .context("Failed to list managed resources")?;
+
+    let now = std::time::SystemTime::now();
+    let rows: Vec<ResourceInfo> = resources
+        .into_iter()
+        .map(|r| ResourceInfo {
+            age: r
+                .created
+                .and_then(|t| now.duration_since(t).ok())
+                .map(k8s::format_age)
+                .unwrap_or_else(|| "-".into()),
+            kind: r.kind,
+            name: r.name,
+            status: r.status,
+        })
+        .collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    for plural in &denied {
+        println!("  Note: no permission to list {} \u{2014} skipped", plural);
+    }
+
+    println!("Resources managed by bakerst-install in '{}':\n", namespace);
+    if rows.is_empty() {
+        println!("  (none found)");
+        return Ok(());
+    }
+    println!("  {:<22} {:<32} {:<6} STATUS", "KIND", "NAME", "AGE");
+    for r in &rows {
+        println!("  {:<22} {:<32} {:<6} {}", r.kind, r.name, r.age, r.status);
+    }
+    println!("\n  {} resource(s)", rows.len());
+    Ok(())
+}
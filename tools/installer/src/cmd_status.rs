@@ -2,12 +2,22 @@
 //!
 //! Reads saved config from ~/.bakerst/config.json, queries K8s for pod/deployment
 //! status, and prints a summary. Supports --json and --watch modes.
+//!
+//! Read-only, so a viewer-scoped kubeconfig is enough. RBAC needed in the
+//! install namespace:
+//! - `list deployments.apps` (required)
+//! - `list secrets` (optional; only key names are shown, and the section is
+//!   hidden without it, as under the built-in `view` role)
+//! - `list pods.metrics.k8s.io` (optional; usage columns)
+//! - `get services` (optional; the UI's URL from its NodePort)
+//! - `list pods` and `list events` (`--pods` only; events are optional)
 
 use anyhow::{Context, Result};
 use serde::Serialize;
+use std::collections::BTreeMap;
 
 use crate::cli::{Cli, StatusArgs};
-use crate::k8s;
+use crate::{instance, k8s};
 
 #[derive(Serialize)]
 struct StatusOutput {
@@ -15,8 +25,14 @@ struct StatusOutput {
     version: Option<String>,
     enabled_features: Vec<String>,
     agent_name: Option<String>,
+    /// Where the UI is reached, from its Service's NodePort
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
     deployments: Vec<DeploymentInfo>,
     secrets: Vec<SecretInfo>,
+    /// True when the kubeconfig may not list secrets
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    secrets_hidden: bool,
 }
 
 #[derive(Serialize)]
@@ -25,6 +41,31 @@ struct DeploymentInfo {
     ready: i32,
     desired: i32,
     image: String,
+    /// Omitted when metrics-server isn't installed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_millicores: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_bytes: Option<u64>,
+    /// With `--pods` only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pods: Option<Vec<PodInfo>>,
+}
+
+#[derive(Serialize)]
+struct PodInfo {
+    name: String,
+    phase: String,
+    ready: bool,
+    restarts: i32,
+    /// Latest Warning event, "Reason: message"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<String>,
+    /// Omitted when metrics-server isn't installed or hasn't sampled the
+    /// pod yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_millicores: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_bytes: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -35,26 +76,77 @@ struct SecretInfo {
 
 /// Entry point for the `status` subcommand.
 pub async fn run(cli: &Cli, args: StatusArgs) -> Result<()> {
+    let color = args.color.enabled();
     if args.watch {
+        let mut previous: Option<Vec<DeploymentInfo>> = None;
         loop {
-            // Clear screen for watch mode
-            print!("\x1B[2J\x1B[1;1H");
-            if let Err(e) = print_status(cli, &args).await {
-                eprintln!("Error: {}", e);
+            // JSON is a stream of one object per tick; the table redraws
+            if !args.json {
+                print!("\x1B[2J\x1B[1;1H");
+            }
+            match collect_status(cli, args.pods).await {
+                Ok(output) => {
+                    let changed = previous
+                        .as_ref()
+                        .map(|prev| changed_deployments(prev, &output.deployments))
+                        .unwrap_or_default();
+                    if args.json {
+                        println!("{}", serde_json::to_string(&output)?);
+                    } else {
+                        print_human(&output, &changed, color);
+                    }
+                    previous = Some(output.deployments);
+                }
+                Err(e) => eprintln!("Error: {}", e),
             }
-            println!("\n(watching — refresh every 5s, Ctrl+C to stop)");
-            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            if !args.json {
+                println!("\n(watching — refresh every {}s, Ctrl+C to stop)", args.interval);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(args.interval)).await;
         }
     } else {
-        print_status(cli, &args).await
+        let output = collect_status(cli, args.pods).await?;
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            print_human(&output, &BTreeMap::new(), color);
+        }
+        Ok(())
     }
 }
 
-async fn print_status(cli: &Cli, args: &StatusArgs) -> Result<()> {
+/// How a deployment changed since the previous watch tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    /// More replicas ready
+    Up,
+    /// Fewer replicas ready
+    Down,
+    /// New, or a different desired count or image
+    Other,
+}
+
+/// Deployments that are new or whose readiness or image differ from the
+/// previous watch tick, and which way their ready count went.
+fn changed_deployments(previous: &[DeploymentInfo], current: &[DeploymentInfo]) -> BTreeMap<String, Change> {
+    current
+        .iter()
+        .filter_map(|d| {
+            let change = match previous.iter().find(|p| p.name == d.name) {
+                Some(p) if p.ready < d.ready => Change::Up,
+                Some(p) if p.ready > d.ready => Change::Down,
+                Some(p) if p.desired == d.desired && p.image == d.image => return None,
+                _ => Change::Other,
+            };
+            Some((d.name.clone(), change))
+        })
+        .collect()
+}
+
+async fn collect_status(cli: &Cli, pods: bool) -> Result<StatusOutput> {
     // Load saved config (non-secret)
-    let config_path = dirs::home_dir()
-        .context("Cannot determine home directory")?
-        .join(".bakerst/config.json");
+    let instance = cli.instance.as_deref();
+    let config_path = instance::config_path(instance)?;
 
     let saved_config: Option<serde_json::Value> = if config_path.exists() {
         let content = std::fs::read_to_string(&config_path)?;
@@ -90,25 +182,75 @@ async fn print_status(cli: &Cli, args: &StatusArgs) -> Result<()> {
         .map(String::from);
 
     // Query K8s
-    let client = kube::Client::try_default()
+    let client = k8s::connect(cli.kube_context.as_deref())
         .await
         .context("Cannot connect to Kubernetes cluster")?;
 
-    let deploy_statuses = k8s::get_deployments_status(&client, &namespace)
+    let deploy_statuses = k8s::get_deployments_status(&client, &namespace, instance)
+        .await
+        .map_err(|e| k8s::explain_read_error(e, "list", "deployments", &namespace))?;
+
+    // Usage columns are best-effort: absent metrics-server just hides them
+    let pod_usage = k8s::get_pod_usage(&client, &namespace, Some(&instance::selector(instance)))
         .await
-        .context("Failed to list deployments")?;
+        .unwrap_or_default();
+    let usage = pod_usage.as_deref().map(k8s::usage_by_app);
 
-    let secrets_info = k8s::get_secrets_info(&client, &namespace)
+    // Best-effort too: the URL line is left out if the Service can't be read
+    let url = k8s::ui_node_port(&client, &namespace, instance)
         .await
-        .context("Failed to list secrets")?;
+        .ok()
+        .flatten()
+        .map(|port| format!("http://localhost:{}", port));
+
+    let mut pods_by_deployment = match pods {
+        true => k8s::get_pods_status(&client, &namespace, instance)
+            .await
+            .map_err(|e| k8s::explain_read_error(e, "list", "pods", &namespace))?,
+        false => Default::default(),
+    };
+
+    // Viewer roles can't list secrets; show everything else
+    let (secrets_info, secrets_hidden) = match k8s::get_secrets_info(&client, &namespace, instance).await {
+        Ok(info) => (info, false),
+        Err(e) if k8s::is_forbidden(&e) => (Vec::new(), true),
+        Err(e) => return Err(k8s::explain_read_error(e, "list", "secrets", &namespace)),
+    };
 
     let deployments: Vec<DeploymentInfo> = deploy_statuses
         .into_iter()
-        .map(|d| DeploymentInfo {
-            name: d.name,
-            ready: d.ready,
-            desired: d.desired,
-            image: d.image,
+        .map(|d| {
+            // Usage is keyed by the unprefixed `app` label
+            let app = instance
+                .and_then(|i| d.name.strip_prefix(&format!("{}-", i)))
+                .unwrap_or(&d.name);
+            let used = usage.as_ref().and_then(|u| u.get(app));
+            let pod_infos = pods_by_deployment
+                .remove(&d.name)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| {
+                    let used = pod_usage.iter().flatten().find(|u| u.pod == p.name).map(|u| u.usage);
+                    PodInfo {
+                        cpu_millicores: used.map(|u| u.cpu_millicores),
+                        memory_bytes: used.map(|u| u.memory_bytes),
+                        name: p.name,
+                        phase: p.phase,
+                        ready: p.ready,
+                        restarts: p.restarts,
+                        warning: p.warning,
+                    }
+                })
+                .collect();
+            DeploymentInfo {
+                cpu_millicores: used.map(|u| u.cpu_millicores),
+                memory_bytes: used.map(|u| u.memory_bytes),
+                name: d.name,
+                ready: d.ready,
+                desired: d.desired,
+                image: d.image,
+                pods: pods.then_some(pod_infos),
+            }
         })
         .collect();
 
@@ -117,25 +259,19 @@ async fn print_status(cli: &Cli, args: &StatusArgs) -> Result<()> {
         .map(|(name, keys)| SecretInfo { name, keys })
         .collect();
 
-    let output = StatusOutput {
+    Ok(StatusOutput {
         namespace: namespace.clone(),
         version,
         enabled_features,
         agent_name,
+        url,
         deployments,
         secrets,
-    };
-
-    if args.json {
-        println!("{}", serde_json::to_string_pretty(&output)?);
-    } else {
-        print_human(&output);
-    }
-
-    Ok(())
+        secrets_hidden,
+    })
 }
 
-fn print_human(output: &StatusOutput) {
+fn print_human(output: &StatusOutput, changed: &BTreeMap<String, Change>, color: bool) {
     println!("Baker Street Status");
     println!("===================");
     println!("Namespace:  {}", output.namespace);
@@ -145,6 +281,9 @@ fn print_human(output: &StatusOutput) {
     if let Some(ref name) = output.agent_name {
         println!("Agent:      {}", name);
     }
+    if let Some(ref url) = output.url {
+        println!("URL:        {}", url);
+    }
 
     if !output.enabled_features.is_empty() {
         println!("Features:   {}", output.enabled_features.join(", "));
@@ -161,16 +300,47 @@ fn print_human(output: &StatusOutput) {
             } else {
                 "\u{2717}"
             };
-            println!(
-                "  {} {:<20} {}/{} ready   {}",
-                status_icon, d.name, d.ready, d.desired, d.image
+            let usage = match (d.cpu_millicores, d.memory_bytes) {
+                (Some(cpu), Some(mem)) => {
+                    format!("{:>5}m {:>5}Mi   ", cpu, mem / (1024 * 1024))
+                }
+                _ => String::new(),
+            };
+            let row = format!(
+                "  {} {:<20} {}/{} ready   {}{}",
+                status_icon, d.name, d.ready, d.desired, usage, d.image
             );
+            // Changed since the last watch tick: green up, red down, yellow otherwise
+            let (mark, code) = match changed.get(&d.name) {
+                Some(Change::Up) => (" \u{2191}", "32"),
+                Some(Change::Down) => (" \u{2193}", "31"),
+                Some(Change::Other) => ("", "33"),
+                None => ("", ""),
+            };
+            if color && !code.is_empty() {
+                println!("\x1B[{}m{}{}\x1B[0m", code, row, mark);
+            } else {
+                println!("{}{}", row, mark);
+            }
+            for pod in d.pods.iter().flatten() {
+                let icon = if pod.ready { "\u{2713}" } else { "\u{2717}" };
+                let usage = match (pod.cpu_millicores, pod.memory_bytes) {
+                    (Some(cpu), Some(mem)) => format!("   {:>5}m {:>5}Mi", cpu, mem / (1024 * 1024)),
+                    _ => String::new(),
+                };
+                println!("      {} {:<40} {:<10} {} restarts{}", icon, pod.name, pod.phase, pod.restarts, usage);
+                if let Some(ref warning) = pod.warning {
+                    println!("        \u{26a0} {}", warning);
+                }
+            }
         }
     }
 
     println!();
     println!("Secrets:");
-    if output.secrets.is_empty() {
+    if output.secrets_hidden {
+        println!("  (hidden: no permission to list secrets)");
+    } else if output.secrets.is_empty() {
         println!("  (none found)");
     } else {
         for s in &output.secrets {
@@ -178,3 +348,40 @@ fn print_human(output: &StatusOutput) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(name: &str, ready: i32) -> DeploymentInfo {
+        DeploymentInfo {
+            name: name.into(),
+            ready,
+            desired: 1,
+            image: "bakerst-brain:0.6.0".into(),
+            cpu_millicores: None,
+            memory_bytes: None,
+            pods: None,
+        }
+    }
+
+    #[test]
+    fn changed_deployments_ignore_usage_only_changes() {
+        let previous = vec![deployment("brain-blue", 0), deployment("ui", 1)];
+        let mut current = vec![deployment("brain-blue", 1), deployment("ui", 1), deployment("voice", 0)];
+        current[1].cpu_millicores = Some(12);
+        let changed = changed_deployments(&previous, &current);
+        assert_eq!(changed.keys().collect::<Vec<_>>(), vec!["brain-blue", "voice"]);
+    }
+
+    #[test]
+    fn changed_deployments_say_which_way_readiness_went() {
+        let previous = vec![deployment("brain-blue", 0), deployment("ui", 1), deployment("worker", 1)];
+        let mut current = vec![deployment("brain-blue", 1), deployment("ui", 0), deployment("worker", 1)];
+        current[2].image = "bakerst-worker:0.6.1".into();
+        let changed = changed_deployments(&previous, &current);
+        assert_eq!(changed["brain-blue"], Change::Up);
+        assert_eq!(changed["ui"], Change::Down);
+        assert_eq!(changed["worker"], Change::Other);
+    }
+}
@@ -1,18 +1,24 @@
 //! Uninstall command — removes all Baker Street resources.
 //!
 //! Deletes the Kubernetes namespace (which cascades to all resources within)
-//! and optionally removes the local ~/.bakerst/ directory.
+//! and optionally removes the local ~/.bakerst/ directory. With `--instance`
+//! only that instance's resources and saved config are removed; the
+//! namespace and other instances are left alone. PersistentVolumes the
+//! storage class retains are cluster-scoped and survive either way unless
+//! `--prune` is given. `--component` removes single components instead.
 
 use anyhow::{Context, Result};
 
 use crate::cli::{Cli, UninstallArgs};
-use crate::k8s;
+use crate::{instance, k8s, telemetry};
+
+/// Components the rest of the install can't run without; removing one
+/// with `--component` takes `--force`.
+pub const CORE_COMPONENTS: &[&str] = &["brain", "worker", "nats", "qdrant", "ui"];
 
 /// Entry point for the `uninstall` subcommand.
 pub async fn run(cli: &Cli, args: UninstallArgs) -> Result<()> {
-    let config_path = dirs::home_dir()
-        .context("Cannot determine home directory")?
-        .join(".bakerst/config.json");
+    let config_path = instance::config_path(cli.instance.as_deref())?;
 
     // Determine namespace from saved config or CLI flag
     let namespace = if config_path.exists() {
@@ -25,10 +31,31 @@ pub async fn run(cli: &Cli, args: UninstallArgs) -> Result<()> {
     } else {
         cli.namespace.clone()
     };
+    k8s::check_namespace(&namespace, cli.i_know_what_im_doing)?;
+
+    if !args.component.is_empty() {
+        return uninstall_components(cli, &args, &namespace, &config_path).await;
+    }
+    if let Some(instance) = &cli.instance {
+        return uninstall_instance(cli, &args, &namespace, instance, &config_path).await;
+    }
+
+    // Never delete a system namespace, even with --i-know-what-im-doing
+    if k8s::PROTECTED_NAMESPACES.contains(&namespace.as_str()) {
+        anyhow::bail!(
+            "Refusing to delete protected namespace '{}'. Remove Baker Street's resources manually \
+             (see `bakerst-install resources`).",
+            namespace
+        );
+    }
 
     println!("Baker Street Uninstaller");
     println!();
-    println!("This will delete namespace '{}' and all resources within it.", namespace);
+    let telemetry_namespace = telemetry::namespace(&namespace, None);
+    println!(
+        "This will delete namespace '{}' and all resources within it, and the '{}' namespace.",
+        namespace, telemetry_namespace
+    );
 
     // Confirm unless non-interactive
     if !args.non_interactive {
@@ -46,11 +73,25 @@ pub async fn run(cli: &Cli, args: UninstallArgs) -> Result<()> {
 
     // Delete namespace (cascades to all resources)
     println!("Deleting namespace '{}'...", namespace);
-    let client = kube::Client::try_default()
+    let client = k8s::connect(cli.kube_context.as_deref())
         .await
         .context("Cannot connect to Kubernetes cluster")?;
-    k8s::delete_namespace(&client, &namespace).await?;
-    println!("Namespace '{}' deleted.", namespace);
+    let deleted = k8s::delete_namespace(&client, &namespace).await?;
+    match deleted {
+        true => println!("Namespace '{}' is being deleted.", namespace),
+        false => println!("Namespace '{}' not found; nothing to delete.", namespace),
+    }
+    // Not there unless the install used --telemetry; that's fine
+    let telemetry_deleted = k8s::delete_namespace(&client, &telemetry_namespace).await?;
+    if !args.no_wait {
+        if deleted {
+            wait_deleted(&client, &namespace, &args).await?;
+        }
+        if telemetry_deleted {
+            wait_deleted(&client, &telemetry_namespace, &args).await?;
+        }
+    }
+    prune_volumes(&client, &namespace, None, args.prune).await?;
 
     // Optionally delete local config
     let bakerst_dir = dirs::home_dir()
@@ -80,3 +121,199 @@ pub async fn run(cli: &Cli, args: UninstallArgs) -> Result<()> {
     println!("\nBaker Street has been uninstalled.");
     Ok(())
 }
+
+/// Delete one named instance: every managed resource carrying its label,
+/// then its saved config.
+async fn uninstall_instance(
+    cli: &Cli,
+    args: &UninstallArgs,
+    namespace: &str,
+    instance: &str,
+    config_path: &std::path::Path,
+) -> Result<()> {
+    println!("Baker Street Uninstaller");
+    println!();
+    println!(
+        "This will delete instance '{}' from namespace '{}'. The namespace and other instances are kept.",
+        instance, namespace
+    );
+
+    if !args.non_interactive {
+        print!("Are you sure? [y/N] ");
+        use std::io::Write;
+        std::io::stdout().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    let client = k8s::connect(cli.kube_context.as_deref())
+        .await
+        .context("Cannot connect to Kubernetes cluster")?;
+    let (resources, denied) = k8s::list_managed_resources(&client, namespace, Some(instance))
+        .await
+        .context("Failed to list the instance's resources")?;
+    if !denied.is_empty() {
+        println!("Note: not allowed to list {}; those were not deleted", denied.join(", "));
+    }
+    for resource in resources.iter().filter(|r| r.kind != "Namespace") {
+        k8s::delete_managed(&client, namespace, &resource.kind, &resource.name).await?;
+        println!("  Deleted {}/{}", resource.kind, resource.name);
+    }
+    let telemetry_namespace = telemetry::namespace(namespace, Some(instance));
+    if k8s::delete_namespace(&client, &telemetry_namespace).await? && !args.no_wait {
+        wait_deleted(&client, &telemetry_namespace, args).await?;
+    }
+    let claims: Vec<String> = resources
+        .iter()
+        .filter(|r| r.kind == "PersistentVolumeClaim")
+        .map(|r| r.name.clone())
+        .collect();
+    if !claims.is_empty() {
+        prune_volumes(&client, namespace, Some(&claims), args.prune).await?;
+    }
+
+    if config_path.exists() {
+        std::fs::remove_file(config_path)?;
+        println!("Removed {}", config_path.display());
+    }
+
+    println!("\nInstance '{}' has been uninstalled.", instance);
+    Ok(())
+}
+
+/// Delete just the named components (`--component`), leaving the namespace
+/// and everything else in place. A removed feature is dropped from the
+/// saved config so `update` doesn't bring it back.
+async fn uninstall_components(
+    cli: &Cli,
+    args: &UninstallArgs,
+    namespace: &str,
+    config_path: &std::path::Path,
+) -> Result<()> {
+    let core: Vec<&str> = args
+        .component
+        .iter()
+        .map(String::as_str)
+        .filter(|c| CORE_COMPONENTS.contains(c))
+        .collect();
+    if !core.is_empty() && !args.force {
+        anyhow::bail!(
+            "{} {} core to Baker Street; pass --force to remove it anyway, or uninstall everything",
+            core.join(", "),
+            if core.len() == 1 { "is" } else { "are" }
+        );
+    }
+
+    let client = k8s::connect(cli.kube_context.as_deref())
+        .await
+        .context("Cannot connect to Kubernetes cluster")?;
+    let instance = cli.instance.as_deref();
+    let mut plan = Vec::new();
+    for component in &args.component {
+        let resources = k8s::component_resources(&client, namespace, instance, component)
+            .await
+            .with_context(|| format!("Failed to find the resources of '{}'", component))?;
+        if resources.resources.is_empty() {
+            anyhow::bail!("No resources of component '{}' found in namespace '{}'", component, namespace);
+        }
+        plan.push((component, resources));
+    }
+
+    println!("Baker Street Uninstaller");
+    println!();
+    println!("This will delete from namespace '{}':", namespace);
+    for (component, found) in &plan {
+        let names: Vec<String> = found.resources.iter().map(|(kind, name)| format!("{}/{}", kind, name)).collect();
+        println!("  {}: {}", component, names.join(", "));
+    }
+    let kept: Vec<&str> = plan.iter().flat_map(|(_, found)| &found.kept_claims).map(String::as_str).collect();
+    if !kept.is_empty() {
+        println!("Kept, with their data: PersistentVolumeClaim {}", kept.join(", "));
+        println!("  Delete them with: kubectl delete pvc -n {} {}", namespace, kept.join(" "));
+    }
+    if !args.non_interactive {
+        print!("Are you sure? [y/N] ");
+        use std::io::Write;
+        std::io::stdout().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    for (_, found) in &plan {
+        for (kind, name) in &found.resources {
+            k8s::delete_managed(&client, namespace, kind, name).await?;
+            println!("  Deleted {}/{}", kind, name);
+        }
+    }
+
+    if config_path.exists() {
+        let mut saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(config_path)?)?;
+        if let Some(features) = saved["enabledFeatures"].as_array_mut() {
+            let before = features.len();
+            features.retain(|f| !args.component.iter().any(|c| f.as_str() == Some(c)));
+            if features.len() != before {
+                instance::write_private(config_path, &serde_json::to_string_pretty(&saved)?)?;
+                println!("Removed from the features in {}", config_path.display());
+            }
+        }
+    }
+
+    println!("\nRemoved {}.", args.component.join(", "));
+    Ok(())
+}
+
+/// Block until a deleted namespace is gone, so a reinstall right after
+/// doesn't find it still terminating (skipped with `--no-wait`).
+async fn wait_deleted(client: &kube::Client, namespace: &str, args: &UninstallArgs) -> Result<()> {
+    println!("Waiting for namespace '{}' to terminate...", namespace);
+    let timeout = args
+        .timeout
+        .map(std::time::Duration::from_secs)
+        .unwrap_or(k8s::DEFAULT_NAMESPACE_DELETE_TIMEOUT);
+    let interval = std::time::Duration::from_secs(2);
+    k8s::wait_for_namespace_deleted(client, namespace, timeout, interval, std::time::Duration::from_secs(10)).await?;
+    println!("Namespace '{}' is gone.", namespace);
+    Ok(())
+}
+
+/// With `--prune`, delete the PersistentVolumes bound to the install's
+/// claims (all of `namespace`'s, or just `claims`); without it, say which
+/// are kept. Volumes whose reclaim policy deletes them with their claim go
+/// anyway, so they aren't listed as kept.
+async fn prune_volumes(client: &kube::Client, namespace: &str, claims: Option<&[String]>, prune: bool) -> Result<()> {
+    let volumes = match k8s::claimed_volumes(client, namespace, claims).await {
+        Ok(volumes) => volumes,
+        Err(e) if k8s::is_forbidden(&e) => {
+            println!("Note: not allowed to list PersistentVolumes; retained volumes were not checked");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    if !prune {
+        let kept: Vec<&str> = volumes.iter().filter(|v| v.retained).map(|v| v.name.as_str()).collect();
+        if !kept.is_empty() {
+            println!(
+                "Kept {} PersistentVolume(s) holding the install's data (reclaim policy Retain): {}",
+                kept.len(),
+                kept.join(", ")
+            );
+            println!("  Pass --prune to delete them; a later install won't reuse them.");
+        }
+        return Ok(());
+    }
+    for volume in &volumes {
+        k8s::delete_volume(client, &volume.name).await?;
+        println!("  Deleted PersistentVolume/{}", volume.name);
+    }
+    Ok(())
+}
@@ -3,11 +3,11 @@
 //! Loads saved config from ~/.bakerst/config.json, compares versions,
 //! and re-applies manifests if a newer version is available.
 
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, Result};
 
 use crate::cli::{Cli, UpdateArgs};
 use crate::config_schema::ConfigSchema;
-use crate::{deploy, fetcher, interview, k8s, verify};
+use crate::{deploy, fetcher, instance, interview, k8s, verify};
 
 /// Entry point for the `update` subcommand.
 pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
@@ -15,9 +15,8 @@ pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
     println!();
 
     // 1. Load saved config
-    let config_path = dirs::home_dir()
-        .context("Cannot determine home directory")?
-        .join(".bakerst/config.json");
+    let instance = cli.instance.as_deref();
+    let config_path = instance::config_path(instance)?;
 
     if !config_path.exists() {
         bail!(
@@ -35,6 +34,7 @@ pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
         .as_str()
         .unwrap_or(&cli.namespace)
         .to_string();
+    k8s::check_namespace(&namespace, cli.i_know_what_im_doing)?;
 
     let current_version = saved["version"].as_str().map(String::from);
 
@@ -45,8 +45,10 @@ pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
 
     // 2. Fetch latest manifest
     println!("\nFetching latest manifest...");
-    let manifest = fetcher::fetch_manifest(None, None).await?;
-    println!("Latest version:  {}", manifest.version);
+    let public_key = crate::manifest::release_public_key(args.manifest_pubkey.as_deref())?;
+    let (manifest, source) = fetcher::fetch_manifest(None, &public_key, true).await?;
+    println!("Latest version:  {} (from {})", manifest.version, source);
+    let probes = deploy::probe_tuning(&manifest, &[], &[])?;
 
     // 3. Compare versions
     if let Some(ref current) = current_version {
@@ -59,8 +61,8 @@ pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
         }
     }
 
-    // 4. Confirm
-    if !args.non_interactive {
+    // 4. Confirm (--diff never applies, so there's nothing to confirm)
+    if !args.non_interactive && !args.diff {
         println!("\nThis will update Baker Street in namespace '{}'.", namespace);
         print!("Continue? [y/N] ");
         use std::io::Write;
@@ -77,42 +79,113 @@ pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
     // 5. Download template
     println!("\nDownloading install template...");
     let work_dir = tempfile::tempdir()?;
-    let template_dir = fetcher::fetch_template(&manifest, None, work_dir.path()).await?;
+    let template_dir = fetcher::fetch_template(&manifest, None, work_dir.path(), false).await?;
 
     // 6. Load config schema
     let schema_path = template_dir.join("config-schema.json");
     let schema = ConfigSchema::from_file(&schema_path)?;
 
     // 7. Build interview result
-    let config = if args.reconfigure {
+    let mut config = if args.reconfigure {
         // Re-collect from environment
         println!("Re-reading configuration from environment...");
+        if let Some(path) = &args.env_file {
+            let count = crate::env_file::load(path)?;
+            println!("  Read {} value(s) from {}", count, path.display());
+        }
         interview::from_env(&schema)?
     } else {
         // Preserve existing secrets from K8s, merge with saved config
-        let client = kube::Client::try_default().await?;
-        rebuild_config_from_cluster(&client, &namespace, &schema, &saved).await?
+        let client = k8s::connect(cli.kube_context.as_deref()).await?;
+        rebuild_config_from_cluster(&client, &namespace, instance, &schema, &saved).await?
     };
+    config.instance = cli.instance.clone();
+    // The PVCs' class is immutable; keep what the install chose
+    config.storage_class = saved["storageClass"].as_str().map(String::from);
+    config.data_dir = saved["dataDir"].as_str().map(String::from);
+    config.stateful_storage = saved["statefulStorage"].as_bool().unwrap_or(false);
+    config.resource_profile = args.resource_profile.unwrap_or_else(|| {
+        saved["resourceProfile"]
+            .as_str()
+            .and_then(deploy::ResourceProfile::from_name)
+            .unwrap_or_default()
+    });
+    config.registry_mirror = args
+        .registry_mirror
+        .clone()
+        .or_else(|| saved["registryMirror"].as_str().map(String::from));
+    if let Some(mirror) = &config.registry_mirror {
+        println!("Registry mirror: {}", mirror);
+    }
+    config.ui_node_port = args
+        .ui_node_port
+        .or_else(|| saved["uiNodePort"].as_u64().and_then(|port| u16::try_from(port).ok()));
+    if let Some(port) = config.ui_node_port {
+        println!("UI NodePort: {}", port);
+    }
+    config.set = merge_set(&saved["set"], &args.set);
+    if !config.set.is_empty() {
+        let keys: Vec<&str> = config.set.iter().map(|(key, _)| key.as_str()).collect();
+        println!("Set: {}", keys.join(", "));
+    }
 
     // 8. Apply
-    let client = kube::Client::try_default().await?;
+    let client = k8s::connect(cli.kube_context.as_deref()).await?;
+    let apply = k8s::ApplyParams {
+        strategy: args.apply_strategy.unwrap_or_default(),
+        field_manager: args
+            .field_manager
+            .clone()
+            .unwrap_or_else(k8s::default_field_manager),
+        dry_run: false,
+        labels: instance::labels(instance),
+        retries: args.apply_retries,
+    };
+    // Update doesn't pre-pull, so the cluster pulls per the release's tags,
+    // pinned to the digests the release records
+    let references: Vec<String> = manifest.images.iter().map(|i| i.reference()).collect();
+    let deploy_opts = deploy::DeployOptions {
+        apply: apply.clone(),
+        ingress_host: args.ingress_host.clone(),
+        ingress_tls_secret: args.ingress_tls_secret.clone(),
+        ingress_class: args.ingress_class.clone(),
+        image_digests: deploy::image_digests(&manifest, None, &references),
+        ..deploy::DeployOptions::from_config(&manifest, &config, probes)
+    };
+    let k8s_dir = template_dir.join("k8s");
+
+    if args.diff {
+        let resources = deploy::render_target(&k8s_dir, &config.enabled_features, &deploy_opts)?;
+        let changed = deploy::diff_against_cluster(&client, &namespace, &resources, &apply).await?;
+        println!("\n{} resource(s) would change. Nothing was applied (--diff).", changed);
+        return Ok(());
+    }
+
+    // qdrant and nats can't switch kind in place
+    let resources = deploy::render_target(&k8s_dir, &config.enabled_features, &deploy_opts)?;
+    deploy::check_workload_kinds(&client, &namespace, &resources).await?;
 
     println!("Applying secrets...");
-    deploy::apply_secrets(&client, &schema, &config).await?;
+    // Merge so keys not re-supplied (e.g. with --reconfigure) survive the update
+    deploy::apply_secrets(&client, &schema, &config, k8s::SecretMode::Merge, !args.no_trim_secrets, &apply).await?;
 
     println!("Applying manifests...");
-    let k8s_dir = template_dir.join("k8s");
-    let remote_overlay = k8s_dir.join("overlays/remote");
-    let manifest_dir = if remote_overlay.exists() {
-        remote_overlay
-    } else {
-        k8s_dir.clone()
-    };
-    deploy::apply_manifests_from_dir(&client, &namespace, &manifest_dir).await?;
+    let manifest_dir = deploy::manifest_dir(&k8s_dir);
+    deploy::apply_manifests_from_dir(&client, &namespace, &manifest_dir, &deploy_opts).await?;
+    deploy::apply_ingress(&client, &namespace, &deploy_opts).await?;
 
     // Apply extension manifests
     let extensions_dir = k8s_dir.join("extensions");
-    deploy::apply_extensions(&client, &namespace, &extensions_dir, &config.enabled_features).await?;
+    deploy::apply_extensions(&client, &namespace, &extensions_dir, &config.enabled_features, &deploy_opts).await?;
+
+    if args.prune {
+        println!("Pruning resources no longer in the manifest...");
+        let rendered = deploy::render_target(&k8s_dir, &config.enabled_features, &deploy_opts)?;
+        let mut owned = deploy::owned_resources(&rendered, &schema, &config);
+        // Without --ingress-host an Ingress from the install is left as is
+        owned.insert(("Ingress".into(), instance::prefixed(instance, "ui")));
+        deploy::prune(&client, &namespace, instance, &owned, args.non_interactive).await?;
+    }
 
     // 9. Verify deployment
     println!("Verifying deployment...");
@@ -133,7 +206,32 @@ pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
         "agentName": config.agent_name,
     });
     saved_config["version"] = serde_json::Value::String(manifest.version.clone());
-    std::fs::write(&config_path, serde_json::to_string_pretty(&saved_config)?)?;
+    if let Some(instance) = instance {
+        saved_config["instance"] = instance.into();
+    }
+    if let Some(class) = &config.storage_class {
+        saved_config["storageClass"] = class.as_str().into();
+    }
+    if let Some(dir) = &config.data_dir {
+        saved_config["dataDir"] = dir.as_str().into();
+    }
+    if config.resource_profile != deploy::ResourceProfile::Standard {
+        saved_config["resourceProfile"] = config.resource_profile.name().into();
+    }
+    if let Some(mirror) = &config.registry_mirror {
+        saved_config["registryMirror"] = mirror.as_str().into();
+    }
+    if let Some(port) = config.ui_node_port {
+        saved_config["uiNodePort"] = port.into();
+    }
+    if config.stateful_storage {
+        saved_config["statefulStorage"] = true.into();
+    }
+    if !config.set.is_empty() {
+        let set: std::collections::BTreeMap<&str, &str> = config.set.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        saved_config["set"] = serde_json::json!(set);
+    }
+    instance::write_private(&config_path, &serde_json::to_string_pretty(&saved_config)?)?;
 
     println!("\nUpdate complete! Now running v{}.", manifest.version);
     Ok(())
@@ -143,13 +241,14 @@ pub async fn run(cli: &Cli, args: UpdateArgs) -> Result<()> {
 async fn rebuild_config_from_cluster(
     client: &kube::Client,
     namespace: &str,
+    instance: Option<&str>,
     schema: &ConfigSchema,
     saved: &serde_json::Value,
 ) -> Result<interview::InterviewResult> {
     let mut secrets = std::collections::HashMap::new();
 
     // Read all bakerst-* secrets from the cluster
-    let cluster_secrets = k8s::get_secrets_info(client, namespace).await?;
+    let cluster_secrets = k8s::get_secrets_info(client, namespace, instance).await?;
     for (secret_name, _keys) in &cluster_secrets {
         if let Some(data) = k8s::read_secret(client, namespace, secret_name).await? {
             for (k, v) in data {
@@ -177,6 +276,51 @@ async fn rebuild_config_from_cluster(
         enabled_features,
         namespace: namespace.to_string(),
         agent_name,
+        instance: instance.map(String::from),
+        storage_class: saved["storageClass"].as_str().map(String::from),
+        data_dir: saved["dataDir"].as_str().map(String::from),
+        resource_profile: Default::default(),
+        registry_mirror: saved["registryMirror"].as_str().map(String::from),
+        ui_node_port: None,
+        stateful_storage: saved["statefulStorage"].as_bool().unwrap_or(false),
+        set: Vec::new(),
     })
 }
 
+
+/// The `--set` values for an update: those the install saved in `saved`,
+/// with this run's `--set` (in `cli`) taking precedence key by key.
+fn merge_set(saved: &serde_json::Value, cli: &[(String, String)]) -> Vec<(String, String)> {
+    let mut set: Vec<(String, String)> = saved
+        .as_object()
+        .map(|vars| {
+            vars.iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    for (key, value) in cli {
+        set.retain(|(saved_key, _)| saved_key != key);
+        set.push((key.clone(), value.clone()));
+    }
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_keeps_saved_values_unless_overridden() {
+        let saved = serde_json::json!({"BRAIN_REPLICAS": "2", "DOOR_POLICY": "closed"});
+        let cli = [("BRAIN_REPLICAS".to_string(), "3".to_string())];
+        assert_eq!(
+            merge_set(&saved, &cli),
+            [
+                ("DOOR_POLICY".to_string(), "closed".to_string()),
+                ("BRAIN_REPLICAS".to_string(), "3".to_string()),
+            ]
+        );
+        assert!(merge_set(&serde_json::Value::Null, &[]).is_empty());
+    }
+}
@@ -3,11 +3,18 @@
 //! Supports environment variable interpolation via `${VAR_NAME}` syntax
 //! so that CI pipelines can inject secrets from GitHub Actions secrets
 //! without committing them to the config file.
+//!
+//! Files ending in `.toml` are parsed as TOML; anything else as YAML.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::cli::{InstallArgs, OutputFormat, Theme};
+use crate::config_schema::ConfigSchema;
+use crate::deploy::ResourceProfile;
+use crate::k8s::{ApplyStrategy, PullPolicy};
 
 /// A user-provided config file for non-interactive installation.
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -36,6 +43,275 @@ pub struct ConfigFile {
     /// Verification configuration
     #[serde(default)]
     pub verify: Option<VerifyConfig>,
+
+    /// Install flags, so a checked-in file can replace a long command line
+    #[serde(default)]
+    pub options: InstallOptions,
+
+    /// Keys not recognised by this installer (reported as warnings)
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// Mirror of the `install` command-line flags, keyed by their camelCase
+/// names (`--ingress-host` is `ingressHost`). Precedence is defaults <
+/// config file < command-line flags. Repeatable flags are maps here
+/// (`set: {KEY: VALUE}`). Left out: `--config` itself, `--env-file` (read
+/// before the file), and the flags that print something and exit.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallOptions {
+    pub manifest: Option<PathBuf>,
+    pub template: Option<PathBuf>,
+    pub manifest_pubkey: Option<PathBuf>,
+    pub assume_cached_manifest: Option<bool>,
+    pub no_cache: Option<bool>,
+    pub image_bundle: Option<PathBuf>,
+    pub log: Option<PathBuf>,
+    pub metrics_file: Option<PathBuf>,
+    pub save_profile: Option<PathBuf>,
+    pub save_profile_secrets: Option<bool>,
+    pub non_interactive: Option<bool>,
+    pub timeout_total: Option<u64>,
+    pub output: Option<OutputFormat>,
+    pub dry_run: Option<bool>,
+    pub show_secrets: Option<bool>,
+    pub diff: Option<bool>,
+    pub prune: Option<bool>,
+    pub server_dry_run: Option<bool>,
+    pub no_trim_secrets: Option<bool>,
+    pub skip_digest_check: Option<bool>,
+    pub pull_concurrency: Option<u64>,
+    pub no_wait: Option<bool>,
+    pub follow_logs: Option<u64>,
+    pub smoke_test: Option<String>,
+    pub apply_strategy: Option<ApplyStrategy>,
+    pub field_manager: Option<String>,
+    pub apply_retries: Option<u32>,
+    #[serde(default)]
+    pub set: BTreeMap<String, String>,
+    pub pull_policy: Option<PullPolicy>,
+    pub storage_class: Option<String>,
+    pub data_dir: Option<String>,
+    pub stateful_storage: Option<bool>,
+    pub resource_profile: Option<ResourceProfile>,
+    pub ingress_host: Option<String>,
+    pub ingress_tls_secret: Option<String>,
+    pub ingress_class: Option<String>,
+    pub registry_mirror: Option<String>,
+    pub ui_node_port: Option<u16>,
+    pub health_timeout: Option<u64>,
+    pub poll_interval: Option<u64>,
+    #[serde(default)]
+    pub probe_initial_delay: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub probe_period: BTreeMap<String, u32>,
+    pub health_stable_cycles: Option<u32>,
+    pub max_recovery_attempts: Option<u32>,
+    pub os_dir: Option<PathBuf>,
+    pub skip_os_configmap: Option<bool>,
+    pub telemetry: Option<bool>,
+    pub telemetry_timeout: Option<u64>,
+    pub rollback_on_failure: Option<bool>,
+    pub auth_token_out: Option<PathBuf>,
+    pub force: Option<bool>,
+    #[serde(default)]
+    pub namespace_annotations: BTreeMap<String, String>,
+    #[serde(default)]
+    pub namespace_labels: BTreeMap<String, String>,
+    pub adopt_namespace: Option<bool>,
+    pub step: Option<bool>,
+    pub theme: Option<Theme>,
+
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl ConfigFile {
+    /// Fill in install flags that weren't given on the command line,
+    /// checking the file's values as the flags' own parsers would and the
+    /// flags it sets against each other and the command line's.
+    pub fn apply_to(&self, args: &mut InstallArgs) -> Result<()> {
+        let opts = &self.options;
+        opts.check()?;
+        // Ids of the flags the file set, for checking them against each other
+        let mut from_file = BTreeSet::new();
+        fill(&mut from_file, "version", &mut args.version, &self.version);
+
+        // Flags that take a value are unset until given
+        fill(&mut from_file, "manifest", &mut args.manifest, &opts.manifest);
+        fill(&mut from_file, "template", &mut args.template, &opts.template);
+        fill(&mut from_file, "manifest_pubkey", &mut args.manifest_pubkey, &opts.manifest_pubkey);
+        fill(&mut from_file, "image_bundle", &mut args.image_bundle, &opts.image_bundle);
+        fill(&mut from_file, "metrics_file", &mut args.metrics_file, &opts.metrics_file);
+        fill(&mut from_file, "save_profile", &mut args.save_profile, &opts.save_profile);
+        fill(&mut from_file, "timeout_total", &mut args.timeout_total, &opts.timeout_total);
+        fill(&mut from_file, "follow_logs", &mut args.follow_logs, &opts.follow_logs);
+        fill(&mut from_file, "smoke_test", &mut args.smoke_test, &opts.smoke_test);
+        fill(&mut from_file, "apply_strategy", &mut args.apply_strategy, &opts.apply_strategy);
+        fill(&mut from_file, "field_manager", &mut args.field_manager, &opts.field_manager);
+        fill(&mut from_file, "pull_policy", &mut args.pull_policy, &opts.pull_policy);
+        fill(&mut from_file, "storage_class", &mut args.storage_class, &opts.storage_class);
+        fill(&mut from_file, "data_dir", &mut args.data_dir, &opts.data_dir);
+        fill(&mut from_file, "resource_profile", &mut args.resource_profile, &opts.resource_profile);
+        fill(&mut from_file, "ingress_host", &mut args.ingress_host, &opts.ingress_host);
+        fill(&mut from_file, "ingress_tls_secret", &mut args.ingress_tls_secret, &opts.ingress_tls_secret);
+        fill(&mut from_file, "ingress_class", &mut args.ingress_class, &opts.ingress_class);
+        fill(&mut from_file, "registry_mirror", &mut args.registry_mirror, &opts.registry_mirror);
+        fill(&mut from_file, "ui_node_port", &mut args.ui_node_port, &opts.ui_node_port);
+        fill(&mut from_file, "health_timeout", &mut args.health_timeout, &opts.health_timeout);
+        fill(&mut from_file, "poll_interval", &mut args.poll_interval, &opts.poll_interval);
+        fill(&mut from_file, "health_stable_cycles", &mut args.health_stable_cycles, &opts.health_stable_cycles);
+        fill(&mut from_file, "max_recovery_attempts", &mut args.max_recovery_attempts, &opts.max_recovery_attempts);
+        fill(&mut from_file, "os_dir", &mut args.os_dir, &opts.os_dir);
+        fill(&mut from_file, "telemetry_timeout", &mut args.telemetry_timeout, &opts.telemetry_timeout);
+        fill(&mut from_file, "auth_token_out", &mut args.auth_token_out, &opts.auth_token_out);
+
+        // Switches and flags with a default can't tell unset from given
+        // apart by value, so the command line's are listed
+        let given = args.from_command_line.clone();
+        fill_unless_given(&given, &mut from_file, "assume_cached_manifest", &mut args.assume_cached_manifest, &opts.assume_cached_manifest);
+        fill_unless_given(&given, &mut from_file, "no_cache", &mut args.no_cache, &opts.no_cache);
+        fill_unless_given(&given, &mut from_file, "log", &mut args.log, &opts.log);
+        fill_unless_given(&given, &mut from_file, "save_profile_secrets", &mut args.save_profile_secrets, &opts.save_profile_secrets);
+        fill_unless_given(&given, &mut from_file, "non_interactive", &mut args.non_interactive, &opts.non_interactive);
+        fill_unless_given(&given, &mut from_file, "output", &mut args.output, &opts.output);
+        fill_unless_given(&given, &mut from_file, "dry_run", &mut args.dry_run, &opts.dry_run);
+        fill_unless_given(&given, &mut from_file, "show_secrets", &mut args.show_secrets, &opts.show_secrets);
+        fill_unless_given(&given, &mut from_file, "diff", &mut args.diff, &opts.diff);
+        fill_unless_given(&given, &mut from_file, "prune", &mut args.prune, &opts.prune);
+        fill_unless_given(&given, &mut from_file, "server_dry_run", &mut args.server_dry_run, &opts.server_dry_run);
+        fill_unless_given(&given, &mut from_file, "no_trim_secrets", &mut args.no_trim_secrets, &opts.no_trim_secrets);
+        fill_unless_given(&given, &mut from_file, "skip_digest_check", &mut args.skip_digest_check, &opts.skip_digest_check);
+        fill_unless_given(&given, &mut from_file, "pull_concurrency", &mut args.pull_concurrency, &opts.pull_concurrency);
+        fill_unless_given(&given, &mut from_file, "no_wait", &mut args.no_wait, &opts.no_wait);
+        fill_unless_given(&given, &mut from_file, "apply_retries", &mut args.apply_retries, &opts.apply_retries);
+        fill_unless_given(&given, &mut from_file, "stateful_storage", &mut args.stateful_storage, &opts.stateful_storage);
+        fill_unless_given(&given, &mut from_file, "skip_os_configmap", &mut args.skip_os_configmap, &opts.skip_os_configmap);
+        fill_unless_given(&given, &mut from_file, "telemetry", &mut args.telemetry, &opts.telemetry);
+        fill_unless_given(&given, &mut from_file, "rollback_on_failure", &mut args.rollback_on_failure, &opts.rollback_on_failure);
+        fill_unless_given(&given, &mut from_file, "force", &mut args.force, &opts.force);
+        fill_unless_given(&given, &mut from_file, "adopt_namespace", &mut args.adopt_namespace, &opts.adopt_namespace);
+        fill_unless_given(&given, &mut from_file, "step", &mut args.step, &opts.step);
+        fill_unless_given(&given, &mut from_file, "theme", &mut args.theme, &opts.theme);
+
+        // Repeatable flags given on the command line replace the file's
+        if args.set.is_empty() {
+            args.set = pairs(&opts.set).map(|var| crate::templates::parse_var(&var)).collect::<Result<_>>()?;
+        }
+        if args.namespace_annotations.is_empty() {
+            args.namespace_annotations = pairs(&opts.namespace_annotations)
+                .map(|a| crate::k8s::parse_annotation(&a))
+                .collect::<Result<_>>()?;
+        }
+        if args.namespace_labels.is_empty() {
+            args.namespace_labels = pairs(&opts.namespace_labels)
+                .map(|l| crate::k8s::parse_label(&l))
+                .collect::<Result<_>>()?;
+        }
+        if args.probe_initial_delay.is_empty() {
+            args.probe_initial_delay = opts.probe_initial_delay.clone().into_iter().collect();
+        }
+        if args.probe_period.is_empty() {
+            args.probe_period = opts.probe_period.clone().into_iter().collect();
+        }
+
+        // clap's requires/conflicts_with, across the flags and the file
+        crate::cli::check_install_relations(args.from_command_line.union(&from_file)).map_err(anyhow::Error::msg)
+    }
+
+    /// Features the file sets that this release's schema doesn't have, e.g.
+    /// from a profile saved against an older release. They are ignored.
+    pub fn unknown_features(&self, schema: &ConfigSchema) -> Vec<String> {
+        let mut unknown: Vec<String> = self
+            .features
+            .keys()
+            .filter(|id| !schema.features.iter().any(|f| &f.id == *id))
+            .cloned()
+            .collect();
+        unknown.sort();
+        unknown
+    }
+
+    /// Dotted paths of keys this installer doesn't understand.
+    pub fn unknown_keys(&self) -> Vec<String> {
+        self.unknown
+            .keys()
+            .cloned()
+            .chain(self.options.unknown.keys().map(|k| format!("options.{}", k)))
+            .collect()
+    }
+}
+
+fn fill<T: Clone>(from_file: &mut BTreeSet<String>, id: &str, arg: &mut Option<T>, file: &Option<T>) {
+    if let (None, Some(value)) = (&arg, file) {
+        *arg = Some(value.clone());
+        from_file.insert(id.to_string());
+    }
+}
+
+/// A switch or defaulted flag counts as set by the file only when the file
+/// changes it, as `dryRun: false` is no `--dry-run`.
+fn fill_unless_given<T: Clone + PartialEq>(
+    given: &BTreeSet<String>,
+    from_file: &mut BTreeSet<String>,
+    id: &str,
+    arg: &mut T,
+    file: &Option<T>,
+) {
+    if let (false, Some(value)) = (given.contains(id), file) {
+        if arg != value {
+            from_file.insert(id.to_string());
+        }
+        *arg = value.clone();
+    }
+}
+
+/// `key=value` for each entry, as the repeatable flags take them.
+fn pairs(map: &BTreeMap<String, String>) -> impl Iterator<Item = String> + '_ {
+    map.iter().map(|(key, value)| format!("{}={}", key, value))
+}
+
+impl InstallOptions {
+    /// The checks clap makes of the same values given as flags.
+    fn check(&self) -> Result<()> {
+        use crate::cli::{parse_data_dir, parse_field_manager, parse_ingress_host, parse_registry_mirror};
+        let checked = |key: &str, result: std::result::Result<String, String>| {
+            result.map(drop).map_err(|e| anyhow::anyhow!("options.{}: {}", key, e))
+        };
+        if let Some(host) = &self.ingress_host {
+            checked("ingressHost", parse_ingress_host(host))?;
+        }
+        if let Some(mirror) = &self.registry_mirror {
+            checked("registryMirror", parse_registry_mirror(mirror))?;
+        }
+        if let Some(dir) = &self.data_dir {
+            checked("dataDir", parse_data_dir(dir))?;
+        }
+        if let Some(manager) = &self.field_manager {
+            checked("fieldManager", parse_field_manager(manager))?;
+        }
+        if self.ui_node_port.is_some_and(|port| !(30000..=32767).contains(&port)) {
+            bail!("options.uiNodePort: must be a NodePort, 30000-32767");
+        }
+        if self.apply_retries.is_some_and(|n| n > crate::k8s::MAX_APPLY_RETRIES) {
+            bail!("options.applyRetries: at most {}", crate::k8s::MAX_APPLY_RETRIES);
+        }
+        for (key, value) in [
+            ("pullConcurrency", self.pull_concurrency),
+            ("healthTimeout", self.health_timeout),
+            ("pollInterval", self.poll_interval),
+            ("telemetryTimeout", self.telemetry_timeout),
+        ] {
+            if value == Some(0) {
+                bail!("options.{}: must be at least 1", key);
+            }
+        }
+        if self.probe_period.values().any(|&secs| secs == 0) {
+            bail!("options.probePeriod: a probe period must be at least 1 second");
+        }
+        Ok(())
+    }
 }
 
 /// Optional verification settings that override defaults.
@@ -58,17 +334,22 @@ pub fn load_config(path: &Path) -> Result<ConfigFile> {
     let content = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read config file: {}", path.display()))?;
     let resolved = resolve_env_vars(&content);
-    let config: ConfigFile = serde_yaml::from_str(&resolved)
-        .with_context(|| format!("Failed to parse config YAML: {}", path.display()))?;
+    let config: ConfigFile = if path.extension().and_then(|e| e.to_str()) == Some("toml") {
+        toml::from_str(&resolved)
+            .with_context(|| format!("Failed to parse config TOML: {}", path.display()))?
+    } else {
+        serde_yaml::from_str(&resolved)
+            .with_context(|| format!("Failed to parse config YAML: {}", path.display()))?
+    };
     Ok(config)
 }
 
-/// Replace `${VAR_NAME}` patterns with values from the environment.
-/// Missing env vars resolve to empty strings.
+/// Replace `${VAR_NAME}` patterns with values from the environment (or
+/// the `--env-file`). Missing env vars resolve to empty strings.
 fn resolve_env_vars(input: &str) -> String {
     let re = regex::Regex::new(r"\$\{(\w+)\}").unwrap();
     re.replace_all(input, |caps: &regex::Captures| {
-        std::env::var(&caps[1]).unwrap_or_default()
+        crate::env_file::var(&caps[1]).unwrap_or_default()
     })
     .to_string()
 }
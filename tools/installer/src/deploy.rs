@@ -1,23 +1,32 @@
 //! Shared deployment helpers used by both install and update commands.
 
-use anyhow::{Context, Result};
-use std::collections::BTreeMap;
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::config_schema::ConfigSchema;
 use crate::interview::InterviewResult;
+use crate::manifest::{Manifest, ProbeTuning};
 use crate::k8s;
 
-/// Apply K8s secrets based on config schema targetSecrets mapping.
-pub async fn apply_secrets(
-    client: &kube::Client,
+/// Route configured secret values to their target K8s Secrets.
+/// Returns a map from K8s secret name -> key/value pairs, following each
+/// key's `targetSecrets` in the schema (prefixed for an `--instance`).
+/// Feature secrets are only routed when the feature is enabled; empty values
+/// are dropped.
+pub fn route_secrets(
     schema: &ConfigSchema,
     config: &InterviewResult,
-) -> Result<()> {
-    // Build secret groups: map from K8s secret name -> key/value pairs
+) -> BTreeMap<String, BTreeMap<String, String>> {
     let mut secret_groups: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
 
-    // Process top-level secrets
-    for secret_def in &schema.secrets {
+    // Top-level secrets, then secrets of enabled features
+    let enabled_feature_secrets = schema
+        .features
+        .iter()
+        .filter(|f| config.enabled_features.contains(&f.id))
+        .flat_map(|f| f.secrets.iter());
+
+    for secret_def in schema.secrets.iter().chain(enabled_feature_secrets) {
         if let Some(value) = config.secrets.get(&secret_def.key) {
             if !value.is_empty() {
                 for target in &secret_def.target_secrets {
@@ -30,27 +39,79 @@ pub async fn apply_secrets(
         }
     }
 
-    // Process feature secrets
-    for feature in &schema.features {
-        if config.enabled_features.contains(&feature.id) {
-            for secret_def in &feature.secrets {
-                if let Some(value) = config.secrets.get(&secret_def.key) {
-                    if !value.is_empty() {
-                        for target in &secret_def.target_secrets {
-                            secret_groups
-                                .entry(target.clone())
-                                .or_default()
-                                .insert(secret_def.key.clone(), value.clone());
-                        }
-                    }
-                }
-            }
+    crate::instance::prefix_secret_names(secret_groups, config.instance.as_deref())
+}
+
+/// Invert the routing: map each input key to the K8s Secret names it lands in.
+pub fn secret_key_targets(
+    routes: &BTreeMap<String, BTreeMap<String, String>>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut targets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (secret_name, data) in routes {
+        for key in data.keys() {
+            targets.entry(key.clone()).or_default().push(secret_name.clone());
         }
     }
+    targets
+}
+
+/// Print the secret routing for the current feature selection without
+/// touching the cluster. Keys that have a value but are not routed anywhere
+/// (unknown key, disabled feature, or no `targetSecrets`) are listed separately.
+pub fn explain_secrets(schema: &ConfigSchema, config: &InterviewResult) {
+    let routes = route_secrets(schema, config);
+    let targets = secret_key_targets(&routes);
+
+    println!("Secret routing for namespace '{}':", config.namespace);
+    if targets.is_empty() {
+        println!("  (no secrets would be created)");
+    }
+    let width = targets.keys().map(|k| k.len()).max().unwrap_or(0);
+    for (key, secret_names) in &targets {
+        println!("  {:<width$}  -> {}", key, secret_names.join(", "), width = width);
+    }
+
+    let mut unrouted: Vec<&String> = config
+        .secrets
+        .iter()
+        .filter(|(k, v)| !v.is_empty() && !targets.contains_key(*k))
+        .map(|(k, _)| k)
+        .collect();
+    unrouted.sort();
+    if !unrouted.is_empty() {
+        println!();
+        println!("Not routed to any secret (unknown key or feature disabled):");
+        for key in unrouted {
+            println!("  {}", key);
+        }
+    }
+
+    println!();
+    println!("K8s Secrets:");
+    for (secret_name, data) in &routes {
+        println!("  {} ({} keys)", secret_name, data.len());
+    }
+}
+
+/// Apply K8s secrets based on config schema targetSecrets mapping.
+/// `mode` decides whether keys already in the cluster but not in `config`
+/// are dropped (fresh install) or preserved (update).
+pub async fn apply_secrets(
+    client: &impl k8s::ClusterClient,
+    schema: &ConfigSchema,
+    config: &InterviewResult,
+    mode: k8s::SecretMode,
+    trim: bool,
+    params: &k8s::ApplyParams,
+) -> Result<()> {
+    let mut secret_groups = route_secrets(schema, config);
+    if trim {
+        trim_secret_values(&mut secret_groups);
+    }
 
     // Create each K8s secret
     for (secret_name, data) in &secret_groups {
-        k8s::create_secret(client, &config.namespace, secret_name, data).await?;
+        k8s::create_secret(client, &config.namespace, secret_name, data, mode, params).await?;
         println!(
             "  Created secret: {} ({} keys)",
             secret_name,
@@ -61,12 +122,935 @@ pub async fn apply_secrets(
     Ok(())
 }
 
-/// Read all YAML files from a directory (sorted), concatenate, and apply.
-pub async fn apply_manifests_from_dir(
-    client: &kube::Client,
+/// Strip trailing whitespace from every secret value. Values pasted or read
+/// with `$(cat token.txt)` often end in a newline that would break auth.
+pub fn trim_secret_values(groups: &mut BTreeMap<String, BTreeMap<String, String>>) {
+    for (secret, data) in groups.iter_mut() {
+        for (key, value) in data.iter_mut() {
+            let trimmed = value.trim_end();
+            if trimmed.len() != value.len() {
+                tracing::debug!("trimmed trailing whitespace from {}/{}", secret, key);
+                value.truncate(trimmed.len());
+            }
+        }
+    }
+}
+
+/// How template manifests are adjusted and applied for this install.
+#[derive(Debug, Clone, Default)]
+pub struct DeployOptions {
+    pub apply: k8s::ApplyParams,
+    /// Overrides the template's `imagePullPolicy` on every workload.
+    pub pull_policy: Option<k8s::PullPolicy>,
+    /// Container resources keyed by image basename, from the manifest's
+    /// hints for enabled features (see [`feature_resources`]).
+    pub resources: BTreeMap<String, ContainerResources>,
+    /// `--instance` to rename the template's resources for.
+    pub instance: Option<String>,
+    /// `--storage-class` to set on every PersistentVolumeClaim.
+    pub storage_class: Option<String>,
+    /// `--data-dir`: drop the PersistentVolumeClaims and mount hostPath
+    /// directories under this node path instead (see [`use_host_paths`]).
+    pub data_dir: Option<String>,
+    /// Probe timing keyed by image basename (see [`probe_tuning`]).
+    pub probes: BTreeMap<String, ProbeTuning>,
+    /// Records the `Kind/name` of each resource as it is applied, so a
+    /// failed apply can be undone (`--rollback-on-failure`).
+    pub journal: Option<ApplyJournal>,
+    /// `--resource-profile` replica counts and sizing. Applied before the
+    /// feature hints in `resources`, which still win.
+    pub resource_profile: ResourceProfile,
+    /// `--ingress-host`: also route this host to the UI through an Ingress
+    /// (see [`ingress_object`]).
+    pub ingress_host: Option<String>,
+    /// `--ingress-tls-secret`: the TLS Secret the Ingress serves its host
+    /// with; without one it serves plain http.
+    pub ingress_tls_secret: Option<String>,
+    /// `--ingress-class`: the Ingress's `ingressClassName`; without one the
+    /// cluster's default class picks it up.
+    pub ingress_class: Option<String>,
+    /// `--registry-mirror` to pull every workload image from (see
+    /// [`crate::images::mirror_image`]).
+    pub registry_mirror: Option<String>,
+    /// Digests keyed by the manifest's image reference (`image:tag`, before
+    /// any mirror) for the images the cluster pulls without them having
+    /// been checked locally; containers running one are pinned to
+    /// `image:tag@digest` (see [`pin_image_digests`]).
+    pub image_digests: BTreeMap<String, String>,
+    /// `--set` values rendered into the release's manifests (see
+    /// [`crate::templates`]); they are left as written when empty.
+    pub template_vars: Vec<(String, String)>,
+    /// `--ui-node-port` to expose the UI's Service on (see
+    /// [`set_ui_node_port`]).
+    pub ui_node_port: Option<u16>,
+    /// `--stateful-storage`: run the [`STATEFUL_COMPONENTS`] as
+    /// StatefulSets (see [`use_stateful_sets`]).
+    pub stateful_storage: bool,
+}
+
+impl DeployOptions {
+    /// The options `config` deploys `manifest` with: its features' resource
+    /// hints, instance, storage, sizing, mirror, NodePort and `--set`
+    /// values, the `probes` timing and the release's default pull policy.
+    /// How resources are applied, the ingress and digest pinning are left
+    /// for the caller to set.
+    pub fn from_config(
+        manifest: &Manifest,
+        config: &InterviewResult,
+        probes: BTreeMap<String, ProbeTuning>,
+    ) -> Self {
+        Self {
+            pull_policy: Some(default_pull_policy(manifest, false)),
+            resources: feature_resources(manifest, &config.enabled_features),
+            probes,
+            instance: config.instance.clone(),
+            storage_class: config.storage_class.clone(),
+            data_dir: config.data_dir.clone(),
+            resource_profile: config.resource_profile,
+            registry_mirror: config.registry_mirror.clone(),
+            template_vars: template_vars(&config.set, config.ui_node_port),
+            ui_node_port: config.ui_node_port,
+            stateful_storage: config.stateful_storage,
+            ..Default::default()
+        }
+    }
+}
+
+/// Requests and limits to set on a container, by resource name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerResources {
+    pub requests: BTreeMap<String, String>,
+    pub limits: BTreeMap<String, String>,
+}
+
+/// `Kind/name` of every resource applied so far, shared with the apply
+/// callbacks.
+pub type ApplyJournal = std::sync::Arc<std::sync::Mutex<Vec<String>>>;
+
+/// `--resource-profile`: replica counts and container sizing to suit the
+/// cluster, from a Raspberry Pi to a large node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceProfile {
+    /// One replica of each component with tight CPU/memory limits
+    Small,
+    /// The templates' own replica counts and resources
+    #[default]
+    Standard,
+    /// Two brain and worker replicas with higher CPU/memory limits
+    Large,
+}
+
+/// What a resource profile sets on one component's Deployment.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileKnobs {
+    pub replicas: Option<i32>,
+    pub resources: ContainerResources,
+}
+
+impl ResourceProfile {
+    pub fn name(self) -> &'static str {
+        match self {
+            ResourceProfile::Small => "small",
+            ResourceProfile::Standard => "standard",
+            ResourceProfile::Large => "large",
+        }
+    }
+
+    /// Parse a profile name as written in the schema defaults or saved
+    /// config.
+    pub fn from_name(name: &str) -> Option<Self> {
+        <Self as clap::ValueEnum>::from_str(name, true).ok()
+    }
+
+    /// Settings per component, keyed by the Deployment's `app` label.
+    /// Components not listed keep the template's values.
+    pub fn knobs(self) -> BTreeMap<&'static str, ProfileKnobs> {
+        let knobs = |replicas, request: (&str, &str), limit: (&str, &str)| ProfileKnobs {
+            replicas: Some(replicas),
+            resources: ContainerResources {
+                requests: BTreeMap::from([("cpu".into(), request.0.into()), ("memory".into(), request.1.into())]),
+                limits: BTreeMap::from([("cpu".into(), limit.0.into()), ("memory".into(), limit.1.into())]),
+            },
+        };
+        match self {
+            ResourceProfile::Small => BTreeMap::from([
+                ("brain", knobs(1, ("100m", "256Mi"), ("500m", "512Mi"))),
+                ("worker", knobs(1, ("100m", "256Mi"), ("500m", "512Mi"))),
+                ("gateway", knobs(1, ("50m", "64Mi"), ("250m", "256Mi"))),
+                ("ui", knobs(1, ("50m", "64Mi"), ("250m", "256Mi"))),
+            ]),
+            ResourceProfile::Standard => BTreeMap::new(),
+            ResourceProfile::Large => BTreeMap::from([
+                ("brain", knobs(2, ("500m", "1Gi"), ("2", "2Gi"))),
+                ("worker", knobs(2, ("500m", "1Gi"), ("2", "2Gi"))),
+            ]),
+        }
+    }
+
+    /// One line per component the profile changes, for the install summary.
+    pub fn describe(self) -> Vec<String> {
+        self.knobs()
+            .iter()
+            .map(|(component, knobs)| {
+                let quantities = |m: &BTreeMap<String, String>| {
+                    format!("{} CPU / {}", m["cpu"], m["memory"])
+                };
+                format!(
+                    "{}: {} replica(s), requests {}, limits {}",
+                    component,
+                    knobs.replicas.unwrap_or(1),
+                    quantities(&knobs.resources.requests),
+                    quantities(&knobs.resources.limits),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Set a Deployment's replica count and every container's resources from a
+/// profile.
+fn apply_profile_knobs(resource: &mut serde_json::Value, knobs: &ProfileKnobs) {
+    if let Some(replicas) = knobs.replicas {
+        resource["spec"]["replicas"] = replicas.into();
+    }
+    let Some(containers) = resource
+        .pointer_mut("/spec/template/spec/containers")
+        .and_then(|c| c.as_array_mut())
+    else {
+        return;
+    };
+    for container in containers {
+        for (section, values) in [("requests", &knobs.resources.requests), ("limits", &knobs.resources.limits)] {
+            for (name, qty) in values {
+                container["resources"][section][name] = qty.clone().into();
+            }
+        }
+    }
+}
+
+/// Fold the manifest's per-component resource hints for the enabled
+/// features into one set per image. When several features raise the same
+/// quantity the larger one wins.
+pub fn feature_resources(
+    manifest: &Manifest,
+    enabled_features: &[String],
+) -> BTreeMap<String, ContainerResources> {
+    let mut out: BTreeMap<String, ContainerResources> = BTreeMap::new();
+    for image in &manifest.images {
+        for hint in &image.resource_hints {
+            if !enabled_features.contains(&hint.feature) {
+                continue;
+            }
+            let entry = out.entry(image_basename(&image.image).to_string()).or_default();
+            for (name, qty) in &hint.requests {
+                raise_quantity(&mut entry.requests, name, qty);
+            }
+            for (name, qty) in &hint.limits {
+                raise_quantity(&mut entry.limits, name, qty);
+            }
+        }
+    }
+    out
+}
+
+fn raise_quantity(map: &mut BTreeMap<String, String>, name: &str, qty: &str) {
+    match map.get(name) {
+        Some(current) if !exceeds(name, qty, current) => {}
+        _ => {
+            map.insert(name.to_string(), qty.to_string());
+        }
+    }
+}
+
+/// Whether quantity `qty` of resource `name` is larger than `current`.
+fn exceeds(name: &str, qty: &str, current: &str) -> bool {
+    let parse = |q: &str| match name {
+        "cpu" => k8s::parse_cpu_millicores(q),
+        _ => k8s::parse_memory_bytes(q),
+    };
+    parse(qty) > parse(current)
+}
+
+/// Pick a pull policy when none was given: `:latest` images that were just
+/// pulled locally should be used as-is, pinned tags are always pulled.
+pub fn default_pull_policy(manifest: &Manifest, prepulled: bool) -> k8s::PullPolicy {
+    let all_latest = manifest.images.iter().all(|i| i.tag == "latest");
+    if all_latest && prepulled {
+        k8s::PullPolicy::IfNotPresent
+    } else {
+        k8s::PullPolicy::Always
+    }
+}
+
+/// Combine the manifest's per-component probe timing with the
+/// `--probe-initial-delay` / `--probe-period` overrides (which win), keyed by
+/// image basename. Overrides must name a manifest component.
+pub fn probe_tuning(
+    manifest: &Manifest,
+    initial_delays: &[(String, u32)],
+    periods: &[(String, u32)],
+) -> Result<BTreeMap<String, ProbeTuning>> {
+    let mut out: BTreeMap<String, ProbeTuning> = manifest
+        .images
+        .iter()
+        .filter_map(|i| Some((image_basename(&i.image).to_string(), i.probes?)))
+        .collect();
+    if let Some(image) = manifest.images.iter().find(|i| i.probes.is_some_and(|p| p.period_seconds == Some(0))) {
+        bail!("Invalid manifest: component '{}' has a probe periodSeconds of 0", image.name);
+    }
+    for (flag, overrides) in [("--probe-initial-delay", initial_delays), ("--probe-period", periods)] {
+        for (component, secs) in overrides {
+            let Some(image) = manifest.images.iter().find(|i| &i.name == component) else {
+                let known: Vec<&str> = manifest.images.iter().map(|i| i.name.as_str()).collect();
+                bail!(
+                    "Unknown component '{}' in {}. Components: {}",
+                    component,
+                    flag,
+                    known.join(", ")
+                );
+            };
+            let tuning = out.entry(image_basename(&image.image).to_string()).or_default();
+            if flag == "--probe-period" {
+                tuning.period_seconds = Some(*secs);
+            } else {
+                tuning.initial_delay_seconds = Some(*secs);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// The template variables for an install: the `--set` values, plus
+/// `UI_NODE_PORT` for `--ui-node-port` unless a `--set` already gives it.
+pub fn template_vars(set: &[(String, String)], ui_node_port: Option<u16>) -> Vec<(String, String)> {
+    let mut vars = set.to_vec();
+    if let Some(port) = ui_node_port {
+        if !vars.iter().any(|(key, _)| key == "UI_NODE_PORT") {
+            vars.push(("UI_NODE_PORT".to_string(), port.to_string()));
+        }
+    }
+    vars
+}
+
+/// Apply per-install adjustments to parsed template resources.
+pub fn customize_resources(resources: &mut Vec<serde_json::Value>, opts: &DeployOptions) {
+    if let Some(instance) = &opts.instance {
+        crate::instance::prefix_resources(resources, instance);
+    }
+    if let Some(root) = &opts.data_dir {
+        use_host_paths(resources, root, opts.instance.as_deref());
+    }
+    let profile = opts.resource_profile.knobs();
+    let ui_service = crate::instance::prefixed(opts.instance.as_deref(), "ui");
+    for resource in resources.iter_mut() {
+        if let Some(policy) = opts.pull_policy {
+            k8s::set_pull_policy(resource, policy);
+        }
+        // Before the mirror, which keeps the digest: the digests are keyed
+        // by the template's own image names
+        if !opts.image_digests.is_empty() {
+            pin_image_digests(resource, &opts.image_digests);
+        }
+        if let Some(mirror) = &opts.registry_mirror {
+            set_image_mirror(resource, mirror);
+        }
+        if resource["kind"] == "Deployment" {
+            if let Some(knobs) = profile.get(component_of(resource)) {
+                apply_profile_knobs(resource, knobs);
+            }
+        }
+        if !opts.resources.is_empty() {
+            set_container_resources(resource, &opts.resources);
+        }
+        if !opts.probes.is_empty() {
+            set_probe_tuning(resource, &opts.probes);
+        }
+        if let Some(class) = &opts.storage_class {
+            if resource["kind"] == "PersistentVolumeClaim" {
+                resource["spec"]["storageClassName"] = class.as_str().into();
+            }
+        }
+        if let Some(port) = opts.ui_node_port {
+            if resource["kind"] == "Service" && resource["metadata"]["name"] == ui_service.as_str() {
+                set_ui_node_port(resource, port);
+            }
+        }
+    }
+    // Last, so the claims and workloads carry every adjustment above
+    if opts.stateful_storage {
+        use_stateful_sets(resources);
+    }
+}
+
+/// Components whose data lives in a volume, and which `--stateful-storage`
+/// runs as StatefulSets.
+pub const STATEFUL_COMPONENTS: &[&str] = &["qdrant", "nats"];
+
+/// Turn the [`STATEFUL_COMPONENTS`]' Deployments into StatefulSets. Each
+/// PersistentVolumeClaim a pod volume uses becomes a volumeClaimTemplate
+/// of the same name, so every replica gets its own volume that follows it
+/// when it's rescheduled; the standalone claims are dropped. The
+/// component's Service (same name) is the StatefulSet's governing Service.
+pub fn use_stateful_sets(resources: &mut Vec<serde_json::Value>) {
+    let claims: BTreeMap<String, serde_json::Value> = resources
+        .iter()
+        .filter(|r| r["kind"] == "PersistentVolumeClaim")
+        .filter_map(|r| Some((r["metadata"]["name"].as_str()?.to_string(), r["spec"].clone())))
+        .collect();
+    let mut templated = BTreeSet::new();
+    for resource in resources.iter_mut() {
+        if resource["kind"] != "Deployment" || !STATEFUL_COMPONENTS.contains(&component_of(resource)) {
+            continue;
+        }
+        let name = resource["metadata"]["name"].clone();
+        let pod_labels = resource["spec"]["template"]["metadata"]["labels"].clone();
+        let spec = &mut resource["spec"];
+        let mut templates = Vec::new();
+        if let Some(volumes) = spec["template"]["spec"]["volumes"].as_array_mut() {
+            volumes.retain(|volume| {
+                let Some(claim) = volume["persistentVolumeClaim"]["claimName"].as_str() else {
+                    return true;
+                };
+                let Some(claim_spec) = claims.get(claim) else {
+                    return true;
+                };
+                let mut labels = pod_labels.clone();
+                labels[k8s::MANAGED_BY_LABEL] = k8s::MANAGED_BY_VALUE.into();
+                templates.push(serde_json::json!({
+                    "metadata": { "name": volume["name"], "labels": labels },
+                    "spec": claim_spec,
+                }));
+                templated.insert(claim.to_string());
+                false
+            });
+        }
+        if let Some(spec) = spec.as_object_mut() {
+            // A Deployment's rollout strategy; StatefulSets roll one pod at a time
+            spec.remove("strategy");
+            spec.insert("serviceName".into(), name);
+            if !templates.is_empty() {
+                spec.insert("volumeClaimTemplates".into(), templates.into());
+            }
+        }
+        resource["kind"] = "StatefulSet".into();
+    }
+    resources.retain(|r| {
+        r["kind"] != "PersistentVolumeClaim" || !r["metadata"]["name"].as_str().is_some_and(|n| templated.contains(n))
+    });
+}
+
+/// Refuse to turn a live [`STATEFUL_COMPONENTS`] workload from a Deployment
+/// into a StatefulSet or back (`--stateful-storage` changed since it was
+/// installed). The StatefulSet's per-replica claims start out empty and the
+/// Deployment's claim would be left behind, so applying would lose the data
+/// from the component's point of view; moving it over is left to the user.
+pub async fn check_workload_kinds(client: &kube::Client, namespace: &str, resources: &[serde_json::Value]) -> Result<()> {
+    for resource in resources {
+        let kind = resource["kind"].as_str().unwrap_or_default();
+        let component = component_of(resource);
+        if !matches!(kind, "Deployment" | "StatefulSet") || !STATEFUL_COMPONENTS.contains(&component) {
+            continue;
+        }
+        let name = resource["metadata"]["name"].as_str().unwrap_or_default();
+        match k8s::workload_kind(client, namespace, name).await? {
+            Some(live) if live != kind => bail!(
+                "{} runs as a {} but would be applied as a {}, which would leave its data in the old volume. \
+                 Keep the storage setting it was installed with ({}--stateful-storage), or back up its data, \
+                 remove it with `bakerst-install uninstall --component {}` and apply again.",
+                name,
+                live,
+                kind,
+                if live == "StatefulSet" { "" } else { "no " },
+                component
+            ),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Expose the UI's Service on `port`: the port that already has a NodePort
+/// (the first one if none does) gets it, and the Service becomes a
+/// NodePort Service. A named instance, whose NodePorts were dropped, gets
+/// one back this way.
+fn set_ui_node_port(service: &mut serde_json::Value, port: u16) {
+    service["spec"]["type"] = "NodePort".into();
+    let Some(ports) = service["spec"]["ports"].as_array_mut() else {
+        return;
+    };
+    let index = ports.iter().position(|p| p.get("nodePort").is_some()).unwrap_or(0);
+    if let Some(entry) = ports.get_mut(index) {
+        entry["nodePort"] = port.into();
+    }
+}
+
+/// Point every container image of a workload at `mirror`.
+fn set_image_mirror(resource: &mut serde_json::Value, mirror: &str) {
+    let Some(pod_spec) = resource.pointer_mut("/spec/template/spec") else {
+        return;
+    };
+    for list in ["containers", "initContainers"] {
+        for c in pod_spec[list].as_array_mut().into_iter().flatten() {
+            if let Some(image) = c["image"].as_str() {
+                c["image"] = crate::images::mirror_image(image, mirror).into();
+            }
+        }
+    }
+}
+
+/// Pin every container whose image is a key of `digests` to that digest,
+/// so the cluster refuses an image other than the one the release names.
+pub fn pin_image_digests(resource: &mut serde_json::Value, digests: &BTreeMap<String, String>) {
+    let Some(pod_spec) = resource.pointer_mut("/spec/template/spec") else {
+        return;
+    };
+    for list in ["containers", "initContainers"] {
+        for c in pod_spec[list].as_array_mut().into_iter().flatten() {
+            let pinned = c["image"].as_str().and_then(|image| Some(format!("{}@{}", image, digests.get(image)?)));
+            if let Some(pinned) = pinned {
+                c["image"] = pinned.into();
+            }
+        }
+    }
+}
+
+/// The manifest's digests for the images in `references`, as pulled
+/// through `mirror` when one is set, for [`DeployOptions::image_digests`].
+/// They are keyed by the manifest's own reference, which is what the
+/// templates name before [`customize_resources`] mirrors them.
+pub fn image_digests(manifest: &Manifest, mirror: Option<&str>, references: &[String]) -> BTreeMap<String, String> {
+    manifest
+        .images
+        .iter()
+        .filter(|i| references.contains(&i.pull_reference(mirror)))
+        .filter_map(|i| Some((i.reference(), i.digest.clone()?)))
+        .collect()
+}
+
+/// Replace the template's PersistentVolumeClaims with hostPath directories
+/// under `root`: the claims are dropped and each pod volume that used one
+/// mounts `{root}/{component}` instead (`{root}/{component}/{volume}` when
+/// a pod has several), created on first use. Named instances get their own
+/// subdirectory so they don't share data.
+pub fn use_host_paths(resources: &mut Vec<serde_json::Value>, root: &str, instance: Option<&str>) {
+    resources.retain(|r| r["kind"] != "PersistentVolumeClaim");
+    let root = match instance {
+        Some(instance) => format!("{}/{}", root, instance),
+        None => root.to_string(),
+    };
+    for resource in resources {
+        let component = component_of(resource).to_string();
+        let Some(volumes) = resource
+            .pointer_mut("/spec/template/spec/volumes")
+            .and_then(|v| v.as_array_mut())
+        else {
+            continue;
+        };
+        let claims = volumes.iter().filter(|v| v.get("persistentVolumeClaim").is_some()).count();
+        for volume in volumes.iter_mut().filter(|v| v.get("persistentVolumeClaim").is_some()) {
+            let path = if claims > 1 {
+                format!("{}/{}/{}", root, component, volume["name"].as_str().unwrap_or_default())
+            } else {
+                format!("{}/{}", root, component)
+            };
+            let name = volume["name"].clone();
+            *volume = serde_json::json!({
+                "name": name,
+                "hostPath": {"path": path, "type": "DirectoryOrCreate"},
+            });
+        }
+    }
+}
+
+/// Raise matching containers' requests/limits to the given values; a
+/// template quantity that is already as large, and the template's other
+/// quantities, are kept.
+fn set_container_resources(
+    resource: &mut serde_json::Value,
+    resources: &BTreeMap<String, ContainerResources>,
+) {
+    let Some(containers) = resource
+        .pointer_mut("/spec/template/spec/containers")
+        .and_then(|c| c.as_array_mut())
+    else {
+        return;
+    };
+    for container in containers {
+        let Some(wanted) = container["image"]
+            .as_str()
+            .and_then(|image| resources.get(image_basename(image)))
+            .cloned()
+        else {
+            continue;
+        };
+        for (section, values) in [("requests", &wanted.requests), ("limits", &wanted.limits)] {
+            for (name, qty) in values {
+                // Quantities may be plain numbers (`cpu: 2`)
+                let current = match &container["resources"][section][name] {
+                    serde_json::Value::String(q) => Some(q.clone()),
+                    serde_json::Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                };
+                if current.is_none_or(|current| exceeds(name, qty, &current)) {
+                    container["resources"][section][name] = qty.clone().into();
+                }
+            }
+        }
+    }
+}
+
+/// Retime the readiness and liveness probes of matching containers. Only
+/// probes the template defines are touched.
+fn set_probe_tuning(resource: &mut serde_json::Value, probes: &BTreeMap<String, ProbeTuning>) {
+    let Some(containers) = resource
+        .pointer_mut("/spec/template/spec/containers")
+        .and_then(|c| c.as_array_mut())
+    else {
+        return;
+    };
+    for container in containers {
+        let Some(tuning) = container["image"]
+            .as_str()
+            .and_then(|image| probes.get(image_basename(image)))
+            .copied()
+        else {
+            continue;
+        };
+        for probe in ["readinessProbe", "livenessProbe"] {
+            let Some(probe) = container.get_mut(probe).filter(|p| p.is_object()) else {
+                continue;
+            };
+            if let Some(secs) = tuning.initial_delay_seconds {
+                probe["initialDelaySeconds"] = secs.into();
+            }
+            if let Some(secs) = tuning.period_seconds {
+                probe["periodSeconds"] = secs.into();
+            }
+        }
+    }
+}
+
+/// Repository name of an image reference, without registry path, tag or
+/// digest: `ghcr.io/org/bakerst-brain:0.6.0` -> `bakerst-brain`.
+pub fn image_basename(image: &str) -> &str {
+    let last = image.rsplit('/').next().unwrap_or(image);
+    let last = last.split('@').next().unwrap_or(last);
+    last.split(':').next().unwrap_or(last)
+}
+
+/// How a component's image changes when an existing install is re-run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageChange {
+    /// Not running yet.
+    New,
+    Unchanged,
+    Upgrade { from: String },
+    /// Older than the running release.
+    Downgrade { from: String },
+    /// A different tag or digest that can't be ordered, e.g. a rebuilt
+    /// `:latest`.
+    Changed { from: String },
+}
+
+/// One manifest component's image against what is running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentUpgrade {
+    pub component: String,
+    /// The manifest's tag (and digest, when it pins one).
+    pub to: String,
+    pub change: ImageChange,
+}
+
+/// The release already installed, without a leading "v": the namespace's
+/// version label when set, else the tag the running release images share.
+/// None when none of the manifest's images are running.
+pub fn installed_version(manifest: &Manifest, live_images: &[String], namespace_label: Option<&str>) -> Option<String> {
+    let ours: BTreeSet<&str> = manifest.images.iter().map(|i| image_basename(&i.image)).collect();
+    let running: Vec<&str> = live_images
+        .iter()
+        .map(String::as_str)
+        .filter(|image| ours.contains(image_basename(image)))
+        .collect();
+    if running.is_empty() {
+        return None;
+    }
+    let version = match namespace_label {
+        Some(label) => label,
+        None => {
+            let tags: BTreeSet<&str> = running.iter().filter_map(|i| image_tag_and_digest(i).0).collect();
+            match tags.len() {
+                1 => tags.into_iter().next()?,
+                _ => return None,
+            }
+        }
+    };
+    Some(version.trim_start_matches('v').to_string())
+}
+
+/// Compare the manifest's images with the container images of the live
+/// deployments, matched by repository basename. None when nothing is
+/// installed yet. Optional components that aren't running are left out.
+pub fn upgrade_plan(manifest: &Manifest, live_images: &[String]) -> Option<Vec<ComponentUpgrade>> {
+    if live_images.is_empty() {
+        return None;
+    }
+    let live: BTreeMap<&str, &str> = live_images.iter().map(|i| (image_basename(i), i.as_str())).collect();
+    let mut plan = Vec::new();
+    for image in &manifest.images {
+        let (tag, digest) = (image.tag.as_str(), image.digest.as_deref());
+        let change = match live.get(image_basename(&image.image)) {
+            None if !image.required => continue,
+            None => ImageChange::New,
+            Some(running) => {
+                let (from_tag, from_digest) = image_tag_and_digest(running);
+                let from = version_label(from_tag, from_digest);
+                match (from_tag == Some(tag), from_digest.zip(digest)) {
+                    (true, Some((a, b))) if a != b => ImageChange::Changed { from },
+                    (true, _) => ImageChange::Unchanged,
+                    (false, _) => match from_tag.and_then(|from_tag| compare_versions(from_tag, tag)) {
+                        Some(std::cmp::Ordering::Less) => ImageChange::Upgrade { from },
+                        Some(std::cmp::Ordering::Greater) => ImageChange::Downgrade { from },
+                        _ => ImageChange::Changed { from },
+                    },
+                }
+            }
+        };
+        plan.push(ComponentUpgrade {
+            component: image.name.clone(),
+            to: version_label(Some(tag), digest),
+            change,
+        });
+    }
+    Some(plan)
+}
+
+/// Tag and digest of an image reference, each if present.
+fn image_tag_and_digest(image: &str) -> (Option<&str>, Option<&str>) {
+    let (name, digest) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (image, None),
+    };
+    let last = name.rsplit('/').next().unwrap_or(name);
+    (last.split_once(':').map(|(_, tag)| tag), digest)
+}
+
+/// `0.6.0`, `0.6.0 (sha256:0123456789ab)` or, untagged, the short digest.
+fn version_label(tag: Option<&str>, digest: Option<&str>) -> String {
+    let short = digest.map(|d| &d[..d.len().min("sha256:".len() + 12)]);
+    match (tag, short) {
+        (Some(tag), Some(short)) => format!("{} ({})", tag, short),
+        (Some(tag), None) => tag.to_string(),
+        (None, Some(short)) => short.to_string(),
+        (None, None) => "latest".to_string(),
+    }
+}
+
+/// Order two release tags (`0.6.0`, `v1.2`, `0.7.0-rc1`) by their numeric
+/// parts; None if either isn't a version.
+pub fn compare_versions(a: &str, b: &str) -> Option<std::cmp::Ordering> {
+    let parse = |v: &str| -> Option<Vec<u64>> {
+        let v = v.strip_prefix('v').unwrap_or(v);
+        v.split('-').next()?.split('.').map(|part| part.parse().ok()).collect()
+    };
+    Some(parse(a)?.cmp(&parse(b)?))
+}
+
+/// Container images (basenames) referenced by workloads in `resources`.
+pub fn referenced_images(resources: &[serde_json::Value]) -> BTreeSet<String> {
+    let mut images = BTreeSet::new();
+    for resource in resources {
+        for list in ["containers", "initContainers"] {
+            let containers = resource["spec"]["template"]["spec"][list].as_array();
+            for c in containers.into_iter().flatten() {
+                if let Some(image) = c["image"].as_str() {
+                    images.insert(image_basename(image).to_string());
+                }
+            }
+        }
+    }
+    images
+}
+
+/// Manifest components whose image no template workload uses. These are
+/// in the release but this installer has nothing to deploy them with.
+pub fn components_without_templates(
+    manifest: &Manifest,
+    resources: &[serde_json::Value],
+) -> Vec<String> {
+    let referenced = referenced_images(resources);
+    manifest
+        .images
+        .iter()
+        .filter(|i| !referenced.contains(image_basename(&i.image)))
+        .map(|i| i.name.clone())
+        .collect()
+}
+
+/// Default NodePort range of the API server.
+pub const NODE_PORT_RANGE: std::ops::RangeInclusive<i32> = 30000..=32767;
+
+/// The NodePort the release's manifests give the UI's Service.
+pub const DEFAULT_UI_NODE_PORT: u16 = 30080;
+
+/// CPU and memory the Deployments in `resources` request across all their
+/// replicas. Containers without requests count as nothing, so this is a
+/// floor rather than an estimate.
+pub fn requested_resources(resources: &[serde_json::Value]) -> k8s::ResourceUsage {
+    // Quantities may be written as YAML numbers ("cpu: 1") or strings
+    let quantity = |v: &serde_json::Value| match v {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    let mut total = k8s::ResourceUsage::default();
+    for resource in resources.iter().filter(|r| r["kind"] == "Deployment") {
+        let replicas = resource["spec"]["replicas"].as_u64().unwrap_or(1);
+        let containers = resource["spec"]["template"]["spec"]["containers"].as_array();
+        for requests in containers.into_iter().flatten().map(|c| &c["resources"]["requests"]) {
+            let cpu = quantity(&requests["cpu"]).and_then(|q| k8s::parse_cpu_millicores(&q));
+            let memory = quantity(&requests["memory"]).and_then(|q| k8s::parse_memory_bytes(&q));
+            total.cpu_millicores += replicas * cpu.unwrap_or(0);
+            total.memory_bytes += replicas * memory.unwrap_or(0);
+        }
+    }
+    total
+}
+
+/// Where `needed` exceeds `free`, one line per resource; empty if it fits.
+pub fn capacity_shortfall(needed: k8s::ResourceUsage, free: k8s::ResourceUsage) -> Vec<String> {
+    const MI: u64 = 1024 * 1024;
+    let mut short = Vec::new();
+    if needed.cpu_millicores > free.cpu_millicores {
+        short.push(format!(
+            "CPU: the install requests {}m but only {}m is free (short by {}m)",
+            needed.cpu_millicores,
+            free.cpu_millicores,
+            needed.cpu_millicores - free.cpu_millicores
+        ));
+    }
+    if needed.memory_bytes > free.memory_bytes {
+        short.push(format!(
+            "memory: the install requests {}Mi but only {}Mi is free (short by {}Mi)",
+            needed.memory_bytes / MI,
+            free.memory_bytes / MI,
+            (needed.memory_bytes - free.memory_bytes).div_ceil(MI)
+        ));
+    }
+    short
+}
+
+/// NodePorts requested by Services in `resources`, as (port, service name).
+pub fn requested_node_ports(resources: &[serde_json::Value]) -> Vec<(i32, String)> {
+    let mut ports = Vec::new();
+    for resource in resources.iter().filter(|r| r["kind"] == "Service") {
+        let name = resource["metadata"]["name"].as_str().unwrap_or_default();
+        for port in resource["spec"]["ports"].as_array().into_iter().flatten() {
+            if let Some(node_port) = port["nodePort"].as_i64() {
+                ports.push((node_port as i32, name.to_string()));
+            }
+        }
+    }
+    ports
+}
+
+/// A requested NodePort already held by a Service we don't own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodePortConflict {
+    pub port: i32,
+    pub service: String,
+    /// "namespace/name" of the Service holding the port.
+    pub held_by: String,
+}
+
+/// Requested NodePorts taken by other Services. The same Service in our
+/// own namespace (a re-install) isn't a conflict.
+pub fn node_port_conflicts(
+    requested: &[(i32, String)],
+    in_use: &BTreeMap<i32, (String, String)>,
     namespace: &str,
-    dir: &std::path::Path,
-) -> Result<()> {
+) -> Vec<NodePortConflict> {
+    requested
+        .iter()
+        .filter_map(|(port, service)| {
+            let (ns, name) = in_use.get(port)?;
+            if ns == namespace && name == service {
+                return None;
+            }
+            Some(NodePortConflict {
+                port: *port,
+                service: service.clone(),
+                held_by: format!("{}/{}", ns, name),
+            })
+        })
+        .collect()
+}
+
+/// Lowest NodePort that is neither allocated nor requested by us.
+pub fn suggest_free_node_port(
+    in_use: &BTreeMap<i32, (String, String)>,
+    requested: &[(i32, String)],
+) -> Option<i32> {
+    NODE_PORT_RANGE
+        .clone()
+        .find(|p| !in_use.contains_key(p) && !requested.iter().any(|(r, _)| r == p))
+}
+
+/// Parse every YAML manifest under `dir`, recursively (kustomization files
+/// excluded).
+pub fn load_resources_recursive(dir: &std::path::Path) -> Result<Vec<serde_json::Value>> {
+    let mut resources = Vec::new();
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Cannot read manifest directory: {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            resources.extend(load_resources_recursive(&path)?);
+            continue;
+        }
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let is_yaml = matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml"));
+        if is_yaml && !name.starts_with("kustomization") {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read manifest: {}", path.display()))?;
+            resources.extend(
+                k8s::parse_yaml_docs(&content)
+                    .with_context(|| format!("Failed to parse manifest: {}", path.display()))?,
+            );
+        }
+    }
+    Ok(resources)
+}
+
+/// Read all YAML files from a directory (sorted) and parse them into
+/// resources, skipping kustomization files. `--set` values in `vars` are
+/// rendered in first; a `{{KEY}}` none of them resolves is an error.
+pub fn load_manifest_dir(dir: &std::path::Path, vars: &[(String, String)]) -> Result<Vec<serde_json::Value>> {
+    let yaml = read_manifest_dir(dir)?;
+    let vars: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let rendered = crate::templates::render_strict(&yaml, &vars)
+        .with_context(|| format!("Failed to render manifests in {}", dir.display()))?;
+    k8s::parse_yaml_docs(&rendered)
+}
+
+/// Keys the release's manifests (and enabled extensions) can take from
+/// `--set`.
+pub fn template_keys(k8s_dir: &std::path::Path, enabled_features: &[String]) -> Result<BTreeSet<String>> {
+    let mut keys = crate::templates::keys(&read_manifest_dir(&manifest_dir(k8s_dir))?);
+    for feature in enabled_features {
+        let ext_dir = k8s_dir.join("extensions").join(feature);
+        if ext_dir.exists() {
+            keys.extend(crate::templates::keys(&read_manifest_dir(&ext_dir)?));
+        }
+    }
+    Ok(keys)
+}
+
+/// The YAML files of a directory, sorted and joined into one stream.
+fn read_manifest_dir(dir: &std::path::Path) -> Result<String> {
     let mut paths: Vec<_> = std::fs::read_dir(dir)
         .with_context(|| format!("Cannot read manifest directory: {}", dir.display()))?
         .filter_map(|e| e.ok())
@@ -75,7 +1059,7 @@ pub async fn apply_manifests_from_dir(
             let is_yaml = matches!(p.extension().and_then(|e| e.to_str()), Some("yaml" | "yml"));
             let is_kustomization = p.file_name()
                 .and_then(|n| n.to_str())
-                .map_or(false, |n| n.starts_with("kustomization"));
+                .is_some_and(|n| n.starts_with("kustomization"));
             is_yaml && !is_kustomization
         })
         .collect();
@@ -87,29 +1071,527 @@ pub async fn apply_manifests_from_dir(
             .with_context(|| format!("Failed to read manifest: {}", path.display()))?;
         yamls.push(content);
     }
-    let combined = yamls.join("\n---\n");
-    let applied = k8s::apply_yaml(client, namespace, &combined).await?;
-    for label in &applied {
-        println!("  Applied: {}", label);
+    Ok(yamls.join("\n---\n"))
+}
+
+/// The directory holding the main manifests: the template always bundles
+/// pre-rendered YAML in overlays/remote/, older layouts keep it in k8s/.
+pub fn manifest_dir(k8s_dir: &std::path::Path) -> std::path::PathBuf {
+    let remote_overlay = k8s_dir.join("overlays/remote");
+    if remote_overlay.exists() {
+        remote_overlay
+    } else {
+        k8s_dir.to_path_buf()
     }
-    Ok(())
 }
 
-/// Apply extension manifests for enabled features.
-pub async fn apply_extensions(
+const INGRESS_TEMPLATE: &str = include_str!("deploy/ingress.yaml");
+
+/// The Ingress routing `host` to the UI Service (of the `opts` instance, if
+/// named), with the `opts` TLS Secret and class if given.
+pub fn ingress_object(host: &str, opts: &DeployOptions) -> Result<serde_json::Value> {
+    let ui_service = crate::instance::prefixed(opts.instance.as_deref(), "ui");
+    let mut vars = vec![("HOST", host), ("UI_SERVICE", ui_service.as_str())];
+    if let Some(secret) = &opts.ingress_tls_secret {
+        vars.push(("TLS_SECRET", secret));
+    }
+    if let Some(class) = &opts.ingress_class {
+        vars.push(("INGRESS_CLASS", class));
+    }
+    let yaml = crate::templates::render_strict(INGRESS_TEMPLATE, &vars)?;
+    k8s::parse_yaml_docs(&yaml)?
+        .pop()
+        .context("ingress template has no object")
+}
+
+/// Where the `--ingress-host` Ingress serves the UI: https with a
+/// `--ingress-tls-secret`, plain http without.
+pub fn ingress_url(host: &str, tls_secret: Option<&str>) -> String {
+    match tls_secret {
+        Some(_) => format!("https://{}", host),
+        None => format!("http://{}", host),
+    }
+}
+
+/// Everything an install or update would apply: the main manifests plus
+/// enabled extensions, with per-install adjustments made.
+pub fn render_target(
+    k8s_dir: &std::path::Path,
+    enabled_features: &[String],
+    opts: &DeployOptions,
+) -> Result<Vec<serde_json::Value>> {
+    let mut resources = load_manifest_dir(&manifest_dir(k8s_dir), &opts.template_vars)?;
+    let extensions_dir = k8s_dir.join("extensions");
+    for feature in enabled_features {
+        let ext_dir = extensions_dir.join(feature);
+        if ext_dir.exists() {
+            resources.extend(load_manifest_dir(&ext_dir, &opts.template_vars)?);
+        }
+    }
+    customize_resources(&mut resources, opts);
+    if let Some(host) = &opts.ingress_host {
+        resources.push(ingress_object(host, opts)?);
+    }
+    Ok(resources)
+}
+
+/// Every object an install applies, in apply order: namespace, secrets,
+/// the bakerst-os ConfigMap, then manifests and enabled extensions. Used by
+/// `--dry-run` to print what would be applied.
+pub fn render_install(
+    namespace: serde_json::Value,
+    schema: &ConfigSchema,
+    config: &InterviewResult,
+    trim_secrets: bool,
+    os_files: Option<&BTreeMap<String, String>>,
+    k8s_dir: &std::path::Path,
+    opts: &DeployOptions,
+) -> Result<Vec<serde_json::Value>> {
+    let ns = config.namespace.as_str();
+    let mut objects = vec![namespace];
+
+    let mut secret_groups = route_secrets(schema, config);
+    if trim_secrets {
+        trim_secret_values(&mut secret_groups);
+    }
+    for (name, data) in &secret_groups {
+        objects.push(k8s::secret_object(ns, name, data));
+    }
+    if let Some(files) = os_files {
+        let name = crate::instance::prefixed(config.instance.as_deref(), k8s::OS_CONFIGMAP);
+        objects.push(k8s::os_configmap_object(ns, &name, files));
+    }
+
+    for mut resource in render_target(k8s_dir, &config.enabled_features, opts)? {
+        resource["metadata"]["namespace"] = ns.into();
+        resource["metadata"]["labels"][k8s::MANAGED_BY_LABEL] = k8s::MANAGED_BY_VALUE.into();
+        objects.push(resource);
+    }
+    // As stamped at apply time; the namespace is shared between instances
+    for object in objects.iter_mut().skip(1) {
+        for (key, value) in &opts.apply.labels {
+            object["metadata"]["labels"][key] = value.as_str().into();
+        }
+    }
+    Ok(objects)
+}
+
+/// Replace Secret values with `***` (as `stringData`, so the object stays
+/// valid) for printing.
+pub fn mask_secret_values(objects: &mut [serde_json::Value]) {
+    for obj in objects.iter_mut().filter(|o| o["kind"] == "Secret") {
+        let Some(map) = obj.as_object_mut() else { continue };
+        let mut keys: Vec<String> = Vec::new();
+        for field in ["data", "stringData"] {
+            if let Some(data) = map.remove(field) {
+                keys.extend(data.as_object().into_iter().flat_map(|d| d.keys().cloned()));
+            }
+        }
+        let masked: serde_json::Map<_, _> = keys.into_iter().map(|k| (k, "***".into())).collect();
+        map.insert("stringData".into(), masked.into());
+    }
+}
+
+/// Multi-document YAML, one object per document.
+pub fn to_yaml_stream(objects: &[serde_json::Value]) -> Result<String> {
+    let mut out = String::new();
+    for obj in objects {
+        out.push_str("---\n");
+        out.push_str(&serde_yaml::to_string(obj)?);
+    }
+    Ok(out)
+}
+
+/// Print a unified diff of every target resource against the live cluster
+/// (server-side dry run vs live, like `kubectl diff`). Nothing is applied.
+/// Returns how many resources would change.
+pub async fn diff_against_cluster(
     client: &kube::Client,
     namespace: &str,
+    resources: &[serde_json::Value],
+    params: &k8s::ApplyParams,
+) -> Result<usize> {
+    let mut changed = 0;
+    for resource in resources {
+        let label = format!(
+            "{}/{}",
+            resource["kind"].as_str().unwrap_or("Unknown"),
+            resource["metadata"]["name"].as_str().unwrap_or("unnamed")
+        );
+        let (live, desired) = k8s::dry_run_apply(client, namespace, resource, params)
+            .await
+            .with_context(|| format!("diff {}", label))?;
+        if let Some(diff) = resource_diff(&label, live.as_ref(), &desired) {
+            print!("{}", diff);
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Unified diff between the live and desired form of one resource, or None
+/// when they match. Secret values are masked.
+pub fn resource_diff(
+    label: &str,
+    live: Option<&serde_json::Value>,
+    desired: &serde_json::Value,
+) -> Option<String> {
+    let mut live = live.cloned();
+    let mut desired = desired.clone();
+    k8s::normalize_for_diff(&mut desired);
+    if let Some(live) = live.as_mut() {
+        k8s::normalize_for_diff(live);
+        if desired["kind"] == "Secret" {
+            k8s::mask_secret_data(live, &mut desired);
+        }
+    } else if desired["kind"] == "Secret" {
+        k8s::mask_secret_data(&mut serde_json::Value::Null, &mut desired);
+    }
+
+    let before = match &live {
+        Some(v) => serde_yaml::to_string(v).unwrap_or_default(),
+        None => String::new(),
+    };
+    let after = serde_yaml::to_string(&desired).unwrap_or_default();
+    if before == after {
+        return None;
+    }
+    let diff = similar::TextDiff::from_lines(&before, &after);
+    Some(
+        diff.unified_diff()
+            .context_radius(3)
+            .header(&format!("live/{}", label), &format!("desired/{}", label))
+            .to_string(),
+    )
+}
+
+/// `(kind, name)` of a resource object.
+pub fn resource_key(resource: &serde_json::Value) -> (String, String) {
+    (
+        resource["kind"].as_str().unwrap_or("Unknown").to_string(),
+        resource["metadata"]["name"].as_str().unwrap_or("unnamed").to_string(),
+    )
+}
+
+/// Everything an install or update owns: the rendered manifests, the
+/// routed secrets and the bakerst-os ConfigMap. Managed resources outside
+/// this set are what `--prune` removes.
+pub fn owned_resources(
+    resources: &[serde_json::Value],
+    schema: &ConfigSchema,
+    config: &InterviewResult,
+) -> BTreeSet<(String, String)> {
+    let mut owned: BTreeSet<_> = resources.iter().map(resource_key).collect();
+    for secret in route_secrets(schema, config).keys() {
+        owned.insert(("Secret".to_string(), secret.clone()));
+    }
+    owned.insert((
+        "ConfigMap".to_string(),
+        crate::instance::prefixed(config.instance.as_deref(), k8s::OS_CONFIGMAP),
+    ));
+    owned
+}
+
+/// Managed resources no longer in `owned`. The namespace itself is never
+/// a candidate.
+pub fn prune_candidates(
+    live: &[k8s::ManagedResource],
+    owned: &BTreeSet<(String, String)>,
+) -> Vec<(String, String)> {
+    live.iter()
+        .filter(|r| r.kind != "Namespace")
+        .map(|r| (r.kind.clone(), r.name.clone()))
+        .filter(|key| !owned.contains(key))
+        .collect()
+}
+
+/// Delete the instance's managed resources that are no longer part of the
+/// install, after listing them and asking for confirmation (unless
+/// `assume_yes`). Returns how many were deleted.
+pub async fn prune(
+    client: &kube::Client,
+    namespace: &str,
+    instance: Option<&str>,
+    owned: &BTreeSet<(String, String)>,
+    assume_yes: bool,
+) -> Result<usize> {
+    let (live, denied) = k8s::list_managed_resources(client, namespace, instance).await?;
+    if !denied.is_empty() {
+        println!("  Note: not allowed to list {}; those kinds were not pruned", denied.join(", "));
+    }
+    let stale = prune_candidates(&live, owned);
+    if stale.is_empty() {
+        println!("  Nothing to prune");
+        return Ok(0);
+    }
+
+    println!("  No longer in the manifest:");
+    for (kind, name) in &stale {
+        println!("    {}/{}", kind, name);
+    }
+    if !assume_yes {
+        use std::io::Write;
+        print!("  Delete these {} resource(s)? [y/N] ", stale.len());
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("  Skipped pruning");
+            return Ok(0);
+        }
+    }
+
+    for (kind, name) in &stale {
+        k8s::delete_managed(client, namespace, kind, name).await?;
+        println!("    \u{2717} pruned {}/{}", kind, name);
+    }
+    Ok(stale.len())
+}
+
+/// What undoing a failed apply deletes: the journaled resources, newest
+/// first, except those that existed before the install started (a
+/// reinstall must not delete what it only updated). Resources of the
+/// `unlisted` kinds, which couldn't be listed beforehand, may have existed
+/// too, so they are left as well.
+pub fn rollback_plan(
+    applied: &[String],
+    preexisting: &BTreeSet<(String, String)>,
+    unlisted: &BTreeSet<String>,
+) -> Vec<(String, String)> {
+    let mut seen = BTreeSet::new();
+    applied
+        .iter()
+        .rev()
+        .filter_map(|label| label.split_once('/'))
+        .map(|(kind, name)| (kind.to_string(), name.to_string()))
+        .filter(|key| !unlisted.contains(&key.0) && !preexisting.contains(key) && seen.insert(key.clone()))
+        .collect()
+}
+
+/// Delete the resources in `plan`, in order, printing each. Keeps going
+/// past failures so as much as possible is undone; returns how many were
+/// deleted.
+pub async fn rollback(client: &kube::Client, namespace: &str, plan: &[(String, String)]) -> usize {
+    let mut deleted = 0;
+    for (kind, name) in plan {
+        match k8s::delete_managed(client, namespace, kind, name).await {
+            Ok(()) => {
+                deleted += 1;
+                println!("    \u{21b6} rolled back {}/{}", kind, name);
+            }
+            Err(e) => println!("    ! could not roll back {}/{}: {:#}", kind, name, e),
+        }
+    }
+    deleted
+}
+
+/// Read all YAML files from a directory (sorted), concatenate, and apply.
+/// Returns the `Kind/name` of each applied resource.
+pub async fn apply_manifests_from_dir(
+    client: &impl k8s::ClusterClient,
+    namespace: &str,
+    dir: &std::path::Path,
+    opts: &DeployOptions,
+) -> Result<Vec<String>> {
+    let mut applied = Vec::new();
+    apply_reported(client, namespace, &load_customized(dir, opts)?, opts, &mut applied).await?;
+    Ok(applied)
+}
+
+/// The resources in `dir`, rendered and customized per `opts`.
+fn load_customized(dir: &std::path::Path, opts: &DeployOptions) -> Result<Vec<serde_json::Value>> {
+    let mut resources = load_manifest_dir(dir, &opts.template_vars)?;
+    customize_resources(&mut resources, opts);
+    Ok(resources)
+}
+
+/// Apply the `--ingress-host` Ingress, if there is one. Returns its
+/// `Kind/name`.
+pub async fn apply_ingress(
+    client: &impl k8s::ClusterClient,
+    namespace: &str,
+    opts: &DeployOptions,
+) -> Result<Vec<String>> {
+    let mut applied = Vec::new();
+    if let Some(host) = &opts.ingress_host {
+        let ingress = ingress_object(host, opts)?;
+        apply_reported(client, namespace, &[ingress], opts, &mut applied).await?;
+    }
+    Ok(applied)
+}
+
+/// Apply `resources`, journaling each as it is applied and printing each
+/// component's once all of them are. The `Kind/name` of each is pushed to
+/// `applied` as it goes in, so a failed apply still records the rest.
+async fn apply_reported(
+    client: &impl k8s::ClusterClient,
+    namespace: &str,
+    resources: &[serde_json::Value],
+    opts: &DeployOptions,
+    applied: &mut Vec<String>,
+) -> Result<()> {
+    // Applies finish out of order, so each component's kind/names are held
+    // back until its last one, then printed together under it
+    let verb = if opts.apply.dry_run { "validated " } else { "" };
+    let mut remaining: BTreeMap<&str, usize> = BTreeMap::new();
+    for resource in resources {
+        *remaining.entry(component_of(resource)).or_default() += 1;
+    }
+    let mut buffered: Vec<(String, Vec<String>)> = Vec::new();
+    let result = k8s::apply_resources(client, namespace, resources, &opts.apply, |resource, label| {
+        let component = component_of(resource);
+        crate::output::emit(&crate::output::Event::Deploy { component, resource: label });
+        if let Some(journal) = &opts.journal {
+            journal.lock().unwrap().push(label.to_string());
+        }
+        applied.push(label.to_string());
+        let line = format!("    \u{2713} {}{}", verb, label);
+        match buffered.iter_mut().find(|(c, _)| c == component) {
+            Some((_, lines)) => lines.push(line),
+            None => buffered.push((component.to_string(), vec![line])),
+        }
+        let left = remaining.get_mut(component).expect("every applied resource was counted");
+        *left -= 1;
+        if *left == 0 {
+            let i = buffered.iter().position(|(c, _)| c == component).unwrap();
+            print_component(&buffered.remove(i));
+        }
+    })
+    .await;
+    // Those a failure left part-applied
+    buffered.iter().for_each(print_component);
+    result.map(|_| ())
+}
+
+/// Print a component's applied resources, indented under its name.
+fn print_component((component, lines): &(String, Vec<String>)) {
+    println!("  {}", component);
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+/// Component a resource belongs to (its `app` label), used to group apply
+/// output. Shared resources such as network policies have no `app` label.
+pub fn component_of(resource: &serde_json::Value) -> &str {
+    resource["metadata"]["labels"]["app"]
+        .as_str()
+        .unwrap_or("shared")
+}
+
+/// Apply extension manifests for enabled features. Returns the
+/// `Kind/name` of each applied resource.
+pub async fn apply_extensions(
+    client: &impl k8s::ClusterClient,
+    namespace: &str,
     extensions_dir: &std::path::Path,
     enabled_features: &[String],
-) -> Result<()> {
+    opts: &DeployOptions,
+) -> Result<Vec<String>> {
+    let mut applied = Vec::new();
     if extensions_dir.exists() {
         for feature in enabled_features {
             let ext_dir = extensions_dir.join(feature);
             if ext_dir.exists() {
                 println!("  Applying extension: {}", feature);
-                apply_manifests_from_dir(client, namespace, &ext_dir).await?;
+                applied.extend(apply_manifests_from_dir(client, namespace, &ext_dir, opts).await?);
             }
         }
     }
+    Ok(applied)
+}
+
+/// One re-runnable part of the apply phase. A failed step can be retried
+/// without re-applying the steps before it, or those of its resources that
+/// did go in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployStep {
+    /// The release's own manifests.
+    Manifests,
+    /// The `--ingress-host` Ingress.
+    Ingress,
+    /// The manifests of one enabled feature.
+    Extension(String),
+}
+
+impl std::fmt::Display for DeployStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeployStep::Manifests => write!(f, "manifests"),
+            DeployStep::Ingress => write!(f, "ingress"),
+            DeployStep::Extension(feature) => write!(f, "extension {}", feature),
+        }
+    }
+}
+
+/// What the apply phase has done so far and what it still has to do.
+#[derive(Debug, Default)]
+pub struct DeployProgress {
+    /// `Kind/name` of each resource applied by the finished steps.
+    pub applied: Vec<String>,
+    /// Steps still to run, the failed one (if any) first.
+    pub remaining: Vec<DeployStep>,
+}
+
+impl DeployProgress {
+    /// The steps of an install into `k8s_dir`: the manifests, the Ingress
+    /// when there is one, then each enabled feature that has extensions.
+    pub fn new(k8s_dir: &std::path::Path, enabled_features: &[String], opts: &DeployOptions) -> Self {
+        let mut remaining = vec![DeployStep::Manifests];
+        if opts.ingress_host.is_some() {
+            remaining.push(DeployStep::Ingress);
+        }
+        let extensions_dir = k8s_dir.join("extensions");
+        remaining.extend(
+            enabled_features
+                .iter()
+                .filter(|feature| extensions_dir.join(feature).exists())
+                .map(|feature| DeployStep::Extension(feature.clone())),
+        );
+        Self { applied: Vec::new(), remaining }
+    }
+
+    /// The step that failed last, if the phase is unfinished.
+    pub fn failed(&self) -> Option<&DeployStep> {
+        self.remaining.first()
+    }
+}
+
+/// Run the remaining steps in order. A step leaves `remaining` only once
+/// it succeeds, so after an error calling this again resumes at the failed
+/// step, applying only what it had not.
+pub async fn apply_steps(
+    client: &impl k8s::ClusterClient,
+    namespace: &str,
+    k8s_dir: &std::path::Path,
+    progress: &mut DeployProgress,
+    opts: &DeployOptions,
+) -> Result<()> {
+    while let Some(step) = progress.remaining.first() {
+        let mut resources = match step {
+            DeployStep::Manifests => load_customized(&manifest_dir(k8s_dir), opts)?,
+            DeployStep::Ingress => match &opts.ingress_host {
+                Some(host) => vec![ingress_object(host, opts)?],
+                None => Vec::new(),
+            },
+            DeployStep::Extension(feature) => {
+                println!("  Applying extension: {}", feature);
+                load_customized(&k8s_dir.join("extensions").join(feature), opts)?
+            }
+        };
+        resources.retain(|r| !progress.applied.contains(&k8s::resource_label(r)));
+        apply_reported(client, namespace, &resources, opts, &mut progress.applied).await?;
+        progress.remaining.remove(0);
+    }
     Ok(())
 }
+
+/// Names of the Deployments (and StatefulSets) among applied `Kind/name`
+/// labels: the components the health wait must watch.
+pub fn applied_deployments(applied: &[String]) -> Vec<String> {
+    applied
+        .iter()
+        .filter_map(|label| label.strip_prefix("Deployment/").or_else(|| label.strip_prefix("StatefulSet/")))
+        .map(String::from)
+        .collect()
+}
@@ -0,0 +1,245 @@
+//! Likely causes and fixes for pods that never became ready.
+//!
+//! A health-wait timeout on its own is a dead end; matching each stuck
+//! pod's state against the common failure patterns turns it into a short,
+//! ranked list of things to check and the commands to check them with.
+
+use crate::health::PodHealth;
+
+/// One likely cause of a pod failure, with what to do about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub cause: String,
+    pub fix: String,
+    /// A command that checks or fixes the cause, if there is one.
+    pub command: Option<String>,
+}
+
+impl Suggestion {
+    fn new(cause: &str, fix: &str, command: Option<String>) -> Self {
+        Self {
+            cause: cause.into(),
+            fix: fix.into(),
+            command,
+        }
+    }
+}
+
+/// Error reasons the model and chat APIs give for a rejected API key or
+/// token, as they appear in the `type` or `code` of the JSON error body.
+const AUTH_REASONS: &[&str] = &["authentication_error", "invalid_api_key", "invalid_token", "Unauthorized"];
+
+/// Whether a log line carries an API error body that rejects the
+/// credentials: an auth reason or a 401 status in the structured error,
+/// rather than those words appearing anywhere in the line.
+fn is_auth_rejection(line: &str) -> bool {
+    let Some(start) = line.find('{') else {
+        return false;
+    };
+    let Some(Ok(body)) = serde_json::Deserializer::from_str(&line[start..])
+        .into_iter::<serde_json::Value>()
+        .next()
+    else {
+        return false;
+    };
+    [&body, &body["error"]].iter().any(|v| {
+        let reason = |key: &str| v[key].as_str().is_some_and(|r| AUTH_REASONS.contains(&r));
+        let status = |key: &str| v[key].as_u64() == Some(401);
+        reason("type") || reason("code") || status("status") || status("statusCode") || status("error_code")
+    })
+}
+
+/// Phrases kube-scheduler uses in a `PodScheduled=False` message, per cause.
+const UNBOUND_CLAIMS: &[&str] = &["unbound immediate PersistentVolumeClaims", "persistentvolumeclaim \""];
+const INSUFFICIENT_RESOURCES: &[&str] = &["Insufficient cpu", "Insufficient memory"];
+const UNTOLERATED_TAINTS: &[&str] = &["untolerated taint", "that the pod didn't tolerate"];
+
+/// Likely causes of `pod` not becoming ready, most likely first. The last
+/// suggestion is always to read the pod's events.
+pub fn diagnose(pod: &PodHealth, namespace: &str) -> Vec<Suggestion> {
+    let describe = format!("kubectl describe pod -n {} {}", namespace, pod.name);
+    let mut out = Vec::new();
+    let error = pod.error.as_deref().unwrap_or_default();
+    // Fatal waiting reasons carry the kubelet's message after the reason
+    let reason = error.split(": ").next().unwrap_or_default();
+
+    match reason {
+        "ImagePullBackOff" | "ErrImagePull" | "InvalidImageName" => {
+            out.push(Suggestion::new(
+                "The image name or tag does not exist",
+                "Check the image reference; a local build must be loaded into the cluster",
+                (!pod.image.is_empty()).then(|| format!("docker pull {}", pod.image)),
+            ));
+            out.push(Suggestion::new(
+                "The registry needs credentials the cluster doesn't have",
+                "Create an image pull secret for the registry, or log in and pre-pull the image",
+                Some(format!("kubectl get events -n {} --field-selector involvedObject.name={}", namespace, pod.name)),
+            ));
+        }
+        "CrashLoopBackOff" => {
+            let logs = pod.logs_tail.as_deref().unwrap_or_default();
+            if logs.lines().any(is_auth_rejection) {
+                out.push(Suggestion::new(
+                    "The container is exiting on a rejected API key or token",
+                    "Check the ANTHROPIC_API_KEY and other tokens, then re-enter them",
+                    Some("bakerst-install update --reconfigure".into()),
+                ));
+            }
+            out.push(Suggestion::new(
+                "The container keeps crashing on startup",
+                "Read the logs of the crashed container",
+                Some(format!("kubectl logs -n {} {} --previous", namespace, pod.name)),
+            ));
+        }
+        "CreateContainerConfigError" => {
+            out.push(Suggestion::new(
+                "A Secret or ConfigMap the container needs is missing",
+                "Check that the install's secrets exist and hold every key",
+                Some(format!("kubectl get secrets,configmaps -n {}", namespace)),
+            ));
+        }
+        _ => {}
+    }
+
+    if let Some(message) = error.strip_prefix("Unschedulable: ") {
+        let mentions = |phrases: &[&str]| phrases.iter().any(|p| message.contains(p));
+        if mentions(UNBOUND_CLAIMS) {
+            out.push(Suggestion::new(
+                "A PersistentVolumeClaim is Pending with no volume to bind",
+                "Check that a storage class exists, or pass --storage-class",
+                Some(format!("kubectl get pvc -n {} && kubectl get storageclass", namespace)),
+            ));
+        }
+        if mentions(INSUFFICIENT_RESOURCES) {
+            out.push(Suggestion::new(
+                "No node has enough free CPU or memory",
+                "Free up capacity, add a node, or install with --resource-profile small",
+                Some("kubectl describe nodes".into()),
+            ));
+        }
+        if mentions(UNTOLERATED_TAINTS) {
+            out.push(Suggestion::new(
+                "Every node has a taint the pod doesn't tolerate",
+                "Remove the taint or schedule on an untainted node",
+                Some("kubectl get nodes -o custom-columns=NAME:.metadata.name,TAINTS:.spec.taints".into()),
+            ));
+        }
+    }
+
+    out.push(Suggestion::new(
+        "Something else is holding the pod back",
+        "Read the pod's events",
+        Some(describe),
+    ));
+    out
+}
+
+/// Print the suggestions for each stuck pod, numbered by rank.
+pub fn print_diagnosis(pods: &[PodHealth], namespace: &str) {
+    for pod in pods {
+        let state = pod.error.as_deref().unwrap_or(&pod.phase);
+        println!("\n  {} ({}) -- likely causes:", pod.name, state);
+        for (i, suggestion) in diagnose(pod, namespace).iter().enumerate() {
+            println!("    {}. {}: {}", i + 1, suggestion.cause, suggestion.fix);
+            if let Some(command) = &suggestion.command {
+                println!("         $ {}", command);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(error: Option<&str>, logs: Option<&str>) -> PodHealth {
+        PodHealth {
+            name: "brain-7f9-a".into(),
+            deployment: "brain".into(),
+            ready: false,
+            phase: "Pending".into(),
+            image: "ghcr.io/org/bakerst-brain:0.6.0".into(),
+            restarts: 0,
+            error: error.map(String::from),
+            logs_tail: logs.map(String::from),
+            usage: None,
+        }
+    }
+
+    fn causes(pod: &PodHealth) -> Vec<String> {
+        diagnose(pod, "bakerst").into_iter().map(|s| s.cause).collect()
+    }
+
+    #[test]
+    fn image_pull_failures_point_at_the_image_then_credentials() {
+        let suggestions = diagnose(&pod(Some("ImagePullBackOff"), None), "bakerst");
+        assert_eq!(suggestions[0].cause, "The image name or tag does not exist");
+        assert_eq!(suggestions[0].command.as_deref(), Some("docker pull ghcr.io/org/bakerst-brain:0.6.0"));
+        assert!(suggestions[1].cause.contains("credentials"));
+        assert_eq!(suggestions.len(), 3);
+    }
+
+    #[test]
+    fn crash_loops_with_auth_errors_point_at_the_token() {
+        let logs = "Error: 401 {\"type\":\"authentication_error\",\"message\":\"invalid x-api-key\"}";
+        let suggestions = diagnose(&pod(Some("CrashLoopBackOff"), Some(logs)), "bakerst");
+        assert!(suggestions[0].cause.contains("rejected API key"));
+        assert_eq!(suggestions[0].command.as_deref(), Some("bakerst-install update --reconfigure"));
+
+        // Without an auth error the logs are the first thing to read
+        let suggestions = diagnose(&pod(Some("CrashLoopBackOff"), Some("panic: boom")), "bakerst");
+        assert_eq!(
+            suggestions[0].command.as_deref(),
+            Some("kubectl logs -n bakerst brain-7f9-a --previous")
+        );
+
+        // Only the error body counts: a 401 or "unauthorized" elsewhere in the line doesn't
+        let logs = "GET /api/users/unauthorized took 401ms\n{\"level\":\"error\",\"msg\":\"boom\"}";
+        assert_eq!(causes(&pod(Some("CrashLoopBackOff"), Some(logs)))[0], "The container keeps crashing on startup");
+
+        let logs = "Error: 401 {\"type\":\"error\",\"error\":{\"type\":\"authentication_error\"}}";
+        assert!(causes(&pod(Some("CrashLoopBackOff"), Some(logs)))[0].contains("rejected API key"));
+    }
+
+    #[test]
+    fn unschedulable_pods_point_at_resources_or_taints() {
+        let p = pod(Some("Unschedulable: 0/1 nodes are available: 1 Insufficient memory."), None);
+        assert_eq!(causes(&p)[0], "No node has enough free CPU or memory");
+
+        let p = pod(
+            Some("Unschedulable: 0/2 nodes are available: 2 node(s) had untolerated taint {gpu: true}."),
+            None,
+        );
+        assert_eq!(causes(&p)[0], "Every node has a taint the pod doesn't tolerate");
+    }
+
+    #[test]
+    fn pending_volume_claims_point_at_the_storage_class() {
+        let p = pod(
+            Some("Unschedulable: 0/1 nodes are available: pod has unbound immediate PersistentVolumeClaims."),
+            None,
+        );
+        let suggestions = diagnose(&p, "bakerst");
+        assert!(suggestions[0].fix.contains("--storage-class"));
+        assert_eq!(
+            suggestions[0].command.as_deref(),
+            Some("kubectl get pvc -n bakerst && kubectl get storageclass")
+        );
+    }
+
+    #[test]
+    fn missing_config_points_at_secrets() {
+        let p = pod(Some("CreateContainerConfigError"), None);
+        assert_eq!(causes(&p)[0], "A Secret or ConfigMap the container needs is missing");
+    }
+
+    #[test]
+    fn unknown_states_fall_back_to_the_pod_events() {
+        let suggestions = diagnose(&pod(None, None), "bakerst");
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(
+            suggestions[0].command.as_deref(),
+            Some("kubectl describe pod -n bakerst brain-7f9-a")
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! `--env-file`: secrets and settings from a `.env`-style file.
+//!
+//! The file's values sit under the real environment: everywhere the
+//! installer reads an env var (the interview's "found in your environment"
+//! defaults, `--non-interactive`, `${VAR}` in a config file) it sees the
+//! environment first and the file second. Values are never printed, and
+//! parse errors name the line, not its contents.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+static VALUES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Read `path` and make its values visible to [`var`]. Returns the number
+/// of keys it set; only the first call in a process takes effect.
+pub fn load(path: &std::path::Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read env file {}", path.display()))?;
+    let values = parse(&text).with_context(|| format!("Invalid env file {}", path.display()))?;
+    let count = values.len();
+    let _ = VALUES.set(values);
+    Ok(count)
+}
+
+/// `key` from the environment, else from the `--env-file`.
+pub fn var(key: &str) -> Option<String> {
+    std::env::var(key).ok().or_else(|| VALUES.get()?.get(key).cloned())
+}
+
+/// Whether `key`'s value comes from the `--env-file` rather than the
+/// environment, so the interview can take it without asking.
+pub fn from_file(key: &str) -> bool {
+    std::env::var_os(key).is_none() && VALUES.get().is_some_and(|values| values.contains_key(key))
+}
+
+/// Parse `KEY=VALUE` lines. Blank lines and `#` comments are skipped, an
+/// `export ` prefix is allowed, and values may be single-quoted (taken
+/// literally) or double-quoted (`\n`, `\"` and `\\` escapes). An unquoted
+/// value ends at a ` #` comment. A later line overrides an earlier one.
+pub fn parse(text: &str) -> Result<HashMap<String, String>> {
+    let mut values = HashMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("line {}: expected KEY=VALUE", i + 1);
+        };
+        let key = key.trim();
+        let valid = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            bail!("line {}: invalid variable name '{}'", i + 1, key);
+        }
+        let value = parse_value(value.trim()).with_context(|| format!("line {}: {}", i + 1, key))?;
+        values.insert(key.to_string(), value);
+    }
+    Ok(values)
+}
+
+fn parse_value(raw: &str) -> Result<String> {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let Some(end) = rest.find('\'') else {
+            bail!("unterminated single quote");
+        };
+        return Ok(rest[..end].to_string());
+    }
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(value),
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        bail!("unterminated double quote");
+    }
+    let value = raw.find(" #").map_or(raw, |i| &raw[..i]);
+    Ok(value.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_comments_quotes_and_export() {
+        let values = parse(
+            "# Baker Street secrets\n\
+             \n\
+             ANTHROPIC_API_KEY=sk-ant-123 # the main key\n\
+             export AGENT_NAME=\"Baker Street\"\n\
+             GREETING=\"line one\\nline \\\"two\\\"\"\n\
+             LITERAL='a \\n # b'\n\
+             EMPTY=\n\
+             ANTHROPIC_API_KEY=sk-ant-456\n",
+        )
+        .unwrap();
+        assert_eq!(values["ANTHROPIC_API_KEY"], "sk-ant-456");
+        assert_eq!(values["AGENT_NAME"], "Baker Street");
+        assert_eq!(values["GREETING"], "line one\nline \"two\"");
+        assert_eq!(values["LITERAL"], "a \\n # b");
+        assert_eq!(values["EMPTY"], "");
+        assert_eq!(values.len(), 5);
+    }
+
+    #[test]
+    fn errors_name_the_line_but_not_the_value() {
+        let err = parse("OK=1\nno equals sign\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected KEY=VALUE");
+        let err = parse("TOKEN=\"sk-secret\n").unwrap_err();
+        assert!(!format!("{:#}", err).contains("sk-secret"), "{:#}", err);
+        assert!(parse("2FA=on").is_err());
+    }
+}
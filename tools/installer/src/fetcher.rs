@@ -8,18 +8,119 @@ use crate::manifest::Manifest;
 const GITHUB_API: &str = "https://api.github.com";
 const REPO: &str = "The-Baker-Street-Project/baker-street";
 
+/// Where a fetched manifest came from, for the status line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestSource {
+    File,
+    Network,
+    /// A copy saved by an earlier run, used because the download failed.
+    Cache,
+}
+
+impl std::fmt::Display for ManifestSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ManifestSource::File => "local file",
+            ManifestSource::Network => "network",
+            ManifestSource::Cache => "cache",
+        })
+    }
+}
+
+/// Load a local manifest (`--manifest`), which is trusted as given.
+pub fn load_local_manifest(path: &Path) -> Result<(Manifest, ManifestSource)> {
+    tracing::info!("Loading manifest from local file: {}", path.display());
+    Ok((Manifest::from_file(path)?, ManifestSource::File))
+}
+
 /// Fetch the release manifest JSON from GitHub for a given version.
-/// If `local_path` is provided, reads from the local file instead.
 /// If `version` is None, fetches the latest release.
+/// A downloaded manifest must come with a `manifest.json.sig` asset that
+/// `public_key` verifies.
+/// Downloads are cached; if one fails, the cached manifest for `version`
+/// (or the newest cached one, for the latest release) is used instead.
+/// `use_cache: false` (`--no-cache`) neither reads nor writes the cache.
 pub async fn fetch_manifest(
-    local_path: Option<&Path>,
     version: Option<&str>,
-) -> Result<Manifest> {
-    if let Some(path) = local_path {
-        tracing::info!("Loading manifest from local file: {}", path.display());
-        return Manifest::from_file(path);
+    public_key: &str,
+    use_cache: bool,
+) -> Result<(Manifest, ManifestSource)> {
+    let err = match download_manifest(version, public_key).await {
+        Ok((manifest, manifest_json, signature)) => {
+            // Keep a copy for offline runs, with its signature so it can be
+            // checked again when loaded; a failed write isn't fatal
+            if use_cache {
+                if let Err(e) = cached_manifest_path(&manifest.version).and_then(|path| {
+                    std::fs::write(signature_path(&path), &signature)?;
+                    Ok(std::fs::write(path, &manifest_json)?)
+                }) {
+                    tracing::warn!("Could not cache manifest: {:#}", e);
+                }
+            }
+            return Ok((manifest, ManifestSource::Network));
+        }
+        Err(e) => e,
+    };
+    if !use_cache {
+        return Err(err);
+    }
+    let cached = match version {
+        Some(v) => Some(cached_manifest_path(v)?).filter(|p| p.exists()),
+        None => newest_cached_manifest(&cache_dir()?)?,
+    };
+    let Some(path) = cached else {
+        return Err(err);
+    };
+    tracing::warn!("Manifest download failed ({:#}); using {}", err, path.display());
+    Ok((read_cached_manifest(&path, public_key)?, ManifestSource::Cache))
+}
+
+/// Where the signature of the manifest cached at `path` is kept.
+fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Read a cached manifest, checking the signature cached beside it just as
+/// a download's is: whoever can write the cache would otherwise choose the
+/// image digests and template checksum.
+fn read_cached_manifest(path: &Path, public_key: &str) -> Result<Manifest> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Cached manifest {} is unreadable", path.display()))?;
+    let sig_path = signature_path(path);
+    let signature = std::fs::read_to_string(&sig_path).with_context(|| {
+        format!(
+            "Cached manifest {} has no signature ({}); run once with network access to refresh it",
+            path.display(),
+            sig_path.display()
+        )
+    })?;
+    Manifest::from_signed_json(&json, &signature, public_key)
+        .with_context(|| format!("Cached manifest {} failed verification", path.display()))
+}
+
+/// The most recently written manifest cached in `dir`, if any.
+fn newest_cached_manifest(dir: &Path) -> Result<Option<PathBuf>> {
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !(name.starts_with("manifest-") && name.ends_with(".json")) {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        if newest.as_ref().is_none_or(|(t, _)| modified > *t) {
+            newest = Some((modified, entry.path()));
+        }
     }
+    Ok(newest.map(|(_, path)| path))
+}
 
+/// Download and verify the manifest, returning it with its raw JSON and
+/// signature.
+async fn download_manifest(version: Option<&str>, public_key: &str) -> Result<(Manifest, String, String)> {
     let release_url = match version {
         Some(v) => format!("{}/repos/{}/releases/tags/v{}", GITHUB_API, REPO, v),
         None => format!("{}/repos/{}/releases/latest", GITHUB_API, REPO),
@@ -54,15 +155,59 @@ pub async fn fetch_manifest(
         .send().await?
         .text().await?;
 
-    Manifest::from_json(&manifest_json)
+    let signature_asset = assets.iter()
+        .find(|a| a["name"].as_str() == Some("manifest.json.sig"))
+        .context("manifest.json.sig not found in release assets; cannot verify the manifest")?;
+    let signature_url = signature_asset["browser_download_url"]
+        .as_str()
+        .context("No download URL for manifest.json.sig")?;
+    let signature = client
+        .get(signature_url)
+        .header("User-Agent", "bakerst-install")
+        .send().await?
+        .error_for_status()
+        .context("Failed to download manifest signature")?
+        .text().await?;
+    let manifest = Manifest::from_signed_json(&manifest_json, &signature, public_key)?;
+    Ok((manifest, manifest_json, signature))
+}
+
+/// Where the manifest for `version` is cached (~/.bakerst/cache/manifest-<version>.json).
+pub fn cached_manifest_path(version: &str) -> Result<PathBuf> {
+    Ok(cache_dir()?.join(format!("manifest-{}.json", version.trim_start_matches('v'))))
+}
+
+/// Where the template tarball for `version` is cached.
+pub fn cached_template_path(version: &str) -> Result<PathBuf> {
+    Ok(cache_dir()?.join(format!("install-template-{}.tar.gz", version.trim_start_matches('v'))))
+}
+
+/// Load the manifest cached by an earlier online run, without touching the
+/// network (`--assume-cached-manifest`), once its cached signature checks
+/// out against the release key (see [`crate::manifest::release_public_key`]).
+pub fn load_cached_manifest(version: &str, manifest_pubkey: Option<&Path>) -> Result<Manifest> {
+    let path = cached_manifest_path(version)?;
+    if !path.exists() {
+        bail!(
+            "No cached manifest for v{} (looked for {}). Run once with network access, \
+             or pass --manifest <file>.",
+            version.trim_start_matches('v'),
+            path.display()
+        );
+    }
+    let public_key = crate::manifest::release_public_key(manifest_pubkey)?;
+    tracing::info!("Loading cached manifest: {}", path.display());
+    read_cached_manifest(&path, &public_key)
 }
 
 /// Download the install template tarball, verify its SHA256, and extract it.
 /// If a local manifest path was provided, looks for a sibling `install-template.tar.gz` first.
+/// Downloads are cached per version; `offline` uses only that cache.
 pub async fn fetch_template(
     manifest: &Manifest,
     local_manifest_path: Option<&Path>,
     dest: &Path,
+    offline: bool,
 ) -> Result<PathBuf> {
     let template_path = dest.join("install-template");
 
@@ -77,6 +222,24 @@ pub async fn fetch_template(
         }
     }
 
+    let cached = cached_template_path(&manifest.version)?;
+    if offline {
+        if !cached.exists() {
+            bail!(
+                "No cached install template for v{} (looked for {}). Run once with network access, \
+                 or pass --template <file>.",
+                manifest.version,
+                cached.display()
+            );
+        }
+        if !manifest.template_sha256.is_empty() {
+            verify_sha256(&std::fs::read(&cached)?, &manifest.template_sha256)?;
+        }
+        tracing::info!("Using cached template: {}", cached.display());
+        extract_tarball(&cached, dest)?;
+        return Ok(template_path);
+    }
+
     let url = &manifest.template_url;
     tracing::info!("Downloading template from: {}", url);
 
@@ -98,6 +261,9 @@ pub async fn fetch_template(
 
     let tarball_path = dest.join("install-template.tar.gz");
     std::fs::write(&tarball_path, &bytes)?;
+    if let Err(e) = std::fs::write(&cached, &bytes) {
+        tracing::warn!("Could not cache template: {}", e);
+    }
 
     extract_tarball(&tarball_path, dest)?;
     Ok(template_path)
@@ -140,3 +306,49 @@ fn extract_tarball(tarball: &Path, dest: &Path) -> Result<()> {
     archive.unpack(dest)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_cached_manifest_ignores_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(newest_cached_manifest(dir.path()).unwrap(), None);
+
+        let old = dir.path().join("manifest-0.5.0.json");
+        let new = dir.path().join("manifest-0.6.0.json");
+        std::fs::write(&old, "{}").unwrap();
+        std::fs::write(&new, "{}").unwrap();
+        std::fs::write(dir.path().join("template-0.7.0.tar.gz"), "").unwrap();
+        let hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options().write(true).open(&old).unwrap().set_modified(hour_ago).unwrap();
+        assert_eq!(newest_cached_manifest(dir.path()).unwrap(), Some(new));
+    }
+
+    #[test]
+    fn cached_manifests_are_verified_like_downloads() {
+        use base64::Engine;
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+        let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let public_key = b64.encode(key.public_key().as_ref());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest-0.6.0.json");
+        let json = r#"{"schemaVersion": 1, "version": "0.6.0", "templateUrl": "", "templateSha256": "", "images": []}"#;
+        std::fs::write(&path, json).unwrap();
+        let err = read_cached_manifest(&path, &public_key).unwrap_err();
+        assert!(format!("{:#}", err).contains("has no signature"), "{:#}", err);
+
+        std::fs::write(signature_path(&path), b64.encode(key.sign(json.as_bytes()).as_ref())).unwrap();
+        assert_eq!(read_cached_manifest(&path, &public_key).unwrap().version, "0.6.0");
+
+        // Someone with write access to the cache edits it
+        std::fs::write(&path, json.replace("\"templateSha256\": \"\"", "\"templateSha256\": \"00\"")).unwrap();
+        let err = read_cached_manifest(&path, &public_key).unwrap_err();
+        assert!(format!("{:#}", err).contains("signature verification failed"), "{:#}", err);
+    }
+}
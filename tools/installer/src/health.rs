@@ -1,14 +1,53 @@
 use anyhow::Result;
-use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
 use k8s_openapi::api::core::v1::Pod;
 use kube::api::{Api, DeleteParams, ListParams, LogParams};
 use kube::Client;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-const POLL_INTERVAL: Duration = Duration::from_secs(2);
-const POD_TIMEOUT: Duration = Duration::from_secs(120);
-const MAX_RECOVERY_ATTEMPTS: u32 = 3;
+/// How long an install waits for its deployments (`--health-timeout`).
+pub const DEFAULT_HEALTH_TIMEOUT: Duration = Duration::from_secs(600);
+/// Time between readiness polls (`--poll-interval`).
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// [`poll_health`]'s timeout and interval, which the TUI's wait keeps
+/// unless `--health-timeout` or `--poll-interval` is given.
+pub const POLL_HEALTH_TIMEOUT: Duration = Duration::from_secs(120);
+pub const POLL_HEALTH_INTERVAL: Duration = Duration::from_secs(2);
+/// Times a crash-looping deployment's pod is deleted before giving up
+/// (`--max-recovery-attempts`).
+pub const DEFAULT_MAX_RECOVERY_ATTEMPTS: u32 = 3;
+/// Consecutive all-ready polls required before declaring success.
+pub const DEFAULT_STABLE_CYCLES: u32 = 2;
+
+/// Counts consecutive all-ready observations so a pod that is Ready for one
+/// poll and then crashes isn't reported healthy. Any regression resets it.
+#[derive(Debug, Clone)]
+pub struct StabilityTracker {
+    required: u32,
+    consecutive: u32,
+}
+
+impl StabilityTracker {
+    pub fn new(required: u32) -> Self {
+        Self { required: required.max(1), consecutive: 0 }
+    }
+
+    /// Record one poll; returns true once `required` all-ready polls in a row
+    /// have been seen.
+    pub fn observe(&mut self, all_ready: bool) -> bool {
+        if all_ready {
+            self.consecutive += 1;
+        } else {
+            self.consecutive = 0;
+        }
+        self.consecutive >= self.required
+    }
+
+    pub fn consecutive(&self) -> u32 {
+        self.consecutive
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct PodHealth {
@@ -20,67 +59,486 @@ pub struct PodHealth {
     pub restarts: i32,
     pub error: Option<String>,
     pub logs_tail: Option<String>,
+    /// Current CPU/memory, when metrics-server is installed.
+    pub usage: Option<crate::k8s::ResourceUsage>,
+}
+
+/// Pod health rolled up per deployment, for the at-a-glance view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub ready: usize,
+    pub total: usize,
+    /// First pod error in the component, e.g. CrashLoopBackOff.
+    pub problem: Option<String>,
+}
+
+impl ComponentHealth {
+    pub fn is_ready(&self) -> bool {
+        self.total > 0 && self.ready == self.total
+    }
+}
+
+impl std::fmt::Display for ComponentHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}/{} ready", self.name, self.ready, self.total)?;
+        if let Some(ref problem) = self.problem {
+            write!(f, " \u{2014} {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Group pods by deployment, in the order components were first seen.
+pub fn component_summary(pods: &[PodHealth]) -> Vec<ComponentHealth> {
+    let mut components: Vec<ComponentHealth> = Vec::new();
+    for pod in pods {
+        let idx = match components.iter().position(|c| c.name == pod.deployment) {
+            Some(idx) => idx,
+            None => {
+                components.push(ComponentHealth {
+                    name: pod.deployment.clone(),
+                    ready: 0,
+                    total: 0,
+                    problem: None,
+                });
+                components.len() - 1
+            }
+        };
+        let component = &mut components[idx];
+        component.total += 1;
+        if pod.ready {
+            component.ready += 1;
+        }
+        if component.problem.is_none() {
+            component.problem = pod.error.clone();
+        }
+    }
+    components
+}
+
+/// The controller kinds whose readiness the health wait understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkloadKind {
+    Deployment,
+    StatefulSet,
+    DaemonSet,
+}
+
+/// A workload to wait for, by kind and name. Its pods are found by its
+/// selector, or the `app=<name>` label every component's pod template
+/// carries while the workload doesn't exist yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkloadRef {
+    pub kind: WorkloadKind,
+    pub name: String,
+}
+
+impl WorkloadRef {
+    pub fn new(kind: WorkloadKind, name: impl Into<String>) -> Self {
+        Self { kind, name: name.into() }
+    }
+
+    /// The workload an applied `Kind/name` label names, if it is one.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let (kind, name) = label.split_once('/')?;
+        let kind = match kind {
+            "Deployment" => WorkloadKind::Deployment,
+            "StatefulSet" => WorkloadKind::StatefulSet,
+            "DaemonSet" => WorkloadKind::DaemonSet,
+            _ => return None,
+        };
+        Some(Self::new(kind, name))
+    }
+}
+
+impl std::fmt::Display for WorkloadRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}/{}", self.kind, self.name)
+    }
+}
+
+/// A workload's pod counts, from the field its kind reports them in, and
+/// its pod selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkloadStatus {
+    pub desired: i32,
+    pub ready: i32,
+    pub selector: Option<String>,
+}
+
+impl WorkloadStatus {
+    pub fn is_ready(&self) -> bool {
+        self.ready >= self.desired
+    }
+
+    pub fn of_deployment(deploy: &Deployment) -> Self {
+        Self {
+            desired: deploy.spec.as_ref().and_then(|s| s.replicas).unwrap_or(1),
+            ready: deploy.status.as_ref().and_then(|s| s.ready_replicas).unwrap_or(0),
+            selector: crate::k8s::match_labels_selector(deploy),
+        }
+    }
+
+    pub fn of_stateful_set(set: &StatefulSet) -> Self {
+        Self {
+            desired: set.spec.as_ref().and_then(|s| s.replicas).unwrap_or(1),
+            ready: set.status.as_ref().and_then(|s| s.ready_replicas).unwrap_or(0),
+            selector: set.spec.as_ref().and_then(|s| crate::k8s::labels_selector(&s.selector)),
+        }
+    }
+
+    /// A DaemonSet wants one pod per eligible node, so its desired count
+    /// is however many the controller has scheduled.
+    pub fn of_daemon_set(set: &DaemonSet) -> Self {
+        let status = set.status.as_ref();
+        Self {
+            desired: status.map(|s| s.desired_number_scheduled).unwrap_or(0),
+            ready: status.map(|s| s.number_ready).unwrap_or(0),
+            selector: set.spec.as_ref().and_then(|s| crate::k8s::labels_selector(&s.selector)),
+        }
+    }
+}
+
+/// Read `workload`'s status; `None` if it doesn't exist (yet).
+pub async fn workload_status(client: &Client, namespace: &str, workload: &WorkloadRef) -> Result<Option<WorkloadStatus>> {
+    let name = workload.name.as_str();
+    Ok(match workload.kind {
+        WorkloadKind::Deployment => Api::<Deployment>::namespaced(client.clone(), namespace)
+            .get_opt(name)
+            .await?
+            .map(|d| WorkloadStatus::of_deployment(&d)),
+        WorkloadKind::StatefulSet => Api::<StatefulSet>::namespaced(client.clone(), namespace)
+            .get_opt(name)
+            .await?
+            .map(|s| WorkloadStatus::of_stateful_set(&s)),
+        WorkloadKind::DaemonSet => Api::<DaemonSet>::namespaced(client.clone(), namespace)
+            .get_opt(name)
+            .await?
+            .map(|s| WorkloadStatus::of_daemon_set(&s)),
+    })
 }
 
 #[derive(Debug, Clone)]
 pub enum HealthEvent {
     PodUpdate(PodHealth),
     RecoveryAttempt { deployment: String, attempt: u32 },
+    /// Auto-recovery stopped deleting `deployment`'s crashing pods, sent
+    /// once: after `attempts` deletions, or at the first crash when it is
+    /// disabled (`attempts` is 0). `reason` says why, for display.
+    RecoveryGaveUp { deployment: String, attempts: u32, reason: String },
+    /// `workload` became ready, or stopped being ready after it was.
+    WorkloadReady { workload: String, ready: bool },
     AllHealthy,
-    Failed { unhealthy: Vec<PodHealth> },
+    /// Polling gave up on `workload`: `reason` is the timeout, or the pod
+    /// and problem that can't clear on its own.
+    Failed { workload: String, reason: String, unhealthy: Vec<PodHealth> },
+    /// A line from a not-ready pod's logs, streamed as it is written.
+    LogLine { pod: String, line: String },
 }
 
-/// Wait for a single deployment to have all replicas ready.
-pub async fn wait_for_rollout(
-    client: &Client,
-    namespace: &str,
-    name: &str,
-    timeout: Duration,
-) -> Result<()> {
-    let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
-    let start = tokio::time::Instant::now();
+/// Log-follow tasks for pods that aren't ready, keyed by pod name, with the
+/// restart count each was started at. Dropping it (when polling ends) stops
+/// every task.
+#[derive(Default)]
+struct LogFollowers(std::collections::HashMap<String, (tokio::task::JoinHandle<()>, i32)>);
 
-    loop {
-        if start.elapsed() > timeout {
-            anyhow::bail!("timeout waiting for deployment {} rollout", name);
+impl LogFollowers {
+    /// Follow `pod`'s logs unless already doing so. A stream that ended
+    /// (the container exited) is only reopened once the container has
+    /// restarted, so the tail isn't sent twice.
+    fn start(&mut self, api: &Api<Pod>, pod: &str, restarts: i32, tx: &mpsc::UnboundedSender<HealthEvent>) {
+        if let Some((handle, started_at)) = self.0.get(pod) {
+            if !handle.is_finished() || *started_at == restarts {
+                return;
+            }
         }
+        let (api, name, tx) = (api.clone(), pod.to_string(), tx.clone());
+        let handle = tokio::spawn(async move {
+            use futures::{AsyncBufReadExt, TryStreamExt};
+            let params = LogParams {
+                follow: true,
+                tail_lines: Some(20),
+                ..Default::default()
+            };
+            let Ok(stream) = api.log_stream(&name, &params).await else {
+                return;
+            };
+            let mut lines = stream.lines();
+            while let Ok(Some(line)) = lines.try_next().await {
+                if tx.send(HealthEvent::LogLine { pod: name.clone(), line }).is_err() {
+                    break;
+                }
+            }
+        });
+        self.0.insert(pod.to_string(), (handle, restarts));
+    }
 
-        let deploy = api.get(name).await?;
-        let status = deploy.status.as_ref();
-        let desired = status
-            .and_then(|s| s.replicas)
-            .unwrap_or(1);
-        let ready = status
-            .and_then(|s| s.ready_replicas)
-            .unwrap_or(0);
+    /// Stop following `pod`, e.g. once it is ready.
+    fn stop(&mut self, pod: &str) {
+        if let Some((handle, _)) = self.0.remove(pod) {
+            handle.abort();
+        }
+    }
+}
 
-        if ready >= desired && desired > 0 {
-            return Ok(());
+impl Drop for LogFollowers {
+    fn drop(&mut self) {
+        for (handle, _) in self.0.values() {
+            handle.abort();
+        }
+    }
+}
+
+/// Container waiting reasons that are a normal part of starting up.
+const STARTUP_REASONS: &[&str] = &["ContainerCreating", "PodInitializing"];
+
+/// Container waiting reasons that won't clear without a fix (a wrong image
+/// tag, a missing pull secret, ConfigMap or Secret), so the health wait
+/// fails on them instead of waiting out the timeout.
+pub const FATAL_REASONS: &[&str] =
+    &["ImagePullBackOff", "ErrImagePull", "CreateContainerConfigError", "InvalidImageName"];
+
+/// Of the [`FATAL_REASONS`], those a registry hiccup can cause: the kubelet
+/// retries the pull, so they only count once the same pod still shows one
+/// on the next poll.
+pub const RETRIED_REASONS: &[&str] = &["ErrImagePull"];
+
+/// Whether `problem` (from [`fatal_problem`]) is one of the
+/// [`RETRIED_REASONS`].
+pub fn is_retried(problem: &str) -> bool {
+    RETRIED_REASONS
+        .iter()
+        .any(|reason| problem.split(':').next() == Some(reason))
+}
+
+/// The first of a pod's (init) containers stuck on one of the
+/// [`FATAL_REASONS`], as `<reason>: <kubelet message>`.
+pub fn fatal_problem(pod: &Pod) -> Option<String> {
+    let status = pod.status.as_ref()?;
+    status
+        .init_container_statuses
+        .iter()
+        .chain(status.container_statuses.iter())
+        .flatten()
+        .filter_map(|cs| cs.state.as_ref()?.waiting.as_ref())
+        .find(|w| w.reason.as_deref().is_some_and(|r| FATAL_REASONS.contains(&r)))
+        .map(|w| match &w.message {
+            Some(message) => format!("{}: {}", w.reason.as_deref().unwrap_or_default(), message),
+            None => w.reason.clone().unwrap_or_default(),
+        })
+}
+
+/// What is keeping a pod from running: the first container waiting reason
+/// that isn't part of a normal start (`CrashLoopBackOff`,
+/// `ImagePullBackOff`, ...), else `Unschedulable: <scheduler message>`
+/// for a pod the scheduler couldn't place.
+pub fn pod_problem(pod: &Pod) -> Option<String> {
+    let status = pod.status.as_ref()?;
+    let waiting = status
+        .container_statuses
+        .iter()
+        .flatten()
+        .filter_map(|cs| cs.state.as_ref()?.waiting.as_ref()?.reason.clone())
+        .find(|reason| !STARTUP_REASONS.contains(&reason.as_str()));
+    if waiting.is_some() {
+        return waiting;
+    }
+    status
+        .conditions
+        .iter()
+        .flatten()
+        .find(|c| c.type_ == "PodScheduled" && c.status == "False")
+        .map(|c| format!("Unschedulable: {}", c.message.as_deref().unwrap_or_default()))
+}
+
+/// The not-ready pods of `workloads`, with what is wrong with each (see
+/// [`pod_problem`]) and, for crashing pods, the end of the previous
+/// container's logs. Used to explain a health-wait timeout.
+pub async fn unready_pods(client: &Client, namespace: &str, workloads: &[WorkloadRef]) -> Result<Vec<PodHealth>> {
+    let pod_api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let mut unready = Vec::new();
+    for workload in workloads {
+        let name = &workload.name;
+        let Some(selector) = workload_status(client, namespace, workload).await?.and_then(|s| s.selector) else {
+            continue;
+        };
+        for pod in pod_api.list(&ListParams::default().labels(&selector)).await?.items {
+            let statuses = pod
+                .status
+                .as_ref()
+                .and_then(|s| s.container_statuses.clone())
+                .unwrap_or_default();
+            let ready = !statuses.is_empty() && statuses.iter().all(|cs| cs.ready);
+            if ready {
+                continue;
+            }
+            let pod_name = pod.metadata.name.clone().unwrap_or_default();
+            let error = pod_problem(&pod);
+            let logs_tail = if error.as_deref() == Some("CrashLoopBackOff") {
+                let params = LogParams {
+                    previous: true,
+                    tail_lines: Some(20),
+                    ..Default::default()
+                };
+                pod_api.logs(&pod_name, &params).await.ok()
+            } else {
+                None
+            };
+            unready.push(PodHealth {
+                name: pod_name,
+                deployment: name.clone(),
+                ready,
+                phase: pod
+                    .status
+                    .as_ref()
+                    .and_then(|s| s.phase.clone())
+                    .unwrap_or_else(|| "Unknown".into()),
+                image: statuses.first().map(|cs| cs.image.clone()).unwrap_or_default(),
+                restarts: statuses.iter().map(|cs| cs.restart_count).sum(),
+                error,
+                logs_tail,
+                usage: None,
+            });
         }
+    }
+    Ok(unready)
+}
 
-        tokio::time::sleep(POLL_INTERVAL).await;
+/// Delete a pod so its controller recreates it. Used both by automatic
+/// recovery and by the user-initiated restart in the TUI.
+pub async fn restart_pod(client: &Client, namespace: &str, pod_name: &str) -> Result<()> {
+    let pod_api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    pod_api.delete(pod_name, &DeleteParams::default()).await?;
+    Ok(())
+}
+
+/// Why auto-recovery stopped after `attempts` pod deletions.
+pub fn recovery_gave_up_reason(attempts: u32) -> String {
+    match attempts {
+        0 => "auto-recovery is disabled (--max-recovery-attempts 0); the crashing pod is left for inspection".into(),
+        n => format!(
+            "still crash-looping after {} restart{}; likely a configuration problem, not a transient one",
+            n,
+            if n == 1 { "" } else { "s" }
+        ),
+    }
+}
+
+/// How [`poll_health`] polls, from the install's flags.
+#[derive(Debug, Clone, Copy)]
+pub struct PollOptions {
+    /// `--health-stable-cycles`
+    pub stable_cycles: u32,
+    /// `--max-recovery-attempts`
+    pub max_recovery_attempts: u32,
+    /// `--health-timeout`
+    pub timeout: Duration,
+    /// `--poll-interval`
+    pub interval: Duration,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            stable_cycles: DEFAULT_STABLE_CYCLES,
+            max_recovery_attempts: DEFAULT_MAX_RECOVERY_ATTEMPTS,
+            timeout: POLL_HEALTH_TIMEOUT,
+            interval: POLL_HEALTH_INTERVAL,
+        }
     }
 }
 
-/// Poll all deployments, send health events, auto-recover crashed pods.
+impl PollOptions {
+    /// The defaults of the install's wait without the TUI
+    /// ([`crate::k8s::wait_for_deployments`]).
+    pub fn deployment_wait() -> Self {
+        Self {
+            timeout: DEFAULT_HEALTH_TIMEOUT,
+            interval: DEFAULT_POLL_INTERVAL,
+            ..Self::default()
+        }
+    }
+}
+
+/// Poll all workloads every `interval`, send health events, and delete
+/// crash-looping pods so they're recreated, up to `max_recovery_attempts`
+/// times per workload (0 disables it). A workload counts as healthy once its
+/// status reports every replica ready and each of its pods is ready; each
+/// time that changes it is reported with [`HealthEvent::WorkloadReady`].
+/// Gives up with [`HealthEvent::Failed`] after `timeout`, or as soon as a
+/// pod hits one of the [`FATAL_REASONS`] ([`RETRIED_REASONS`] on two polls
+/// in a row). Logs of started pods that aren't
+/// ready are streamed as [`HealthEvent::LogLine`]s until they become ready
+/// or polling ends.
 pub async fn poll_health(
     client: &Client,
     namespace: &str,
-    deployment_names: &[&str],
+    workloads: &[WorkloadRef],
+    opts: &PollOptions,
     tx: mpsc::UnboundedSender<HealthEvent>,
 ) -> Result<()> {
+    let PollOptions { stable_cycles, max_recovery_attempts, timeout, interval } = *opts;
     let pod_api: Api<Pod> = Api::namespaced(client.clone(), namespace);
     let mut recovery_attempts: std::collections::HashMap<String, u32> = Default::default();
+    let mut gave_up: std::collections::HashSet<String> = Default::default();
+    let mut ready_workloads: std::collections::HashSet<String> = Default::default();
+    let mut stability = StabilityTracker::new(stable_cycles);
+    let mut followers = LogFollowers::default();
+    // Pods that failed a pull on the last poll, see RETRIED_REASONS
+    let mut pull_failures: std::collections::HashSet<String> = Default::default();
+    // Cleared once the metrics API turns out not to be installed
+    let mut metrics_available = true;
 
     let start = tokio::time::Instant::now();
 
     loop {
         let mut all_healthy = true;
         let mut unhealthy = Vec::new();
+        // Workloads still waited for, as `name (ready/desired)`
+        let mut pending = Vec::new();
+        let mut fatal = None;
+        let mut failed_pulls = std::collections::HashSet::new();
+        // Best-effort: a failed read just leaves this poll's usage out
+        let usage = match metrics_available {
+            true => match crate::k8s::get_pod_usage(client, namespace, None).await {
+                Ok(Some(usage)) => usage,
+                Ok(None) => {
+                    metrics_available = false;
+                    Vec::new()
+                }
+                Err(_) => Vec::new(),
+            },
+            false => Vec::new(),
+        };
 
-        for deploy_name in deployment_names {
-            let lp = ListParams::default().labels(&format!("app={}", deploy_name));
-            let pods = pod_api.list(&lp).await?;
+        for workload in workloads {
+            let deploy_name = &workload.name;
+            // Missing, or its controller hasn't got every replica ready
+            let status = workload_status(client, namespace, workload).await?;
+            match &status {
+                Some(status) if status.is_ready() => {
+                    if ready_workloads.insert(deploy_name.clone()) {
+                        tx.send(HealthEvent::WorkloadReady { workload: deploy_name.clone(), ready: true }).ok();
+                    }
+                }
+                _ => {
+                    all_healthy = false;
+                    if ready_workloads.remove(deploy_name) {
+                        tx.send(HealthEvent::WorkloadReady { workload: deploy_name.clone(), ready: false }).ok();
+                    }
+                    pending.push(match &status {
+                        Some(status) => format!("{} ({}/{})", deploy_name, status.ready, status.desired),
+                        None => format!("{} (missing)", deploy_name),
+                    });
+                }
+            }
+
+            // Pods by the workload's selector; `app=<name>` until it exists
+            let selector = status
+                .and_then(|s| s.selector)
+                .unwrap_or_else(|| format!("app={}", deploy_name));
+            let pods = pod_api.list(&ListParams::default().labels(&selector)).await?;
 
             for pod in &pods.items {
                 let pod_name = pod.metadata.name.clone().unwrap_or_default();
@@ -102,19 +560,28 @@ pub async fn poll_health(
 
                 // Check for CrashLoopBackOff
                 let is_crash_loop = container_statuses.iter().any(|cs| {
-                    cs.state.as_ref().map_or(false, |s| {
-                        s.waiting.as_ref().map_or(false, |w| {
+                    cs.state.as_ref().is_some_and(|s| {
+                        s.waiting.as_ref().is_some_and(|w| {
                             w.reason.as_deref() == Some("CrashLoopBackOff")
                         })
                     })
                 });
 
-                let mut error = None;
+                let fatal_error = fatal_problem(pod);
+                if let Some(problem) = &fatal_error {
+                    let retried = is_retried(problem);
+                    if retried {
+                        failed_pulls.insert(pod_name.clone());
+                    }
+                    if fatal.is_none() && (!retried || pull_failures.contains(&pod_name)) {
+                        fatal = Some((deploy_name.clone(), format!("{}: {}", pod_name, problem)));
+                    }
+                }
+                let error = fatal_error.or_else(|| pod_problem(pod));
                 if is_crash_loop {
-                    error = Some("CrashLoopBackOff".into());
                     let attempts = recovery_attempts.entry(deploy_name.to_string()).or_insert(0);
 
-                    if *attempts < MAX_RECOVERY_ATTEMPTS {
+                    if *attempts < max_recovery_attempts {
                         *attempts += 1;
                         tx.send(HealthEvent::RecoveryAttempt {
                             deployment: deploy_name.to_string(),
@@ -127,12 +594,25 @@ pub async fn poll_health(
                             ..Default::default()
                         }).await.unwrap_or_default();
 
-                        // Delete pod to trigger recreation
-                        pod_api.delete(&pod_name, &DeleteParams::default()).await.ok();
+                        restart_pod(client, namespace, &pod_name).await.ok();
+                    } else if gave_up.insert(deploy_name.to_string()) {
+                        tx.send(HealthEvent::RecoveryGaveUp {
+                            deployment: deploy_name.to_string(),
+                            attempts: *attempts,
+                            reason: recovery_gave_up_reason(*attempts),
+                        }).ok();
                     }
                 }
 
+                if ready {
+                    followers.stop(&pod_name);
+                } else if phase != "Pending" {
+                    // Pending pods have no container output yet
+                    followers.start(&pod_api, &pod_name, restarts, &tx);
+                }
+
                 let health = PodHealth {
+                    usage: usage.iter().find(|u| u.pod == pod_name).map(|u| u.usage),
                     name: pod_name,
                     deployment: deploy_name.to_string(),
                     ready,
@@ -152,12 +632,21 @@ pub async fn poll_health(
             }
         }
 
-        if all_healthy && !deployment_names.is_empty() {
+        pull_failures = failed_pulls;
+
+        if stability.observe(all_healthy && !workloads.is_empty()) {
             tx.send(HealthEvent::AllHealthy).ok();
             return Ok(());
         }
 
-        if start.elapsed() > POD_TIMEOUT {
+        if let Some((workload, reason)) = fatal {
+            // Waiting out the timeout won't help; the containers never ran,
+            // so there are no logs to fetch either
+            tx.send(HealthEvent::Failed { workload, reason, unhealthy }).ok();
+            return Ok(());
+        }
+
+        if start.elapsed() > timeout {
             // Fetch logs for unhealthy pods
             for pod in &mut unhealthy {
                 let logs = pod_api.logs(&pod.name, &LogParams {
@@ -166,10 +655,155 @@ pub async fn poll_health(
                 }).await.unwrap_or_default();
                 pod.logs_tail = Some(logs);
             }
-            tx.send(HealthEvent::Failed { unhealthy }).ok();
+            // Attribute the timeout to the first workload still pending
+            let workload = pending
+                .first()
+                .or(unhealthy.first().map(|p| &p.deployment))
+                .and_then(|w| w.split(' ').next())
+                .unwrap_or_default()
+                .to_string();
+            let reason = match pending.is_empty() {
+                true => format!("Timed out after {}s waiting for pods to stay ready", timeout.as_secs()),
+                false => format!("Timed out after {}s waiting for: {}", timeout.as_secs(), pending.join(", ")),
+            };
+            tx.send(HealthEvent::Failed { workload, reason, unhealthy }).ok();
             return Ok(());
         }
 
-        tokio::time::sleep(POLL_INTERVAL).await;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stability_requires_consecutive_ready_polls() {
+        let mut t = StabilityTracker::new(2);
+        assert!(!t.observe(true));
+        assert!(!t.observe(false)); // regression resets the count
+        assert_eq!(t.consecutive(), 0);
+        assert!(!t.observe(true));
+        assert!(t.observe(true));
+    }
+
+    fn pod(name: &str, deployment: &str, ready: bool, error: Option<&str>) -> PodHealth {
+        PodHealth {
+            name: name.into(),
+            deployment: deployment.into(),
+            ready,
+            phase: "Running".into(),
+            image: String::new(),
+            restarts: 0,
+            error: error.map(String::from),
+            logs_tail: None,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn component_summary_rolls_pods_up_by_deployment() {
+        let pods = [
+            pod("brain-7f9-a", "brain", true, None),
+            pod("worker-5c2-a", "worker", false, None),
+            pod("worker-5c2-b", "worker", false, Some("CrashLoopBackOff")),
+        ];
+        let summary = component_summary(&pods);
+        let lines: Vec<String> = summary.iter().map(|c| c.to_string()).collect();
+        assert_eq!(lines, vec!["brain: 1/1 ready", "worker: 0/2 ready \u{2014} CrashLoopBackOff"]);
+        assert!(summary[0].is_ready());
+        assert!(!summary[1].is_ready());
+    }
+
+    #[test]
+    fn pod_problem_skips_startup_reasons_and_reports_scheduling() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "status": {
+                "phase": "Pending",
+                "containerStatuses": [
+                    {"name": "a", "image": "", "imageID": "", "ready": false, "restartCount": 0,
+                     "state": {"waiting": {"reason": "ContainerCreating"}}},
+                    {"name": "b", "image": "", "imageID": "", "ready": false, "restartCount": 0,
+                     "state": {"waiting": {"reason": "ImagePullBackOff"}}},
+                ],
+            },
+        }))
+        .unwrap();
+        assert_eq!(pod_problem(&pod).as_deref(), Some("ImagePullBackOff"));
+
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "status": {
+                "phase": "Pending",
+                "conditions": [{"type": "PodScheduled", "status": "False", "reason": "Unschedulable",
+                                "message": "0/1 nodes are available: 1 Insufficient memory."}],
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            pod_problem(&pod).as_deref(),
+            Some("Unschedulable: 0/1 nodes are available: 1 Insufficient memory.")
+        );
+    }
+
+    #[test]
+    fn image_pull_failures_are_fatal_with_the_kubelet_message() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "status": {
+                "phase": "Pending",
+                "containerStatuses": [
+                    {"name": "brain", "image": "", "imageID": "", "ready": false, "restartCount": 0,
+                     "state": {"waiting": {"reason": "ErrImagePull",
+                                           "message": "manifest for brain:nope not found"}}},
+                ],
+            },
+        }))
+        .unwrap();
+        assert_eq!(fatal_problem(&pod).as_deref(), Some("ErrImagePull: manifest for brain:nope not found"));
+        assert!(is_retried("ErrImagePull: manifest for brain:nope not found"), "a pull is retried once");
+        assert!(!is_retried("ImagePullBackOff: Back-off pulling image"));
+        assert!(!is_retried("CreateContainerConfigError: secret \"bakerst-secrets\" not found"));
+
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "status": {
+                "phase": "Running",
+                "containerStatuses": [
+                    {"name": "brain", "image": "", "imageID": "", "ready": false, "restartCount": 4,
+                     "state": {"waiting": {"reason": "CrashLoopBackOff"}}},
+                ],
+            },
+        }))
+        .unwrap();
+        assert_eq!(fatal_problem(&pod), None, "crash loops are left to recovery");
+    }
+
+    #[test]
+    fn workload_refs_come_from_applied_labels() {
+        assert_eq!(
+            WorkloadRef::from_label("StatefulSet/qdrant"),
+            Some(WorkloadRef::new(WorkloadKind::StatefulSet, "qdrant"))
+        );
+        assert_eq!(WorkloadRef::from_label("Service/ui"), None);
+        assert_eq!(WorkloadRef::new(WorkloadKind::DaemonSet, "agent").to_string(), "DaemonSet/agent");
+    }
+
+    #[test]
+    fn daemon_set_readiness_is_scheduled_versus_ready() {
+        let set: DaemonSet = serde_json::from_value(serde_json::json!({
+            "spec": {"selector": {"matchLabels": {"app": "agent"}}, "template": {}},
+            "status": {"desiredNumberScheduled": 3, "numberReady": 2, "currentNumberScheduled": 3,
+                       "numberMisscheduled": 0},
+        }))
+        .unwrap();
+        let status = WorkloadStatus::of_daemon_set(&set);
+        assert_eq!((status.desired, status.ready), (3, 2));
+        assert_eq!(status.selector.as_deref(), Some("app=agent"));
+        assert!(!status.is_ready());
+    }
+
+    #[test]
+    fn stability_of_zero_means_one() {
+        let mut t = StabilityTracker::new(0);
+        assert!(t.observe(true));
     }
 }
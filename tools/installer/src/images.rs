@@ -1,28 +1,322 @@
+use std::future::Future;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 
-const MAX_CONCURRENT: usize = 4;
+/// Default for `--pull-concurrency`.
+pub const DEFAULT_PULL_CONCURRENCY: usize = 4;
 const MAX_RETRIES: u32 = 3;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
 pub enum PullEvent {
     Started { index: usize, image: String },
-    Completed { index: usize, image: String, elapsed: Duration },
+    Completed {
+        index: usize,
+        image: String,
+        #[serde(serialize_with = "crate::output::as_seconds")]
+        elapsed: Duration,
+    },
     Failed { index: usize, image: String, error: String, attempt: u32 },
     Retrying { index: usize, image: String, attempt: u32 },
+    /// How far a pull has got, from the layer statuses `docker pull`
+    /// prints. Never sent when its output can't be read.
+    Progress { index: usize, percent: u8 },
+    /// The registry refused the pull for lack of credentials (not retried).
+    AuthRequired { index: usize, image: String, registry: String },
+}
+
+/// Why a pull failed. Auth failures get their own variant so the user is
+/// told to log in rather than shown a generic error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullError {
+    AuthRequired { registry: String },
+    /// The pulled image isn't the one the manifest pins.
+    DigestMismatch { expected: String, actual: String },
+    Failed(String),
+}
+
+impl std::fmt::Display for PullError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PullError::AuthRequired { registry } => write!(
+                f,
+                "Authentication required for {} \u{2014} run `docker login {}`",
+                registry, registry
+            ),
+            PullError::DigestMismatch { expected, actual } => {
+                write!(f, "digest mismatch: expected {}, got {}", expected, actual)
+            }
+            PullError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Running tally of pull results. Failed pulls count toward `finished`
+/// (so progress reaches the total) but never toward `succeeded`, which is
+/// what gates moving on to the next phase.
+#[derive(Debug, Clone, Default)]
+pub struct PullProgress {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: Vec<String>,
+}
+
+impl PullProgress {
+    pub fn new(total: usize) -> Self {
+        Self { total, ..Default::default() }
+    }
+
+    pub fn record(&mut self, event: &PullEvent) {
+        match event {
+            PullEvent::Completed { .. } => self.succeeded += 1,
+            PullEvent::Failed { image, .. } | PullEvent::AuthRequired { image, .. } => {
+                self.failed.push(image.clone())
+            }
+            PullEvent::Started { .. } | PullEvent::Retrying { .. } | PullEvent::Progress { .. } => {}
+        }
+    }
+
+    pub fn finished(&self) -> usize {
+        self.succeeded + self.failed.len()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished() >= self.total
+    }
+
+    pub fn all_succeeded(&self) -> bool {
+        self.succeeded >= self.total
+    }
+}
+
+impl std::fmt::Display for PullProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.succeeded, self.total)?;
+        if !self.failed.is_empty() {
+            write!(f, " ({} failed)", self.failed.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of one `docker` invocation: only what the pull logic looks at.
+#[derive(Debug, Clone, Default)]
+pub struct DockerOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs `docker` subcommands. [`Docker`] shells out to the real CLI; tests
+/// substitute a fake so pulls can be exercised without a daemon.
+pub trait ImageRuntime: Send + Sync + 'static {
+    fn docker(&self, args: Vec<String>) -> impl Future<Output = std::io::Result<DockerOutput>> + Send;
+
+    /// [`docker`](Self::docker), calling `on_line` with each line of stdout
+    /// as it's printed. By default the lines are replayed once it exits.
+    fn docker_streaming(
+        &self,
+        args: Vec<String>,
+        mut on_line: impl FnMut(&str) + Send,
+    ) -> impl Future<Output = std::io::Result<DockerOutput>> + Send {
+        async move {
+            let output = self.docker(args).await?;
+            output.stdout.lines().for_each(&mut on_line);
+            Ok(output)
+        }
+    }
+}
+
+/// The local `docker` CLI.
+pub struct Docker;
+
+impl ImageRuntime for Docker {
+    async fn docker(&self, args: Vec<String>) -> std::io::Result<DockerOutput> {
+        let command = format!("docker {}", args.join(" "));
+        tracing::debug!(%command, "running");
+        let started = Instant::now();
+        let output = Command::new("docker").args(&args).output().await.inspect_err(|e| {
+            tracing::debug!(%command, error = %e, "could not run");
+        })?;
+        let output = DockerOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        };
+        tracing::debug!(
+            %command,
+            success = output.success,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            stderr = output.stderr.trim(),
+            "finished"
+        );
+        Ok(output)
+    }
+
+    async fn docker_streaming(&self, args: Vec<String>, mut on_line: impl FnMut(&str) + Send) -> std::io::Result<DockerOutput> {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+        let command = format!("docker {}", args.join(" "));
+        tracing::debug!(%command, "running");
+        let started = Instant::now();
+        let mut child = Command::new("docker")
+            .args(&args)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+        let mut stdout_pipe = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        let mut stderr_pipe = child.stderr.take().expect("stderr is piped");
+
+        let read_stdout = async {
+            let mut stdout = String::new();
+            while let Some(line) = stdout_pipe.next_line().await? {
+                on_line(&line);
+                stdout.push_str(&line);
+                stdout.push('\n');
+            }
+            std::io::Result::Ok(stdout)
+        };
+        let read_stderr = async {
+            let mut stderr = String::new();
+            stderr_pipe.read_to_string(&mut stderr).await?;
+            std::io::Result::Ok(stderr)
+        };
+        let (stdout, stderr) = tokio::try_join!(read_stdout, read_stderr)?;
+        let status = child.wait().await?;
+        tracing::debug!(
+            %command,
+            success = status.success(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            stderr = stderr.trim(),
+            "finished"
+        );
+        Ok(DockerOutput { success: status.success(), stdout, stderr })
+    }
+}
+
+/// Per-layer state of one `docker pull`, read from the status lines it
+/// prints (`<layer id>: Pulling fs layer`, `: Download complete`,
+/// `: Pull complete`, ...). Downloading a layer counts for half of it and
+/// extracting for the other half.
+#[derive(Debug, Default)]
+pub struct LayerProgress {
+    /// Layer id to halves done (0, 1 or 2), in first-seen order.
+    layers: Vec<(String, u8)>,
+}
+
+impl LayerProgress {
+    /// Take in one line of `docker pull` output. Lines that aren't a layer
+    /// status (the tag line, the digest, the summary) are ignored.
+    pub fn update(&mut self, line: &str) {
+        let Some((id, status)) = line.trim().split_once(": ") else {
+            return;
+        };
+        if id.len() < 12 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return;
+        }
+        let done = match status.split_whitespace().collect::<Vec<_>>().join(" ").as_str() {
+            "Pulling fs layer" | "Waiting" => 0,
+            s if s.starts_with("Downloading") || s.starts_with("Verifying") => 0,
+            "Download complete" => 1,
+            s if s.starts_with("Extracting") => 1,
+            "Pull complete" | "Already exists" => 2,
+            _ => return,
+        };
+        match self.layers.iter_mut().find(|(layer, _)| layer == id) {
+            Some((_, halves)) => *halves = (*halves).max(done),
+            None => self.layers.push((id.to_string(), done)),
+        }
+    }
+
+    /// Percent of the pull done, once any layer is known.
+    pub fn percent(&self) -> Option<u8> {
+        if self.layers.is_empty() {
+            return None;
+        }
+        let halves: usize = self.layers.iter().map(|(_, h)| *h as usize).sum();
+        Some((halves * 100 / (self.layers.len() * 2)) as u8)
+    }
+}
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|a| a.to_string()).collect()
+}
+
+/// Check whether a Docker daemon is reachable.
+pub async fn docker_available(runtime: &impl ImageRuntime) -> bool {
+    runtime
+        .docker(args(&["version", "--format", "{{.Server.Version}}"]))
+        .await
+        .map(|o| o.success)
+        .unwrap_or(false)
+}
+
+/// Whether `image` is already in the local Docker image store.
+pub async fn image_present(runtime: &impl ImageRuntime, image: &str) -> bool {
+    runtime
+        .docker(args(&["image", "inspect", image]))
+        .await
+        .map(|o| o.success)
+        .unwrap_or(false)
+}
+
+/// Docker's registry error codes for missing or bad credentials, which
+/// lead the registry's message: `denied: requested access to the resource
+/// is denied`.
+const REGISTRY_AUTH_CODES: &[&str] = &["unauthorized", "denied"];
+
+/// Errors where the registry rejected us for missing or bad credentials
+/// (private GHCR packages without `docker login`). Only Docker's structured
+/// registry errors count, not the words anywhere in the output.
+fn is_auth_error(stderr: &str) -> bool {
+    stderr.lines().any(|line| {
+        let line = line.trim();
+        let message = line.strip_prefix("Error response from daemon: ").unwrap_or(line);
+        // After the request that failed: `Head "https://…": unauthorized`
+        let message = message.rsplit_once("\": ").map_or(message, |(_, m)| m);
+        let message = message.strip_prefix("error from registry: ").unwrap_or(message);
+        message.starts_with("pull access denied for ")
+            || message.starts_with("no basic auth credentials")
+            || REGISTRY_AUTH_CODES
+                .iter()
+                .any(|code| message.strip_prefix(code).is_some_and(|rest| rest.is_empty() || rest.starts_with(": ")))
+    })
+}
+
+/// Registry host of an image reference ("docker.io" when none is given).
+pub fn registry_of(image: &str) -> String {
+    match image.split_once('/') {
+        Some((host, _)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+            host.to_string()
+        }
+        _ => "docker.io".to_string(),
+    }
 }
 
 /// Errors that indicate a local Docker configuration issue (not transient).
 /// These should fail immediately without retrying.
 fn is_local_docker_error(stderr: &str) -> bool {
     let lower = stderr.to_lowercase();
-    lower.contains("credential") || lower.contains("not found in PATH")
+    lower.contains("error getting credentials") || lower.contains("not found in $path")
         || lower.contains("docker daemon is not running")
         || lower.contains("permission denied")
         || lower.contains("cannot connect to the docker daemon")
 }
 
+/// `image` as pulled through `--registry-mirror`: everything before the
+/// last path segment is replaced by `mirror`, keeping the name, tag and
+/// digest (`ghcr.io/org/bakerst-brain:0.6.0` with `harbor.internal/baker`
+/// becomes `harbor.internal/baker/bakerst-brain:0.6.0`). Single-segment
+/// names (local builds, Docker Hub library images) are left alone.
+pub fn mirror_image(image: &str, mirror: &str) -> String {
+    match image.rsplit_once('/') {
+        Some((_, name)) => format!("{}/{}", mirror.trim_end_matches('/'), name),
+        None => image.to_string(),
+    }
+}
+
 /// Check if an image is a local build (no registry domain — no dots in the name part).
 fn is_local_image(image: &str) -> bool {
     let name_part = image.split(':').next().unwrap_or(image);
@@ -32,39 +326,58 @@ fn is_local_image(image: &str) -> bool {
 /// Pull a single image via `docker pull`, with retries.
 /// Local images (no registry domain) are verified with `docker image inspect` instead.
 /// Credential helper and docker-not-running errors fail immediately (no retry).
-async fn pull_one(image: &str) -> Result<Duration, String> {
+/// `on_progress` gets the percent done each time it moves (see [`LayerProgress`]).
+async fn pull_one(
+    runtime: &impl ImageRuntime,
+    image: &str,
+    mut on_progress: impl FnMut(u8) + Send,
+) -> Result<Duration, PullError> {
     // Local images: just verify they exist, don't try to pull from a registry
     if is_local_image(image) {
         let start = Instant::now();
-        let output = Command::new("docker")
-            .args(["image", "inspect", image])
-            .output()
+        let output = runtime
+            .docker(args(&["image", "inspect", image]))
             .await
-            .map_err(|e| format!("failed to run docker: {}", e))?;
+            .map_err(|e| PullError::Failed(format!("failed to run docker: {}", e)))?;
 
-        if output.status.success() {
+        if output.success {
             return Ok(start.elapsed());
         }
-        return Err(format!("local image not found: {}", image));
+        return Err(PullError::Failed(format!("local image not found: {}", image)));
     }
 
     for attempt in 1..=MAX_RETRIES {
         let start = Instant::now();
-        let output = Command::new("docker")
-            .args(["pull", image])
-            .output()
+        let mut layers = LayerProgress::default();
+        let mut reported = None;
+        let output = runtime
+            .docker_streaming(args(&["pull", image]), |line| {
+                layers.update(line);
+                if layers.percent() != reported {
+                    reported = layers.percent();
+                    reported.into_iter().for_each(&mut on_progress);
+                }
+            })
             .await
-            .map_err(|e| format!("failed to run docker: {}", e))?;
+            .map_err(|e| PullError::Failed(format!("failed to run docker: {}", e)))?;
 
-        if output.status.success() {
+        if output.success {
             return Ok(start.elapsed());
         }
 
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let stderr = output.stderr;
 
         // Don't retry local configuration errors — they won't self-heal
         if is_local_docker_error(&stderr) {
-            return Err(format!("docker config error (skipping retries): {}", stderr.trim()));
+            return Err(PullError::Failed(format!(
+                "docker config error (skipping retries): {}",
+                stderr.trim()
+            )));
+        }
+
+        // Auth failures won't fix themselves either, and need a login hint
+        if is_auth_error(&stderr) {
+            return Err(PullError::AuthRequired { registry: registry_of(image) });
         }
 
         if attempt < MAX_RETRIES {
@@ -72,39 +385,74 @@ async fn pull_one(image: &str) -> Result<Duration, String> {
             tokio::time::sleep(backoff).await;
             continue;
         }
-        return Err(stderr.trim().to_string());
+        return Err(PullError::Failed(stderr.trim().to_string()));
     }
     unreachable!()
 }
 
-/// Pull all images in parallel (max MAX_CONCURRENT at once).
+/// The digest part of a repo digest (`ghcr.io/org/img@sha256:...`).
+pub fn digest_of(repo_digest: &str) -> &str {
+    repo_digest.rsplit_once('@').map_or(repo_digest, |(_, digest)| digest)
+}
+
+/// Check a pulled image's registry digest against `expected`. An image can
+/// carry several repo digests (one per repository it was pulled from); any
+/// of them matching is enough.
+pub async fn verify_digest(runtime: &impl ImageRuntime, image: &str, expected: &str) -> Result<(), PullError> {
+    let output = runtime
+        .docker(args(&["image", "inspect", "--format", "{{json .RepoDigests}}", image]))
+        .await
+        .map_err(|e| PullError::Failed(format!("failed to run docker: {}", e)))?;
+    if !output.success {
+        return Err(PullError::Failed(format!("cannot inspect {}: {}", image, output.stderr.trim())));
+    }
+    let repo_digests: Vec<String> = serde_json::from_str(output.stdout.trim()).unwrap_or_default();
+    if repo_digests.iter().any(|d| digest_of(d) == expected) {
+        return Ok(());
+    }
+    Err(PullError::DigestMismatch {
+        expected: expected.to_string(),
+        actual: repo_digests.first().map_or("none", |d| digest_of(d)).to_string(),
+    })
+}
+
+/// Pull all images in parallel (max `concurrency` at once).
 /// Sends PullEvent messages on the channel for TUI updates.
 pub async fn pull_all(
+    runtime: Arc<impl ImageRuntime>,
     images: Vec<String>,
+    concurrency: usize,
     tx: mpsc::UnboundedSender<PullEvent>,
-) -> Vec<Result<Duration, String>> {
+) -> Vec<Result<Duration, PullError>> {
     use tokio::sync::Semaphore;
-    use std::sync::Arc;
 
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT));
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
     let mut handles = Vec::new();
 
     for (index, image) in images.into_iter().enumerate() {
         let permit = semaphore.clone().acquire_owned().await.unwrap();
         let tx = tx.clone();
         let img = image.clone();
+        let runtime = runtime.clone();
 
         let handle = tokio::spawn(async move {
             tx.send(PullEvent::Started { index, image: img.clone() }).ok();
 
-            let result = pull_one(&img).await;
+            let progress = tx.clone();
+            let result = pull_one(runtime.as_ref(), &img, move |percent| {
+                progress.send(PullEvent::Progress { index, percent }).ok();
+            })
+            .await;
 
             match &result {
                 Ok(elapsed) => {
                     tx.send(PullEvent::Completed { index, image: img, elapsed: *elapsed }).ok();
                 }
+                Err(PullError::AuthRequired { registry }) => {
+                    tx.send(PullEvent::AuthRequired { index, image: img, registry: registry.clone() }).ok();
+                }
                 Err(err) => {
-                    tx.send(PullEvent::Failed { index, image: img, error: err.clone(), attempt: MAX_RETRIES }).ok();
+                    tx.send(PullEvent::Failed { index, image: img, error: err.to_string(), attempt: MAX_RETRIES }).ok();
                 }
             }
 
@@ -122,13 +470,191 @@ pub async fn pull_all(
     results
 }
 
+/// Load a `docker save` tarball (`--image-bundle`). Returns the tagged
+/// images it contained.
+pub async fn load_bundle(runtime: &impl ImageRuntime, path: &std::path::Path) -> Result<Vec<String>, PullError> {
+    let path = path.to_string_lossy();
+    let output = runtime
+        .docker(args(&["load", "-i", &path]))
+        .await
+        .map_err(|e| PullError::Failed(format!("failed to run docker: {}", e)))?;
+    if !output.success {
+        return Err(PullError::Failed(format!("docker load failed: {}", output.stderr.trim())));
+    }
+    Ok(loaded_images(&output.stdout))
+}
+
+/// The image ID (config digest) a `docker save` tarball records for each
+/// of its tags, from the `manifest.json` at its root. `docker load` drops
+/// registry digests, so this is what a loaded image is checked against.
+pub fn bundle_image_ids(path: &std::path::Path) -> Result<std::collections::BTreeMap<String, String>, PullError> {
+    #[derive(serde::Deserialize)]
+    struct Entry {
+        #[serde(rename = "Config")]
+        config: String,
+        #[serde(rename = "RepoTags", default)]
+        repo_tags: Vec<String>,
+    }
+
+    let unreadable = |e: &dyn std::fmt::Display| PullError::Failed(format!("cannot read image bundle {}: {}", path.display(), e));
+    let file = std::fs::File::open(path).map_err(|e| unreadable(&e))?;
+    let mut archive = tar::Archive::new(file);
+    for entry in archive.entries().map_err(|e| unreadable(&e))? {
+        let entry = entry.map_err(|e| unreadable(&e))?;
+        if entry.path().map_err(|e| unreadable(&e))?.as_os_str() != "manifest.json" {
+            continue;
+        }
+        let entries: Vec<Entry> = serde_json::from_reader(entry).map_err(|e| unreadable(&e))?;
+        return Ok(entries
+            .into_iter()
+            .flat_map(|e| {
+                // `blobs/sha256/<hex>` (OCI layout) or `<hex>.json` (older)
+                let hex = e.config.rsplit('/').next().unwrap_or_default().trim_end_matches(".json").to_string();
+                e.repo_tags.into_iter().map(move |tag| (tag, format!("sha256:{}", hex)))
+            })
+            .collect());
+    }
+    Err(unreadable(&"no manifest.json (not a `docker save` tarball?)"))
+}
+
+/// Check a local image's ID against the config digest `expected`, e.g.
+/// one from [`bundle_image_ids`].
+pub async fn verify_image_id(runtime: &impl ImageRuntime, image: &str, expected: &str) -> Result<(), PullError> {
+    let output = runtime
+        .docker(args(&["image", "inspect", "--format", "{{.Id}}", image]))
+        .await
+        .map_err(|e| PullError::Failed(format!("failed to run docker: {}", e)))?;
+    if !output.success {
+        return Err(PullError::Failed(format!("cannot inspect {}: {}", image, output.stderr.trim())));
+    }
+    match output.stdout.trim() {
+        id if id == expected => Ok(()),
+        id => Err(PullError::DigestMismatch { expected: expected.to_string(), actual: id.to_string() }),
+    }
+}
+
+/// Tagged images in `docker load` output (`Loaded image: repo:tag`).
+/// Untagged images (`Loaded image ID: sha256:...`) can't be matched to
+/// the manifest and are skipped.
+pub fn loaded_images(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Loaded image: "))
+        .map(String::from)
+        .collect()
+}
+
+/// Check that each image is in the local Docker cache, e.g. after
+/// [`load_bundle`], sending the same events as [`pull_all`] so progress
+/// is reported the same way. Nothing is pulled.
+pub async fn inspect_all(
+    runtime: Arc<impl ImageRuntime>,
+    images: Vec<String>,
+    tx: mpsc::UnboundedSender<PullEvent>,
+) -> Vec<Result<Duration, PullError>> {
+    let mut results = Vec::new();
+    for (index, image) in images.into_iter().enumerate() {
+        tx.send(PullEvent::Started { index, image: image.clone() }).ok();
+        let start = Instant::now();
+        let result = match runtime.docker(args(&["image", "inspect", &image])).await {
+            Ok(output) if output.success => Ok(start.elapsed()),
+            Ok(_) => Err(PullError::Failed("not in the image bundle".into())),
+            Err(e) => Err(PullError::Failed(format!("failed to run docker: {}", e))),
+        };
+        match &result {
+            Ok(elapsed) => tx.send(PullEvent::Completed { index, image, elapsed: *elapsed }).ok(),
+            Err(err) => tx.send(PullEvent::Failed { index, image, error: err.to_string(), attempt: 1 }).ok(),
+        };
+        results.push(result);
+    }
+    results
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn progress_counts_failures_as_finished_not_succeeded() {
+        let mut progress = PullProgress::new(3);
+        progress.record(&PullEvent::Completed { index: 0, image: "a".into(), elapsed: Duration::ZERO });
+        progress.record(&PullEvent::Completed { index: 1, image: "b".into(), elapsed: Duration::ZERO });
+        progress.record(&PullEvent::Failed { index: 2, image: "c".into(), error: "boom".into(), attempt: 3 });
+        assert!(progress.is_done());
+        assert!(!progress.all_succeeded());
+        assert_eq!(progress.to_string(), "2/3 (1 failed)");
+    }
+
+    #[test]
+    fn layer_statuses_become_a_percentage() {
+        let mut layers = LayerProgress::default();
+        layers.update("0.6.0: Pulling from org/bakerst-brain");
+        assert_eq!(layers.percent(), None);
+        for line in [
+            "a1b2c3d4e5f6: Pulling fs layer",
+            "0f9e8d7c6b5a: Pulling fs layer",
+            "a1b2c3d4e5f6: Downloading  12.5MB/50MB",
+        ] {
+            layers.update(line);
+        }
+        assert_eq!(layers.percent(), Some(0));
+        layers.update("a1b2c3d4e5f6: Download complete");
+        layers.update("0f9e8d7c6b5a: Pull complete");
+        assert_eq!(layers.percent(), Some(75));
+        // A late "Waiting" doesn't move a layer backwards; unknown lines are ignored
+        layers.update("a1b2c3d4e5f6: Waiting");
+        layers.update("Digest: sha256:0123abcd");
+        layers.update("a1b2c3d4e5f6: something new");
+        assert_eq!(layers.percent(), Some(75));
+    }
+
+    #[test]
+    fn loaded_images_are_read_from_docker_load_output() {
+        let stdout = "Loaded image: ghcr.io/org/bakerst-brain:0.6.0\n\
+                      Loaded image ID: sha256:0123abcd\n\
+                      Loaded image: bakerst-ui:dev\n";
+        assert_eq!(loaded_images(stdout), ["ghcr.io/org/bakerst-brain:0.6.0", "bakerst-ui:dev"]);
+    }
+
+    #[test]
+    fn mirrored_images_keep_their_name_tag_and_digest() {
+        let mirror = "harbor.internal/baker/";
+        assert_eq!(
+            mirror_image("ghcr.io/the-baker-street-project/bakerst-brain:0.6.0", mirror),
+            "harbor.internal/baker/bakerst-brain:0.6.0"
+        );
+        assert_eq!(
+            mirror_image("qdrant/qdrant:v1.9@sha256:abc", mirror),
+            "harbor.internal/baker/qdrant:v1.9@sha256:abc"
+        );
+        assert_eq!(mirror_image("bakerst-ui:dev", mirror), "bakerst-ui:dev");
+    }
+
+    #[test]
+    fn auth_errors_are_distinguished() {
+        assert!(is_auth_error("Error response from daemon: Head \"https://ghcr.io/v2/x/manifests/1\": unauthorized"));
+        assert!(is_auth_error("Error response from daemon: denied: requested access to the resource is denied"));
+        assert!(!is_auth_error("Error response from daemon: Get \"https://ghcr.io/v2/\": net/http: TLS handshake timeout"));
+        assert!(!is_auth_error("permission denied while trying to connect to the Docker daemon socket"));
+        assert!(is_auth_error("Error response from daemon: pull access denied for bakerst-brain, repository does not exist or may require 'docker login'"));
+        assert!(!is_auth_error("Error response from daemon: manifest for ghcr.io/org/unauthorized-demo:1 not found: manifest unknown"));
+        // A broken credential helper is the local setup, not the registry
+        let helper = "error getting credentials - err: exec: \"docker-credential-desktop\": executable file not found in $PATH";
+        assert!(is_local_docker_error(helper));
+        assert!(!is_local_docker_error("Error response from daemon: Get \"https://ghcr.io/v2/\": no basic auth credentials"));
+        assert!(is_auth_error("Error response from daemon: Get \"https://ghcr.io/v2/\": no basic auth credentials"));
+        assert_eq!(registry_of("ghcr.io/org/bakerst-brain:0.6.0"), "ghcr.io");
+        assert_eq!(registry_of("localhost:5000/brain:dev"), "localhost:5000");
+        assert_eq!(registry_of("library/nats:2"), "docker.io");
+        assert_eq!(
+            PullError::AuthRequired { registry: "ghcr.io".into() }.to_string(),
+            "Authentication required for ghcr.io \u{2014} run `docker login ghcr.io`"
+        );
+    }
+
     #[tokio::test]
     async fn pull_nonexistent_image_fails() {
-        let result = pull_one("ghcr.io/nonexistent/image:99.99.99").await;
+        let result = pull_one(&Docker, "ghcr.io/nonexistent/image:99.99.99", |_| {}).await;
         assert!(result.is_err());
     }
 }
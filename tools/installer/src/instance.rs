@@ -0,0 +1,266 @@
+//! Named instances — several agents side by side in one namespace.
+//!
+//! `--instance <name>` prefixes every resource the installer creates with
+//! `<name>-` (`team-a-brain`, `team-a-bakerst-brain-secrets`, ...), labels
+//! each object and pod with [`INSTANCE_LABEL`], and scopes selectors to it so
+//! instances never route to or watch each other's pods. Without `--instance`
+//! names stay unprefixed, as before.
+
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// Label carrying the instance name on every object of a named instance.
+pub const INSTANCE_LABEL: &str = "bakerst.io/instance";
+
+/// Longest accepted instance name, leaving room in the 63-character name
+/// limit for the longest template names once prefixed.
+pub const MAX_NAME_LEN: usize = 20;
+
+/// Validate an instance name: a short DNS-1123 label.
+pub fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && !name.ends_with('-');
+    if !valid {
+        bail!(
+            "Invalid instance name '{}': use up to {} lowercase letters, digits and '-', starting with a letter",
+            name,
+            MAX_NAME_LEN
+        );
+    }
+    Ok(())
+}
+
+/// `name` as it is called in `instance`.
+pub fn prefixed(instance: Option<&str>, name: &str) -> String {
+    match instance {
+        Some(instance) => format!("{}-{}", instance, name),
+        None => name.to_string(),
+    }
+}
+
+/// Label selector matching only `instance`'s objects; for the default
+/// instance, only objects without an instance label.
+pub fn selector(instance: Option<&str>) -> String {
+    match instance {
+        Some(instance) => format!("{}={}", INSTANCE_LABEL, instance),
+        None => format!("!{}", INSTANCE_LABEL),
+    }
+}
+
+/// Extra labels for every object of `instance` (see `ApplyParams::labels`).
+pub fn labels(instance: Option<&str>) -> BTreeMap<String, String> {
+    instance
+        .map(|instance| BTreeMap::from([(INSTANCE_LABEL.to_string(), instance.to_string())]))
+        .unwrap_or_default()
+}
+
+/// Where an instance's non-secret config is saved: `~/.bakerst/config.json`
+/// for the default instance, `~/.bakerst/config-<instance>.json` otherwise.
+pub fn config_path(instance: Option<&str>) -> Result<PathBuf> {
+    let file = match instance {
+        Some(instance) => format!("config-{}.json", instance),
+        None => "config.json".to_string(),
+    };
+    Ok(dirs::home_dir()
+        .context("Cannot determine home directory")?
+        .join(".bakerst")
+        .join(file))
+}
+
+/// Write `contents` to `path` readable only by the owner (on Unix): the
+/// file is created 0600, so it is never briefly world-readable, and an
+/// existing one is tightened too. For the saved config and profiles.
+pub fn write_private(path: &std::path::Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // mode() only applies to new files
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+/// Rename template resources for `instance` and rewrite everything that
+/// refers to them by name: secret/ConfigMap/PVC references in pod specs,
+/// service accounts, role bindings, and in-cluster URLs to the template's
+/// Services in env values. Pod templates, Deployment and Service selectors
+/// and NetworkPolicy pod selectors gain the instance label. Fixed NodePorts
+/// are dropped so the cluster assigns free ones instead of clashing with
+/// the other instances.
+pub fn prefix_resources(resources: &mut [serde_json::Value], instance: &str) {
+    let services: BTreeSet<String> = resources
+        .iter()
+        .filter(|r| r["kind"] == "Service")
+        .filter_map(|r| r["metadata"]["name"].as_str().map(String::from))
+        .collect();
+
+    for resource in resources.iter_mut() {
+        if resource["kind"] == "Namespace" {
+            continue;
+        }
+        rename(resource, "/metadata/name", instance);
+        match resource["kind"].as_str().unwrap_or_default() {
+            "Deployment" | "StatefulSet" | "DaemonSet" => {
+                add_label(&mut resource["spec"]["selector"]["matchLabels"], instance);
+                let template = &mut resource["spec"]["template"];
+                add_label(&mut template["metadata"]["labels"], instance);
+                prefix_pod_spec(&mut template["spec"], instance, &services);
+            }
+            "Service" => {
+                if resource["spec"]["selector"].is_object() {
+                    add_label(&mut resource["spec"]["selector"], instance);
+                }
+                for port in items(&mut resource["spec"], "ports") {
+                    if let Some(port) = port.as_object_mut() {
+                        port.remove("nodePort");
+                    }
+                }
+            }
+            "RoleBinding" => {
+                if resource["roleRef"]["kind"] == "Role" {
+                    rename(resource, "/roleRef/name", instance);
+                }
+                for subject in items(resource, "subjects") {
+                    if subject["kind"] == "ServiceAccount" {
+                        rename(subject, "/name", instance);
+                    }
+                }
+            }
+            "NetworkPolicy" => {
+                add_label(&mut resource["spec"]["podSelector"]["matchLabels"], instance);
+                for (direction, peers) in [("ingress", "from"), ("egress", "to")] {
+                    for rule in items(&mut resource["spec"], direction) {
+                        for peer in items(rule, peers) {
+                            // Only same-namespace pod peers; namespace and IP peers are shared
+                            if peer["podSelector"].is_object() && peer.get("namespaceSelector").is_none() {
+                                add_label(&mut peer["podSelector"]["matchLabels"], instance);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Prefix the name at `pointer`, if there is one.
+fn rename(value: &mut serde_json::Value, pointer: &str, instance: &str) {
+    if let Some(target) = value.pointer_mut(pointer) {
+        if let Some(name) = target.as_str() {
+            *target = prefixed(Some(instance), name).into();
+        }
+    }
+}
+
+/// The elements of the array at `key`, if present. Unlike indexing, this
+/// doesn't insert `null` for a missing key.
+fn items<'a>(value: &'a mut serde_json::Value, key: &str) -> impl Iterator<Item = &'a mut serde_json::Value> {
+    value.get_mut(key).and_then(|v| v.as_array_mut()).into_iter().flatten()
+}
+
+/// Add the instance label to a label map (created if absent).
+fn add_label(labels: &mut serde_json::Value, instance: &str) {
+    if labels.is_null() || labels.is_object() {
+        labels[INSTANCE_LABEL] = instance.into();
+    }
+}
+
+/// Rewrite the name references inside one pod spec.
+fn prefix_pod_spec(spec: &mut serde_json::Value, instance: &str, services: &BTreeSet<String>) {
+    rename(spec, "/serviceAccountName", instance);
+    for volume in items(spec, "volumes") {
+        rename(volume, "/secret/secretName", instance);
+        rename(volume, "/configMap/name", instance);
+        rename(volume, "/persistentVolumeClaim/claimName", instance);
+    }
+    for key in ["containers", "initContainers"] {
+        for container in items(spec, key) {
+            for source in items(container, "envFrom") {
+                rename(source, "/secretRef/name", instance);
+                rename(source, "/configMapRef/name", instance);
+            }
+            for var in items(container, "env") {
+                rename(var, "/valueFrom/secretKeyRef/name", instance);
+                rename(var, "/valueFrom/configMapKeyRef/name", instance);
+                if let Some(value) = var.get_mut("value") {
+                    if let Some(url) = value.as_str() {
+                        *value = prefix_service_hosts(url, instance, services).into();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Point in-cluster URLs at the instance's Services: `http://brain:3000`
+/// becomes `http://<instance>-brain:3000`. Only hosts that are one of the
+/// template's Services are touched.
+pub fn prefix_service_hosts(value: &str, instance: &str, services: &BTreeSet<String>) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(pos) = rest.find("://") {
+        let (head, tail) = rest.split_at(pos + 3);
+        out.push_str(head);
+        let host_len = tail
+            .find([':', '/', '.', ',', ' ', '?'])
+            .unwrap_or(tail.len());
+        let host = &tail[..host_len];
+        if services.contains(host) {
+            out.push_str(&prefixed(Some(instance), host));
+        } else {
+            out.push_str(host);
+        }
+        rest = &tail[host_len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Prefix the K8s Secret names of routed secrets.
+pub fn prefix_secret_names(
+    routes: BTreeMap<String, BTreeMap<String, String>>,
+    instance: Option<&str>,
+) -> BTreeMap<String, BTreeMap<String, String>> {
+    routes
+        .into_iter()
+        .map(|(name, data)| (prefixed(instance, &name), data))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_names_are_short_dns_labels() {
+        assert!(check_name("team-a").is_ok());
+        assert!(check_name("Team").is_err());
+        assert!(check_name("1st").is_err());
+        assert!(check_name("a-").is_err());
+        assert!(check_name(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn only_template_service_hosts_are_prefixed() {
+        let services = BTreeSet::from(["brain".to_string(), "nats".to_string()]);
+        assert_eq!(
+            prefix_service_hosts("nats://nats:4222,http://brain.bakerst.svc:3000/x", "a", &services),
+            "nats://a-nats:4222,http://a-brain.bakerst.svc:3000/x"
+        );
+        assert_eq!(
+            prefix_service_hosts("https://api.anthropic.com", "a", &services),
+            "https://api.anthropic.com"
+        );
+    }
+}
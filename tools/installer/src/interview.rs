@@ -3,14 +3,16 @@
 //! Three modes:
 //! - `from_config_file`: non-interactive, reads a YAML config file
 //! - `from_env`: non-interactive, reads secrets from environment variables
+//!   (and the `--env-file`, under them)
 //! - `run_interactive`: stdin-based interactive interview
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use std::collections::HashMap;
 use std::io::{self, BufRead, BufReader, Write};
 
 use crate::config_file::ConfigFile;
-use crate::config_schema::ConfigSchema;
+use crate::config_schema::{ConfigSchema, SecretDef};
+use crate::deploy::ResourceProfile;
 
 type StdinReader = BufReader<std::io::Stdin>;
 
@@ -22,12 +24,35 @@ enum Provider {
     Ollama,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct InterviewResult {
     pub secrets: HashMap<String, String>,
     pub enabled_features: Vec<String>,
     pub namespace: String,
     pub agent_name: String,
+    /// `--instance` name, prefixed to every resource name; `None` for the
+    /// default, unprefixed install.
+    pub instance: Option<String>,
+    /// `--storage-class` for the install's PVCs; `None` uses the cluster
+    /// default. Saved so updates re-apply the same (immutable) class.
+    pub storage_class: Option<String>,
+    /// `--data-dir` the install's volumes are hostPath directories under,
+    /// in place of PVCs. Saved so updates don't switch back to claims.
+    pub data_dir: Option<String>,
+    /// `--resource-profile` the install was sized with, saved so updates
+    /// keep it.
+    pub resource_profile: ResourceProfile,
+    /// `--registry-mirror` every image is pulled from, saved so updates
+    /// keep it.
+    pub registry_mirror: Option<String>,
+    /// `--ui-node-port` the UI's Service is exposed on; `None` keeps the
+    /// release's own (see [`crate::deploy::DEFAULT_UI_NODE_PORT`]).
+    pub ui_node_port: Option<u16>,
+    /// `--stateful-storage`, saved so updates keep the StatefulSets.
+    pub stateful_storage: bool,
+    /// `--set` template values, saved so updates render the manifests with
+    /// them too.
+    pub set: Vec<(String, String)>,
 }
 
 impl InterviewResult {
@@ -35,12 +60,95 @@ impl InterviewResult {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let non_secret = serde_json::json!({
+        let mut non_secret = serde_json::json!({
             "namespace": self.namespace,
             "enabledFeatures": self.enabled_features,
             "agentName": self.agent_name,
         });
-        std::fs::write(path, serde_json::to_string_pretty(&non_secret)?)?;
+        if let Some(instance) = &self.instance {
+            non_secret["instance"] = instance.as_str().into();
+        }
+        if let Some(class) = &self.storage_class {
+            non_secret["storageClass"] = class.as_str().into();
+        }
+        if let Some(dir) = &self.data_dir {
+            non_secret["dataDir"] = dir.as_str().into();
+        }
+        if self.resource_profile != ResourceProfile::Standard {
+            non_secret["resourceProfile"] = self.resource_profile.name().into();
+        }
+        if let Some(mirror) = &self.registry_mirror {
+            non_secret["registryMirror"] = mirror.as_str().into();
+        }
+        if let Some(port) = self.ui_node_port {
+            non_secret["uiNodePort"] = port.into();
+        }
+        if self.stateful_storage {
+            non_secret["statefulStorage"] = true.into();
+        }
+        if !self.set.is_empty() {
+            let set: std::collections::BTreeMap<&str, &str> = self.set.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            non_secret["set"] = serde_json::json!(set);
+        }
+        crate::instance::write_private(path, &serde_json::to_string_pretty(&non_secret)?)?;
+        Ok(())
+    }
+
+    /// Write everything collected as a config file that `--profile` (or
+    /// `--config`) loads, so a reinstall needs no prompts. TOML if the path
+    /// ends in `.toml`, YAML otherwise. Unless `include_secrets`, values of
+    /// `secret` inputs become `${KEY}` references resolved from the
+    /// environment on load, and auto-generated ones are regenerated.
+    pub fn save_profile(&self, path: &std::path::Path, schema: &ConfigSchema, include_secrets: bool) -> Result<()> {
+        let defs: HashMap<&str, &SecretDef> = schema
+            .secrets
+            .iter()
+            .chain(schema.features.iter().flat_map(|f| &f.secrets))
+            .map(|d| (d.key.as_str(), d))
+            .collect();
+        let secrets: std::collections::BTreeMap<&str, String> = self
+            .secrets
+            .iter()
+            .map(|(key, value)| {
+                let value = match defs.get(key.as_str()) {
+                    Some(def) if def.input_type == "secret" && !include_secrets => {
+                        if def.auto_generate.is_some() {
+                            "auto".to_string()
+                        } else {
+                            format!("${{{}}}", key)
+                        }
+                    }
+                    _ => value.clone(),
+                };
+                (key.as_str(), value)
+            })
+            .collect();
+        let features: std::collections::BTreeMap<&str, bool> = schema
+            .features
+            .iter()
+            .map(|f| (f.id.as_str(), self.enabled_features.contains(&f.id)))
+            .collect();
+        let profile = serde_json::json!({
+            "namespace": self.namespace,
+            "secrets": secrets,
+            "features": features,
+        });
+
+        let mut content = String::from("# Baker Street install profile: bakerst-install install --profile <this file>\n");
+        if !include_secrets {
+            content.push_str("# Secret values are read from the environment variables named in ${...}.\n");
+        }
+        if path.extension().and_then(|e| e.to_str()) == Some("toml") {
+            content.push_str(&toml::to_string_pretty(&profile)?);
+        } else {
+            content.push_str(&serde_yaml::to_string(&profile)?);
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Private either way: even without secrets it names the install
+        crate::instance::write_private(path, &content)
+            .with_context(|| format!("Failed to write profile {}", path.display()))?;
         Ok(())
     }
 }
@@ -104,7 +212,7 @@ pub fn from_config_file(schema: &ConfigSchema, config: &ConfigFile) -> Result<In
         .provider_validation
         .require_at_least_one
         .iter()
-        .any(|key| secrets.get(key).map_or(false, |v| !v.is_empty()));
+        .any(|key| secrets.get(key).is_some_and(|v| !v.is_empty()));
     if !has_provider {
         bail!("{}", schema.provider_validation.message);
     }
@@ -114,12 +222,23 @@ pub fn from_config_file(schema: &ConfigSchema, config: &ConfigFile) -> Result<In
         enabled_features,
         namespace,
         agent_name: schema.defaults.agent_name.clone(),
+        ..Default::default()
     })
 }
 
 /// Run the full interactive interview. Walks the user through provider selection,
 /// model role assignment, security, memory, and features.
-pub async fn run_interactive(schema: &ConfigSchema) -> Result<InterviewResult> {
+/// `reveal_secrets` (from `--reveal-secrets-in-confirm`) shows the entered
+/// secret values in the review instead of masking them.
+/// `upgrade` is the installed and the new version, when an install
+/// already exists, so the review shows what is being replaced. `theme`
+/// `mono` keeps the prompts to the terminal's own colors.
+pub async fn run_interactive(
+    schema: &ConfigSchema,
+    reveal_secrets: bool,
+    upgrade: Option<(&str, &str)>,
+    theme: crate::cli::Theme,
+) -> Result<InterviewResult> {
     let stdin = io::stdin();
     let mut reader = BufReader::new(stdin);
 
@@ -147,9 +266,34 @@ pub async fn run_interactive(schema: &ConfigSchema) -> Result<InterviewResult> {
     // Section 5: Features
     let enabled_features = section_features(&mut reader, schema, &mut secrets).await?;
 
-    // Section 6: Confirmation
-    if !section_confirm(&mut reader, &namespace, &agent_name, provider, &secrets, &enabled_features)? {
-        anyhow::bail!("Installation cancelled by user.");
+    // Section 6: Confirmation, re-shown after each secret is edited
+    let secret_keys = secret_input_keys(schema);
+    let mut edited = Vec::new();
+    loop {
+        let review = Review {
+            namespace: &namespace,
+            agent_name: &agent_name,
+            provider,
+            secrets: &secrets,
+            features: &enabled_features,
+            total_features: schema.features.len(),
+            secret_keys: secret_keys.clone(),
+            edited: &edited,
+            reveal_secrets,
+            color: theme != crate::cli::Theme::Mono && crate::cli::ColorMode::Auto.enabled(),
+            upgrade,
+        };
+        match section_confirm(&mut reader, &review)? {
+            Confirm::Proceed => break,
+            Confirm::Cancel => anyhow::bail!("Installation cancelled by user."),
+            Confirm::Edit => {
+                if let Some(key) = edit_secret(&mut reader, &mut secrets, &secret_keys)? {
+                    if !edited.contains(&key) {
+                        edited.push(key);
+                    }
+                }
+            }
+        }
     }
 
     Ok(InterviewResult {
@@ -157,6 +301,7 @@ pub async fn run_interactive(schema: &ConfigSchema) -> Result<InterviewResult> {
         enabled_features,
         namespace,
         agent_name,
+        ..Default::default()
     })
 }
 
@@ -187,7 +332,7 @@ fn prompt_text(
 pub fn from_env(schema: &ConfigSchema) -> Result<InterviewResult> {
     let mut secrets = HashMap::new();
     for secret_def in &schema.secrets {
-        if let Ok(val) = std::env::var(&secret_def.key) {
+        if let Some(val) = crate::env_file::var(&secret_def.key) {
             secrets.insert(secret_def.key.clone(), val);
         } else if let Some(ref auto_gen) = secret_def.auto_generate {
             secrets.insert(secret_def.key.clone(), generate_value(auto_gen)?);
@@ -205,7 +350,7 @@ pub fn from_env(schema: &ConfigSchema) -> Result<InterviewResult> {
         .provider_validation
         .require_at_least_one
         .iter()
-        .any(|key| secrets.get(key).map_or(false, |v| !v.is_empty()));
+        .any(|key| secrets.get(key).is_some_and(|v| !v.is_empty()));
     if !has_provider {
         bail!("{}", schema.provider_validation.message);
     }
@@ -214,7 +359,8 @@ pub fn from_env(schema: &ConfigSchema) -> Result<InterviewResult> {
         secrets,
         enabled_features,
         namespace: schema.defaults.namespace.clone(),
-        agent_name: schema.defaults.agent_name.clone(),
+        agent_name: resolve_env_key("AGENT_NAME").unwrap_or_else(|| schema.defaults.agent_name.clone()),
+        ..Default::default()
     })
 }
 
@@ -244,10 +390,11 @@ fn section_basics(reader: &mut StdinReader, schema: &ConfigSchema) -> Result<(St
         false,
     )?;
 
+    let agent_default = resolve_env_key("AGENT_NAME").unwrap_or_else(|| schema.defaults.agent_name.clone());
     let agent_name = prompt_text(
         reader,
         "What name would you like to give your AI assistant?",
-        Some(&schema.defaults.agent_name),
+        Some(&agent_default),
         false,
     )?;
 
@@ -331,21 +478,12 @@ async fn collect_anthropic(
 
     // Check env var first
     let env_key = resolve_env_key("ANTHROPIC_API_KEY");
-    let key = if let Some(ref env_val) = env_key {
-        let masked = mask_value(env_val);
-        let use_it = prompt_text(
-            reader,
-            &format!("Found ANTHROPIC_API_KEY in env ({}). Use this?", masked),
-            Some("Y"),
-            false,
-        )?;
-        if use_it.trim().eq_ignore_ascii_case("n") {
-            None
-        } else {
-            Some(env_val.clone())
+    let key = match env_key {
+        Some(env_val) => {
+            let question = format!("Found ANTHROPIC_API_KEY in env ({}). Use this?", mask_value(&env_val));
+            use_found(reader, "ANTHROPIC_API_KEY", &env_val, &question)?.then_some(env_val)
         }
-    } else {
-        None
+        None => None,
     };
 
     // Collect and validate API key
@@ -424,21 +562,12 @@ async fn collect_openai(
 
     // Check env var first
     let env_key = resolve_env_key("OPENAI_API_KEY");
-    let key = if let Some(ref env_val) = env_key {
-        let masked = mask_value(env_val);
-        let use_it = prompt_text(
-            reader,
-            &format!("Found OPENAI_API_KEY in env ({}). Use this?", masked),
-            Some("Y"),
-            false,
-        )?;
-        if use_it.trim().eq_ignore_ascii_case("n") {
-            None
-        } else {
-            Some(env_val.clone())
+    let key = match env_key {
+        Some(env_val) => {
+            let question = format!("Found OPENAI_API_KEY in env ({}). Use this?", mask_value(&env_val));
+            use_found(reader, "OPENAI_API_KEY", &env_val, &question)?.then_some(env_val)
         }
-    } else {
-        None
+        None => None,
     };
 
     let api_key = if let Some(k) = key {
@@ -515,21 +644,12 @@ async fn collect_openrouter(
 
     // Check env var first
     let env_key = resolve_env_key("OPENROUTER_API_KEY");
-    let key = if let Some(ref env_val) = env_key {
-        let masked = mask_value(env_val);
-        let use_it = prompt_text(
-            reader,
-            &format!("Found OPENROUTER_API_KEY in env ({}). Use this?", masked),
-            Some("Y"),
-            false,
-        )?;
-        if use_it.trim().eq_ignore_ascii_case("n") {
-            None
-        } else {
-            Some(env_val.clone())
+    let key = match env_key {
+        Some(env_val) => {
+            let question = format!("Found OPENROUTER_API_KEY in env ({}). Use this?", mask_value(&env_val));
+            use_found(reader, "OPENROUTER_API_KEY", &env_val, &question)?.then_some(env_val)
         }
-    } else {
-        None
+        None => None,
     };
 
     let api_key = if let Some(k) = key {
@@ -673,7 +793,7 @@ async fn collect_ollama(
         secrets.insert("WORKER_MODEL".into(), worker_model);
     } else {
         // Sort by size descending
-        all_models.sort_by(|a, b| b.size.cmp(&a.size));
+        all_models.sort_by_key(|m| std::cmp::Reverse(m.size));
 
         println!();
         println!("Found {} model(s):", all_models.len());
@@ -764,6 +884,10 @@ fn section_security(reader: &mut StdinReader) -> Result<String> {
     println!("--- 🔒 Security ---");
     println!();
 
+    if let Some(token) = resolve_env_key("AUTH_TOKEN").filter(|_| crate::env_file::from_file("AUTH_TOKEN")) {
+        println!("  Using AUTH_TOKEN from --env-file ({})", mask_value(&token));
+        return Ok(token);
+    }
     let token = prompt_text(
         reader,
         "Enter an auth token, or press Enter to generate one automatically",
@@ -793,25 +917,17 @@ async fn section_memory(reader: &mut StdinReader) -> Result<Option<String>> {
     println!();
 
     // Check env var first
-    if let Ok(env_key) = std::env::var("VOYAGE_API_KEY") {
-        if !env_key.is_empty() {
-            let masked = mask_value(&env_key);
-            let use_it = prompt_text(
-                reader,
-                &format!("I found a Voyage AI key in your environment ({}). Use this?", masked),
-                Some("Y"),
-                false,
-            )?;
-            if !use_it.trim().eq_ignore_ascii_case("n") {
-                print!("  Verifying... ");
-                std::io::stdout().flush()?;
-                match validation::validate_voyage_key(&env_key).await {
-                    Ok(()) => {
-                        println!("✓ Key verified");
-                        return Ok(Some(env_key));
-                    }
-                    Err(e) => println!("✗ {}", e),
+    if let Some(env_key) = resolve_env_key("VOYAGE_API_KEY") {
+        let question = format!("I found a Voyage AI key in your environment ({}). Use this?", mask_value(&env_key));
+        if use_found(reader, "VOYAGE_API_KEY", &env_key, &question)? {
+            print!("  Verifying... ");
+            std::io::stdout().flush()?;
+            match validation::validate_voyage_key(&env_key).await {
+                Ok(()) => {
+                    println!("✓ Key verified");
+                    return Ok(Some(env_key));
                 }
+                Err(e) => println!("✗ {}", e),
             }
         }
     }
@@ -884,23 +1000,18 @@ async fn section_features(
             }
 
             // Check env var
-            let env_val = std::env::var(&secret_def.key).ok().filter(|v| !v.is_empty());
+            let env_val = resolve_env_key(&secret_def.key);
             let value = if let Some(env_val) = &env_val {
-                let masked = mask_value(env_val);
-                let use_it = prompt_text(
-                    reader,
-                    &format!(
-                        "I found {} in your environment ({}). Use this?",
-                        secret_def.key, masked
-                    ),
-                    Some("Y"),
-                    false,
-                )?;
-                if use_it.trim().eq_ignore_ascii_case("n") {
+                let question = format!(
+                    "I found {} in your environment ({}). Use this?",
+                    secret_def.key,
+                    mask_value(env_val)
+                );
+                if use_found(reader, &secret_def.key, env_val, &question)? {
+                    env_val.clone()
+                } else {
                     let prompt = secret_def.prompt.as_deref().unwrap_or(&secret_def.description);
                     prompt_text(reader, prompt, None, secret_def.required)?
-                } else {
-                    env_val.clone()
                 }
             } else {
                 let prompt = secret_def.prompt.as_deref().unwrap_or(&secret_def.description);
@@ -942,19 +1053,126 @@ async fn section_features(
 }
 
 /// Section 6: Confirmation summary.
-fn section_confirm(
-    reader: &mut StdinReader,
-    namespace: &str,
-    agent_name: &str,
+/// Everything the review screen shows.
+struct Review<'a> {
+    namespace: &'a str,
+    agent_name: &'a str,
     provider: Provider,
+    secrets: &'a HashMap<String, String>,
+    features: &'a [String],
+    total_features: usize,
+    /// Keys entered as secrets, listed masked (or revealed) in the review
+    secret_keys: Vec<String>,
+    /// Secrets changed from the review, marked as such
+    edited: &'a [String],
+    reveal_secrets: bool,
+    /// Whether the plain-text warning may use ANSI colors (a terminal,
+    /// without NO_COLOR or `--theme mono`)
+    color: bool,
+    /// Installed and new version, when replacing an existing install
+    upgrade: Option<(&'a str, &'a str)>,
+}
+
+/// Answer to the review's proceed prompt.
+#[derive(Debug, PartialEq)]
+enum Confirm {
+    Proceed,
+    Cancel,
+    /// Go back and change one of the entered secrets
+    Edit,
+}
+
+impl Confirm {
+    fn parse(answer: &str) -> Self {
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "n" | "no" => Confirm::Cancel,
+            "e" | "edit" => Confirm::Edit,
+            _ => Confirm::Proceed,
+        }
+    }
+}
+
+/// Keys the schema collects with `inputType: secret`, features included.
+pub fn secret_input_keys(schema: &ConfigSchema) -> Vec<String> {
+    schema
+        .secrets
+        .iter()
+        .chain(schema.features.iter().flat_map(|f| f.secrets.iter()))
+        .filter(|s| s.input_type == "secret")
+        .map(|s| s.key.clone())
+        .collect()
+}
+
+/// The `keys` that were given a value, in review order.
+fn entered_secret_keys<'a>(secrets: &HashMap<String, String>, keys: &'a [String]) -> Vec<&'a String> {
+    keys.iter()
+        .filter(|key| secrets.get(*key).is_some_and(|v| !v.is_empty()))
+        .collect()
+}
+
+/// Numbered review lines for the entered secrets, masked unless `reveal`;
+/// `edited` ones are marked.
+fn secret_review_lines(
     secrets: &HashMap<String, String>,
-    features: &[String],
-) -> Result<bool> {
+    keys: &[String],
+    edited: &[String],
+    reveal: bool,
+) -> Vec<String> {
+    entered_secret_keys(secrets, keys)
+        .into_iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let value = &secrets[key];
+            let shown = if reveal { value.clone() } else { mask_value(value) };
+            let mark = if edited.contains(key) { "  (edited)" } else { "" };
+            format!("[{}] {:<22} {}{}", i + 1, key, shown, mark)
+        })
+        .collect()
+}
+
+/// Let the user pick one of the entered secrets and type a new value.
+/// Returns the changed key, or `None` if nothing changed.
+fn edit_secret(
+    reader: &mut StdinReader,
+    secrets: &mut HashMap<String, String>,
+    keys: &[String],
+) -> Result<Option<String>> {
+    let entered = entered_secret_keys(secrets, keys);
+    if entered.is_empty() {
+        println!("  No secrets to edit.");
+        return Ok(None);
+    }
+    let choice = prompt_text(reader, &format!("Secret to change [1-{}]", entered.len()), None, false)?;
+    let Some(key) = choice
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| entered.get(i))
+        .map(|k| k.to_string())
+    else {
+        println!("  No such secret; nothing changed.");
+        return Ok(None);
+    };
+    let value = prompt_secret(reader, &format!("New value for {} (Enter keeps the current one)", key))?;
+    if value.is_empty() {
+        return Ok(None);
+    }
+    secrets.insert(key.clone(), value);
+    Ok(Some(key))
+}
+
+fn section_confirm(reader: &mut StdinReader, review: &Review) -> Result<Confirm> {
+    let &Review { namespace, agent_name, provider, secrets, features, total_features, .. } = review;
     println!();
     println!("--- ✅ Review ---");
     println!();
     println!("  Namespace:    {}", namespace);
     println!("  Agent name:   {}", agent_name);
+    match review.upgrade {
+        Some((installed, new)) if installed == new => println!("  Installed:    v{} (reinstall)", installed),
+        Some((installed, new)) => println!("  Installed:    v{} (replaced by v{})", installed, new),
+        None => {}
+    }
     println!();
 
     let provider_str = match provider {
@@ -995,20 +1213,54 @@ fn section_confirm(
 
     println!();
 
-    if features.is_empty() {
-        println!("  Features:     (none)");
-    } else {
-        println!("  Features:     {}", features.join(", "));
-    }
+    // Keep the review (and the prompt under it) on screen: a long feature
+    // list collapses to a count rather than wrapping off the terminal
+    let width = crossterm::terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
+    println!("  Features:     {}", features_summary(features, total_features, width.saturating_sub(16)));
 
     if secrets.contains_key("VOYAGE_API_KEY") {
         println!("  Memory:       Voyage AI embeddings");
     }
 
+    let secret_lines = secret_review_lines(secrets, &review.secret_keys, review.edited, review.reveal_secrets);
+    if !secret_lines.is_empty() {
+        println!();
+        if review.reveal_secrets {
+            let warning = "\u{26a0} SECRET VALUES SHOWN IN PLAIN TEXT (--reveal-secrets-in-confirm) \u{2014} clear your scrollback afterwards";
+            match review.color {
+                true => println!("  \x1B[1;31m{}\x1B[0m", warning),
+                false => println!("  {}", warning),
+            }
+        }
+        println!("  Secrets:");
+        for line in &secret_lines {
+            println!("    {}", line);
+        }
+    }
+
     println!();
 
-    let proceed = prompt_text(reader, "Proceed with installation?", Some("Y"), false)?;
-    Ok(!proceed.trim().eq_ignore_ascii_case("n"))
+    let question = if secret_lines.is_empty() {
+        "Proceed with installation?"
+    } else {
+        "Proceed with installation? (e to edit a secret)"
+    };
+    let answer = prompt_text(reader, question, Some("Y"), false)?;
+    Ok(Confirm::parse(&answer))
+}
+
+/// One-line feature summary for the review: the enabled names when they fit
+/// in `max_width`, otherwise "N features, M enabled".
+pub fn features_summary(enabled: &[String], total: usize, max_width: usize) -> String {
+    if enabled.is_empty() {
+        return "(none)".into();
+    }
+    let listed = enabled.join(", ");
+    if listed.chars().count() <= max_width {
+        listed
+    } else {
+        format!("{} features, {} enabled", total.max(enabled.len()), enabled.len())
+    }
 }
 
 /// Resolve a model default using provider-scoped env vars.
@@ -1024,16 +1276,26 @@ fn resolve_model_default(provider_prefix: &str, role: &str, hardcoded: &str) ->
     let scoped_key = format!("{}_{}_MODEL", provider_prefix, role);
 
     // Provider-scoped first, then generic, then hardcoded
-    std::env::var(&scoped_key)
-        .ok()
-        .filter(|v| !v.is_empty())
-        .or_else(|| std::env::var(&env_key).ok().filter(|v| !v.is_empty()))
+    resolve_env_key(&scoped_key)
+        .or_else(|| resolve_env_key(&env_key))
         .unwrap_or_else(|| hardcoded.to_string())
 }
 
-/// Resolve the API key from a provider-scoped env var.
+/// Resolve the API key from a provider-scoped env var (or the `--env-file`).
 fn resolve_env_key(env_var: &str) -> Option<String> {
-    std::env::var(env_var).ok().filter(|v| !v.is_empty())
+    crate::env_file::var(env_var).filter(|v| !v.is_empty())
+}
+
+/// Whether to use `value`, found for `key` in the environment or the
+/// `--env-file`. A value from the file was put there for this install, so
+/// it's taken without asking.
+fn use_found(reader: &mut impl BufRead, key: &str, value: &str, question: &str) -> Result<bool> {
+    if crate::env_file::from_file(key) {
+        println!("  Using {} from --env-file ({})", key, mask_value(value));
+        return Ok(true);
+    }
+    let answer = prompt_text(reader, question, Some("Y"), false)?;
+    Ok(!answer.trim().eq_ignore_ascii_case("n"))
 }
 
 /// Mask a secret value for display: show first 4 and last 4 chars.
@@ -1054,6 +1316,45 @@ fn prompt_secret(reader: &mut StdinReader, prompt: &str) -> Result<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn review_masks_secrets_unless_revealed() {
+        let secrets: HashMap<String, String> = [
+            ("ANTHROPIC_API_KEY".to_string(), "sk-ant-abcdefgh1234".to_string()),
+            ("DEFAULT_MODEL".to_string(), "claude".to_string()),
+        ]
+        .into();
+        let keys = vec!["ANTHROPIC_API_KEY".to_string(), "OPENAI_API_KEY".to_string()];
+        let masked = secret_review_lines(&secrets, &keys, &[], false);
+        assert_eq!(masked.len(), 1);
+        assert!(masked[0].contains("sk-a...1234") && !masked[0].contains("abcdefgh"));
+        assert!(secret_review_lines(&secrets, &keys, &[], true)[0].contains("sk-ant-abcdefgh1234"));
+    }
+
+    #[test]
+    fn review_numbers_secrets_and_marks_edited_ones() {
+        let secrets: HashMap<String, String> = [
+            ("ANTHROPIC_API_KEY".to_string(), "sk-ant-abcdefgh1234".to_string()),
+            ("TELEGRAM_BOT_TOKEN".to_string(), "123456:abcdefghijk".to_string()),
+        ]
+        .into();
+        let keys = vec![
+            "ANTHROPIC_API_KEY".to_string(),
+            "OPENAI_API_KEY".to_string(),
+            "TELEGRAM_BOT_TOKEN".to_string(),
+        ];
+        let lines = secret_review_lines(&secrets, &keys, &["TELEGRAM_BOT_TOKEN".to_string()], false);
+        assert!(lines[0].starts_with("[1] ANTHROPIC_API_KEY") && !lines[0].contains("(edited)"));
+        assert!(lines[1].starts_with("[2] TELEGRAM_BOT_TOKEN") && lines[1].ends_with("(edited)"));
+    }
+
+    #[test]
+    fn confirm_answers() {
+        assert_eq!(Confirm::parse(""), Confirm::Proceed);
+        assert_eq!(Confirm::parse("y"), Confirm::Proceed);
+        assert_eq!(Confirm::parse("N"), Confirm::Cancel);
+        assert_eq!(Confirm::parse(" e "), Confirm::Edit);
+    }
+
     #[test]
     fn test_generate_hex_value() {
         let val = generate_value("hex:32").unwrap();
@@ -1061,6 +1362,14 @@ mod tests {
         assert!(val.chars().all(|c| c.is_ascii_hexdigit()));
     }
 
+    #[test]
+    fn test_features_summary_collapses_when_too_wide() {
+        let enabled: Vec<String> = ["telegram", "discord", "github"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(features_summary(&enabled, 8, 80), "telegram, discord, github");
+        assert_eq!(features_summary(&enabled, 8, 10), "8 features, 3 enabled");
+        assert_eq!(features_summary(&[], 8, 80), "(none)");
+    }
+
     #[test]
     fn test_generate_unknown_format() {
         assert!(generate_value("base64:32").is_err());
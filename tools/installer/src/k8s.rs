@@ -1,41 +1,649 @@
 use anyhow::{bail, Context, Result};
-use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
 use k8s_openapi::api::core::v1::{
-    ConfigMap, Namespace, PersistentVolumeClaim, Secret, Service, ServiceAccount,
+    ConfigMap, Event, Namespace, Node, PersistentVolume, PersistentVolumeClaim, Pod, PodSpec, PodTemplateSpec,
+    Secret, Service, ServiceAccount,
 };
-use k8s_openapi::api::networking::v1::NetworkPolicy;
+use k8s_openapi::api::networking::v1::{Ingress, NetworkPolicy};
 use k8s_openapi::api::rbac::v1::{Role, RoleBinding};
-use kube::api::{Api, DeleteParams, ListParams, Patch, PatchParams};
-use kube::Client;
+use k8s_openapi::api::storage::v1::StorageClass;
+use kube::api::{
+    Api, ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, LogParams,
+    Patch, PatchParams, PostParams,
+};
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::{Client, Resource, ResourceExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::collections::BTreeMap;
 
 const PATCH_PARAMS: &str = "bakerst-install";
 
+/// Default SSA field manager, versioned so `--show-managed-fields` tells
+/// which installer release last wrote a field.
+pub fn default_field_manager() -> String {
+    format!("{}/{}", PATCH_PARAMS, env!("CARGO_PKG_VERSION"))
+}
+
+/// How, and as whom, resources are written to the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplyParams {
+    pub strategy: ApplyStrategy,
+    pub field_manager: String,
+    /// Server-side dry run: the API server validates and admits each object
+    /// but persists nothing.
+    pub dry_run: bool,
+    /// Extra labels set on every object next to the ownership label, e.g.
+    /// the `--instance` label. Not set on the namespace, which is shared.
+    pub labels: BTreeMap<String, String>,
+    /// How many times a transiently failing apply is retried (see
+    /// `is_retryable_apply_error`) before the step fails.
+    pub retries: u32,
+}
+
+impl Default for ApplyParams {
+    fn default() -> Self {
+        Self {
+            strategy: ApplyStrategy::Auto,
+            field_manager: default_field_manager(),
+            dry_run: false,
+            labels: BTreeMap::new(),
+            retries: DEFAULT_APPLY_RETRIES,
+        }
+    }
+}
+
+/// Default for `--apply-retries`.
+pub const DEFAULT_APPLY_RETRIES: u32 = 3;
+/// Most `--apply-retries` allowed.
+pub const MAX_APPLY_RETRIES: u32 = 10;
+/// Wait before the first apply retry, doubled for each one after up to
+/// [`MAX_APPLY_RETRY_BACKOFF`].
+const APPLY_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+const MAX_APPLY_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long to wait before retry number `attempt` (from 1).
+fn apply_retry_backoff(attempt: u32) -> std::time::Duration {
+    2u32.checked_pow(attempt.saturating_sub(1))
+        .and_then(|factor| APPLY_RETRY_BACKOFF.checked_mul(factor))
+        .map_or(MAX_APPLY_RETRY_BACKOFF, |backoff| backoff.min(MAX_APPLY_RETRY_BACKOFF))
+}
+
+/// System namespaces the installer refuses to install into or delete.
+pub const PROTECTED_NAMESPACES: &[&str] = &["default", "kube-system", "kube-public", "kube-node-lease"];
+
+/// Validate a target namespace: DNS-1123 label syntax, and not one of
+/// `PROTECTED_NAMESPACES`. `allow_default` (from `--i-know-what-im-doing`)
+/// permits `default` only; the kube-* namespaces are never allowed.
+pub fn check_namespace(name: &str, allow_default: bool) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-');
+    if !valid {
+        bail!(
+            "Invalid namespace '{}': must be 1-63 lowercase letters, digits or '-', starting and ending with a letter or digit",
+            name
+        );
+    }
+    if PROTECTED_NAMESPACES.contains(&name) && !(name == "default" && allow_default) {
+        if name == "default" {
+            bail!("Refusing to use the 'default' namespace (pass --i-know-what-im-doing to override)");
+        }
+        bail!("Refusing to use protected system namespace '{}'", name);
+    }
+    Ok(())
+}
+
+/// Whether an error is the API server refusing the request (HTTP 403).
+pub fn is_forbidden(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        matches!(e.downcast_ref::<kube::Error>(), Some(kube::Error::Api(resp)) if resp.code == 403)
+    })
+}
+
+/// Whether a failed apply may succeed if retried: a conflict (409), rate
+/// limiting (429), a server error (5xx) or a dropped connection. Other 4xx
+/// responses mean the object itself was rejected, and fail straight away.
+pub fn is_retryable_apply_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| match e.downcast_ref::<kube::Error>() {
+        Some(kube::Error::Api(resp)) => resp.code == 409 || resp.code == 429 || resp.code >= 500,
+        Some(kube::Error::HyperError(_) | kube::Error::Service(_)) => true,
+        _ => false,
+    })
+}
+
+/// Shown when the cluster rejects the kubeconfig's credentials.
+pub const EXPIRED_CREDENTIALS_HINT: &str =
+    "Your kubeconfig credentials appear expired \u{2014} re-authenticate \
+     (e.g. run your cloud CLI's get-credentials)";
+
+/// Whether an error means the kubeconfig's token or client certificate was
+/// rejected: an HTTP 401 from the API server, or the credential plugin
+/// failing to hand out a fresh token.
+pub fn is_expired_credentials(err: &anyhow::Error) -> bool {
+    use kube::client::AuthError;
+    err.chain().any(|e| match e.downcast_ref::<kube::Error>() {
+        Some(kube::Error::Api(resp)) => resp.code == 401 || resp.reason == "Unauthorized",
+        Some(kube::Error::Auth(AuthError::AuthExecRun { .. } | AuthError::UnrefreshableTokenResponse)) => true,
+        _ => false,
+    })
+}
+
+/// Turn a 403 from a read into an error naming the RBAC permission that's
+/// missing; any other error just gets `verb resource` as context.
+pub fn explain_read_error(err: anyhow::Error, verb: &str, resource: &str, namespace: &str) -> anyhow::Error {
+    if is_expired_credentials(&err) {
+        return err.context(EXPIRED_CREDENTIALS_HINT);
+    }
+    if is_forbidden(&err) {
+        anyhow::anyhow!(
+            "Permission denied: your kubeconfig can't {} {} in namespace '{}'. \
+             Grant the '{}' verb on '{}' (e.g. via the built-in 'view' ClusterRole).",
+            verb, resource, namespace, verb, resource
+        )
+    } else {
+        err.context(format!("Failed to {} {}", verb, resource))
+    }
+}
+
+/// Ownership label stamped on every resource the installer applies.
+pub const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+pub const MANAGED_BY_VALUE: &str = "bakerst-install";
+
+/// Every kind `apply_resource` knows how to apply: (kind, group, version, plural, namespaced).
+pub const MANAGED_KINDS: &[(&str, &str, &str, &str, bool)] = &[
+    ("Namespace", "", "v1", "namespaces", false),
+    ("Deployment", "apps", "v1", "deployments", true),
+    ("StatefulSet", "apps", "v1", "statefulsets", true),
+    ("DaemonSet", "apps", "v1", "daemonsets", true),
+    ("Service", "", "v1", "services", true),
+    ("ConfigMap", "", "v1", "configmaps", true),
+    ("Secret", "", "v1", "secrets", true),
+    ("PersistentVolumeClaim", "", "v1", "persistentvolumeclaims", true),
+    ("ServiceAccount", "", "v1", "serviceaccounts", true),
+    ("Role", "rbac.authorization.k8s.io", "v1", "roles", true),
+    ("RoleBinding", "rbac.authorization.k8s.io", "v1", "rolebindings", true),
+    ("NetworkPolicy", "networking.k8s.io", "v1", "networkpolicies", true),
+    ("Ingress", "networking.k8s.io", "v1", "ingresses", true),
+];
+
+fn managed_labels() -> BTreeMap<String, String> {
+    BTreeMap::from([(MANAGED_BY_LABEL.to_string(), MANAGED_BY_VALUE.to_string())])
+}
+
+/// The cluster operations the deploy path needs (secrets and manifests).
+/// `kube::Client` is the real implementation; tests drive the deploy
+/// helpers against a recording fake instead of a cluster.
+pub trait ClusterClient: Sync {
+    /// Apply one resource object (any of `MANAGED_KINDS`) to `namespace`.
+    fn apply(
+        &self,
+        namespace: &str,
+        resource: &serde_json::Value,
+        params: &ApplyParams,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Decoded data of a Secret, or None if it doesn't exist.
+    fn read_secret(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> impl std::future::Future<Output = Result<Option<BTreeMap<String, String>>>> + Send;
+}
+
+impl ClusterClient for Client {
+    async fn apply(
+        &self,
+        namespace: &str,
+        resource: &serde_json::Value,
+        params: &ApplyParams,
+    ) -> Result<()> {
+        apply_resource(self, namespace, resource, params).await
+    }
+
+    async fn read_secret(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<Option<BTreeMap<String, String>>> {
+        read_secret(self, namespace, name).await
+    }
+}
+
+/// How resources are written to the cluster.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyStrategy {
+    /// Server-side apply, falling back to create-or-replace when the
+    /// cluster rejects it.
+    #[default]
+    #[value(skip)]
+    Auto,
+    /// Server-side apply only.
+    Ssa,
+    /// Client-side create-or-replace only.
+    Client,
+}
+
+/// Container `imagePullPolicy`, spelled as Kubernetes spells it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+pub enum PullPolicy {
+    #[value(name = "Always")]
+    Always,
+    #[value(name = "IfNotPresent")]
+    IfNotPresent,
+    #[value(name = "Never")]
+    Never,
+}
+
+impl PullPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PullPolicy::Always => "Always",
+            PullPolicy::IfNotPresent => "IfNotPresent",
+            PullPolicy::Never => "Never",
+        }
+    }
+}
+
+/// Set `imagePullPolicy` on every container and init container of a
+/// workload's pod template. Other kinds are left untouched.
+pub fn set_pull_policy(resource: &mut serde_json::Value, policy: PullPolicy) {
+    let Some(pod_spec) = resource.pointer_mut("/spec/template/spec") else {
+        return;
+    };
+    for list in ["containers", "initContainers"] {
+        if let Some(containers) = pod_spec[list].as_array_mut() {
+            for c in containers {
+                c["imagePullPolicy"] = policy.as_str().into();
+            }
+        }
+    }
+}
+
+/// Whether an API error means server-side apply is unavailable on this
+/// cluster (feature-gated off, or an old/managed API server).
+pub fn is_ssa_unsupported(code: u16, reason: &str) -> bool {
+    code == 415 || reason == "UnsupportedMediaType"
+}
+
+/// Write one object per `params`. Every apply path goes through here, so
+/// `params.dry_run` covers all of them.
+async fn apply_object<K>(api: &Api<K>, name: &str, obj: &K, params: &ApplyParams) -> Result<()>
+where
+    K: Resource + Clone + Serialize + DeserializeOwned + std::fmt::Debug,
+{
+    let labeled;
+    let obj = if params.labels.is_empty() {
+        obj
+    } else {
+        let mut copy = obj.clone();
+        copy.labels_mut().extend(params.labels.clone());
+        labeled = copy;
+        &labeled
+    };
+    if params.strategy != ApplyStrategy::Client {
+        let mut pp = PatchParams::apply(&params.field_manager).force();
+        pp.dry_run = params.dry_run;
+        match api.patch(name, &pp, &Patch::Apply(obj)).await {
+            Ok(_) => return Ok(()),
+            Err(kube::Error::Api(e))
+                if params.strategy == ApplyStrategy::Auto
+                    && is_ssa_unsupported(e.code, &e.reason) =>
+            {
+                tracing::warn!(
+                    "server-side apply unsupported ({}), falling back to create/replace for {}",
+                    e.message,
+                    name
+                );
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    create_or_replace(api, name, obj, params).await
+}
+
+/// Client-side apply: create, or on 409 replace the existing object
+/// carrying over its `resourceVersion` and the fields the API server
+/// won't let a replace change (see [`keep_immutable_fields`]).
+async fn create_or_replace<K>(api: &Api<K>, name: &str, obj: &K, params: &ApplyParams) -> Result<()>
+where
+    K: Resource + Clone + Serialize + DeserializeOwned + std::fmt::Debug,
+{
+    let pp = PostParams {
+        dry_run: params.dry_run,
+        field_manager: Some(params.field_manager.clone()),
+    };
+    match api.create(&pp, obj).await {
+        Ok(_) => Ok(()),
+        Err(kube::Error::Api(e)) if e.code == 409 => {
+            let existing = api.get(name).await?;
+            let mut desired = serde_json::to_value(obj)?;
+            keep_immutable_fields(&serde_json::to_value(&existing)?, &mut desired);
+            let mut obj: K = serde_json::from_value(desired)?;
+            obj.meta_mut().resource_version = existing.resource_version();
+            api.replace(name, &pp, &obj).await?;
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Service fields the cluster allocates and a replace can't change.
+const SERVICE_ALLOCATED_FIELDS: &[&str] = &["clusterIP", "clusterIPs", "ipFamilies"];
+
+/// Copy from the `live` object into `desired` what a replace isn't allowed
+/// to change: a Service's allocated cluster IPs (unless `desired` sets
+/// them) and a PersistentVolumeClaim's whole spec, which is immutable once
+/// the claim is bound.
+fn keep_immutable_fields(live: &serde_json::Value, desired: &mut serde_json::Value) {
+    match desired["kind"].as_str() {
+        Some("Service") => {
+            for field in SERVICE_ALLOCATED_FIELDS {
+                if let Some(value) = live["spec"].get(*field) {
+                    if desired["spec"].get(*field).is_none() {
+                        desired["spec"][*field] = value.clone();
+                    }
+                }
+            }
+        }
+        Some("PersistentVolumeClaim") if live.get("spec").is_some() => {
+            desired["spec"] = live["spec"].clone();
+        }
+        _ => {}
+    }
+}
+
+/// Build a client for the named kubeconfig context (`--kube-context`), or
+/// for the current context when `None`.
+pub async fn connect(context: Option<&str>) -> Result<Client> {
+    let Some(context) = context else {
+        return Ok(Client::try_default().await?);
+    };
+    let kubeconfig = require_context(context)?;
+    let options = KubeConfigOptions {
+        context: Some(context.to_string()),
+        ..Default::default()
+    };
+    let config = kube::Config::from_custom_kubeconfig(kubeconfig, &options).await?;
+    Ok(Client::try_from(config)?)
+}
+
+/// Read the kubeconfig, failing if it has no context called `name`.
+pub fn require_context(name: &str) -> Result<Kubeconfig> {
+    let kubeconfig = Kubeconfig::read().context("read kubeconfig")?;
+    let available: Vec<&str> = kubeconfig.contexts.iter().map(|c| c.name.as_str()).collect();
+    check_context(&available, name)?;
+    Ok(kubeconfig)
+}
+
+/// Fail with the list of available contexts if `name` isn't one of them.
+pub fn check_context(available: &[&str], name: &str) -> Result<()> {
+    if available.contains(&name) {
+        return Ok(());
+    }
+    if available.is_empty() {
+        bail!("Kubernetes context '{}' not found: the kubeconfig has no contexts", name);
+    }
+    bail!(
+        "Kubernetes context '{}' not found. Available contexts: {}",
+        name,
+        available.join(", ")
+    );
+}
+
+/// The context a client from [`connect`] talks to: the named one, or the
+/// kubeconfig's current context.
+pub fn context_name(context: Option<&str>) -> Option<String> {
+    match context {
+        Some(name) => Some(name.to_string()),
+        None => Kubeconfig::read().ok()?.current_context,
+    }
+}
+
 /// Check if the K8s cluster is reachable. Returns the server version string.
-pub async fn check_cluster() -> Result<String> {
-    let client = Client::try_default().await?;
+pub async fn check_cluster(context: Option<&str>) -> Result<String> {
+    let client = connect(context).await?;
     let ver = client.apiserver_version().await?;
     Ok(format!("{}.{}", ver.major, ver.minor))
 }
 
+/// Build the Namespace object. When the install template ships a
+/// `namespace.yaml`, its labels and annotations are kept and only the name is
+/// replaced; the installed version is recorded as a label.
+pub fn render_namespace(
+    template: Option<&str>,
+    name: &str,
+    version: Option<&str>,
+) -> Result<serde_json::Value> {
+    let mut ns: serde_json::Value = match template {
+        Some(yaml) => serde_yaml::from_str(yaml).context("parse namespace template")?,
+        None => serde_json::json!({ "apiVersion": "v1", "kind": "Namespace", "metadata": {} }),
+    };
+    if ns["kind"] != "Namespace" {
+        bail!("namespace template does not define a Namespace");
+    }
+    ns["metadata"]["name"] = name.into();
+    ns["metadata"]["labels"][MANAGED_BY_LABEL] = MANAGED_BY_VALUE.into();
+    if let Some(version) = version {
+        ns["metadata"]["labels"]["app.kubernetes.io/version"] = version.into();
+    }
+    Ok(ns)
+}
+
+/// Merge extra annotations (e.g. from `--namespace-annotations`) into a
+/// rendered Namespace, overriding template values with the same key.
+pub fn annotate_namespace(ns: &mut serde_json::Value, annotations: &[(String, String)]) {
+    for (key, value) in annotations {
+        ns["metadata"]["annotations"][key] = value.as_str().into();
+    }
+}
+
+/// Merge `--namespace-label` labels into a rendered Namespace, overriding
+/// template values with the same key.
+pub fn label_namespace(ns: &mut serde_json::Value, labels: &[(String, String)]) {
+    for (key, value) in labels {
+        ns["metadata"]["labels"][key] = value.as_str().into();
+    }
+}
+
+/// Parse a `key=value` label argument, validating both halves. The
+/// ownership label is the installer's to set.
+pub fn parse_label(arg: &str) -> Result<(String, String)> {
+    let (key, value) = arg
+        .split_once('=')
+        .with_context(|| format!("expected key=value, got '{}'", arg))?;
+    validate_key("label", key)?;
+    if key == MANAGED_BY_LABEL {
+        bail!("label '{}' is set by the installer and can't be overridden", key);
+    }
+    let valid_value = value.is_empty()
+        || (value.len() <= 63
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && value.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && value.ends_with(|c: char| c.is_ascii_alphanumeric()));
+    if !valid_value {
+        bail!(
+            "invalid label value '{}': must be at most 63 alphanumeric characters, '-', '_' or '.'",
+            value
+        );
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Parse a `key=value` annotation argument, validating the key.
+pub fn parse_annotation(arg: &str) -> Result<(String, String)> {
+    let (key, value) = arg
+        .split_once('=')
+        .with_context(|| format!("expected key=value, got '{}'", arg))?;
+    validate_annotation_key(key)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Check an annotation key against the Kubernetes syntax: an optional DNS
+/// subdomain prefix and a slash, then a name of at most 63 characters that
+/// starts and ends alphanumeric and may contain `-`, `_` and `.`.
+pub fn validate_annotation_key(key: &str) -> Result<()> {
+    validate_key("annotation", key)
+}
+
+/// Label and annotation keys share one syntax; `what` names which it is in
+/// the error.
+fn validate_key(what: &str, key: &str) -> Result<()> {
+    let (prefix, name) = match key.rsplit_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+    if let Some(prefix) = prefix {
+        let valid_label = |l: &str| {
+            !l.is_empty()
+                && l.len() <= 63
+                && l.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                && !l.starts_with('-')
+                && !l.ends_with('-')
+        };
+        if prefix.is_empty() || prefix.len() > 253 || !prefix.split('.').all(valid_label) {
+            bail!("invalid {} key '{}': prefix must be a DNS subdomain", what, key);
+        }
+    }
+    let valid_name = !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric());
+    if !valid_name {
+        bail!(
+            "invalid {} key '{}': name must be 1-63 alphanumeric characters, '-', '_' or '.'",
+            what,
+            key
+        );
+    }
+    Ok(())
+}
+
 /// Create a namespace (idempotent).
 pub async fn create_namespace(client: &Client, name: &str) -> Result<()> {
+    apply_namespace(client, &render_namespace(None, name, None)?, &ApplyParams::default()).await
+}
+
+/// Apply a rendered Namespace object (see `render_namespace`).
+pub async fn apply_namespace(
+    client: &Client,
+    ns: &serde_json::Value,
+    params: &ApplyParams,
+) -> Result<()> {
+    let name = ns["metadata"]["name"].as_str().unwrap_or_default();
     let api: Api<Namespace> = Api::all(client.clone());
-    let ns: Namespace = serde_json::from_value(serde_json::json!({
-        "apiVersion": "v1",
-        "kind": "Namespace",
-        "metadata": { "name": name }
-    }))?;
-    api.patch(name, &PatchParams::apply(PATCH_PARAMS), &Patch::Apply(&ns))
+    let ns: Namespace = serde_json::from_value(ns.clone())?;
+    // Instances share the namespace, so it carries none of their labels
+    let params = ApplyParams {
+        labels: BTreeMap::new(),
+        ..params.clone()
+    };
+    apply_object(&api, name, &ns, &params)
         .await
-        .context("create namespace")?;
-    Ok(())
+        .context("create namespace")
+}
+
+/// Annotations marking the cluster's default storage class (GA and the
+/// older beta form some distributions still use).
+pub const DEFAULT_STORAGE_CLASS_ANNOTATIONS: &[&str] = &[
+    "storageclass.kubernetes.io/is-default-class",
+    "storageclass.beta.kubernetes.io/is-default-class",
+];
+
+/// Whether a storage class's annotations mark it as the default.
+pub fn is_default_storage_class(annotations: &BTreeMap<String, String>) -> bool {
+    DEFAULT_STORAGE_CLASS_ANNOTATIONS
+        .iter()
+        .any(|key| annotations.get(*key).is_some_and(|v| v == "true"))
+}
+
+/// Name of the cluster's default storage class, if it has one. PVCs that
+/// don't name a class stay `Pending` without it.
+pub async fn default_storage_class(client: &Client) -> Result<Option<String>> {
+    let api: Api<StorageClass> = Api::all(client.clone());
+    let classes = api.list(&ListParams::default()).await?;
+    Ok(classes
+        .items
+        .into_iter()
+        .find(|class| is_default_storage_class(class.annotations()))
+        .map(|class| class.name_any()))
+}
+
+/// Labels of the namespace, or None if it doesn't exist.
+pub async fn namespace_labels(client: &Client, name: &str) -> Result<Option<BTreeMap<String, String>>> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    Ok(api.get_opt(name).await?.map(|ns| ns.labels().clone()))
+}
+
+/// Refuse a namespace that exists but wasn't created by the installer (no
+/// ownership label), so a coincidentally named one isn't patched into.
+/// `--adopt-namespace` overrides this; the label is added on apply.
+pub fn check_namespace_owner(name: &str, labels: Option<&BTreeMap<String, String>>) -> Result<()> {
+    let Some(labels) = labels else {
+        return Ok(());
+    };
+    if labels.get(MANAGED_BY_LABEL).map(String::as_str) == Some(MANAGED_BY_VALUE) {
+        return Ok(());
+    }
+    bail!(
+        "Namespace '{}' already exists and was not created by bakerst-install (no {}={} label). \
+         Install into another namespace, or pass --adopt-namespace to install into this one anyway.",
+        name,
+        MANAGED_BY_LABEL,
+        MANAGED_BY_VALUE
+    )
+}
+
+/// Whether `namespace` holds an install of any instance: a Deployment with
+/// the installer's label, or the OS ConfigMap, which installs from before
+/// that label (and the namespace's) also created.
+pub async fn has_installer_resources(client: &Client, namespace: &str) -> Result<bool> {
+    let lp = ListParams::default()
+        .labels(&format!("{}={}", MANAGED_BY_LABEL, MANAGED_BY_VALUE))
+        .limit(1);
+    let deployments: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    if !deployments.list(&lp).await?.items.is_empty() {
+        return Ok(true);
+    }
+    let config_maps: Api<ConfigMap> = Api::namespaced(client.clone(), namespace);
+    Ok(config_maps.get_opt(OS_CONFIGMAP).await?.is_some())
+}
+
+/// Kind of the live workload called `name`: `Deployment`, `StatefulSet`,
+/// or None if there's neither.
+pub async fn workload_kind(client: &Client, namespace: &str, name: &str) -> Result<Option<&'static str>> {
+    let deployments: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    if deployments.get_opt(name).await?.is_some() {
+        return Ok(Some("Deployment"));
+    }
+    let stateful_sets: Api<StatefulSet> = Api::namespaced(client.clone(), namespace);
+    Ok(stateful_sets.get_opt(name).await?.map(|_| "StatefulSet"))
+}
+
+/// Whether the namespace exists in the cluster.
+pub async fn namespace_exists(client: &Client, name: &str) -> Result<bool> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    Ok(api.get_opt(name).await?.is_some())
 }
 
 /// Apply a YAML document containing one or more K8s resources.
 /// Parses multi-document YAML (separated by ---) and applies each.
-pub async fn apply_yaml(client: &Client, namespace: &str, yaml: &str) -> Result<Vec<String>> {
-    let mut applied = Vec::new();
+pub async fn apply_yaml(
+    client: &Client,
+    namespace: &str,
+    yaml: &str,
+    params: &ApplyParams,
+) -> Result<Vec<String>> {
+    apply_resources(client, namespace, &parse_yaml_docs(yaml)?, params, |_, _| {}).await
+}
+
+/// Parse multi-document YAML (separated by ---) into resource objects.
+pub fn parse_yaml_docs(yaml: &str) -> Result<Vec<serde_json::Value>> {
+    let mut resources = Vec::new();
     for doc in yaml.split("\n---") {
         // Strip leading comment lines (e.g. "# Brain ServiceAccount + Role")
         // but keep the YAML content that follows
@@ -51,93 +659,349 @@ pub async fn apply_yaml(client: &Client, namespace: &str, yaml: &str) -> Result<
         if doc.is_empty() {
             continue;
         }
-        let resource: serde_json::Value =
-            serde_yaml::from_str(doc).context("parse YAML document")?;
-        let kind = resource["kind"].as_str().unwrap_or("Unknown");
-        let name = resource["metadata"]["name"].as_str().unwrap_or("unnamed");
-        let label = format!("{}/{}", kind, name);
+        resources.push(serde_yaml::from_str(doc).context("parse YAML document")?);
+    }
+    Ok(resources)
+}
 
-        apply_resource(client, namespace, &resource)
-            .await
-            .with_context(|| format!("apply {}", label))?;
-        applied.push(label);
+/// Apply parsed resources, returning a "Kind/name" label for each in the
+/// order given. `on_applied` is called with each resource and its label as
+/// soon as it's applied, so callers can stream progress.
+///
+/// Resources are applied in stages (see [`apply_stage`]): everything in a
+/// stage is applied concurrently, and a stage starts once the one before
+/// it is done, which saves a round trip per object on a distant API
+/// server. A failure still lets the rest of its stage finish, then stops.
+pub async fn apply_resources(
+    client: &impl ClusterClient,
+    namespace: &str,
+    resources: &[serde_json::Value],
+    params: &ApplyParams,
+    on_applied: impl FnMut(&serde_json::Value, &str),
+) -> Result<Vec<String>> {
+    let mut stages: Vec<u8> = resources.iter().map(apply_stage).collect();
+    stages.sort();
+    stages.dedup();
+
+    // The applies of a stage run on this task, so the callback is never
+    // borrowed twice at once
+    let on_applied = std::cell::RefCell::new(on_applied);
+    let mut labels = vec![String::new(); resources.len()];
+    for stage in stages {
+        let applying = resources
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| apply_stage(r) == stage)
+            .map(|(i, resource)| {
+                let on_applied = &on_applied;
+                async move {
+                    let label = apply_with_retries(client, namespace, resource, params).await?;
+                    (on_applied.borrow_mut())(resource, &label);
+                    anyhow::Ok((i, label))
+                }
+            });
+        for result in futures::future::join_all(applying).await {
+            let (i, label) = result?;
+            labels[i] = label;
+        }
+    }
+    Ok(labels)
+}
+
+/// Components the services connect to as they start, so their workloads
+/// and Services are applied a stage ahead of the rest.
+pub const INFRA_COMPONENTS: &[&str] = &["nats", "qdrant"];
+
+/// When a resource is applied, earliest first. Config, storage and RBAC
+/// only need the namespace; workloads mount and reference them, the
+/// [`INFRA_COMPONENTS`] before the services that use them; an Ingress
+/// routes to Services.
+pub fn apply_stage(resource: &serde_json::Value) -> u8 {
+    let infra = resource["metadata"]["labels"]["app"]
+        .as_str()
+        .is_some_and(|app| INFRA_COMPONENTS.contains(&app));
+    match resource["kind"].as_str().unwrap_or_default() {
+        "Namespace" => 0,
+        "ConfigMap" | "Secret" | "PersistentVolumeClaim" | "ServiceAccount" | "Role" | "RoleBinding"
+        | "NetworkPolicy" => 1,
+        "Ingress" => 4,
+        _ if infra => 2,
+        _ => 3,
+    }
+}
+
+/// The "Kind/name" label [`apply_resources`] returns for `resource`.
+pub fn resource_label(resource: &serde_json::Value) -> String {
+    format!(
+        "{}/{}",
+        resource["kind"].as_str().unwrap_or("Unknown"),
+        resource["metadata"]["name"].as_str().unwrap_or("unnamed")
+    )
+}
+
+/// Apply one resource, retrying transient failures with backoff. Returns
+/// its "Kind/name" label.
+async fn apply_with_retries(
+    client: &impl ClusterClient,
+    namespace: &str,
+    resource: &serde_json::Value,
+    params: &ApplyParams,
+) -> Result<String> {
+    let kind = resource["kind"].as_str().unwrap_or("Unknown");
+    let name = resource["metadata"]["name"].as_str().unwrap_or("unnamed");
+    let label = resource_label(resource);
+
+    let component = resource["metadata"]["labels"]["app"].as_str().unwrap_or(name);
+    let mut attempt = 0;
+    loop {
+        let err = match client.apply(namespace, resource, params).await {
+            Ok(()) => {
+                tracing::debug!(kind, name, namespace, attempt, "applied");
+                return Ok(label);
+            }
+            Err(e) => e,
+        };
+        tracing::debug!(kind, name, namespace, attempt, error = %format!("{:#}", err), "apply failed");
+        if attempt >= params.retries || !is_retryable_apply_error(&err) {
+            return Err(err.context(crate::output::ComponentError::new(component, format!("apply {}", label))));
+        }
+        attempt += 1;
+        let backoff = apply_retry_backoff(attempt);
+        println!(
+            "    \u{21bb} {}: {:#}; retrying in {:.1}s ({}/{})",
+            label,
+            err,
+            backoff.as_secs_f64(),
+            attempt,
+            params.retries
+        );
+        crate::output::emit(&crate::output::Event::Retry {
+            resource: &label,
+            attempt,
+            error: &format!("{:#}", err),
+        });
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+/// Dynamic API handle for one of `MANAGED_KINDS`.
+fn managed_api(client: &Client, namespace: &str, kind: &str) -> Result<(Api<DynamicObject>, bool)> {
+    let &(kind, group, version, plural, namespaced) = MANAGED_KINDS
+        .iter()
+        .find(|(k, ..)| *k == kind)
+        .with_context(|| format!("unsupported resource kind: {}", kind))?;
+    let ar = ApiResource::from_gvk_with_plural(&GroupVersionKind::gvk(group, version, kind), plural);
+    let api = if namespaced {
+        Api::namespaced_with(client.clone(), namespace, &ar)
+    } else {
+        Api::all_with(client.clone(), &ar)
+    };
+    Ok((api, namespaced))
+}
+
+/// Fetch the live object and what it would become if `resource` were
+/// applied (server-side dry run, so defaults are filled in as on a real
+/// apply). If the dry run can't run, e.g. because the namespace doesn't
+/// exist yet, the desired object is returned as-is.
+pub async fn dry_run_apply(
+    client: &Client,
+    namespace: &str,
+    resource: &serde_json::Value,
+    params: &ApplyParams,
+) -> Result<(Option<serde_json::Value>, serde_json::Value)> {
+    let kind = resource["kind"].as_str().unwrap_or("");
+    let name = resource["metadata"]["name"].as_str().unwrap_or("");
+    let (api, namespaced) = managed_api(client, namespace, kind)?;
+
+    let mut desired = resource.clone();
+    desired["metadata"]["labels"][MANAGED_BY_LABEL] = MANAGED_BY_VALUE.into();
+    if namespaced {
+        desired["metadata"]["namespace"] = namespace.into();
+    }
+
+    let live = api.get_opt(name).await?.map(serde_json::to_value).transpose()?;
+    let pp = PatchParams::apply(&params.field_manager).force().dry_run();
+    let after = match api.patch(name, &pp, &Patch::Apply(&desired)).await {
+        Ok(obj) => serde_json::to_value(obj)?,
+        Err(kube::Error::Api(e)) if e.code == 404 && live.is_none() => desired,
+        Err(e) => return Err(e.into()),
+    };
+    Ok((live, after))
+}
+
+/// Strip server-managed noise (status, managedFields, resourceVersion, ...)
+/// so live and desired objects compare on what the user controls.
+pub fn normalize_for_diff(obj: &mut serde_json::Value) {
+    let Some(map) = obj.as_object_mut() else {
+        return;
+    };
+    map.remove("status");
+    if let Some(meta) = map.get_mut("metadata").and_then(|m| m.as_object_mut()) {
+        for key in ["managedFields", "resourceVersion", "uid", "generation", "creationTimestamp", "selfLink"] {
+            meta.remove(key);
+        }
+        if let Some(annotations) = meta.get_mut("annotations").and_then(|a| a.as_object_mut()) {
+            annotations.remove("kubectl.kubernetes.io/last-applied-configuration");
+            annotations.remove("deployment.kubernetes.io/revision");
+            if annotations.is_empty() {
+                meta.remove("annotations");
+            }
+        }
+    }
+}
+
+/// Replace Secret values with markers so a diff shows which keys change
+/// without printing them, as `kubectl diff` does.
+pub fn mask_secret_data(before: &mut serde_json::Value, after: &mut serde_json::Value) {
+    for field in ["data", "stringData"] {
+        let old = before[field].as_object().cloned().unwrap_or_default();
+        if let Some(new) = after.get_mut(field).and_then(|d| d.as_object_mut()) {
+            for (key, value) in new.iter_mut() {
+                let same = old.get(key) == Some(value);
+                *value = if same { "***" } else { "*** (after)" }.into();
+            }
+        }
+        if let Some(prev) = before.get_mut(field).and_then(|d| d.as_object_mut()) {
+            let new = after[field].as_object().cloned().unwrap_or_default();
+            for (key, value) in prev.iter_mut() {
+                let same = new.get(key).is_some_and(|v| v == "***");
+                *value = if same { "***" } else { "*** (before)" }.into();
+            }
+        }
     }
-    Ok(applied)
 }
 
-/// Apply a single parsed K8s resource using server-side apply.
+/// Apply a single parsed K8s resource (server-side apply unless `params`
+/// says otherwise).
 async fn apply_resource(
     client: &Client,
     namespace: &str,
     resource: &serde_json::Value,
+    params: &ApplyParams,
 ) -> Result<()> {
     let kind = resource["kind"].as_str().unwrap_or("");
     let name = resource["metadata"]["name"].as_str().unwrap_or("");
-    let pp = PatchParams::apply(PATCH_PARAMS).force();
+
+    let mut resource = resource.clone();
+    resource["metadata"]["labels"][MANAGED_BY_LABEL] = MANAGED_BY_VALUE.into();
+    let resource = &resource;
 
     match kind {
         "Namespace" => {
             let api: Api<Namespace> = Api::all(client.clone());
             let obj: Namespace = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "Deployment" => {
             let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
             let obj: Deployment = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
+        }
+        "StatefulSet" => {
+            let api: Api<StatefulSet> = Api::namespaced(client.clone(), namespace);
+            let obj: StatefulSet = serde_json::from_value(resource.clone())?;
+            apply_object(&api, name, &obj, params).await?;
+        }
+        "DaemonSet" => {
+            let api: Api<DaemonSet> = Api::namespaced(client.clone(), namespace);
+            let obj: DaemonSet = serde_json::from_value(resource.clone())?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "Service" => {
             let api: Api<Service> = Api::namespaced(client.clone(), namespace);
             let obj: Service = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "ConfigMap" => {
             let api: Api<ConfigMap> = Api::namespaced(client.clone(), namespace);
             let obj: ConfigMap = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "Secret" => {
             let api: Api<Secret> = Api::namespaced(client.clone(), namespace);
             let obj: Secret = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "PersistentVolumeClaim" => {
             let api: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), namespace);
             let obj: PersistentVolumeClaim = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "ServiceAccount" => {
             let api: Api<ServiceAccount> = Api::namespaced(client.clone(), namespace);
             let obj: ServiceAccount = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "Role" => {
             let api: Api<Role> = Api::namespaced(client.clone(), namespace);
             let obj: Role = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "RoleBinding" => {
             let api: Api<RoleBinding> = Api::namespaced(client.clone(), namespace);
             let obj: RoleBinding = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
         }
         "NetworkPolicy" => {
             let api: Api<NetworkPolicy> = Api::namespaced(client.clone(), namespace);
             let obj: NetworkPolicy = serde_json::from_value(resource.clone())?;
-            api.patch(name, &pp, &Patch::Apply(&obj)).await?;
+            apply_object(&api, name, &obj, params).await?;
+        }
+        "Ingress" => {
+            let api: Api<Ingress> = Api::namespaced(client.clone(), namespace);
+            let obj: Ingress = serde_json::from_value(resource.clone())?;
+            apply_object(&api, name, &obj, params).await?;
         }
         _ => anyhow::bail!("unsupported resource kind: {}", kind),
     }
     Ok(())
 }
 
+/// How `create_secret` treats keys already stored in the cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretMode {
+    /// Fresh install: the Secret holds exactly the given keys.
+    Replace,
+    /// Update: given keys overwrite existing ones, all other keys are kept.
+    Merge,
+}
+
+/// Overlay `new` onto `existing`, keeping keys that `new` doesn't mention.
+pub fn merge_secret_data(
+    existing: BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut merged = existing;
+    merged.extend(new.iter().map(|(k, v)| (k.clone(), v.clone())));
+    merged
+}
+
 /// Create a K8s Secret from key-value pairs (values are base64-encoded automatically).
+/// Server-side apply replaces `data` wholesale, so `SecretMode::Merge` reads the
+/// existing Secret first to avoid dropping keys that weren't provided.
 pub async fn create_secret(
-    client: &Client,
+    client: &impl ClusterClient,
     namespace: &str,
     name: &str,
     data: &BTreeMap<String, String>,
+    mode: SecretMode,
+    params: &ApplyParams,
 ) -> Result<()> {
+    let data = match mode {
+        SecretMode::Replace => data.clone(),
+        SecretMode::Merge => {
+            let existing = client.read_secret(namespace, name).await?.unwrap_or_default();
+            merge_secret_data(existing, data)
+        }
+    };
+    client
+        .apply(namespace, &secret_object(namespace, name, &data), params)
+        .await
+        .context("create secret")
+}
+
+/// The Secret object `create_secret` applies.
+pub fn secret_object(namespace: &str, name: &str, data: &BTreeMap<String, String>) -> serde_json::Value {
     let encoded: BTreeMap<String, k8s_openapi::ByteString> = data
         .iter()
         .map(|(k, v)| (k.clone(), k8s_openapi::ByteString(v.as_bytes().to_vec())))
@@ -147,49 +1011,48 @@ pub async fn create_secret(
         metadata: kube::api::ObjectMeta {
             name: Some(name.into()),
             namespace: Some(namespace.into()),
+            labels: Some(managed_labels()),
             ..Default::default()
         },
         data: Some(encoded),
         ..Default::default()
     };
-
-    let api: Api<Secret> = Api::namespaced(client.clone(), namespace);
-    api.patch(
-        name,
-        &PatchParams::apply(PATCH_PARAMS).force(),
-        &Patch::Apply(&secret),
-    )
-    .await
-    .context("create secret")?;
-    Ok(())
+    serde_json::to_value(&secret).unwrap_or_default()
 }
 
-/// Create the bakerst-os ConfigMap from operating system files.
+/// Name of the ConfigMap holding the operating system files (before any
+/// `--instance` prefix).
+pub const OS_CONFIGMAP: &str = "bakerst-os";
+
+/// Create the bakerst-os ConfigMap (called `name`) from operating system files.
 /// Files are provided as key-value pairs (filename -> content), fetched at runtime.
 pub async fn create_os_configmap(
     client: &Client,
     namespace: &str,
+    name: &str,
     files: &BTreeMap<String, String>,
+    params: &ApplyParams,
 ) -> Result<()> {
+    let cm: ConfigMap = serde_json::from_value(os_configmap_object(namespace, name, files))?;
+    let api: Api<ConfigMap> = Api::namespaced(client.clone(), namespace);
+    apply_object(&api, name, &cm, params)
+        .await
+        .with_context(|| format!("create {} configmap", name))
+}
+
+/// The bakerst-os ConfigMap object `create_os_configmap` applies.
+pub fn os_configmap_object(namespace: &str, name: &str, files: &BTreeMap<String, String>) -> serde_json::Value {
     let cm = ConfigMap {
         metadata: kube::api::ObjectMeta {
-            name: Some("bakerst-os".into()),
+            name: Some(name.into()),
             namespace: Some(namespace.into()),
+            labels: Some(managed_labels()),
             ..Default::default()
         },
         data: Some(files.clone()),
         ..Default::default()
     };
-
-    let api: Api<ConfigMap> = Api::namespaced(client.clone(), namespace);
-    api.patch(
-        "bakerst-os",
-        &PatchParams::apply(PATCH_PARAMS).force(),
-        &Patch::Apply(&cm),
-    )
-    .await
-    .context("create bakerst-os configmap")?;
-    Ok(())
+    serde_json::to_value(&cm).unwrap_or_default()
 }
 
 /// Get the current image for a deployment's first container.
@@ -232,60 +1095,728 @@ pub async fn restart_deployment(client: &Client, namespace: &str, name: &str) ->
     Ok(())
 }
 
-/// Delete a single deployment (idempotent — ignores "not found").
-pub async fn delete_deployment(client: &Client, namespace: &str, name: &str) -> Result<()> {
-    let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
-    api.delete(name, &DeleteParams::default()).await.ok();
-    Ok(())
+/// Delete a single deployment (idempotent — ignores "not found").
+pub async fn delete_deployment(client: &Client, namespace: &str, name: &str) -> Result<()> {
+    let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    api.delete(name, &DeleteParams::default()).await.ok();
+    Ok(())
+}
+
+/// Delete a namespace (cascades to all resources within).
+pub async fn delete_namespace(client: &Client, name: &str) -> Result<bool> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    match api.delete(name, &DeleteParams::default()).await {
+        Ok(_) => Ok(true),
+        // Already gone, or never created (e.g. telemetry without --telemetry)
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("delete namespace {}", name)),
+    }
+}
+
+/// How long uninstall waits for a deleted namespace to be gone.
+pub const DEFAULT_NAMESPACE_DELETE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Wait until the deleted namespace `name` no longer exists, printing a
+/// status line every `report_every` while it terminates. On timeout the
+/// error says what is holding it up and how to clear it.
+pub async fn wait_for_namespace_deleted(
+    client: &Client,
+    name: &str,
+    timeout: std::time::Duration,
+    interval: std::time::Duration,
+    report_every: std::time::Duration,
+) -> Result<()> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    let started = std::time::Instant::now();
+    let mut reported = std::time::Duration::ZERO;
+    loop {
+        let Some(namespace) = api.get_opt(name).await? else {
+            return Ok(());
+        };
+        let waited = started.elapsed();
+        let reason = namespace_stuck_reason(&namespace);
+        if waited >= timeout {
+            bail!(
+                "Namespace '{}' is still terminating after {}s{}.\n  \
+                 Check what is left with `kubectl get namespace {} -o yaml` and `kubectl api-resources --verbs=list \
+                 --namespaced -o name | xargs -n1 kubectl get -n {} --ignore-not-found`; a resource whose \
+                 controller is gone keeps its finalizers until they're removed by hand.",
+                name,
+                timeout.as_secs(),
+                reason.map(|r| format!(": {}", r)).unwrap_or_default(),
+                name,
+                name
+            );
+        }
+        if waited >= reported + report_every {
+            reported = waited;
+            match &reason {
+                Some(reason) => println!("  Waiting for namespace '{}' ({}s): {}", name, waited.as_secs(), reason),
+                None => println!("  Waiting for namespace '{}' to terminate ({}s)", name, waited.as_secs()),
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// What a terminating namespace reports it's waiting on: its deletion
+/// conditions that hold (remaining content or finalizers, failed deletes).
+pub fn namespace_stuck_reason(namespace: &Namespace) -> Option<String> {
+    let conditions = namespace.status.as_ref()?.conditions.as_ref()?;
+    let reasons: Vec<String> = conditions
+        .iter()
+        .filter(|c| c.status == "True" && c.type_.starts_with("Namespace"))
+        .map(|c| c.message.clone().unwrap_or_else(|| c.type_.clone()))
+        .collect();
+    (!reasons.is_empty()).then(|| reasons.join("; "))
+}
+
+/// A PersistentVolume bound (or last bound) to one of the install's claims.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaimedVolume {
+    pub name: String,
+    /// Its reclaim policy keeps it once the claim is gone (anything but
+    /// `Delete`).
+    pub retained: bool,
+}
+
+/// PersistentVolumes bound (or last bound) to claims in `namespace`, or to
+/// just the named `claims` there. PVs are cluster-scoped, so one whose
+/// reclaim policy retains it outlives the namespace and its claims.
+pub async fn claimed_volumes(client: &Client, namespace: &str, claims: Option<&[String]>) -> Result<Vec<ClaimedVolume>> {
+    let api: Api<PersistentVolume> = Api::all(client.clone());
+    let volumes = api.list(&ListParams::default()).await.context("list PersistentVolumes")?;
+    Ok(volumes
+        .items
+        .iter()
+        .filter(|pv| volume_claimed_from(pv, namespace, claims))
+        .map(|pv| ClaimedVolume {
+            name: pv.name_any(),
+            retained: volume_retained(pv),
+        })
+        .collect())
+}
+
+/// Whether `pv` is (or was) bound to a claim in `namespace` (one of
+/// `claims`, if given). The managed-by label isn't enough: every install
+/// in the cluster sets it, whatever its namespace.
+pub fn volume_claimed_from(pv: &PersistentVolume, namespace: &str, claims: Option<&[String]>) -> bool {
+    let claim = pv.spec.as_ref().and_then(|s| s.claim_ref.as_ref());
+    claim.is_some_and(|c| {
+        c.namespace.as_deref() == Some(namespace)
+            && claims.is_none_or(|claims| c.name.as_ref().is_some_and(|n| claims.contains(n)))
+    })
+}
+
+/// Whether `pv` outlives its claim. Its reclaim policy defaults to `Retain`
+/// when unset, as for a statically created volume.
+pub fn volume_retained(pv: &PersistentVolume) -> bool {
+    pv.spec.as_ref().and_then(|s| s.persistent_volume_reclaim_policy.as_deref()) != Some("Delete")
+}
+
+/// Delete a PersistentVolume. One still bound is removed once its claim
+/// is gone (the API server holds it with a protection finalizer).
+pub async fn delete_volume(client: &Client, name: &str) -> Result<()> {
+    let api: Api<PersistentVolume> = Api::all(client.clone());
+    match api.delete(name, &DeleteParams::background()).await {
+        Ok(_) => Ok(()),
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("delete PersistentVolume/{}", name)),
+    }
+}
+
+/// Delete one installer-managed resource by kind and name (see `--prune`).
+pub async fn delete_managed(client: &Client, namespace: &str, kind: &str, name: &str) -> Result<()> {
+    let (api, _) = managed_api(client, namespace, kind)?;
+    match api.delete(name, &DeleteParams::background()).await {
+        Ok(_) => Ok(()),
+        // Already gone (e.g. garbage-collected with its owner)
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("delete {}/{}", kind, name)),
+    }
+}
+
+/// Container images of the instance's existing deployments, for comparing
+/// a re-install against what is running. Empty if there are none.
+pub async fn deployment_images(client: &Client, namespace: &str, instance: Option<&str>) -> Result<Vec<String>> {
+    let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let lp = ListParams::default().labels(&crate::instance::selector(instance));
+    let deployments = api.list(&lp).await?;
+    Ok(deployments
+        .items
+        .into_iter()
+        .filter_map(|d| d.spec.and_then(|s| s.template.spec))
+        .flat_map(|s| s.containers)
+        .filter_map(|c| c.image)
+        .collect())
+}
+
+/// Status of a single deployment (for --status output).
+pub struct DeploymentStatus {
+    pub name: String,
+    pub desired: i32,
+    pub ready: i32,
+    pub image: String,
+}
+
+/// List the instance's deployments (and StatefulSets) in a namespace with
+/// their status.
+pub async fn get_deployments_status(
+    client: &Client,
+    namespace: &str,
+    instance: Option<&str>,
+) -> Result<Vec<DeploymentStatus>> {
+    let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let lp = ListParams::default().labels(&crate::instance::selector(instance));
+    let deployments = api.list(&lp).await?;
+
+    let mut statuses = Vec::new();
+    for deploy in deployments.items {
+        let name = deploy.metadata.name.unwrap_or_default();
+        let status = deploy.status.as_ref();
+        let desired = status.and_then(|s| s.replicas).unwrap_or(0);
+        let ready = status.and_then(|s| s.ready_replicas).unwrap_or(0);
+        let image = deploy
+            .spec
+            .and_then(|s| {
+                s.template
+                    .spec
+                    .and_then(|s| s.containers.first().map(|c| c.image.clone().unwrap_or_default()))
+            })
+            .unwrap_or_default();
+
+        statuses.push(DeploymentStatus {
+            name,
+            desired,
+            ready,
+            image,
+        });
+    }
+
+    // --stateful-storage runs some components as StatefulSets
+    let api: Api<StatefulSet> = Api::namespaced(client.clone(), namespace);
+    for set in api.list(&lp).await?.items {
+        let status = set.status.as_ref();
+        statuses.push(DeploymentStatus {
+            name: set.name_any(),
+            desired: status.map(|s| s.replicas).unwrap_or(0),
+            ready: status.and_then(|s| s.ready_replicas).unwrap_or(0),
+            image: set
+                .spec
+                .and_then(|s| s.template.spec)
+                .and_then(|s| s.containers.first().and_then(|c| c.image.clone()))
+                .unwrap_or_default(),
+        });
+    }
+    statuses.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(statuses)
+}
+
+/// One pod of a deployment (for `status --pods`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PodStatus {
+    pub name: String,
+    pub phase: String,
+    pub ready: bool,
+    pub restarts: i32,
+    /// The pod's latest Warning event, as "Reason: message".
+    pub warning: Option<String>,
+}
+
+/// The pods of each of the instance's deployments, by deployment name,
+/// with each pod's latest Warning event. Events are best-effort: without
+/// permission to list them the warnings are left out.
+pub async fn get_pods_status(
+    client: &Client,
+    namespace: &str,
+    instance: Option<&str>,
+) -> Result<BTreeMap<String, Vec<PodStatus>>> {
+    let deploy_api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let pod_api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let event_api: Api<Event> = Api::namespaced(client.clone(), namespace);
+    let warnings = event_api
+        .list(&ListParams::default().fields("type=Warning,involvedObject.kind=Pod"))
+        .await
+        .map(|events| latest_warnings(&events.items))
+        .unwrap_or_default();
+
+    let lp = ListParams::default().labels(&crate::instance::selector(instance));
+    let mut workloads: Vec<(String, Option<String>)> = deploy_api
+        .list(&lp)
+        .await?
+        .items
+        .iter()
+        .map(|d| (d.name_any(), match_labels_selector(d)))
+        .collect();
+    let set_api: Api<StatefulSet> = Api::namespaced(client.clone(), namespace);
+    for set in set_api.list(&lp).await?.items {
+        let selector = set.spec.as_ref().and_then(|s| labels_selector(&s.selector));
+        workloads.push((set.name_any(), selector));
+    }
+    let mut pods = BTreeMap::new();
+    for (name, selector) in workloads {
+        let Some(selector) = selector else {
+            continue;
+        };
+        let statuses = pod_api
+            .list(&ListParams::default().labels(&selector))
+            .await?
+            .items
+            .into_iter()
+            .map(|pod| {
+                let name = pod.name_any();
+                let status = pod.status.unwrap_or_default();
+                let containers = status.container_statuses.unwrap_or_default();
+                PodStatus {
+                    warning: warnings.get(&name).cloned(),
+                    name,
+                    phase: status.phase.unwrap_or_else(|| "Unknown".into()),
+                    ready: !containers.is_empty() && containers.iter().all(|cs| cs.ready),
+                    restarts: containers.iter().map(|cs| cs.restart_count).sum(),
+                }
+            })
+            .collect();
+        pods.insert(name, statuses);
+    }
+    Ok(pods)
+}
+
+/// The most recent Warning event of each object in `events`, by object
+/// name, as "Reason: message".
+pub fn latest_warnings(events: &[Event]) -> BTreeMap<String, String> {
+    let when = |e: &Event| {
+        e.last_timestamp
+            .as_ref()
+            .map(|t| t.0)
+            .or_else(|| e.event_time.as_ref().map(|t| t.0))
+            .or_else(|| e.metadata.creation_timestamp.as_ref().map(|t| t.0))
+    };
+    let mut latest: BTreeMap<String, &Event> = BTreeMap::new();
+    for event in events.iter().filter(|e| e.type_.as_deref() == Some("Warning")) {
+        let Some(name) = event.involved_object.name.clone() else {
+            continue;
+        };
+        match latest.get(&name) {
+            Some(seen) if when(seen) >= when(event) => {}
+            _ => {
+                latest.insert(name, event);
+            }
+        }
+    }
+    latest
+        .into_iter()
+        .map(|(name, e)| {
+            let reason = e.reason.as_deref().unwrap_or("Warning");
+            (name, format!("{}: {}", reason, e.message.as_deref().unwrap_or_default().trim()))
+        })
+        .collect()
+}
+
+/// Current resource usage reported by metrics-server.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_millicores: u64,
+    pub memory_bytes: u64,
+}
+
+impl std::fmt::Display for ResourceUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}m CPU, {}Mi", self.cpu_millicores, self.memory_bytes / (1024 * 1024))
+    }
+}
+
+/// One pod's current usage, summed over its containers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PodUsage {
+    pub pod: String,
+    /// The pod's `app` label (its unprefixed deployment name), or its name.
+    pub app: String,
+    pub usage: ResourceUsage,
+}
+
+/// CPU/memory usage of each pod matching `selector` (every pod in the
+/// namespace if `None`). Returns `None` when the metrics API isn't
+/// installed in the cluster.
+pub async fn get_pod_usage(client: &Client, namespace: &str, selector: Option<&str>) -> Result<Option<Vec<PodUsage>>> {
+    let gvk = GroupVersionKind::gvk("metrics.k8s.io", "v1beta1", "PodMetrics");
+    let ar = ApiResource::from_gvk_with_plural(&gvk, "pods");
+    let api: Api<DynamicObject> = Api::namespaced_with(client.clone(), namespace, &ar);
+    let mut lp = ListParams::default();
+    if let Some(selector) = selector {
+        lp = lp.labels(selector);
+    }
+    let pods = match api.list(&lp).await {
+        Ok(list) => list,
+        Err(kube::Error::Api(e)) if e.code == 404 || e.code == 503 => return Ok(None),
+        Err(e) => return Err(e).context("list pod metrics"),
+    };
+
+    let mut usage = Vec::new();
+    for pod in pods.items {
+        let name = pod.metadata.name.unwrap_or_default();
+        let app = pod
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|l| l.get("app").cloned())
+            .unwrap_or_else(|| name.clone());
+        let mut total = ResourceUsage::default();
+        for container in pod.data["containers"].as_array().into_iter().flatten() {
+            let cpu = container["usage"]["cpu"].as_str().unwrap_or("0");
+            let mem = container["usage"]["memory"].as_str().unwrap_or("0");
+            total.cpu_millicores += parse_cpu_millicores(cpu).unwrap_or(0);
+            total.memory_bytes += parse_memory_bytes(mem).unwrap_or(0);
+        }
+        usage.push(PodUsage { pod: name, app, usage: total });
+    }
+    Ok(Some(usage))
+}
+
+/// Sum `pods`' usage per `app` label (i.e. per deployment, keyed by its
+/// unprefixed name).
+pub fn usage_by_app(pods: &[PodUsage]) -> BTreeMap<String, ResourceUsage> {
+    let mut usage: BTreeMap<String, ResourceUsage> = BTreeMap::new();
+    for pod in pods {
+        let entry = usage.entry(pod.app.clone()).or_default();
+        entry.cpu_millicores += pod.usage.cpu_millicores;
+        entry.memory_bytes += pod.usage.memory_bytes;
+    }
+    usage
+}
+
+/// CPU and memory the schedulable nodes offer pods (their allocatable).
+pub async fn node_allocatable(client: &Client) -> Result<ResourceUsage> {
+    let api: Api<Node> = Api::all(client.clone());
+    let mut total = ResourceUsage::default();
+    for node in api.list(&ListParams::default()).await.context("list nodes")?.items {
+        if node.spec.and_then(|s| s.unschedulable).unwrap_or(false) {
+            continue;
+        }
+        let allocatable = node.status.and_then(|s| s.allocatable).unwrap_or_default();
+        total.cpu_millicores += allocatable.get("cpu").and_then(|q| parse_cpu_millicores(&q.0)).unwrap_or(0);
+        total.memory_bytes += allocatable.get("memory").and_then(|q| parse_memory_bytes(&q.0)).unwrap_or(0);
+    }
+    Ok(total)
+}
+
+/// CPU and memory already requested by scheduled, unfinished pods outside
+/// `namespace`. The namespace's own pods are left out: an install there
+/// replaces them.
+pub async fn requested_outside(client: &Client, namespace: &str) -> Result<ResourceUsage> {
+    let api: Api<Pod> = Api::all(client.clone());
+    let lp = ListParams::default().fields("status.phase!=Succeeded,status.phase!=Failed");
+    let mut total = ResourceUsage::default();
+    for pod in api.list(&lp).await.context("list pods")?.items {
+        if pod.metadata.namespace.as_deref() == Some(namespace) {
+            continue;
+        }
+        let Some(spec) = pod.spec.filter(|s| s.node_name.is_some()) else {
+            continue;
+        };
+        for requests in spec.containers.into_iter().filter_map(|c| c.resources?.requests) {
+            total.cpu_millicores += requests.get("cpu").and_then(|q| parse_cpu_millicores(&q.0)).unwrap_or(0);
+            total.memory_bytes += requests.get("memory").and_then(|q| parse_memory_bytes(&q.0)).unwrap_or(0);
+        }
+    }
+    Ok(total)
+}
+
+/// Parse a K8s CPU quantity ("250m", "1", "12345678n") into millicores.
+pub fn parse_cpu_millicores(quantity: &str) -> Option<u64> {
+    let q = quantity.trim();
+    if let Some(n) = q.strip_suffix('n') {
+        n.parse::<u64>().ok().map(|v| v / 1_000_000)
+    } else if let Some(u) = q.strip_suffix('u') {
+        u.parse::<u64>().ok().map(|v| v / 1_000)
+    } else if let Some(m) = q.strip_suffix('m') {
+        m.parse::<u64>().ok()
+    } else {
+        q.parse::<f64>().ok().map(|v| (v * 1000.0) as u64)
+    }
+}
+
+/// Parse a K8s memory quantity ("180Mi", "123456Ki", "1G", "1048576") into bytes.
+pub fn parse_memory_bytes(quantity: &str) -> Option<u64> {
+    const SUFFIXES: &[(&str, u64)] = &[
+        ("Ki", 1 << 10),
+        ("Mi", 1 << 20),
+        ("Gi", 1 << 30),
+        ("Ti", 1 << 40),
+        ("k", 1_000),
+        ("M", 1_000_000),
+        ("G", 1_000_000_000),
+        ("T", 1_000_000_000_000),
+    ];
+    let q = quantity.trim();
+    for (suffix, factor) in SUFFIXES {
+        if let Some(n) = q.strip_suffix(suffix) {
+            return n.parse::<u64>().ok().map(|v| v * factor);
+        }
+    }
+    q.parse::<u64>().ok()
+}
+
+/// The managed objects making up one component (`uninstall --component`).
+pub struct ComponentResources {
+    /// Its Deployments and StatefulSets, the Services selecting their pods,
+    /// and the Secrets only its pods use, as (kind, name) in deletion order.
+    pub resources: Vec<(&'static str, String)>,
+    /// PersistentVolumeClaims its pods mount, which are left in place
+    /// with the data on them.
+    pub kept_claims: Vec<String>,
+}
+
+/// Find what `uninstall --component` removes of `component`, and the
+/// claims it keeps (see [`ComponentResources`]).
+pub async fn component_resources(
+    client: &Client,
+    namespace: &str,
+    instance: Option<&str>,
+    component: &str,
+) -> Result<ComponentResources> {
+    let lp = ListParams::default().labels(&format!(
+        "{}={},{}",
+        MANAGED_BY_LABEL,
+        MANAGED_BY_VALUE,
+        crate::instance::selector(instance)
+    ));
+    let deployments: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let stateful_sets: Api<StatefulSet> = Api::namespaced(client.clone(), namespace);
+    // (kind, name, labels, pod template, name prefixes of the claims a
+    // StatefulSet creates from its volumeClaimTemplates) of each workload
+    type Workload = (&'static str, String, BTreeMap<String, String>, PodTemplateSpec, Vec<String>);
+    let mut workloads: Vec<Workload> = Vec::new();
+    for d in deployments.list(&lp).await.context("list deployments")?.items {
+        let template = d.spec.as_ref().map(|s| s.template.clone()).unwrap_or_default();
+        workloads.push(("Deployment", d.name_any(), d.labels().clone(), template, Vec::new()));
+    }
+    for s in stateful_sets.list(&lp).await.context("list statefulsets")?.items {
+        let template = s.spec.as_ref().map(|s| s.template.clone()).unwrap_or_default();
+        let claim_prefixes = s
+            .spec
+            .iter()
+            .flat_map(|spec| spec.volume_claim_templates.iter().flatten())
+            .map(|t| format!("{}-{}-", t.name_any(), s.name_any()))
+            .collect();
+        workloads.push(("StatefulSet", s.name_any(), s.labels().clone(), template, claim_prefixes));
+    }
+    let (ours, others): (Vec<_>, Vec<_>) = workloads
+        .into_iter()
+        .partition(|(_, name, labels, ..)| is_component(name, labels, component, instance));
+
+    let mut resources: Vec<(&'static str, String)> = ours.iter().map(|(kind, name, ..)| (*kind, name.clone())).collect();
+    let pod_labels: Vec<BTreeMap<String, String>> =
+        ours.iter().filter_map(|(_, _, _, template, _)| template.metadata.as_ref()?.labels.clone()).collect();
+    let services: Api<Service> = Api::namespaced(client.clone(), namespace);
+    for service in services.list(&lp).await.context("list services")?.items {
+        let selector = service.spec.as_ref().and_then(|s| s.selector.clone()).unwrap_or_default();
+        let selects = !selector.is_empty()
+            && pod_labels.iter().any(|labels| selector.iter().all(|(k, v)| labels.get(k) == Some(v)));
+        if selects || is_component(&service.name_any(), service.labels(), component, instance) {
+            resources.push(("Service", service.name_any()));
+        }
+    }
+
+    // A Secret another component's pods also read stays
+    let pod_secrets = |workloads: &[Workload]| {
+        workloads
+            .iter()
+            .filter_map(|(_, _, _, template, _)| template.spec.as_ref())
+            .flat_map(pod_secret_names)
+            .collect::<std::collections::BTreeSet<String>>()
+    };
+    let shared = pod_secrets(&others);
+    let dedicated: Vec<String> = pod_secrets(&ours).into_iter().filter(|s| !shared.contains(s)).collect();
+    if !dedicated.is_empty() {
+        let secrets: Api<Secret> = Api::namespaced(client.clone(), namespace);
+        match secrets.list_metadata(&lp).await {
+            Ok(list) => {
+                let managed: Vec<String> = list.items.iter().map(|s| s.name_any()).collect();
+                resources.extend(dedicated.into_iter().filter(|s| managed.contains(s)).map(|s| ("Secret", s)));
+            }
+            // Not allowed to see Secrets: leave them rather than fail
+            Err(kube::Error::Api(e)) if e.code == 403 => {}
+            Err(e) => return Err(e).context("list secrets"),
+        }
+    }
+
+    // Claims stay, so the data survives; they're only listed
+    let mounted: Vec<String> = ours
+        .iter()
+        .filter_map(|(_, _, _, template, _)| template.spec.as_ref())
+        .flat_map(|spec| spec.volumes.iter().flatten())
+        .filter_map(|v| Some(v.persistent_volume_claim.as_ref()?.claim_name.clone()))
+        .collect();
+    let prefixes: Vec<&String> = ours.iter().flat_map(|(.., prefixes)| prefixes).collect();
+    let mut kept_claims = Vec::new();
+    if !mounted.is_empty() || !prefixes.is_empty() {
+        let claims: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), namespace);
+        match claims.list_metadata(&ListParams::default()).await {
+            Ok(list) => kept_claims.extend(
+                list.items
+                    .iter()
+                    .map(|c| c.name_any())
+                    .filter(|name| mounted.contains(name) || prefixes.iter().any(|p| name.starts_with(p.as_str()))),
+            ),
+            Err(kube::Error::Api(e)) if e.code == 403 => kept_claims = mounted,
+            Err(e) => return Err(e).context("list persistentvolumeclaims"),
+        }
+    }
+    Ok(ComponentResources { resources, kept_claims })
+}
+
+/// Whether an object is part of `component`: named after it (brain-blue is
+/// part of brain), or labeled `app: <component>`, `app: bakerst-<component>`
+/// or `extension: <component>` (ext-github is the github extension).
+pub fn is_component(name: &str, labels: &BTreeMap<String, String>, component: &str, instance: Option<&str>) -> bool {
+    let prefixed = crate::instance::prefixed(instance, component);
+    let named = name == prefixed || name.strip_prefix(&prefixed).is_some_and(|rest| rest.starts_with('-'));
+    let app = labels.get("app").map(String::as_str);
+    named
+        || app == Some(component)
+        || app == Some(&format!("bakerst-{}", component))
+        || labels.get("extension").map(String::as_str) == Some(component)
 }
 
-/// Delete a namespace (cascades to all resources within).
-pub async fn delete_namespace(client: &Client, name: &str) -> Result<()> {
-    let api: Api<Namespace> = Api::all(client.clone());
-    api.delete(name, &DeleteParams::default()).await.ok();
-    Ok(())
+/// Secrets a pod reads: `envFrom`, `secretKeyRef`s and secret volumes.
+pub fn pod_secret_names(spec: &PodSpec) -> Vec<String> {
+    let containers = spec.containers.iter().chain(spec.init_containers.iter().flatten());
+    let mut names = Vec::new();
+    for container in containers {
+        for from in container.env_from.iter().flatten() {
+            names.extend(from.secret_ref.as_ref().map(|r| r.name.clone()));
+        }
+        for env in container.env.iter().flatten() {
+            names.extend(env.value_from.as_ref().and_then(|v| v.secret_key_ref.as_ref()).map(|r| r.name.clone()));
+        }
+    }
+    for volume in spec.volumes.iter().flatten() {
+        names.extend(volume.secret.as_ref().and_then(|s| s.secret_name.clone()));
+    }
+    names
 }
 
-/// Status of a single deployment (for --status output).
-pub struct DeploymentStatus {
+/// A resource carrying the installer's ownership label (for `resources` output).
+pub struct ManagedResource {
+    pub kind: String,
     pub name: String,
-    pub desired: i32,
-    pub ready: i32,
-    pub image: String,
+    pub created: Option<std::time::SystemTime>,
+    pub status: String,
 }
 
-/// List all deployments in a namespace with their status.
-pub async fn get_deployments_status(
+/// List every resource of the instance labelled as managed by the
+/// installer, across all kinds in `MANAGED_KINDS` (the namespace itself
+/// included for the default instance). Kinds the kubeconfig may not list
+/// (e.g. Secrets under a viewer role) are skipped and returned as the
+/// second element.
+pub async fn list_managed_resources(
     client: &Client,
     namespace: &str,
-) -> Result<Vec<DeploymentStatus>> {
-    let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
-    let lp = ListParams::default();
-    let deployments = api.list(&lp).await?;
+    instance: Option<&str>,
+) -> Result<(Vec<ManagedResource>, Vec<String>)> {
+    let lp = ListParams::default().labels(&format!(
+        "{}={},{}",
+        MANAGED_BY_LABEL,
+        MANAGED_BY_VALUE,
+        crate::instance::selector(instance)
+    ));
+    let mut resources = Vec::new();
+    let mut denied = Vec::new();
 
-    let mut statuses = Vec::new();
-    for deploy in deployments.items {
-        let name = deploy.metadata.name.unwrap_or_default();
-        let status = deploy.status.as_ref();
-        let desired = status.and_then(|s| s.replicas).unwrap_or(0);
-        let ready = status.and_then(|s| s.ready_replicas).unwrap_or(0);
-        let image = deploy
-            .spec
-            .and_then(|s| {
-                s.template
-                    .spec
-                    .and_then(|s| s.containers.first().map(|c| c.image.clone().unwrap_or_default()))
-            })
-            .unwrap_or_default();
+    for &(kind, group, version, plural, namespaced) in MANAGED_KINDS {
+        let gvk = GroupVersionKind::gvk(group, version, kind);
+        let ar = ApiResource::from_gvk_with_plural(&gvk, plural);
+        let api: Api<DynamicObject> = if namespaced {
+            Api::namespaced_with(client.clone(), namespace, &ar)
+        } else {
+            Api::all_with(client.clone(), &ar)
+        };
+        let list = match api.list(&lp).await {
+            Ok(list) => list,
+            Err(kube::Error::Api(e)) if e.code == 403 => {
+                denied.push(plural.to_string());
+                continue;
+            }
+            Err(e) => return Err(e).with_context(|| format!("list {}", plural)),
+        };
 
-        statuses.push(DeploymentStatus {
-            name,
-            desired,
-            ready,
-            image,
-        });
+        for obj in list.items {
+            let name = obj.metadata.name.clone().unwrap_or_default();
+            if !namespaced && name != namespace {
+                continue;
+            }
+            resources.push(ManagedResource {
+                kind: kind.to_string(),
+                status: resource_status(kind, &obj.data),
+                created: obj
+                    .metadata
+                    .creation_timestamp
+                    .as_ref()
+                    .map(|t| std::time::SystemTime::from(t.0)),
+                name,
+            });
+        }
     }
-    Ok(statuses)
+    Ok((resources, denied))
+}
+
+/// One-word status for a resource, where the kind has a meaningful one.
+fn resource_status(kind: &str, data: &serde_json::Value) -> String {
+    match kind {
+        "Deployment" | "StatefulSet" => format!(
+            "{}/{} ready",
+            data["status"]["readyReplicas"].as_i64().unwrap_or(0),
+            data["spec"]["replicas"].as_i64().unwrap_or(1)
+        ),
+        "Namespace" | "PersistentVolumeClaim" => data["status"]["phase"]
+            .as_str()
+            .unwrap_or("Unknown")
+            .to_string(),
+        _ => "-".to_string(),
+    }
+}
+
+/// Format an age the way kubectl does: "45s", "12m", "3h", "5d".
+pub fn format_age(age: std::time::Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// NodePorts allocated anywhere in the cluster, mapped to the owning
+/// Service as (namespace, name).
+pub async fn node_ports_in_use(client: &Client) -> Result<BTreeMap<i32, (String, String)>> {
+    let api: Api<Service> = Api::all(client.clone());
+    let services = api.list(&ListParams::default()).await.context("list services")?;
+    let mut ports = BTreeMap::new();
+    for svc in services.items {
+        let ns = svc.metadata.namespace.clone().unwrap_or_default();
+        let name = svc.metadata.name.clone().unwrap_or_default();
+        let svc_ports = svc.spec.and_then(|s| s.ports).unwrap_or_default();
+        for port in svc_ports {
+            if let Some(node_port) = port.node_port {
+                ports.insert(node_port, (ns.clone(), name.clone()));
+            }
+        }
+    }
+    Ok(ports)
+}
+
+/// The NodePort the UI's Service is exposed on, read from the cluster
+/// rather than assumed; `None` if the Service is missing or has none (a
+/// named instance without `--ui-node-port`).
+pub async fn ui_node_port(client: &Client, namespace: &str, instance: Option<&str>) -> Result<Option<i32>> {
+    let api: Api<Service> = Api::namespaced(client.clone(), namespace);
+    let name = crate::instance::prefixed(instance, "ui");
+    let Some(svc) = api.get_opt(&name).await.with_context(|| format!("get service {}", name))? else {
+        return Ok(None);
+    };
+    let ports = svc.spec.and_then(|s| s.ports).unwrap_or_default();
+    Ok(ports.into_iter().find_map(|p| p.node_port))
 }
 
 /// Scale a deployment to N replicas.
@@ -341,11 +1872,14 @@ pub async fn get_brain_service_selector(
     Ok(slot)
 }
 
-/// List known secrets with their key names (not values) for status display.
+/// List the instance's known secrets with their key names (not values) for
+/// status display.
 pub async fn get_secrets_info(
     client: &Client,
     namespace: &str,
+    instance: Option<&str>,
 ) -> Result<Vec<(String, Vec<String>)>> {
+    let prefix = crate::instance::prefixed(instance, "bakerst-");
     let api: Api<Secret> = Api::namespaced(client.clone(), namespace);
     let lp = ListParams::default().labels(""); // all secrets
     let secrets = api.list(&lp).await?;
@@ -353,8 +1887,8 @@ pub async fn get_secrets_info(
     let mut result = Vec::new();
     for secret in secrets.items {
         let name = secret.metadata.name.unwrap_or_default();
-        // Only show bakerst-* secrets
-        if !name.starts_with("bakerst-") {
+        // Only show bakerst-* secrets (<instance>-bakerst-* for an instance)
+        if !name.starts_with(&prefix) {
             continue;
         }
         let keys: Vec<String> = secret
@@ -387,71 +1921,146 @@ pub async fn read_secret(
     }
 }
 
-/// Wait for all deployments in a namespace to have desired replicas ready.
-/// Polls every 5 seconds up to `timeout`. Skips deployments scaled to 0.
-/// Prints live feedback as each deployment becomes ready.
+/// Wait for `workloads` (those the install applied) to have every replica
+/// ready, printing live feedback as each becomes ready (with the logs of
+/// pods that are not ready yet, recovery restarts and the problem it
+/// stopped on); others in the namespace are ignored. Polls with [`crate::health::poll_health`] under
+/// `opts`. Returns how long after the wait began each workload (last)
+/// became ready, in that order.
 pub async fn wait_for_deployments(
     client: &Client,
     namespace: &str,
-    timeout: std::time::Duration,
-) -> Result<()> {
-    let deadline = std::time::Instant::now() + timeout;
-    let mut announced_ready: std::collections::HashSet<String> = std::collections::HashSet::new();
-    let mut first_poll = true;
-
-    loop {
-        let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
-        let deployments = api.list(&ListParams::default()).await?;
+    workloads: &[crate::health::WorkloadRef],
+    opts: &crate::health::PollOptions,
+) -> Result<Vec<(String, std::time::Duration)>> {
+    use crate::health::HealthEvent;
 
-        let mut not_ready = Vec::new();
-        let mut total_tracked = 0;
+    let started = std::time::Instant::now();
+    let names: Vec<&str> = workloads.iter().map(|w| w.name.as_str()).collect();
+    println!("  Waiting for {} workload(s): {}", names.len(), names.join(", "));
 
-        for deploy in &deployments.items {
-            let name = deploy.metadata.name.clone().unwrap_or_default();
-            let desired = deploy
-                .spec
-                .as_ref()
-                .and_then(|s| s.replicas)
-                .unwrap_or(1);
-            if desired == 0 {
-                continue; // skip scaled-to-zero
-            }
-            total_tracked += 1;
-            let status = deploy.status.as_ref();
-            let ready = status.and_then(|s| s.ready_replicas).unwrap_or(0);
-            if ready >= desired {
-                if announced_ready.insert(name.clone()) {
-                    println!("  ✓ {} ready", name);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let report = async {
+        let mut ready_after: Vec<(String, std::time::Duration)> = Vec::new();
+        while let Some(event) = rx.recv().await {
+            match event {
+                HealthEvent::WorkloadReady { workload, ready: true } => {
+                    let elapsed = started.elapsed();
+                    println!("  ✓ {} ready ({:.1}s)", workload, elapsed.as_secs_f64());
+                    tracing::debug!(workload = %workload, namespace, "workload ready");
+                    crate::output::emit(&crate::output::Event::Health { deployment: &workload, ready: true });
+                    ready_after.push((workload, elapsed));
+                }
+                HealthEvent::WorkloadReady { workload, ready: false } => {
+                    ready_after.retain(|(n, _)| n != &workload);
+                    println!("  ✗ {} no longer ready", workload);
+                    tracing::debug!(workload = %workload, namespace, "workload no longer ready");
+                    crate::output::emit(&crate::output::Event::Health { deployment: &workload, ready: false });
+                }
+                HealthEvent::RecoveryAttempt { deployment, attempt } => {
+                    println!(
+                        "  ↻ {}: restarted crash-looping pod (attempt {}/{})",
+                        deployment, attempt, opts.max_recovery_attempts
+                    );
+                }
+                HealthEvent::RecoveryGaveUp { deployment, reason, .. } => {
+                    println!("  ✗ {}: {}", deployment, reason);
                 }
-            } else {
-                not_ready.push(format!("{} ({}/{})", name, ready, desired));
+                HealthEvent::LogLine { pod, line } => println!("    {} | {}", pod, line),
+                HealthEvent::AllHealthy => return Ok(ready_after),
+                HealthEvent::Failed { workload, reason, .. } => {
+                    println!("  ✗ {}: {}", workload, reason);
+                    println!("  {} of {} workloads ready", ready_after.len(), workloads.len());
+                    return Err(crate::output::ComponentError::new(&workload, reason).into());
+                }
+                _ => {}
             }
         }
+        anyhow::bail!("health polling stopped unexpectedly")
+    };
+    // Polling ends by sending AllHealthy or Failed, which ends the report
+    let (polled, reported) = tokio::join!(crate::health::poll_health(client, namespace, workloads, opts, tx), report);
+    polled?;
+    reported
+}
 
-        if not_ready.is_empty() {
-            return Ok(());
-        }
+/// Stream logs from one running pod of each deployment, interleaved and
+/// prefixed with the deployment name, until `duration` has passed or Ctrl+C.
+/// Deployments without a running pod are noted and skipped.
+pub async fn follow_logs(
+    client: &Client,
+    namespace: &str,
+    deployments: &[String],
+    duration: std::time::Duration,
+) -> Result<()> {
+    use futures::{AsyncBufReadExt, TryStreamExt};
 
-        if first_poll {
-            println!(
-                "  Waiting for {} deployment(s): {}",
-                not_ready.len(),
-                not_ready.join(", ")
-            );
-            first_poll = false;
-        }
+    let deploy_api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let pod_api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let width = deployments.iter().map(|d| d.len()).max().unwrap_or(0);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<(String, String)>();
 
-        if std::time::Instant::now() > deadline {
-            // Print summary of what's still pending
-            println!("  {} of {} deployments ready", announced_ready.len(), total_tracked);
-            bail!(
-                "Timed out waiting for deployments: {}",
-                not_ready.join(", ")
-            );
-        }
+    for name in deployments {
+        let deploy = deploy_api.get(name).await?;
+        let Some(selector) = match_labels_selector(&deploy) else {
+            continue;
+        };
+        let pods = pod_api.list(&ListParams::default().labels(&selector)).await?;
+        let running = pods.items.iter().find(|p| {
+            p.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running")
+        });
+        let Some(pod) = running.map(|p| p.name_any()) else {
+            println!("  {:<width$} | (no running pod)", name, width = width);
+            continue;
+        };
+        let params = LogParams {
+            follow: true,
+            tail_lines: Some(10),
+            ..Default::default()
+        };
+        let mut lines = pod_api.log_stream(&pod, &params).await?.lines();
+        let tx = tx.clone();
+        let name = name.clone();
+        tokio::spawn(async move {
+            while let Ok(Some(line)) = lines.try_next().await {
+                if tx.send((name.clone(), line)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
 
-        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            next = rx.recv() => match next {
+                Some((name, line)) => println!("  {:<width$} | {}", name, line, width = width),
+                None => break, // every stream ended
+            },
+            _ = &mut deadline => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
     }
+    Ok(())
+}
+
+/// A deployment's `matchLabels` as a label selector string.
+pub(crate) fn match_labels_selector(deploy: &Deployment) -> Option<String> {
+    labels_selector(&deploy.spec.as_ref()?.selector)
+}
+
+/// A label selector's `matchLabels` as a `k=v,...` list selector.
+pub(crate) fn labels_selector(selector: &k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector) -> Option<String> {
+    let labels = selector.match_labels.as_ref()?;
+    Some(
+        labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(","),
+    )
 }
 
 // ---------------------------------------------------------------------------
@@ -543,6 +2152,592 @@ pub async fn use_context(name: &str) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn latest_warning_per_pod_wins() {
+        let event = |pod: &str, type_: &str, reason: &str, at: &str| -> Event {
+            serde_json::from_value(serde_json::json!({
+                "metadata": { "name": format!("{}.{}", pod, reason) },
+                "involvedObject": { "kind": "Pod", "name": pod },
+                "type": type_,
+                "reason": reason,
+                "message": format!("{} happened\n", reason),
+                "lastTimestamp": at,
+            }))
+            .unwrap()
+        };
+        let events = [
+            event("brain-0", "Warning", "FailedScheduling", "2026-01-01T10:00:00Z"),
+            event("brain-0", "Warning", "BackOff", "2026-01-01T10:05:00Z"),
+            event("brain-0", "Warning", "Unhealthy", "2026-01-01T10:01:00Z"),
+            event("brain-0", "Normal", "Pulled", "2026-01-01T10:09:00Z"),
+            event("ui-0", "Normal", "Started", "2026-01-01T10:00:00Z"),
+        ];
+        let warnings = latest_warnings(&events);
+        assert_eq!(warnings["brain-0"], "BackOff: BackOff happened");
+        assert!(!warnings.contains_key("ui-0"));
+    }
+
+    #[test]
+    fn components_are_matched_by_name_or_label_and_their_secrets_found() {
+        let labels = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert!(is_component("brain-blue", &labels(&[("app", "brain")]), "brain", None));
+        assert!(is_component("voice", &labels(&[("app", "bakerst-voice")]), "voice", None));
+        assert!(is_component("ext-github", &labels(&[("extension", "github")]), "github", None));
+        assert!(is_component("team-a-voice", &labels(&[]), "voice", Some("team-a")));
+        assert!(!is_component("brainstorm", &labels(&[]), "brain", None));
+        assert!(!is_component("ext-github", &labels(&[("app", "bakerst-extension")]), "voice", None));
+
+        let deployment: Deployment = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "voice" },
+            "spec": { "selector": {}, "template": { "spec": {
+                "containers": [{
+                    "name": "voice",
+                    "envFrom": [{ "secretRef": { "name": "bakerst-voice-secrets" } }],
+                    "env": [{ "name": "AUTH_TOKEN", "valueFrom": {
+                        "secretKeyRef": { "name": "bakerst-brain-secrets", "key": "AUTH_TOKEN" } } }],
+                }],
+                "volumes": [{ "name": "creds", "secret": { "secretName": "bakerst-voice-creds" } }],
+            }}},
+        }))
+        .unwrap();
+        let spec = deployment.spec.unwrap().template.spec.unwrap();
+        assert_eq!(
+            pod_secret_names(&spec),
+            ["bakerst-voice-secrets", "bakerst-brain-secrets", "bakerst-voice-creds"]
+        );
+    }
+
+    #[test]
+    fn stuck_namespaces_report_their_deletion_conditions() {
+        let namespace: Namespace = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "bakerst" },
+            "status": { "phase": "Terminating", "conditions": [
+                { "type": "NamespaceDeletionDiscoveryFailure", "status": "False" },
+                { "type": "NamespaceContentRemaining", "status": "True",
+                  "message": "Some resources are remaining: persistentvolumeclaims. has 1 resource instances" },
+                { "type": "NamespaceFinalizersRemaining", "status": "True",
+                  "message": "Some content in the namespace has finalizers remaining: kubernetes.io/pvc-protection in 1 resource instances" },
+            ]},
+        }))
+        .unwrap();
+        let reason = namespace_stuck_reason(&namespace).unwrap();
+        assert!(reason.starts_with("Some resources are remaining"), "{}", reason);
+        assert!(reason.ends_with("kubernetes.io/pvc-protection in 1 resource instances"), "{}", reason);
+        assert!(!reason.contains("Discovery"));
+
+        let fresh: Namespace = serde_json::from_value(serde_json::json!({ "metadata": { "name": "bakerst" } })).unwrap();
+        assert_eq!(namespace_stuck_reason(&fresh), None);
+    }
+
+    #[test]
+    fn volumes_are_matched_by_claim_namespace_only() {
+        let pv = |claim_ns: &str, claim: &str, managed: bool| {
+            let mut pv: PersistentVolume = serde_json::from_value(serde_json::json!({
+                "metadata": { "name": format!("pvc-{}", claim) },
+                "spec": { "claimRef": { "namespace": claim_ns, "name": claim } },
+            }))
+            .unwrap();
+            if managed {
+                pv.metadata.labels = Some(managed_labels());
+            }
+            pv
+        };
+        assert!(volume_claimed_from(&pv("bakerst", "brain-data", false), "bakerst", None));
+        assert!(!volume_claimed_from(&pv("other", "brain-data", false), "bakerst", None));
+        // Another install's volume carries the same label
+        assert!(!volume_claimed_from(&pv("other", "data", true), "bakerst", None));
+
+        // An instance only takes its own claims
+        let claims = ["team-a-brain-data".to_string()];
+        assert!(volume_claimed_from(&pv("bakerst", "team-a-brain-data", false), "bakerst", Some(&claims)));
+        assert!(!volume_claimed_from(&pv("bakerst", "brain-data", true), "bakerst", Some(&claims)));
+
+        let mut deleted = pv("bakerst", "brain-data", false);
+        assert!(volume_retained(&deleted));
+        deleted.spec.as_mut().unwrap().persistent_volume_reclaim_policy = Some("Delete".into());
+        assert!(!volume_retained(&deleted));
+    }
+
+    #[test]
+    fn render_namespace_keeps_template_labels() {
+        let template = "apiVersion: v1\nkind: Namespace\nmetadata:\n  name: bakerst\n  labels:\n    team: ops\n";
+        let ns = render_namespace(Some(template), "custom", Some("0.6.0")).unwrap();
+        assert_eq!(ns["metadata"]["name"], "custom");
+        assert_eq!(ns["metadata"]["labels"]["team"], "ops");
+        assert_eq!(ns["metadata"]["labels"]["app.kubernetes.io/version"], "0.6.0");
+    }
+
+    #[test]
+    fn annotation_keys_are_validated() {
+        assert!(validate_annotation_key("owner").is_ok());
+        assert!(validate_annotation_key("cost.example.com/team").is_ok());
+        assert!(validate_annotation_key("policies.kyverno.io/scored").is_ok());
+        assert!(validate_annotation_key("").is_err());
+        assert!(validate_annotation_key("/team").is_err());
+        assert!(validate_annotation_key("Example.com/team").is_err());
+        assert!(validate_annotation_key("example.com/-team").is_err());
+        assert!(parse_annotation("no-equals").is_err());
+        assert_eq!(
+            parse_annotation("example.com/team=infra=core").unwrap(),
+            ("example.com/team".to_string(), "infra=core".to_string())
+        );
+    }
+
+    #[test]
+    fn only_the_installers_own_namespaces_are_used_without_adopting() {
+        let owned = managed_labels();
+        let foreign = BTreeMap::from([("team".to_string(), "payments".to_string())]);
+        assert!(check_namespace_owner("bakerst", None).is_ok());
+        assert!(check_namespace_owner("bakerst", Some(&owned)).is_ok());
+        let err = check_namespace_owner("bakerst", Some(&foreign)).unwrap_err();
+        assert!(err.to_string().starts_with("Namespace 'bakerst' already exists and was not created by bakerst-install"));
+        assert!(err.to_string().contains("--adopt-namespace"));
+    }
+
+    #[test]
+    fn namespace_labels_are_validated_and_merged() {
+        assert_eq!(parse_label("team=platform").unwrap(), ("team".to_string(), "platform".to_string()));
+        assert_eq!(parse_label("example.com/cost-center=").unwrap().1, "");
+        assert!(parse_label("team=a b").unwrap_err().to_string().contains("invalid label value"));
+        assert!(parse_label("Team.com/x=y").unwrap_err().to_string().starts_with("invalid label key"));
+        assert!(parse_label("app.kubernetes.io/managed-by=helm").is_err());
+
+        let template = "apiVersion: v1\nkind: Namespace\nmetadata:\n  labels:\n    team: from-template\n";
+        let mut ns = render_namespace(Some(template), "bakerst", None).unwrap();
+        label_namespace(&mut ns, &[("team".into(), "platform".into()), ("cost-center".into(), "cc-42".into())]);
+        assert_eq!(ns["metadata"]["labels"]["team"], "platform");
+        assert_eq!(ns["metadata"]["labels"]["cost-center"], "cc-42");
+        assert_eq!(ns["metadata"]["labels"][MANAGED_BY_LABEL], MANAGED_BY_VALUE);
+    }
+
+    #[test]
+    fn annotations_merge_over_template() {
+        let template = "apiVersion: v1\nkind: Namespace\nmetadata:\n  name: x\n  annotations:\n    a: from-template\n    b: kept\n";
+        let mut ns = render_namespace(Some(template), "bakerst", None).unwrap();
+        annotate_namespace(&mut ns, &[("a".into(), "from-flag".into())]);
+        assert_eq!(ns["metadata"]["annotations"]["a"], "from-flag");
+        assert_eq!(ns["metadata"]["annotations"]["b"], "kept");
+        assert_eq!(ns["metadata"]["labels"][MANAGED_BY_LABEL], MANAGED_BY_VALUE);
+    }
+
+    #[test]
+    fn render_namespace_without_template() {
+        let ns = render_namespace(None, "bakerst", None).unwrap();
+        assert_eq!(ns["kind"], "Namespace");
+        assert_eq!(ns["metadata"]["name"], "bakerst");
+    }
+
+    #[test]
+    fn merge_secret_data_preserves_unspecified_keys() {
+        let existing = BTreeMap::from([
+            ("A".to_string(), "old-a".to_string()),
+            ("B".to_string(), "old-b".to_string()),
+        ]);
+        let new = BTreeMap::from([("A".to_string(), "new-a".to_string())]);
+        let merged = merge_secret_data(existing, &new);
+        assert_eq!(merged["A"], "new-a");
+        assert_eq!(merged["B"], "old-b");
+    }
+
+    #[test]
+    fn parse_yaml_docs_skips_comments_and_empty_docs() {
+        let yaml = "# header\napiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: a\n---\n# only a comment\n---\nkind: Service\nmetadata:\n  name: b\n";
+        let docs = parse_yaml_docs(yaml).unwrap();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[1]["kind"], "Service");
+    }
+
+    #[test]
+    fn pull_policy_set_on_all_containers() {
+        let mut deploy = serde_json::json!({
+            "kind": "Deployment",
+            "spec": { "template": { "spec": {
+                "initContainers": [{ "name": "init", "imagePullPolicy": "Never" }],
+                "containers": [{ "name": "a", "imagePullPolicy": "Never" }, { "name": "b" }]
+            }}}
+        });
+        set_pull_policy(&mut deploy, PullPolicy::Always);
+        let spec = &deploy["spec"]["template"]["spec"];
+        assert_eq!(spec["containers"][0]["imagePullPolicy"], "Always");
+        assert_eq!(spec["containers"][1]["imagePullPolicy"], "Always");
+        assert_eq!(spec["initContainers"][0]["imagePullPolicy"], "Always");
+
+        let mut svc = serde_json::json!({ "kind": "Service", "spec": { "ports": [] } });
+        set_pull_policy(&mut svc, PullPolicy::Always);
+        assert!(svc["spec"].get("template").is_none());
+    }
+
+    #[test]
+    fn protected_namespaces_are_rejected() {
+        assert!(check_namespace("bakerst", false).is_ok());
+        assert!(check_namespace("kube-system", true).is_err());
+        assert!(check_namespace("kube-public", false).is_err());
+        assert!(check_namespace("default", false).is_err());
+        assert!(check_namespace("default", true).is_ok());
+        assert!(check_namespace("Bakerst", false).is_err());
+        assert!(check_namespace("bakerst-", false).is_err());
+    }
+
+    #[test]
+    fn forbidden_reads_name_the_missing_permission() {
+        let forbidden = anyhow::Error::from(kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".into(),
+            message: "secrets is forbidden".into(),
+            reason: "Forbidden".into(),
+            code: 403,
+        }));
+        assert!(is_forbidden(&forbidden));
+        let msg = explain_read_error(forbidden, "list", "secrets", "bakerst").to_string();
+        assert!(msg.starts_with("Permission denied"));
+        assert!(msg.contains("'list' verb on 'secrets'"));
+
+        assert!(!is_forbidden(&anyhow::anyhow!("connection refused")));
+    }
+
+    fn api_error(code: u16) -> anyhow::Error {
+        anyhow::Error::from(kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".into(),
+            message: format!("HTTP {}", code),
+            reason: String::new(),
+            code,
+        }))
+    }
+
+    #[test]
+    fn only_transient_apply_errors_are_retried() {
+        for code in [409, 429, 500, 503] {
+            assert!(is_retryable_apply_error(&api_error(code).context("apply")), "{}", code);
+        }
+        for code in [400, 403, 404, 422] {
+            assert!(!is_retryable_apply_error(&api_error(code)), "{}", code);
+        }
+        assert!(!is_retryable_apply_error(&anyhow::anyhow!("missing field `spec`")));
+    }
+
+    /// Fails each apply with `codes` in turn, then succeeds.
+    struct FlakyCluster {
+        codes: std::sync::Mutex<Vec<u16>>,
+        attempts: std::sync::atomic::AtomicU32,
+    }
+
+    impl ClusterClient for FlakyCluster {
+        async fn apply(&self, _: &str, _: &serde_json::Value, _: &ApplyParams) -> Result<()> {
+            self.attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match self.codes.lock().unwrap().pop() {
+                Some(code) => Err(api_error(code)),
+                None => Ok(()),
+            }
+        }
+
+        async fn read_secret(&self, _: &str, _: &str) -> Result<Option<BTreeMap<String, String>>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn apply_retry_backoff_doubles_up_to_a_cap() {
+        let secs = |attempt| apply_retry_backoff(attempt).as_secs_f64();
+        assert_eq!((secs(1), secs(2), secs(3)), (0.5, 1.0, 2.0));
+        assert_eq!(secs(7), 30.0);
+        // Past where 2^n overflows
+        assert_eq!(secs(40), 30.0);
+        assert_eq!(secs(u32::MAX), 30.0);
+    }
+
+    /// A cluster where the object being created already exists as `live`;
+    /// it answers creates with a 409 and records the bodies of replaces.
+    fn conflicting_cluster(live: serde_json::Value) -> (Client, std::sync::Arc<std::sync::Mutex<Vec<serde_json::Value>>>) {
+        let replaced = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = replaced.clone();
+        let service = tower::service_fn(move |req: http::Request<kube::client::Body>| {
+            let (live, seen) = (live.clone(), seen.clone());
+            async move {
+                let (status, body) = match *req.method() {
+                    http::Method::POST => (
+                        409,
+                        serde_json::json!({"kind": "Status", "apiVersion": "v1", "status": "Failure",
+                                           "message": "already exists", "reason": "AlreadyExists", "code": 409}),
+                    ),
+                    http::Method::GET => (200, live),
+                    _ => {
+                        let body: serde_json::Value = serde_json::from_slice(&req.into_body().collect_bytes().await?)?;
+                        seen.lock().unwrap().push(body.clone());
+                        (200, body)
+                    }
+                };
+                let response = http::Response::builder()
+                    .status(status)
+                    .body(kube::client::Body::from(serde_json::to_vec(&body)?))?;
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>(response)
+            }
+        });
+        (Client::new(service, "bakerst"), replaced)
+    }
+
+    #[tokio::test]
+    async fn client_apply_replaces_keeping_allocated_service_ips_and_the_claim_spec() {
+        let params = ApplyParams { strategy: ApplyStrategy::Client, ..Default::default() };
+
+        let (client, replaced) = conflicting_cluster(serde_json::json!({
+            "apiVersion": "v1", "kind": "Service",
+            "metadata": {"name": "brain", "namespace": "bakerst", "resourceVersion": "7"},
+            "spec": {"clusterIP": "10.0.0.5", "clusterIPs": ["10.0.0.5"], "ipFamilies": ["IPv4"], "ports": [{"port": 80}]},
+        }));
+        let service = serde_json::json!({
+            "apiVersion": "v1", "kind": "Service", "metadata": {"name": "brain"}, "spec": {"ports": [{"port": 8080}]},
+        });
+        apply_resource(&client, "bakerst", &service, &params).await.unwrap();
+        let replaced = replaced.lock().unwrap()[0].clone();
+        let spec = &replaced["spec"];
+        assert_eq!(replaced["metadata"]["resourceVersion"], "7");
+        assert_eq!(spec["clusterIP"], "10.0.0.5");
+        assert_eq!(spec["clusterIPs"], serde_json::json!(["10.0.0.5"]));
+        assert_eq!(spec["ipFamilies"], serde_json::json!(["IPv4"]));
+        assert_eq!(spec["ports"][0]["port"], 8080, "the rest of the spec is replaced");
+
+        let live_claim = serde_json::json!({"accessModes": ["ReadWriteOnce"], "resources": {"requests": {"storage": "1Gi"}},
+                                            "storageClassName": "standard", "volumeName": "pv-1"});
+        let (client, replaced) = conflicting_cluster(serde_json::json!({
+            "apiVersion": "v1", "kind": "PersistentVolumeClaim",
+            "metadata": {"name": "qdrant-data", "namespace": "bakerst", "resourceVersion": "9"},
+            "spec": live_claim,
+        }));
+        let claim = serde_json::json!({
+            "apiVersion": "v1", "kind": "PersistentVolumeClaim", "metadata": {"name": "qdrant-data"},
+            "spec": {"accessModes": ["ReadWriteOnce"], "resources": {"requests": {"storage": "2Gi"}}},
+        });
+        apply_resource(&client, "bakerst", &claim, &params).await.unwrap();
+        assert_eq!(replaced.lock().unwrap()[0]["spec"], live_claim);
+    }
+
+    #[tokio::test]
+    async fn apply_retries_transient_failures_but_not_rejections() {
+        let resource = serde_json::json!({"kind": "ConfigMap", "metadata": {"name": "brain-config"}});
+        let flaky = |codes: Vec<u16>| FlakyCluster {
+            codes: std::sync::Mutex::new(codes),
+            attempts: Default::default(),
+        };
+        let params = ApplyParams { retries: 1, ..Default::default() };
+
+        let cluster = flaky(vec![503]);
+        let applied = apply_resources(&cluster, "bakerst", std::slice::from_ref(&resource), &params, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(applied, ["ConfigMap/brain-config"]);
+        assert_eq!(cluster.attempts.into_inner(), 2);
+
+        let cluster = flaky(vec![422]);
+        let err = apply_resources(&cluster, "bakerst", std::slice::from_ref(&resource), &params, |_, _| {})
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "apply ConfigMap/brain-config");
+        assert_eq!(cluster.attempts.into_inner(), 1);
+    }
+
+    /// Records each apply as it starts and ends, yielding in between so
+    /// concurrent applies interleave.
+    #[derive(Default)]
+    struct RecordingCluster {
+        log: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ClusterClient for RecordingCluster {
+        async fn apply(&self, _: &str, resource: &serde_json::Value, _: &ApplyParams) -> Result<()> {
+            let name = resource["metadata"]["name"].as_str().unwrap_or_default().to_string();
+            self.log.lock().unwrap().push(format!("+{}", name));
+            tokio::task::yield_now().await;
+            self.log.lock().unwrap().push(format!("-{}", name));
+            Ok(())
+        }
+
+        async fn read_secret(&self, _: &str, _: &str) -> Result<Option<BTreeMap<String, String>>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn independent_resources_are_applied_together_in_stages() {
+        let resources = parse_yaml_docs(
+            "kind: Deployment\nmetadata: {name: brain}\n---\nkind: Ingress\nmetadata: {name: ui}\n\
+             ---\nkind: ConfigMap\nmetadata: {name: brain-config}\n\
+             ---\nkind: Service\nmetadata: {name: nats, labels: {app: nats}}\n\
+             ---\nkind: PersistentVolumeClaim\nmetadata: {name: qdrant-data}\n",
+        )
+        .unwrap();
+        let cluster = RecordingCluster::default();
+        let mut streamed = Vec::new();
+        let applied = apply_resources(&cluster, "bakerst", &resources, &ApplyParams::default(), |_, label| {
+            streamed.push(label.to_string())
+        })
+        .await
+        .unwrap();
+
+        // Labels come back in the order given, and each was streamed
+        assert_eq!(
+            applied,
+            ["Deployment/brain", "Ingress/ui", "ConfigMap/brain-config", "Service/nats", "PersistentVolumeClaim/qdrant-data"]
+        );
+        assert_eq!(streamed.len(), 5);
+        // Config and storage go in together, then the infra, the services
+        // using it and the Ingress
+        assert_eq!(
+            cluster.log.into_inner().unwrap(),
+            [
+                "+brain-config", "+qdrant-data", "-brain-config", "-qdrant-data",
+                "+nats", "-nats",
+                "+brain", "-brain",
+                "+ui", "-ui",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejected_credentials_are_detected() {
+        let unauthorized = anyhow::Error::from(kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".into(),
+            message: "Unauthorized".into(),
+            reason: "Unauthorized".into(),
+            code: 401,
+        }));
+        assert!(is_expired_credentials(&unauthorized));
+        let msg = explain_read_error(unauthorized, "list", "pods", "bakerst").to_string();
+        assert_eq!(msg, EXPIRED_CREDENTIALS_HINT);
+
+        let refresh = anyhow::Error::from(kube::Error::Auth(kube::client::AuthError::UnrefreshableTokenResponse))
+            .context("failed to connect");
+        assert!(is_expired_credentials(&refresh));
+        assert!(!is_expired_credentials(&anyhow::anyhow!("connection refused")));
+        // Only the structured error counts, not a message that mentions it
+        assert!(!is_expired_credentials(&anyhow::anyhow!("secret 'unauthorized-users' not found")));
+    }
+
+    #[test]
+    fn logs_follow_pods_matched_by_the_deployment_selector() {
+        let deploy: Deployment = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "brain"},
+            "spec": {
+                "selector": {"matchLabels": {"app": "brain", "bakerst.io/instance": "a"}},
+                "template": {}
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            match_labels_selector(&deploy).as_deref(),
+            Some("app=brain,bakerst.io/instance=a")
+        );
+    }
+
+    #[test]
+    fn default_storage_class_is_read_from_either_annotation() {
+        let annotated = |key: &str, value: &str| BTreeMap::from([(key.to_string(), value.to_string())]);
+        assert!(is_default_storage_class(&annotated("storageclass.kubernetes.io/is-default-class", "true")));
+        assert!(is_default_storage_class(&annotated("storageclass.beta.kubernetes.io/is-default-class", "true")));
+        assert!(!is_default_storage_class(&annotated("storageclass.kubernetes.io/is-default-class", "false")));
+        assert!(!is_default_storage_class(&BTreeMap::new()));
+    }
+
+    #[test]
+    fn unknown_context_lists_available_ones() {
+        assert!(check_context(&["docker-desktop", "prod"], "prod").is_ok());
+        let err = check_context(&["docker-desktop", "prod"], "staging").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Kubernetes context 'staging' not found. Available contexts: docker-desktop, prod"
+        );
+    }
+
+    #[test]
+    fn default_field_manager_is_versioned() {
+        let params = ApplyParams::default();
+        assert_eq!(params.field_manager, format!("bakerst-install/{}", env!("CARGO_PKG_VERSION")));
+        assert_eq!(params.strategy, ApplyStrategy::Auto);
+    }
+
+    #[test]
+    fn normalize_strips_server_fields() {
+        let mut obj = serde_json::json!({
+            "kind": "ConfigMap",
+            "metadata": {
+                "name": "a",
+                "resourceVersion": "42",
+                "managedFields": [],
+                "annotations": { "kubectl.kubernetes.io/last-applied-configuration": "{}" }
+            },
+            "data": { "k": "v" },
+            "status": {}
+        });
+        normalize_for_diff(&mut obj);
+        assert_eq!(obj, serde_json::json!({ "kind": "ConfigMap", "metadata": { "name": "a" }, "data": { "k": "v" } }));
+    }
+
+    #[test]
+    fn secret_values_are_masked() {
+        let mut before = serde_json::json!({ "data": { "same": "YQ==", "changed": "Yg==" } });
+        let mut after = serde_json::json!({ "data": { "same": "YQ==", "changed": "Yw==", "new": "ZA==" } });
+        mask_secret_data(&mut before, &mut after);
+        assert_eq!(before["data"]["same"], "***");
+        assert_eq!(before["data"]["changed"], "*** (before)");
+        assert_eq!(after["data"]["same"], "***");
+        assert_eq!(after["data"]["changed"], "*** (after)");
+        assert_eq!(after["data"]["new"], "*** (after)");
+    }
+
+    #[test]
+    fn ssa_unsupported_detection() {
+        assert!(is_ssa_unsupported(415, "UnsupportedMediaType"));
+        assert!(is_ssa_unsupported(400, "UnsupportedMediaType"));
+        assert!(!is_ssa_unsupported(409, "Conflict"));
+        assert!(!is_ssa_unsupported(422, "Invalid"));
+    }
+
+    #[test]
+    fn format_age_units() {
+        use std::time::Duration;
+        assert_eq!(format_age(Duration::from_secs(45)), "45s");
+        assert_eq!(format_age(Duration::from_secs(12 * 60)), "12m");
+        assert_eq!(format_age(Duration::from_secs(3 * 3600)), "3h");
+        assert_eq!(format_age(Duration::from_secs(5 * 86400)), "5d");
+    }
+
+    #[test]
+    fn deployment_status_from_replicas() {
+        let data = serde_json::json!({ "spec": { "replicas": 2 }, "status": { "readyReplicas": 1 } });
+        assert_eq!(resource_status("Deployment", &data), "1/2 ready");
+        assert_eq!(resource_status("ConfigMap", &data), "-");
+    }
+
+    #[test]
+    fn parse_cpu_quantities() {
+        assert_eq!(parse_cpu_millicores("250m"), Some(250));
+        assert_eq!(parse_cpu_millicores("2"), Some(2000));
+        assert_eq!(parse_cpu_millicores("0.5"), Some(500));
+        assert_eq!(parse_cpu_millicores("12345678n"), Some(12));
+        assert_eq!(parse_cpu_millicores("garbage"), None);
+    }
+
+    #[test]
+    fn parse_memory_quantities() {
+        assert_eq!(parse_memory_bytes("180Mi"), Some(180 * 1024 * 1024));
+        assert_eq!(parse_memory_bytes("2048Ki"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_memory_bytes("1G"), Some(1_000_000_000));
+        assert_eq!(parse_memory_bytes("1048576"), Some(1_048_576));
+    }
+
+    #[test]
+    fn pod_usage_adds_up_per_app() {
+        let pod = |pod: &str, app: &str, cpu: u64, mib: u64| PodUsage {
+            pod: pod.into(),
+            app: app.into(),
+            usage: ResourceUsage { cpu_millicores: cpu, memory_bytes: mib * 1024 * 1024 },
+        };
+        let pods = [pod("brain-a", "brain", 120, 300), pod("brain-b", "brain", 80, 200), pod("nats-a", "nats", 5, 20)];
+        let by_app = usage_by_app(&pods);
+        assert_eq!(by_app["brain"], ResourceUsage { cpu_millicores: 200, memory_bytes: 500 * 1024 * 1024 });
+        assert_eq!(by_app["nats"].to_string(), "5m CPU, 20Mi");
+    }
+
     #[test]
     fn classify_docker_desktop() {
         assert!(matches!(
@@ -2,17 +2,27 @@ pub mod cli;
 pub mod manifest;
 pub mod config_schema;
 pub mod config_file;
+pub mod env_file;
 pub mod fetcher;
 pub mod k8s;
 pub mod health;
+pub mod diagnose;
 pub mod images;
 pub mod interview;
 pub mod app;
 pub mod tui;
+pub mod tui_driver;
 pub mod validation;
 pub mod verify;
 pub mod deploy;
+pub mod instance;
+pub mod metrics;
+pub mod output;
+pub mod signals;
+pub mod telemetry;
+pub mod templates;
 pub mod cmd_install;
 pub mod cmd_status;
+pub mod cmd_resources;
 pub mod cmd_update;
 pub mod cmd_uninstall;
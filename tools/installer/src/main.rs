@@ -1,12 +1,13 @@
-use bakerst_install::{cli, cmd_install, cmd_status, cmd_update, cmd_uninstall};
-use clap::Parser;
+use bakerst_install::{cli, cmd_install, cmd_resources, cmd_status, cmd_update, cmd_uninstall, signals};
 use anyhow::Result;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 use std::fs;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let mut cli = cli::Cli::parse();
+    let mut cli = cli::parse();
 
     // Ensure ~/.bakerst/ exists
     let bakerst_dir = dirs::home_dir()
@@ -14,8 +15,20 @@ async fn main() -> Result<()> {
         .join(".bakerst");
     fs::create_dir_all(&bakerst_dir)?;
 
+    // Extract command BEFORE matching to avoid partial move of cli
+    let mut command = cli.command.take()
+        .unwrap_or_else(|| cli::Commands::Install(Box::default()));
+
+    // The config file can set any install flag, --log included
+    let inputs = match &mut command {
+        cli::Commands::Install(args) => cmd_install::read_inputs(args)?,
+        _ => Default::default(),
+    };
+
     // Setup logging
-    let log_file = if let Some(cli::Commands::Install(ref args)) = cli.command {
+    let log_file = if let Some(path) = &cli.trace_file {
+        path.clone()
+    } else if let cli::Commands::Install(args) = &command {
         args.log.clone()
     } else {
         bakerst_dir.join("install.log")
@@ -27,13 +40,14 @@ async fn main() -> Result<()> {
     );
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| if cli.verbose { "debug".into() } else { "info".into() })
-        )
-        .with_writer(non_blocking)
-        .json()
+    // -v traces this crate, not every HTTP frame of its dependencies
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| if cli.verbose > 0 { "info,bakerst_install=debug".into() } else { "info".into() });
+    let stderr = (cli.verbose > 1).then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().json().with_writer(non_blocking))
+        .with(stderr)
         .init();
 
     // Setup panic hook for terminal cleanup
@@ -42,19 +56,32 @@ async fn main() -> Result<()> {
         let _ = crossterm::terminal::disable_raw_mode();
         let _ = crossterm::execute!(
             std::io::stderr(),
+            crossterm::event::DisableMouseCapture,
             crossterm::terminal::LeaveAlternateScreen
         );
         original_hook(panic_info);
     }));
 
-    // Extract command BEFORE matching to avoid partial move of cli
-    let command = cli.command.take()
-        .unwrap_or(cli::Commands::Install(cli::InstallArgs::default()));
-
-    match command {
-        cli::Commands::Install(args) => cmd_install::run(&cli, args).await,
-        cli::Commands::Status(args) => cmd_status::run(&cli, args).await,
-        cli::Commands::Update(args) => cmd_update::run(&cli, args).await,
-        cli::Commands::Uninstall(args) => cmd_uninstall::run(&cli, args).await,
+    // When run as a K8s Job, eviction arrives as SIGTERM
+    signals::install_handler();
+
+    // Install handles SIGTERM itself so it can record where it stopped
+    let result = match command {
+        cli::Commands::Install(args) => cmd_install::run(&cli, *args, inputs).await,
+        cli::Commands::Status(args) => signals::or_terminated(cmd_status::run(&cli, args)).await,
+        cli::Commands::Resources(args) => signals::or_terminated(cmd_resources::run(&cli, args)).await,
+        cli::Commands::Update(args) => signals::or_terminated(cmd_update::run(&cli, args)).await,
+        cli::Commands::Uninstall(args) => signals::or_terminated(cmd_uninstall::run(&cli, args)).await,
+    };
+
+    if let Err(ref e) = result {
+        if signals::exit_code(e) == signals::SIGTERM_EXIT_CODE {
+            tracing::warn!("exiting after SIGTERM");
+            eprintln!("\n{}", e);
+            // Flush the log before exiting; process::exit skips destructors
+            drop(_guard);
+            std::process::exit(signals::SIGTERM_EXIT_CODE);
+        }
     }
+    result
 }
@@ -1,7 +1,16 @@
 use serde::{Deserialize, Serialize};
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 
-const MAX_SUPPORTED_SCHEMA: u32 = 1;
+/// Newest manifest `schemaVersion` this installer understands.
+pub const SUPPORTED_SCHEMA_VERSION: u32 = 1;
+
+/// Base64 Ed25519 public key the release manifest is signed with, embedded
+/// at build time from `BAKERST_MANIFEST_PUBKEY` by the release pipeline. A
+/// build without it can't download manifests (see [`release_public_key`]).
+pub const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("BAKERST_MANIFEST_PUBKEY");
+
+/// Releases older than this get a note that a newer one may be available.
+pub const STALE_RELEASE_AGE: std::time::Duration = std::time::Duration::from_secs(180 * 24 * 60 * 60);
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -24,6 +33,63 @@ pub struct ManifestImage {
     pub tag: String,
     pub required: bool,
     pub architectures: Vec<String>,
+    /// Content digest (`sha256:...`) of the published image, checked
+    /// against the pulled image before deploying.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Extra resources this component needs when a feature is enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resource_hints: Vec<ResourceHint>,
+    /// Timing for this component's readiness and liveness probes, in place
+    /// of the template's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probes: Option<ProbeTuning>,
+}
+
+/// Probe timing overrides, in seconds. Unset fields keep the template value.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeTuning {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_delay_seconds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period_seconds: Option<u32>,
+}
+
+/// Container requests/limits (`cpu`, `memory`, ...) to raise a component to
+/// while `feature` is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResourceHint {
+    pub feature: String,
+    #[serde(default)]
+    pub requests: std::collections::BTreeMap<String, String>,
+    #[serde(default)]
+    pub limits: std::collections::BTreeMap<String, String>,
+}
+
+impl ManifestImage {
+    /// Full image reference (`image:tag`) as pulled by Docker.
+    pub fn reference(&self) -> String {
+        format!("{}:{}", self.image, self.tag)
+    }
+
+    /// The reference Docker pulls: [`reference`](Self::reference), through
+    /// `mirror` when `--registry-mirror` is set.
+    pub fn pull_reference(&self, mirror: Option<&str>) -> String {
+        match mirror {
+            Some(mirror) => crate::images::mirror_image(&self.reference(), mirror),
+            None => self.reference(),
+        }
+    }
+
+    /// The reference a workload runs: `image:tag@digest` when the manifest
+    /// pins a digest, so the cluster can't pull anything else.
+    pub fn pinned_reference(&self) -> String {
+        match &self.digest {
+            Some(digest) => format!("{}@{}", self.reference(), digest),
+            None => self.reference(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -35,12 +101,43 @@ pub struct ManifestInstaller {
 }
 
 impl Manifest {
+    /// How long before `now` the release was published, from `releaseDate`
+    /// (RFC 3339, or a bare `YYYY-MM-DD`). `None` if the date is missing,
+    /// unparseable or in the future.
+    pub fn release_age(&self, now: std::time::SystemTime) -> Option<std::time::Duration> {
+        use k8s_openapi::chrono::{DateTime, NaiveDate};
+        let date = self.release_date.as_deref()?.trim();
+        let released = match DateTime::parse_from_rfc3339(date) {
+            Ok(t) => t.timestamp(),
+            Err(_) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)?
+                .and_utc()
+                .timestamp(),
+        };
+        let now = now.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64;
+        u64::try_from(now - released).ok().map(std::time::Duration::from_secs)
+    }
+
+    /// An informational note when the release is older than
+    /// [`STALE_RELEASE_AGE`]. Old releases still install.
+    pub fn staleness_note(&self, now: std::time::SystemTime) -> Option<String> {
+        let age = self.release_age(now).filter(|age| *age > STALE_RELEASE_AGE)?;
+        let months = age.as_secs() / (30 * 24 * 60 * 60);
+        Some(format!(
+            "Release {} is {} months old; a newer one may be available.",
+            self.version, months
+        ))
+    }
+
     pub fn check_schema_version(&self, max_supported: u32) -> Result<()> {
         if self.schema_version > max_supported {
             bail!(
-                "Manifest schema version {} is newer than this installer supports (max: {}). \
+                "Manifest schema v{} is not supported by installer v{} (max: v{}). \
                  Please download the latest installer.",
-                self.schema_version, max_supported
+                self.schema_version,
+                env!("CARGO_PKG_VERSION"),
+                max_supported
             );
         }
         if self.schema_version == 0 {
@@ -49,13 +146,44 @@ impl Manifest {
         Ok(())
     }
 
+    /// Each image maps to one deployed component, so names must be unique;
+    /// otherwise a later entry would silently shadow an earlier one.
+    pub fn check_unique_images(&self) -> Result<()> {
+        let mut seen = std::collections::BTreeSet::new();
+        let mut duplicates = std::collections::BTreeSet::new();
+        for image in &self.images {
+            if !seen.insert(image.name.as_str()) {
+                duplicates.insert(image.name.as_str());
+            }
+        }
+        if !duplicates.is_empty() {
+            bail!(
+                "Invalid manifest: duplicate image names: {}",
+                duplicates.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+        Ok(())
+    }
+
     pub fn required_images(&self) -> impl Iterator<Item = &ManifestImage> {
         self.images.iter().filter(|i| i.required)
     }
 
     pub fn from_json(json: &str) -> Result<Self> {
+        // Check the version before the full parse: a newer schema may have
+        // fields this installer doesn't know are required, and "upgrade" is
+        // more useful than "missing field"
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Versioned {
+            #[serde(default)]
+            schema_version: u32,
+        }
+        let versioned: Versioned = serde_json::from_str(json).context("Invalid manifest")?;
+        Manifest { schema_version: versioned.schema_version, ..Default::default() }
+            .check_schema_version(SUPPORTED_SCHEMA_VERSION)?;
         let manifest: Self = serde_json::from_str(json)?;
-        manifest.check_schema_version(MAX_SUPPORTED_SCHEMA)?;
+        manifest.check_unique_images()?;
         Ok(manifest)
     }
 
@@ -63,4 +191,52 @@ impl Manifest {
         let content = std::fs::read_to_string(path)?;
         Self::from_json(&content)
     }
+
+    /// Parse a downloaded manifest only after checking its detached
+    /// signature, so nothing in an unsigned or tampered manifest is trusted.
+    pub fn from_signed_json(json: &str, signature: &str, public_key: &str) -> Result<Self> {
+        verify_signature(json.as_bytes(), signature, public_key)?;
+        Self::from_json(json)
+    }
+}
+
+/// The key to verify downloaded manifests with: the contents of
+/// `--manifest-pubkey` if given, else the key embedded in this build. With
+/// neither, manifests can't be verified, so this fails rather than letting
+/// an unchecked one through.
+pub fn release_public_key(path: Option<&std::path::Path>) -> Result<String> {
+    // An unset CI variable embeds an empty key
+    match (path, RELEASE_PUBLIC_KEY.filter(|key| !key.trim().is_empty())) {
+        (Some(path), _) => {
+            let key = std::fs::read_to_string(path)
+                .with_context(|| format!("Cannot read manifest public key {}", path.display()))?;
+            Ok(key.trim().to_string())
+        }
+        (None, Some(key)) => Ok(key.to_string()),
+        (None, None) => bail!(
+            "This installer was built without the release manifest's public key, so it can't verify a \
+             downloaded manifest. Pass --manifest-pubkey <file>, or --manifest <file> for a manifest you trust."
+        ),
+    }
+}
+
+/// Check a base64 Ed25519 `signature` of `data` against a base64 public key.
+pub fn verify_signature(data: &[u8], signature: &str, public_key: &str) -> Result<()> {
+    use base64::Engine;
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let key = engine
+        .decode(public_key.trim())
+        .context("Manifest public key is not valid base64")?;
+    let signature = engine
+        .decode(signature.trim())
+        .context("Manifest signature is not valid base64")?;
+    if UnparsedPublicKey::new(&ED25519, &key).verify(data, &signature).is_err() {
+        bail!(
+            "Release manifest signature verification failed. The release may have been tampered \
+             with; refusing to use it."
+        );
+    }
+    Ok(())
 }
@@ -0,0 +1,152 @@
+//! Install metrics — Prometheus text-format output for `--metrics-file`.
+//!
+//! Records how long each install phase took plus image and result counts,
+//! so a node-exporter textfile collector can scrape install runs.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Debug)]
+pub struct InstallMetrics {
+    started: Instant,
+    current: Option<(String, Instant)>,
+    pub phases: Vec<(String, Duration)>,
+    pub images_total: usize,
+    pub images_failed: usize,
+}
+
+impl Default for InstallMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstallMetrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            current: None,
+            phases: Vec::new(),
+            images_total: 0,
+            images_failed: 0,
+        }
+    }
+
+    /// Start timing `name`, closing whichever phase was running.
+    pub fn phase(&mut self, name: &str) {
+        self.finish_phase();
+        crate::output::emit(&crate::output::Event::Phase { phase: name });
+        self.current = Some((name.to_string(), Instant::now()));
+    }
+
+    /// Time since the install started.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Each phase's duration so far, the running one included.
+    pub fn phase_times(&self) -> Vec<(String, Duration)> {
+        let mut times = self.phases.clone();
+        if let Some((name, start)) = &self.current {
+            times.push((name.clone(), start.elapsed()));
+        }
+        times
+    }
+
+    /// Name of the running phase, if any.
+    pub fn current_phase(&self) -> Option<&str> {
+        self.current.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// Close the running phase, if any.
+    pub fn finish_phase(&mut self) {
+        if let Some((name, start)) = self.current.take() {
+            self.phases.push((name, start.elapsed()));
+        }
+    }
+
+    /// Render in the Prometheus text exposition format. `success` is the
+    /// outcome of the whole run.
+    pub fn render(&self, success: bool) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP bakerst_install_duration_seconds Time spent in each install phase.\n");
+        out.push_str("# TYPE bakerst_install_duration_seconds gauge\n");
+        for (phase, elapsed) in &self.phases {
+            out.push_str(&format!(
+                "bakerst_install_duration_seconds{{phase=\"{}\"}} {:.3}\n",
+                phase,
+                elapsed.as_secs_f64()
+            ));
+        }
+        out.push_str(&format!(
+            "bakerst_install_duration_seconds{{phase=\"total\"}} {:.3}\n",
+            self.started.elapsed().as_secs_f64()
+        ));
+
+        out.push_str("# HELP bakerst_install_images_total Images in the release manifest.\n");
+        out.push_str("# TYPE bakerst_install_images_total gauge\n");
+        out.push_str(&format!("bakerst_install_images_total {}\n", self.images_total));
+        out.push_str("# HELP bakerst_install_images_failed Images that failed to pull.\n");
+        out.push_str("# TYPE bakerst_install_images_failed gauge\n");
+        out.push_str(&format!("bakerst_install_images_failed {}\n", self.images_failed));
+
+        out.push_str("# HELP bakerst_install_result Outcome of the last install run.\n");
+        out.push_str("# TYPE bakerst_install_result gauge\n");
+        for result in ["success", "failure"] {
+            let value = u8::from((result == "success") == success);
+            out.push_str(&format!("bakerst_install_result{{result=\"{}\"}} {}\n", result, value));
+        }
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        out.push_str("# HELP bakerst_install_last_run_timestamp_seconds When the install finished.\n");
+        out.push_str("# TYPE bakerst_install_last_run_timestamp_seconds gauge\n");
+        out.push_str(&format!("bakerst_install_last_run_timestamp_seconds {}\n", now.as_secs()));
+        out
+    }
+
+    /// Write the metrics file. Goes through a temp file and rename so a
+    /// collector never scrapes a half-written file.
+    pub fn write(&self, path: &Path, success: bool) -> Result<()> {
+        let tmp = path.with_extension("prom.tmp");
+        std::fs::write(&tmp, self.render(success))
+            .with_context(|| format!("Failed to write metrics: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to write metrics: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phase_times_include_the_running_phase() {
+        let mut m = InstallMetrics::new();
+        m.phase("pull");
+        m.phase("apply");
+        let names: Vec<String> = m.phase_times().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["pull", "apply"]);
+        assert_eq!(m.phases.len(), 1);
+    }
+
+    #[test]
+    fn render_includes_phases_and_result() {
+        let mut m = InstallMetrics::new();
+        m.phase("pull");
+        m.phase("apply");
+        m.finish_phase();
+        m.images_total = 4;
+        m.images_failed = 1;
+
+        let text = m.render(false);
+        assert!(text.contains("bakerst_install_duration_seconds{phase=\"pull\"} "));
+        assert!(text.contains("bakerst_install_duration_seconds{phase=\"apply\"} "));
+        assert!(text.contains("bakerst_install_images_failed 1\n"));
+        assert!(text.contains("bakerst_install_result{result=\"success\"} 0\n"));
+        assert!(text.contains("bakerst_install_result{result=\"failure\"} 1\n"));
+    }
+}
@@ -0,0 +1,264 @@
+//! Machine-readable output for `--output json`.
+//!
+//! A non-interactive install streams one JSON object per line on stdout:
+//! progress [`Event`]s as phases start, images are pulled, resources are
+//! applied and deployments become ready, then a final object with a
+//! `result` key, either a [`Summary`] or an [`ErrorReport`] naming the
+//! phase it was in and the component at fault when one is known. Wrappers
+//! don't have to parse the human-readable text, which moves to stderr.
+
+use serde::Serialize;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::images::PullEvent;
+
+/// Where JSON lines go once [`enable_json`] has been called.
+static JSON_OUT: OnceLock<Mutex<Box<dyn Write + Send>>> = OnceLock::new();
+
+/// Switch to `--output json`. On Unix the original stdout is kept for the
+/// JSON lines and everything else printed to stdout goes to stderr instead;
+/// elsewhere the two stay interleaved on stdout.
+pub fn enable_json() -> anyhow::Result<()> {
+    std::io::stdout().flush()?;
+    #[cfg(unix)]
+    let out: Box<dyn Write + Send> = {
+        use std::os::fd::{FromRawFd, OwnedFd};
+        // SAFETY: dup takes no pointers; it only reads the descriptor table.
+        let fd = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if fd < 0 {
+            anyhow::bail!("Cannot keep stdout for --output json: {}", std::io::Error::last_os_error());
+        }
+        // SAFETY: dup just returned this descriptor, so it is open and
+        // nothing else owns it; it is closed when the OwnedFd drops.
+        let json_fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        // SAFETY: dup2 takes no pointers. It atomically closes fd 1 and
+        // reopens it on stderr; std's Stdout holds no descriptor of its own
+        // (it writes to fd 1 each time) and was flushed above, so no
+        // buffered output goes to the wrong stream.
+        if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } < 0 {
+            anyhow::bail!("Cannot redirect stdout for --output json: {}", std::io::Error::last_os_error());
+        }
+        Box::new(std::fs::File::from(json_fd))
+    };
+    #[cfg(not(unix))]
+    let out: Box<dyn Write + Send> = Box::new(std::io::stdout());
+    JSON_OUT.set(Mutex::new(out)).ok();
+    Ok(())
+}
+
+/// Write `value` as one JSON line, if `--output json` is on.
+pub fn emit(value: &impl Serialize) {
+    let Some(out) = JSON_OUT.get() else {
+        return;
+    };
+    if let Ok(line) = serde_json::to_string(value) {
+        let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(out, "{}", line).ok();
+        out.flush().ok();
+    }
+}
+
+/// A progress event.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// An install phase started (the names match `--metrics-file`).
+    Phase { phase: &'a str },
+    /// Preflight checks passed.
+    Preflight { context: Option<&'a str>, docker: bool },
+    /// An image pull finished or failed.
+    Pull(&'a PullEvent),
+    /// A resource was applied.
+    Deploy { component: &'a str, resource: &'a str },
+    /// Applying a resource failed transiently and is being retried.
+    Retry { resource: &'a str, attempt: u32, error: &'a str },
+    /// A deployment became ready, or stopped being ready.
+    Health { deployment: &'a str, ready: bool },
+}
+
+/// The final object printed when an install succeeds.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub result: &'static str,
+    pub version: String,
+    pub namespace: String,
+    pub agent_name: String,
+    pub url: String,
+    pub auth_token: TokenLocation,
+    /// False with `--no-wait`: the pods' health was not checked.
+    pub health_verified: bool,
+    pub timing: Timing,
+    /// `--smoke-test` requests against the running app, when run.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub smoke_test: Vec<crate::verify::Check>,
+}
+
+/// Where the install's time went, in seconds.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Timing {
+    #[serde(serialize_with = "as_seconds")]
+    pub total: Duration,
+    pub phases: Vec<Elapsed>,
+    /// How long after the health wait began each deployment became ready.
+    pub ready_after: Vec<Elapsed>,
+}
+
+/// One timed phase or deployment.
+#[derive(Debug, Clone, Serialize)]
+pub struct Elapsed {
+    pub name: String,
+    #[serde(serialize_with = "as_seconds")]
+    pub seconds: Duration,
+}
+
+impl Timing {
+    pub fn new(total: Duration, phases: Vec<(String, Duration)>, ready_after: Vec<(String, Duration)>) -> Self {
+        let elapsed = |v: Vec<(String, Duration)>| v.into_iter().map(|(name, seconds)| Elapsed { name, seconds }).collect();
+        Self {
+            total,
+            phases: elapsed(phases),
+            ready_after: elapsed(ready_after),
+        }
+    }
+}
+
+/// Durations in JSON output are seconds, to a tenth.
+pub(crate) fn as_seconds<S: serde::Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f64((d.as_secs_f64() * 10.0).round() / 10.0)
+}
+
+/// Where to find the install's auth token.
+#[derive(Debug, Serialize)]
+pub struct TokenLocation {
+    /// `--auth-token-out` file, if one was written.
+    pub file: Option<std::path::PathBuf>,
+    /// Kubernetes Secret holding it, under the `AUTH_TOKEN` key.
+    pub secret: Option<String>,
+}
+
+/// An error attributed to one component, so the JSON report can name it.
+/// Displays as its message alone, leaving the human-readable output as it
+/// was.
+#[derive(Debug)]
+pub struct ComponentError {
+    pub component: String,
+    pub message: String,
+}
+
+impl ComponentError {
+    pub fn new(component: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            component: component.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ComponentError {}
+
+/// The final object printed when an install fails.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    pub error: String,
+}
+
+impl ErrorReport {
+    /// Report `err`, raised during `phase`. The component is taken from the
+    /// innermost [`ComponentError`] in the error's chain, if any.
+    pub fn new(phase: Option<&str>, err: &anyhow::Error) -> Self {
+        Self {
+            result: "error",
+            phase: phase.map(String::from),
+            component: err
+                .chain()
+                .filter_map(|e| e.downcast_ref::<ComponentError>())
+                .last()
+                .map(|e| e.component.clone()),
+            error: format!("{:#}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn report_names_the_failing_component() {
+        let err = Err::<(), _>(anyhow::Error::new(ComponentError::new("brain", "Timed out waiting for deployments: brain (0/1)")))
+            .context("Pods did not become ready within 600s")
+            .unwrap_err();
+        let report = serde_json::to_value(ErrorReport::new(Some("wait"), &err)).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "result": "error",
+                "phase": "wait",
+                "component": "brain",
+                "error": "Pods did not become ready within 600s: Timed out waiting for deployments: brain (0/1)",
+            })
+        );
+    }
+
+    #[test]
+    fn timing_is_reported_in_tenths_of_a_second() {
+        let timing = Timing::new(
+            Duration::from_millis(95_432),
+            vec![("pull".into(), Duration::from_millis(41_049))],
+            vec![("brain".into(), Duration::from_millis(30_260))],
+        );
+        assert_eq!(
+            serde_json::to_value(&timing).unwrap(),
+            serde_json::json!({
+                "total": 95.4,
+                "phases": [{"name": "pull", "seconds": 41.0}],
+                "ready_after": [{"name": "brain", "seconds": 30.3}],
+            })
+        );
+    }
+
+    #[test]
+    fn events_are_tagged_and_pulls_keep_their_status() {
+        let pull = PullEvent::Completed {
+            index: 0,
+            image: "ghcr.io/org/bakerst-brain:0.6.0".into(),
+            elapsed: std::time::Duration::from_millis(1500),
+        };
+        assert_eq!(
+            serde_json::to_value(Event::Pull(&pull)).unwrap(),
+            serde_json::json!({
+                "event": "pull",
+                "status": "completed",
+                "index": 0,
+                "image": "ghcr.io/org/bakerst-brain:0.6.0",
+                "elapsed": 1.5,
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(Event::Deploy { component: "brain", resource: "Deployment/brain" }).unwrap(),
+            serde_json::json!({"event": "deploy", "component": "brain", "resource": "Deployment/brain"})
+        );
+    }
+
+    #[test]
+    fn report_omits_an_unknown_component() {
+        let report = ErrorReport::new(None, &anyhow::anyhow!("No Kubernetes contexts found"));
+        let json = serde_json::to_value(report).unwrap();
+        assert!(json.get("component").is_none());
+        assert!(json.get("phase").is_none());
+    }
+}
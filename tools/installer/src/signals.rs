@@ -0,0 +1,100 @@
+//! SIGTERM handling — lets the installer stop cleanly when run as a K8s Job
+//! that is evicted or deleted.
+//!
+//! On SIGTERM the running command is cancelled at its next await point so it
+//! can record state (e.g. `--metrics-file`), the log is flushed, and the
+//! process exits with [`SIGTERM_EXIT_CODE`]. A command stuck in a blocking
+//! prompt is force-exited after [`GRACE_PERIOD`].
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Exit code after SIGTERM (128 + 15, as a shell reports it), so a Job's
+/// pod failure policy can tell termination apart from an install failure.
+pub const SIGTERM_EXIT_CODE: i32 = 143;
+
+/// How long a cancelled command gets to wind down before a forced exit.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+static TERMINATED: OnceLock<watch::Receiver<bool>> = OnceLock::new();
+
+/// The command was stopped by SIGTERM.
+#[derive(Debug)]
+pub struct Terminated;
+
+impl std::fmt::Display for Terminated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Terminated by SIGTERM")
+    }
+}
+
+impl std::error::Error for Terminated {}
+
+/// Start listening for SIGTERM. Call once, from inside the runtime.
+pub fn install_handler() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let Ok(mut sigterm) = signal(SignalKind::terminate()) else {
+            return;
+        };
+        let (tx, rx) = watch::channel(false);
+        if TERMINATED.set(rx).is_err() {
+            return;
+        }
+        tokio::spawn(async move {
+            sigterm.recv().await;
+            tracing::warn!("received SIGTERM");
+            tx.send(true).ok();
+            tokio::time::sleep(GRACE_PERIOD).await;
+            eprintln!("\nTerminated by SIGTERM (did not stop within {}s)", GRACE_PERIOD.as_secs());
+            std::process::exit(SIGTERM_EXIT_CODE);
+        });
+    }
+}
+
+/// Resolves once SIGTERM has been received; never, if no handler is installed.
+pub async fn terminated() -> Terminated {
+    match TERMINATED.get() {
+        Some(rx) => {
+            let mut rx = rx.clone();
+            if rx.wait_for(|t| *t).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+            Terminated
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Run `fut`, failing with [`Terminated`] if SIGTERM arrives first.
+pub async fn or_terminated<T>(fut: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+    tokio::select! {
+        result = fut => result,
+        t = terminated() => Err(t.into()),
+    }
+}
+
+/// Exit code for a command's error: [`SIGTERM_EXIT_CODE`] when it was
+/// terminated, otherwise 1.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    if err.is::<Terminated>() {
+        SIGTERM_EXIT_CODE
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminated_gets_its_own_exit_code() {
+        assert_eq!(exit_code(&Terminated.into()), SIGTERM_EXIT_CODE);
+        assert_eq!(exit_code(&anyhow::anyhow!("boom")), 1);
+    }
+}
@@ -0,0 +1,211 @@
+//! Telemetry stack — Prometheus and Grafana in their own namespace.
+//!
+//! Deployed only with `--telemetry`. The templates are built into the
+//! installer rather than shipped with the release; the images come from the
+//! release manifest like every other component's, so they are pinned to its
+//! digests and follow `--registry-mirror`. Uninstall removes the namespace
+//! along with the install's.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::manifest::Manifest;
+use crate::{instance, k8s, templates};
+
+/// Suffix of the namespace the stack runs in (see [`namespace`]).
+pub const TELEMETRY_SUFFIX: &str = "telemetry";
+/// Deployments the stack creates, watched (without failing the install)
+/// after the install's own.
+pub const DEPLOYMENTS: &[&str] = &["prometheus", "grafana"];
+/// Manifest images (by name) the stack runs, pulled only when it's deployed.
+pub const IMAGES: &[&str] = &["prometheus", "grafana"];
+/// How long to wait for the stack once the install is ready (--telemetry-timeout).
+pub const DEFAULT_TELEMETRY_TIMEOUT: Duration = Duration::from_secs(120);
+/// Secret with Grafana's admin login. Generated on the first install and
+/// kept by later ones, since Grafana has no anonymous access.
+pub const GRAFANA_ADMIN_SECRET: &str = "grafana-admin";
+
+const STACK_TEMPLATE: &str = include_str!("telemetry/stack.yaml");
+const RBAC_TEMPLATE: &str = include_str!("telemetry/rbac.yaml");
+
+/// The rendered stack for one install.
+#[derive(Debug, Clone)]
+pub struct Telemetry {
+    pub namespace: String,
+    /// The install's namespace, whose pods are scraped.
+    pub install_namespace: String,
+    /// Objects applied into [`Telemetry::namespace`].
+    pub stack: Vec<serde_json::Value>,
+    /// The Role and RoleBinding applied into the install's namespace so
+    /// Prometheus can discover its pods.
+    pub rbac: Vec<serde_json::Value>,
+}
+
+/// Telemetry namespace for an install: `{install_namespace}-telemetry`,
+/// prefixed per named instance, so installs in different namespaces don't
+/// share (and repoint, or delete) one stack.
+pub fn namespace(install_namespace: &str, instance: Option<&str>) -> String {
+    instance::prefixed(instance, &format!("{}-{}", install_namespace, TELEMETRY_SUFFIX))
+}
+
+/// Render the stack to scrape `install_namespace`, running the images
+/// `manifest` pins for it, pulled through `mirror` when one is set.
+pub fn render(install_namespace: &str, instance: Option<&str>, manifest: &Manifest, mirror: Option<&str>) -> Result<Telemetry> {
+    let namespace = namespace(install_namespace, instance);
+    let image = |name: &str| {
+        manifest
+            .images
+            .iter()
+            .find(|i| i.name == name)
+            .map(|i| match mirror {
+                Some(mirror) => crate::images::mirror_image(&i.pinned_reference(), mirror),
+                None => i.pinned_reference(),
+            })
+            .with_context(|| format!("Release {} has no '{}' image for the telemetry stack", manifest.version, name))
+    };
+    let (prometheus, grafana) = (image("prometheus")?, image("grafana")?);
+    let vars = [
+        ("NAMESPACE", install_namespace),
+        ("TELEMETRY_NAMESPACE", namespace.as_str()),
+        ("PROMETHEUS_IMAGE", prometheus.as_str()),
+        ("GRAFANA_IMAGE", grafana.as_str()),
+        ("GRAFANA_ADMIN_SECRET", GRAFANA_ADMIN_SECRET),
+    ];
+    let mut stack = vec![grafana_admin_secret(&namespace)?];
+    stack.extend(k8s::parse_yaml_docs(&templates::render_strict(STACK_TEMPLATE, &vars)?)?);
+    Ok(Telemetry {
+        stack,
+        rbac: k8s::parse_yaml_docs(&templates::render_strict(RBAC_TEMPLATE, &vars)?)?,
+        namespace,
+        install_namespace: install_namespace.to_string(),
+    })
+}
+
+/// A new admin login for Grafana.
+fn grafana_admin_secret(namespace: &str) -> Result<serde_json::Value> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow::anyhow!("Failed to generate random bytes: {}", e))?;
+    let data = BTreeMap::from([
+        ("admin-user".to_string(), "admin".to_string()),
+        ("admin-password".to_string(), hex::encode(bytes)),
+    ]);
+    Ok(k8s::secret_object(namespace, GRAFANA_ADMIN_SECRET, &data))
+}
+
+fn is_admin_secret(resource: &serde_json::Value) -> bool {
+    resource["kind"] == "Secret" && resource["metadata"]["name"] == GRAFANA_ADMIN_SECRET
+}
+
+/// Every object in apply order (namespace first), for `--dry-run`.
+pub fn objects(telemetry: &Telemetry, version: &str) -> Result<Vec<serde_json::Value>> {
+    let mut objects = vec![k8s::render_namespace(None, &telemetry.namespace, Some(version))?];
+    objects.extend(telemetry.stack.iter().cloned());
+    for rbac in &telemetry.rbac {
+        let mut rbac = rbac.clone();
+        rbac["metadata"]["namespace"] = telemetry.install_namespace.as_str().into();
+        objects.push(rbac);
+    }
+    Ok(objects)
+}
+
+/// Create the telemetry namespace and apply the stack, printing each
+/// object as it is applied. An existing Grafana login is left as it is.
+/// Returns the `Kind/name` of each.
+pub async fn apply(
+    client: &kube::Client,
+    telemetry: &Telemetry,
+    version: &str,
+    params: &k8s::ApplyParams,
+) -> Result<Vec<String>> {
+    let namespace = k8s::render_namespace(None, &telemetry.namespace, Some(version))?;
+    k8s::apply_namespace(client, &namespace, params).await?;
+    let print = |_: &serde_json::Value, label: &str| {
+        println!("    \u{2713} {}", label);
+        crate::output::emit(&crate::output::Event::Deploy { component: "telemetry", resource: label });
+    };
+    let existing_login = k8s::read_secret(client, &telemetry.namespace, GRAFANA_ADMIN_SECRET).await?.is_some();
+    let stack: Vec<serde_json::Value> =
+        telemetry.stack.iter().filter(|r| !(existing_login && is_admin_secret(r))).cloned().collect();
+    let mut applied = k8s::apply_resources(client, &telemetry.namespace, &stack, params, print).await?;
+    applied.extend(k8s::apply_resources(client, &telemetry.install_namespace, &telemetry.rbac, params, print).await?);
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::ManifestImage;
+
+    fn manifest() -> Manifest {
+        let image = |name: &str, image: &str, digest: Option<&str>| ManifestImage {
+            name: name.into(),
+            image: image.into(),
+            tag: "1.0".into(),
+            digest: digest.map(String::from),
+            ..Default::default()
+        };
+        Manifest {
+            version: "0.6.0".into(),
+            images: vec![
+                image("prometheus", "mirror.local/prom/prometheus", Some("sha256:abc")),
+                image("grafana", "mirror.local/grafana/grafana", None),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn container(telemetry: &Telemetry, deployment: &str) -> serde_json::Value {
+        let deployment = telemetry.stack.iter().find(|r| r["metadata"]["name"] == deployment && r["kind"] == "Deployment");
+        deployment.unwrap()["spec"]["template"]["spec"]["containers"][0].clone()
+    }
+
+    #[test]
+    fn stack_runs_the_manifest_images_pinned_to_their_digests() {
+        let telemetry = render("team-a", None, &manifest(), None).unwrap();
+        assert_eq!(container(&telemetry, "prometheus")["image"], "mirror.local/prom/prometheus:1.0@sha256:abc");
+        assert_eq!(container(&telemetry, "grafana")["image"], "mirror.local/grafana/grafana:1.0");
+
+        let mirrored = render("team-a", None, &manifest(), Some("harbor.internal/baker")).unwrap();
+        assert_eq!(container(&mirrored, "prometheus")["image"], "harbor.internal/baker/prometheus:1.0@sha256:abc");
+
+        let mut without = manifest();
+        without.images.retain(|i| i.name != "grafana");
+        let err = render("team-a", None, &without, None).unwrap_err();
+        assert!(err.to_string().contains("has no 'grafana' image"), "{}", err);
+    }
+
+    #[test]
+    fn grafana_requires_the_generated_admin_login() {
+        let telemetry = render("team-a", None, &manifest(), None).unwrap();
+        let env = container(&telemetry, "grafana")["env"].clone();
+        let var = |name: &str| env.as_array().unwrap().iter().find(|e| e["name"] == name).cloned().unwrap();
+        assert_eq!(var("GF_AUTH_ANONYMOUS_ENABLED")["value"], "false");
+        assert_eq!(var("GF_SECURITY_ADMIN_PASSWORD")["valueFrom"]["secretKeyRef"]["name"], GRAFANA_ADMIN_SECRET);
+        let secret = telemetry.stack.iter().find(|r| is_admin_secret(r)).unwrap();
+        assert_eq!(secret["data"]["admin-password"].as_str().unwrap().len(), 44);
+    }
+
+    #[test]
+    fn stack_scrapes_the_install_namespace_from_its_own() {
+        let telemetry = render("team-a", Some("dev"), &manifest(), None).unwrap();
+        assert_eq!(telemetry.namespace, "dev-team-a-telemetry");
+        assert_eq!(namespace("bakerst", None), "bakerst-telemetry");
+        assert_ne!(namespace("team-a", None), namespace("team-b", None));
+        let deployments: Vec<&str> = telemetry
+            .stack
+            .iter()
+            .filter(|r| r["kind"] == "Deployment")
+            .filter_map(|r| r["metadata"]["name"].as_str())
+            .collect();
+        assert_eq!(deployments, DEPLOYMENTS);
+        let binding = telemetry.rbac.iter().find(|r| r["kind"] == "RoleBinding").unwrap();
+        assert_eq!(binding["metadata"]["name"], "dev-team-a-telemetry");
+        assert_eq!(binding["subjects"][0]["namespace"], "dev-team-a-telemetry");
+
+        let objects = objects(&telemetry, "0.6.0").unwrap();
+        assert_eq!(objects[0]["kind"], "Namespace");
+        assert_eq!(objects.last().unwrap()["metadata"]["namespace"], "team-a");
+    }
+}
@@ -0,0 +1,202 @@
+//! The installer's own YAML templates (the Ingress, the telemetry stack).
+//!
+//! `{{KEY}}` is replaced with the value of `KEY`, and
+//! `{{#if KEY}}...{{else}}...{{/if}}` keeps one branch or the other, so a
+//! template can carry an optional env var or container itself instead of
+//! the Rust that fills it in. Blocks don't nest. A tag alone on its line
+//! takes the line with it, keeping the YAML's indentation intact.
+//! [`render_strict`] is what the deploy path uses, so a value left out is
+//! caught where it's missing.
+//!
+//! The release's own manifests are rendered strictly too, with the `--set`
+//! values: an escape hatch for knobs that have no flag of their own.
+
+use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+
+/// Render `template` with `vars`. A key missing from `vars` is false in an
+/// `{{#if}}` and left as written in a `{{KEY}}`.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> Result<String> {
+    let lookup = |key: &str| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+    let mut out = String::with_capacity(template.len());
+    // In an {{#if}}: whether its condition held, and whether past {{else}}
+    let mut block: Option<(bool, bool)> = None;
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + len + 4;
+        let tag = rest[start + 2..end - 2].trim();
+        let emitting = block.is_none_or(|(condition, in_else)| condition != in_else);
+        let is_block = tag.starts_with("#if ") || tag == "else" || tag == "/if";
+
+        // A block tag alone on its line removes the whole line
+        let (mut before, mut after) = (&rest[..start], &rest[end..]);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = after.find('\n').filter(|&i| after[..i].trim().is_empty());
+        if let (true, true, Some(i)) = (is_block, before[line_start..].trim().is_empty(), line_end) {
+            before = &before[..line_start];
+            after = &after[i + 1..];
+        }
+        if emitting {
+            out.push_str(before);
+        }
+
+        if let Some(key) = tag.strip_prefix("#if ") {
+            if block.is_some() {
+                bail!("template: nested {{{{#if {}}}}} is not supported", key.trim());
+            }
+            block = Some((is_truthy(lookup(key.trim())), false));
+        } else if tag == "else" {
+            match &mut block {
+                Some((_, in_else)) if !*in_else => *in_else = true,
+                _ => bail!("template: {{{{else}}}} outside an {{{{#if}}}} block"),
+            }
+        } else if tag == "/if" {
+            if block.take().is_none() {
+                bail!("template: {{{{/if}}}} without an {{{{#if}}}}");
+            }
+        } else if emitting {
+            out.push_str(lookup(tag).unwrap_or(&rest[start..end]));
+        }
+        rest = after;
+    }
+
+    if block.is_some() {
+        bail!("template: {{{{#if}}}} block is never closed with {{{{/if}}}}");
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// [`render`], failing if any `{{KEY}}` is left unresolved; a missing
+/// value would otherwise surface later as a confusing YAML or API error.
+pub fn render_strict(template: &str, vars: &[(&str, &str)]) -> Result<String> {
+    let out = render(template, vars)?;
+    match unresolved(&out).as_slice() {
+        [] => Ok(out),
+        [key] => bail!("unresolved template variable: {}", key),
+        keys => bail!("unresolved template variables: {}", keys.join(", ")),
+    }
+}
+
+/// The `{{KEY}}` placeholders left in `text`, each once, in order.
+pub fn unresolved(text: &str) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for key in tags(text).filter(|tag| is_key(tag)) {
+        if !keys.iter().any(|k| k == key) {
+            keys.push(key.to_string());
+        }
+    }
+    keys
+}
+
+/// Every key `template` refers to, in a `{{KEY}}` or an `{{#if KEY}}`.
+pub fn keys(template: &str) -> BTreeSet<String> {
+    tags(template)
+        .map(|tag| tag.strip_prefix("#if ").map_or(tag, str::trim))
+        .filter(|tag| is_key(tag))
+        .map(String::from)
+        .collect()
+}
+
+/// Parse a `--set KEY=VALUE` argument. The key is written as in `{{KEY}}`.
+pub fn parse_var(arg: &str) -> Result<(String, String)> {
+    let Some((key, value)) = arg.split_once('=') else {
+        bail!("expected KEY=VALUE, got '{}'", arg);
+    };
+    if !is_key(key) {
+        bail!("invalid template variable '{}': use letters, digits and '_', not starting with a digit", key);
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// The trimmed contents of each `{{...}}` in `text`.
+fn tags(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let start = rest.find("{{")?;
+        let len = rest[start + 2..].find("}}")?;
+        let tag = rest[start + 2..start + 2 + len].trim();
+        rest = &rest[start + len + 4..];
+        Some(tag)
+    })
+}
+
+/// Whether `tag` is a variable name rather than a block tag or some other
+/// brace syntax (e.g. Go templates in a dashboard).
+fn is_key(tag: &str) -> bool {
+    tag != "else"
+        && tag.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// An `{{#if}}` holds for a set, non-empty value other than "false".
+fn is_truthy(value: Option<&str>) -> bool {
+    value.is_some_and(|v| !v.is_empty() && v != "false")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "env:\n  - name: HOST\n    value: \"{{HOST}}\"\n  {{#if VOICE}}\n  - name: VOICE_URL\n    value: http://voice:8080\n  {{else}}\n  - name: VOICE_URL\n    value: \"\"\n  {{/if}}\n";
+
+    #[test]
+    fn if_else_keeps_one_branch_and_its_lines() {
+        let on = render(TEMPLATE, &[("HOST", "bakerst.example.com"), ("VOICE", "true")]).unwrap();
+        assert_eq!(
+            on,
+            "env:\n  - name: HOST\n    value: \"bakerst.example.com\"\n  - name: VOICE_URL\n    value: http://voice:8080\n"
+        );
+        let off = render(TEMPLATE, &[("HOST", "bakerst.example.com"), ("VOICE", "false")]).unwrap();
+        assert!(off.ends_with("  - name: VOICE_URL\n    value: \"\"\n"), "{}", off);
+
+        // Inline blocks work too
+        assert_eq!(render("a{{#if X}}b{{/if}}c", &[("X", "1")]).unwrap(), "abc");
+    }
+
+    #[test]
+    fn unknown_keys_are_falsy_and_left_in_place() {
+        let out = render(TEMPLATE, &[]).unwrap();
+        assert!(out.contains("value: \"{{HOST}}\""));
+        assert!(out.contains("value: \"\"") && !out.contains("voice:8080"));
+        assert_eq!(render("{{#if EMPTY}}x{{/if}}", &[("EMPTY", "")]).unwrap(), "");
+    }
+
+    #[test]
+    fn strict_rendering_names_the_unresolved_variables() {
+        let template = "image: {{IMAGE_BRAIN}}\nhost: {{HOST}}\nagain: {{ IMAGE_BRAIN }}\n{{#if MISSING}}{{SKIPPED}}{{/if}}";
+        assert_eq!(unresolved(template), ["IMAGE_BRAIN", "HOST", "SKIPPED"]);
+
+        let err = render_strict(template, &[("HOST", "bakerst.local")]).unwrap_err();
+        assert_eq!(err.to_string(), "unresolved template variable: IMAGE_BRAIN");
+        let err = render_strict(template, &[]).unwrap_err();
+        assert_eq!(err.to_string(), "unresolved template variables: IMAGE_BRAIN, HOST");
+        assert!(render_strict(template, &[("HOST", "h"), ("IMAGE_BRAIN", "b")]).is_ok());
+        // Other brace syntax isn't a placeholder
+        assert!(unresolved("format: '{{.Server.Version}}'").is_empty());
+    }
+
+    #[test]
+    fn set_arguments_and_template_keys() {
+        assert_eq!(parse_var("DOOR_POLICY=closed").unwrap(), ("DOOR_POLICY".to_string(), "closed".to_string()));
+        assert_eq!(parse_var("URL=http://x/?a=b").unwrap().1, "http://x/?a=b");
+        assert!(parse_var("DOOR_POLICY").is_err());
+        assert!(parse_var("2FA=on").is_err());
+        assert!(parse_var("door-policy=closed").is_err());
+
+        let keys = keys(TEMPLATE);
+        assert_eq!(keys.into_iter().collect::<Vec<_>>(), ["HOST", "VOICE"]);
+    }
+
+    #[test]
+    fn malformed_blocks_are_errors() {
+        assert!(render("{{#if A}}{{#if B}}{{/if}}{{/if}}", &[]).is_err());
+        assert!(render("{{#if A}}never closed", &[]).is_err());
+        assert!(render("{{/if}}", &[]).is_err());
+        assert!(render("{{#if A}}{{else}}{{else}}{{/if}}", &[]).is_err());
+    }
+}
@@ -2,25 +2,85 @@
 //!
 //! Three-zone layout: header (title), main (phases + details), status bar.
 //! Shows completed phases with a checkmark, current phase with a spinner,
-//! and pending phases dimmed.
+//! and pending phases dimmed. Colors come from the `--theme` [`Palette`];
+//! the glyphs alone tell the states apart, for `mono`.
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Terminal,
 };
 use std::io::stdout;
 use std::time::Instant;
 
-use crate::app::{App, Phase};
+use crate::app::{self, App, Phase};
+use crate::cli::Theme;
+
+/// The colors the display is drawn in, picked by `--theme`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// Title, help keys and suggested commands
+    pub accent: Color,
+    /// Completed phases, ready pods
+    pub ok: Color,
+    /// The active phase, pods still starting
+    pub pending: Color,
+    /// Failures
+    pub error: Color,
+    /// Secondary text: pending phases, log entries, the status bar
+    pub dim: Color,
+}
+
+impl Palette {
+    /// The palette for `theme`; `no_color` (NO_COLOR is set) always means
+    /// [`Theme::Mono`].
+    pub fn new(theme: Theme, no_color: bool) -> Self {
+        match (theme, no_color) {
+            (Theme::Mono, _) | (_, true) => Self {
+                accent: Color::Reset,
+                ok: Color::Reset,
+                pending: Color::Reset,
+                error: Color::Reset,
+                dim: Color::Reset,
+            },
+            (Theme::Dark, false) => Self {
+                accent: Color::Cyan,
+                ok: Color::Green,
+                pending: Color::Yellow,
+                error: Color::Red,
+                dim: Color::DarkGray,
+            },
+            // Yellow and the grays wash out on a light background
+            (Theme::Light, false) => Self {
+                accent: Color::Blue,
+                ok: Color::Green,
+                pending: Color::Magenta,
+                error: Color::Red,
+                dim: Color::Reset,
+            },
+        }
+    }
+
+    /// The palette for `theme`, honoring NO_COLOR.
+    pub fn from_env(theme: Theme) -> Self {
+        Self::new(theme, std::env::var_os("NO_COLOR").is_some())
+    }
+}
+
+/// The restart prompt's buttons.
+const CONFIRM_YES: &str = "[ Yes ]";
+const CONFIRM_NO: &str = "[ No ]";
 
 const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
@@ -39,39 +99,100 @@ const PHASE_LABELS: &[(u8, &str)] = &[
 pub struct Tui {
     terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
     start: Instant,
+    palette: Palette,
+    /// The main pane as last drawn: first line shown, the furthest it can
+    /// scroll, and its height. Scrolling keys move relative to these.
+    top: usize,
+    max_top: usize,
+    page: usize,
+    /// Where clicks land, as last drawn: the main pane, the line of the
+    /// first pod in it, and the Yes/No buttons of a restart prompt.
+    main_area: Rect,
+    first_pod_line: Option<usize>,
+    confirm_buttons: Option<(Rect, Rect)>,
 }
 
+/// Lines the mouse wheel scrolls the main pane by.
+const WHEEL_LINES: usize = 3;
+
 impl Tui {
-    pub fn new() -> anyhow::Result<Self> {
+    pub fn new(theme: Theme) -> anyhow::Result<Self> {
         enable_raw_mode()?;
         stdout().execute(EnterAlternateScreen)?;
+        stdout().execute(EnableMouseCapture)?;
         let backend = CrosstermBackend::new(stdout());
         let terminal = Terminal::new(backend)?;
         Ok(Self {
             terminal,
             start: Instant::now(),
+            palette: Palette::from_env(theme),
+            top: 0,
+            max_top: 0,
+            page: 0,
+            main_area: Rect::default(),
+            first_pod_line: None,
+            confirm_buttons: None,
         })
     }
 
     pub fn restore(&mut self) -> anyhow::Result<()> {
         disable_raw_mode()?;
+        stdout().execute(DisableMouseCapture)?;
         stdout().execute(LeaveAlternateScreen)?;
         Ok(())
     }
 
-    /// Poll for a key event with a short timeout (for non-blocking TUI loop).
-    /// Returns true if the user pressed 'q' to quit.
-    pub fn handle_input(&self, app: &App) -> anyhow::Result<bool> {
+    /// Poll for a key or mouse event with a short timeout (for non-blocking
+    /// TUI loop). Returns true if the user pressed 'q' or Ctrl+C to quit.
+    pub fn handle_input(&self, app: &mut App) -> anyhow::Result<bool> {
         if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                self.handle_mouse(app, mouse);
+                return Ok(false);
+            }
+            if let Event::Key(key) = event {
                 if key.kind != KeyEventKind::Press {
                     return Ok(false);
                 }
+                if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(true);
+                }
+                // A pending restart confirmation takes the next key
+                if app.confirm_restart.is_some() {
+                    app.answer_restart(matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')));
+                    return Ok(false);
+                }
+                // The help overlay swallows every key but its own and 'q'
+                if app.show_help {
+                    match key.code {
+                        KeyCode::Char('?') | KeyCode::Esc => app.toggle_help(),
+                        KeyCode::Char('q') => return Ok(true),
+                        _ => {}
+                    }
+                    return Ok(false);
+                }
                 match key.code {
                     KeyCode::Char('q') => return Ok(true),
-                    KeyCode::Char('c') => {
-                        if let Some(ref token) = app.auth_token {
-                            copy_to_clipboard(token);
+                    KeyCode::Char('?') => app.toggle_help(),
+                    // Up/Down pick a pod when there are any, else scroll
+                    KeyCode::Up if app.pods.is_empty() => app.scroll_to(self.top.saturating_sub(1)),
+                    KeyCode::Down if app.pods.is_empty() => app.scroll_to((self.top + 1).min(self.max_top)),
+                    KeyCode::Up => app.select_prev_pod(),
+                    KeyCode::Down => app.select_next_pod(),
+                    KeyCode::PageUp => app.scroll_to(self.top.saturating_sub(self.page.max(1))),
+                    KeyCode::PageDown => app.scroll_to((self.top + self.page.max(1)).min(self.max_top)),
+                    KeyCode::Char('R') => app.request_restart(),
+                    KeyCode::Char(' ') => app.toggle_step_mode(),
+                    KeyCode::Enter => app.resume(),
+                    KeyCode::Char('c') | KeyCode::Char('t') => {
+                        if let Some(token) = app.auth_token.clone() {
+                            copy_value(app, "Auth token", &token);
+                        }
+                    }
+                    KeyCode::Char('u') => {
+                        if let Some(url) = app.access_url.clone() {
+                            copy_value(app, "URL", &url);
                         }
                     }
                     _ => {}
@@ -81,10 +202,41 @@ impl Tui {
         Ok(false)
     }
 
+    /// Clicks do what the keys do: a pod row selects that pod (like the
+    /// arrows), Yes/No answer a restart prompt, and anywhere closes the
+    /// help overlay. The wheel scrolls the main pane.
+    fn handle_mouse(&self, app: &mut App, mouse: MouseEvent) {
+        let at = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if app.show_help {
+                    app.toggle_help();
+                } else if let Some((yes, no)) = self.confirm_buttons {
+                    if yes.contains(at) {
+                        app.answer_restart(true);
+                    } else if no.contains(at) {
+                        app.answer_restart(false);
+                    }
+                } else if let (true, Some(first)) = (self.main_area.contains(at), self.first_pod_line) {
+                    let line = self.top + (mouse.row - self.main_area.y) as usize;
+                    if let Some(index) = line.checked_sub(first) {
+                        app.select_pod(index);
+                    }
+                }
+            }
+            MouseEventKind::ScrollUp => app.scroll_to(self.top.saturating_sub(WHEEL_LINES)),
+            MouseEventKind::ScrollDown => app.scroll_to((self.top + WHEEL_LINES).min(self.max_top)),
+            _ => {}
+        }
+    }
+
     pub fn draw(&mut self, app: &App) -> anyhow::Result<()> {
         let elapsed = self.start.elapsed();
         let spinner_idx = (elapsed.as_millis() / 80) as usize % SPINNER_FRAMES.len();
         let spinner = SPINNER_FRAMES[spinner_idx];
+        let palette = self.palette;
+        let (mut top, mut max_top, mut page) = (0, 0, 0);
+        let (mut main_area, mut first_pod_line, mut confirm_buttons) = (Rect::default(), None, None);
 
         self.terminal.draw(|frame| {
             let chunks = Layout::default()
@@ -101,12 +253,12 @@ impl Tui {
                 Span::styled(
                     " Baker Street Installer ",
                     Style::default()
-                        .fg(Color::Cyan)
+                        .fg(palette.accent)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
                     format!("v{}", env!("CARGO_PKG_VERSION")),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(palette.dim),
                 ),
             ]))
             .block(Block::default().borders(Borders::BOTTOM));
@@ -120,33 +272,40 @@ impl Tui {
             let mut lines: Vec<Line> = Vec::new();
             lines.push(Line::from(""));
 
+            let verify_idx = phase_index(&Phase::Verify);
             for &(idx, label) in PHASE_LABELS {
-                let line = if is_failed && idx == current_idx {
+                let line = if app.health_skipped && idx == verify_idx && idx < current_idx {
+                    // Bypassed by --no-wait: neither passed nor failed
+                    Line::from(vec![
+                        Span::styled("  - ", Style::default().fg(palette.dim)),
+                        Span::styled(format!("{} (skipped)", label), Style::default().fg(palette.dim)),
+                    ])
+                } else if is_failed && idx == current_idx {
                     // Failed phase
                     Line::from(vec![
-                        Span::styled("  \u{2717} ", Style::default().fg(Color::Red)),
-                        Span::styled(label, Style::default().fg(Color::Red)),
+                        Span::styled("  \u{2717} ", Style::default().fg(palette.error)),
+                        Span::styled(label, Style::default().fg(palette.error)),
                     ])
                 } else if idx < current_idx || (is_complete && idx <= current_idx) {
                     // Completed phase
                     Line::from(vec![
                         Span::styled(
                             "  \u{2713} ",
-                            Style::default().fg(Color::Green),
+                            Style::default().fg(palette.ok),
                         ),
-                        Span::styled(label, Style::default().fg(Color::Green)),
+                        Span::styled(label, Style::default().fg(palette.ok)),
                     ])
                 } else if idx == current_idx && !is_failed {
                     // Active phase with spinner
                     Line::from(vec![
                         Span::styled(
                             format!("  {} ", spinner),
-                            Style::default().fg(Color::Yellow),
+                            Style::default().fg(palette.pending),
                         ),
                         Span::styled(
                             label,
                             Style::default()
-                                .fg(Color::Yellow)
+                                .fg(palette.pending)
                                 .add_modifier(Modifier::BOLD),
                         ),
                     ])
@@ -154,7 +313,7 @@ impl Tui {
                     // Pending phase
                     Line::from(vec![
                         Span::styled("    ", Style::default()),
-                        Span::styled(label, Style::default().fg(Color::DarkGray)),
+                        Span::styled(label, Style::default().fg(palette.dim)),
                     ])
                 };
                 lines.push(line);
@@ -166,8 +325,106 @@ impl Tui {
             for entry in &app.log_entries[detail_start..] {
                 lines.push(Line::from(Span::styled(
                     format!("  {}", entry),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(palette.dim),
+                )));
+            }
+
+            // Component summary above the pod detail
+            if !app.pods.is_empty() {
+                lines.push(Line::from(""));
+                for component in crate::health::component_summary(&app.pods) {
+                    let color = if component.is_ready() {
+                        palette.ok
+                    } else if component.problem.is_some() {
+                        palette.error
+                    } else {
+                        palette.pending
+                    };
+                    let mut spans = vec![Span::styled(
+                        format!("  {}", component),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    )];
+                    if let Some(note) = app.recovery_note(&component.name) {
+                        spans.push(Span::styled(format!(" ({})", note), Style::default().fg(palette.dim)));
+                    }
+                    lines.push(Line::from(spans));
+                }
+            }
+
+            // Pod health (during apply/verify): select with arrows, R restarts
+            let mut selected_line = None;
+            if !app.pods.is_empty() {
+                lines.push(Line::from(""));
+                first_pod_line = Some(lines.len());
+                for (i, pod) in app.pods.iter().enumerate() {
+                    let (mark, color) = if pod.ready {
+                        ("\u{2713}", palette.ok)
+                    } else if pod.error.is_some() {
+                        ("\u{2717}", palette.error)
+                    } else {
+                        ("\u{2026}", palette.pending)
+                    };
+                    let mut style = Style::default().fg(color);
+                    if i == app.selected_pod {
+                        style = style.add_modifier(Modifier::REVERSED);
+                        selected_line = Some(lines.len());
+                    }
+                    let detail = pod.error.as_deref().unwrap_or(&pod.phase);
+                    let usage = pod.usage.map(|u| format!(", {}", u)).unwrap_or_default();
+                    lines.push(Line::from(Span::styled(
+                        format!("  {} {} ({}, {} restarts{})", mark, pod.name, detail, pod.restarts, usage),
+                        style,
+                    )));
+                }
+            }
+
+            // Once health has failed, what to try for the highlighted pod
+            if let (true, Some(pod)) = (is_failed, app.pods.get(app.selected_pod).filter(|p| !p.ready)) {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("  Likely causes for {}:", pod.name),
+                    Style::default().add_modifier(Modifier::BOLD),
                 )));
+                for (i, suggestion) in crate::diagnose::diagnose(pod, &app.namespace).iter().enumerate() {
+                    lines.push(Line::from(format!("  {}. {}: {}", i + 1, suggestion.cause, suggestion.fix)));
+                    if let Some(command) = &suggestion.command {
+                        lines.push(Line::from(Span::styled(
+                            format!("       $ {}", command),
+                            Style::default().fg(palette.accent),
+                        )));
+                    }
+                }
+            }
+
+            // Where to reach the install, with the token masked
+            if is_complete {
+                lines.push(Line::from(""));
+                if let Some(url) = &app.access_url {
+                    lines.push(Line::from(format!("  URL:        {}", url)));
+                }
+                if app.auth_token.is_some() {
+                    let saved = match &app.auth_token_path {
+                        Some(path) => format!(" (saved to {})", path.display()),
+                        None => String::new(),
+                    };
+                    lines.push(Line::from(format!("  Auth token: ********{}", saved)));
+                }
+                if let Some(timing) = &app.timing {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        format!("  Timing ({:.1}s total)", timing.total.as_secs_f64()),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )));
+                    for phase in &timing.phases {
+                        lines.push(Line::from(format!("    {:<16} {:>7.1}s", phase.name, phase.seconds.as_secs_f64())));
+                    }
+                    for deployment in &timing.ready_after {
+                        lines.push(Line::from(Span::styled(
+                            format!("    {:<16} {:>7.1}s to ready", deployment.name, deployment.seconds.as_secs_f64()),
+                            Style::default().fg(palette.dim),
+                        )));
+                    }
+                }
             }
 
             // Show errors if any
@@ -176,38 +433,121 @@ impl Tui {
                 for err in &app.errors {
                     lines.push(Line::from(Span::styled(
                         format!("  ERROR: {}", err),
-                        Style::default().fg(Color::Red),
+                        Style::default().fg(palette.error),
                     )));
                 }
             }
 
-            let main = Paragraph::new(lines).block(
-                Block::default()
-                    .borders(Borders::NONE),
-            );
-            frame.render_widget(main, chunks[1]);
+            // Live logs of the highlighted pod under the pod list; the
+            // newest lines that fit are shown, so the pane scrolls
+            let logs = app.selected_pod_logs();
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(6), Constraint::Length(if logs.is_some() { 12 } else { 0 })])
+                .split(chunks[1]);
+
+            // Everything above scrolls once it outgrows the pane, keeping
+            // the highlighted pod in view unless scrolled away by hand
+            let total = lines.len();
+            page = areas[0].height as usize;
+            max_top = total.saturating_sub(page);
+            let keep = selected_line.filter(|_| app.follow_selection);
+            top = app::scroll_top(total, page, app.scroll, keep);
+            main_area = areas[0];
+            let main = Paragraph::new(lines)
+                .block(Block::default().borders(Borders::NONE))
+                .scroll((top as u16, 0));
+            frame.render_widget(main, areas[0]);
+            if total > page {
+                let mut state = ScrollbarState::new(max_top).position(top);
+                frame.render_stateful_widget(Scrollbar::new(ScrollbarOrientation::VerticalRight), areas[0], &mut state);
+            }
+
+            if let Some((pod, log)) = logs {
+                let visible = areas[1].height.saturating_sub(2) as usize;
+                let log_lines: Vec<Line> = log
+                    .iter()
+                    .skip(log.len().saturating_sub(visible))
+                    .map(|l| Line::from(Span::raw(l.as_str())))
+                    .collect();
+                let pane = Paragraph::new(log_lines).block(
+                    Block::default()
+                        .borders(Borders::TOP)
+                        .title(format!(" Logs: {} ", pod)),
+                );
+                frame.render_widget(pane, areas[1]);
+            }
 
             // --- Status bar ---
             let elapsed_secs = elapsed.as_secs();
             let elapsed_str = format!("{}:{:02}", elapsed_secs / 60, elapsed_secs % 60);
 
-            let status_text = if let Some(ref msg) = app.status_message {
+            let status_text = if let Some(ref pod) = app.confirm_restart {
+                format!("Restart pod {}? (y/n)  ", pod)
+            } else if let Some(flash) = app.current_flash() {
+                flash.to_string()
+            } else if let Some(ref msg) = app.status_message {
                 msg.clone()
+            } else if app.show_help {
+                "? or Esc: close help".into()
+            } else if app.paused {
+                "Paused — press Enter to continue, Space to stop stepping".into()
             } else if is_complete {
-                "Installation complete! Press 'c' to copy auth token, 'q' to exit".into()
+                "Installation complete! 'u': copy URL  't': copy auth token  'q': exit".into()
             } else if is_failed {
                 "Installation failed. Press 'q' to exit".into()
+            } else if app.step_mode {
+                format!("Elapsed: {}  |  step mode  |  Space: run through  ?: help  q: quit", elapsed_str)
+            } else if !app.pods.is_empty() {
+                format!("Elapsed: {}  |  \u{2191}\u{2193}: select pod  PgUp/PgDn: scroll  R: restart pod  ?: help  q: quit", elapsed_str)
             } else {
-                format!("Elapsed: {}  |  q: quit", elapsed_str)
+                format!("Elapsed: {}  |  Space: step mode  ?: help  q: quit", elapsed_str)
             };
 
-            let status_bar = Paragraph::new(Line::from(Span::styled(
-                format!(" {}", status_text),
-                Style::default().fg(Color::DarkGray),
-            )))
-            .block(Block::default().borders(Borders::TOP));
+            let mut status_spans = vec![Span::styled(format!(" {}", status_text), Style::default().fg(palette.dim))];
+            if app.confirm_restart.is_some() {
+                // Clickable buttons after the prompt, for mouse users
+                let row = chunks[2].y + 1;
+                let mut x = chunks[2].x + 1 + status_text.chars().count() as u16;
+                let mut button = |label: &str| {
+                    let width = label.chars().count() as u16;
+                    let area = Rect::new(x, row, width, 1).intersection(chunks[2]);
+                    x += width + 2;
+                    area
+                };
+                confirm_buttons = Some((button(CONFIRM_YES), button(CONFIRM_NO)));
+                status_spans.push(Span::styled(CONFIRM_YES, Style::default().fg(palette.ok).add_modifier(Modifier::BOLD)));
+                status_spans.push(Span::raw("  "));
+                status_spans.push(Span::styled(CONFIRM_NO, Style::default().fg(palette.error).add_modifier(Modifier::BOLD)));
+            }
+            let status_bar = Paragraph::new(Line::from(status_spans)).block(Block::default().borders(Borders::TOP));
             frame.render_widget(status_bar, chunks[2]);
+
+            // --- Help overlay, drawn last so it floats over the rest ---
+            if app.show_help {
+                let keys = app.help_keys();
+                let help_lines: Vec<Line> = keys
+                    .iter()
+                    .map(|(key, action)| {
+                        Line::from(vec![
+                            Span::styled(format!(" {:<10}", key), Style::default().fg(palette.accent)),
+                            Span::raw(*action),
+                        ])
+                    })
+                    .collect();
+                let area = centered(frame.area(), 44, keys.len() as u16 + 2);
+                let help = Paragraph::new(help_lines).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Keys ")
+                        .title_bottom(" ?/Esc: close "),
+                );
+                frame.render_widget(Clear, area);
+                frame.render_widget(help, area);
+            }
         })?;
+        (self.top, self.max_top, self.page) = (top, max_top, page);
+        (self.main_area, self.first_pod_line, self.confirm_buttons) = (main_area, first_pod_line, confirm_buttons);
         Ok(())
     }
 }
@@ -239,12 +579,35 @@ fn phase_index(phase: &Phase) -> u8 {
     }
 }
 
+/// A `width` x `height` box in the middle of `area`, shrunk to fit.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Copy `value` and confirm briefly. Without a clipboard (headless, SSH)
+/// the value stays in the status bar to be copied by hand.
+fn copy_value(app: &mut App, label: &str, value: &str) {
+    if copy_to_clipboard(value) {
+        app.flash(format!("{} copied!", label));
+    } else {
+        app.status_message = Some(format!("No clipboard available \u{2014} {}: {}", label, value));
+    }
+}
+
 /// Attempt to copy text to the system clipboard.
 /// Falls back to shell commands if the clipboard crate fails.
-fn copy_to_clipboard(text: &str) {
+/// Returns whether anything accepted the text.
+fn copy_to_clipboard(text: &str) -> bool {
     // Try cli-clipboard first
     if cli_clipboard::set_contents(text.to_string()).is_ok() {
-        return;
+        return true;
     }
     // Fallback: try platform-specific commands
     let commands: &[(&str, &[&str])] = &[
@@ -259,14 +622,29 @@ fn copy_to_clipboard(text: &str) {
             .stdin(std::process::Stdio::piped())
             .spawn()
         {
-            if let Some(ref mut stdin) = child.stdin {
+            if let Some(mut stdin) = child.stdin.take() {
                 use std::io::Write;
                 let _ = stdin.write_all(text.as_bytes());
             }
-            let _ = child.wait();
-            return;
+            if child.wait().is_ok_and(|status| status.success()) {
+                return true;
+            }
         }
     }
-    // If all else fails, just print it
-    eprintln!("Could not copy to clipboard. Auth token: {}", text);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_color_means_mono_whatever_the_theme() {
+        let mono = Palette::new(Theme::Mono, false);
+        assert_eq!(mono.ok, Color::Reset);
+        assert_eq!(mono.error, Color::Reset);
+        assert_eq!(Palette::new(Theme::Dark, true), mono);
+        assert_eq!(Palette::new(Theme::Light, true), mono);
+        assert_ne!(Palette::new(Theme::Light, false), Palette::new(Theme::Dark, false));
+    }
 }
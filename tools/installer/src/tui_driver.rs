@@ -0,0 +1,109 @@
+//! Runs the [`Tui`] for the parts of an interactive install worth watching.
+//!
+//! The install itself prints line by line; when it runs in a terminal
+//! without `--non-interactive`, the health wait is shown in the TUI instead,
+//! where pods can be selected (keys or mouse), their logs read and
+//! restarted. The alternate screen is left again once the wait ends, and
+//! entered once more for the Complete screen at the end.
+
+use anyhow::Result;
+use kube::Client;
+use std::time::{Duration, Instant};
+
+use crate::app::{App, Phase};
+use crate::cli::{InstallArgs, Theme};
+use crate::health::{self, HealthEvent, PollOptions, WorkloadRef};
+use crate::tui::Tui;
+
+/// Whether `args` describe an install a person is watching in a terminal.
+pub fn enabled(args: &InstallArgs) -> bool {
+    use std::io::IsTerminal;
+    !args.non_interactive && std::io::stdout().is_terminal()
+}
+
+/// [`crate::k8s::wait_for_deployments`] in the TUI: poll `workloads` under
+/// `opts`, showing each pod in `app` as it changes. Returns how long after
+/// the wait began each workload became ready. A failed wait stays on
+/// screen, with the likely causes for the selected pod, until 'q'; quitting
+/// earlier aborts the install.
+pub async fn wait_for_workloads(
+    client: &Client,
+    namespace: &str,
+    workloads: &[WorkloadRef],
+    opts: &PollOptions,
+    theme: Theme,
+    app: &mut App,
+) -> Result<Vec<(String, Duration)>> {
+    app.phase = Phase::Verify;
+    let names: Vec<&str> = workloads.iter().map(|w| w.name.as_str()).collect();
+    app.log_entries.push(format!("Waiting for {} workload(s): {}", names.len(), names.join(", ")));
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let poll = {
+        let (client, namespace, workloads, opts) = (client.clone(), namespace.to_string(), workloads.to_vec(), *opts);
+        tokio::spawn(async move { health::poll_health(&client, &namespace, &workloads, &opts, tx).await })
+    };
+
+    let mut tui = Tui::new(theme)?;
+    let started = Instant::now();
+    let mut ready_after: Vec<(String, Duration)> = Vec::new();
+    let mut failure = None;
+    loop {
+        while let Ok(event) = rx.try_recv() {
+            match &event {
+                HealthEvent::WorkloadReady { workload, ready: true } => {
+                    ready_after.push((workload.clone(), started.elapsed()));
+                }
+                HealthEvent::WorkloadReady { workload, ready: false } => {
+                    ready_after.retain(|(name, _)| name != workload);
+                }
+                HealthEvent::AllHealthy => {
+                    app.record_health_event(event);
+                    return Ok(ready_after);
+                }
+                HealthEvent::Failed { workload, reason, .. } => {
+                    failure = Some(crate::output::ComponentError::new(workload, reason.clone()));
+                    app.phase = Phase::Failed;
+                }
+                _ => {}
+            }
+            app.record_health_event(event);
+        }
+        if poll.is_finished() && failure.is_none() {
+            // Polling only stops on its own after AllHealthy or Failed
+            poll.await??;
+            anyhow::bail!("health polling stopped unexpectedly");
+        }
+
+        // 'R' restarts, confirmed in the TUI; not counted against recovery
+        for pod in app.take_restart_requests() {
+            match health::restart_pod(client, namespace, &pod).await {
+                Ok(()) => app.flash(format!("Restarted {}", pod)),
+                Err(e) => app.flash(format!("Could not restart {}: {:#}", pod, e)),
+            }
+        }
+
+        tui.draw(app)?;
+        let quit = tokio::task::block_in_place(|| tui.handle_input(app))?;
+        if quit {
+            poll.abort();
+            return Err(match failure {
+                Some(failure) => failure.into(),
+                None => anyhow::anyhow!("Install aborted while waiting for pods"),
+            });
+        }
+    }
+}
+
+/// The Complete screen: where to reach the install, its auth token (masked,
+/// 'u' and 't' copy them) and the timing, from `app`, until 'q'.
+pub fn show_complete(theme: Theme, app: &mut App) -> Result<()> {
+    app.phase = Phase::Complete;
+    let mut tui = Tui::new(theme)?;
+    loop {
+        tui.draw(app)?;
+        if tokio::task::block_in_place(|| tui.handle_input(app))? {
+            return Ok(());
+        }
+    }
+}
@@ -159,7 +159,7 @@ pub async fn discover_ollama_models(endpoint: &str) -> Result<Vec<OllamaModel>>
 
     let body = resp.text().await?;
     let mut models = parse_ollama_models(&body)?;
-    models.sort_by(|a, b| b.size.cmp(&a.size));
+    models.sort_by_key(|m| std::cmp::Reverse(m.size));
     Ok(models)
 }
 
@@ -167,7 +167,7 @@ pub async fn discover_ollama_models(endpoint: &str) -> Result<Vec<OllamaModel>>
 pub fn parse_ollama_models(json: &str) -> Result<Vec<OllamaModel>> {
     let resp: OllamaTagsResponse = serde_json::from_str(json)?;
     let mut models = resp.models;
-    models.sort_by(|a, b| b.size.cmp(&a.size));
+    models.sort_by_key(|m| std::cmp::Reverse(m.size));
     Ok(models)
 }
 
@@ -47,21 +47,23 @@ pub async fn run_checks(
 ) -> Result<VerifyResult> {
     let start = std::time::Instant::now();
     let mut checks = Vec::new();
+    // Only this instance's pods and deployments are checked
+    let instance = config.instance.as_deref();
 
     // Check 1: All expected pods are Running
-    checks.push(check_pods_running(client, namespace).await);
+    checks.push(check_pods_running(client, namespace, instance).await);
 
     // Check 2: Key deployments have desired replicas ready
-    checks.push(check_deployments_ready(client, namespace).await);
+    checks.push(check_deployments_ready(client, namespace, instance).await);
 
     // Check 3: Brain health endpoint via kubectl exec
-    checks.push(check_brain_health(namespace).await);
+    checks.push(check_brain_health(namespace, instance).await);
 
     // Check 4: NATS connectivity
-    checks.push(check_nats_health(namespace).await);
+    checks.push(check_nats_health(namespace, instance).await);
 
     // Check 5: Send test prompt (if an AI provider key is configured and non-empty)
-    let has_provider = |key: &str| config.secrets.get(key).map_or(false, |v| !v.is_empty());
+    let has_provider = |key: &str| config.secrets.get(key).is_some_and(|v| !v.is_empty());
     if has_provider("ANTHROPIC_API_KEY")
         || has_provider("OPENAI_API_KEY")
         || has_provider("OLLAMA_ENDPOINTS")
@@ -75,9 +77,9 @@ pub async fn run_checks(
     })
 }
 
-async fn check_pods_running(client: &Client, namespace: &str) -> Check {
+async fn check_pods_running(client: &Client, namespace: &str, instance: Option<&str>) -> Check {
     let start = std::time::Instant::now();
-    match k8s_check_pods(client, namespace).await {
+    match k8s_check_pods(client, namespace, instance).await {
         Ok(msg) => Check {
             name: "pods_running".into(),
             passed: true,
@@ -93,9 +95,9 @@ async fn check_pods_running(client: &Client, namespace: &str) -> Check {
     }
 }
 
-async fn k8s_check_pods(client: &Client, namespace: &str) -> Result<String> {
+async fn k8s_check_pods(client: &Client, namespace: &str, instance: Option<&str>) -> Result<String> {
     let api: Api<Pod> = Api::namespaced(client.clone(), namespace);
-    let pods = api.list(&ListParams::default()).await?;
+    let pods = api.list(&ListParams::default().labels(&crate::instance::selector(instance))).await?;
 
     let total = pods.items.len();
     let non_running: Vec<String> = pods
@@ -123,9 +125,9 @@ async fn k8s_check_pods(client: &Client, namespace: &str) -> Result<String> {
     }
 }
 
-async fn check_deployments_ready(client: &Client, namespace: &str) -> Check {
+async fn check_deployments_ready(client: &Client, namespace: &str, instance: Option<&str>) -> Check {
     let start = std::time::Instant::now();
-    match k8s_check_deployments(client, namespace).await {
+    match k8s_check_deployments(client, namespace, instance).await {
         Ok(msg) => Check {
             name: "deployments_ready".into(),
             passed: true,
@@ -141,9 +143,11 @@ async fn check_deployments_ready(client: &Client, namespace: &str) -> Check {
     }
 }
 
-async fn k8s_check_deployments(client: &Client, namespace: &str) -> Result<String> {
+async fn k8s_check_deployments(client: &Client, namespace: &str, instance: Option<&str>) -> Result<String> {
     let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
-    let deployments = api.list(&ListParams::default()).await?;
+    let deployments = api
+        .list(&ListParams::default().labels(&crate::instance::selector(instance)))
+        .await?;
 
     let total = deployments.items.len();
     let mut not_ready = Vec::new();
@@ -175,9 +179,11 @@ async fn k8s_check_deployments(client: &Client, namespace: &str) -> Result<Strin
     }
 }
 
-async fn find_brain_deploy(namespace: &str) -> &'static str {
+async fn find_brain_deploy(namespace: &str, instance: Option<&str>) -> String {
     // Try blue/green slots first, fall back to plain "brain"
-    for name in ["deploy/brain-blue", "deploy/brain-green", "deploy/brain"] {
+    let names = ["brain-blue", "brain-green", "brain"]
+        .map(|name| format!("deploy/{}", crate::instance::prefixed(instance, name)));
+    for name in &names {
         let ok = tokio::process::Command::new("kubectl")
             .args(["get", "-n", namespace, name])
             .stdout(std::process::Stdio::null())
@@ -187,15 +193,15 @@ async fn find_brain_deploy(namespace: &str) -> &'static str {
             .map(|s| s.success())
             .unwrap_or(false);
         if ok {
-            return name;
+            return name.clone();
         }
     }
-    "deploy/brain-blue" // default; will fail with clear error
+    names[0].clone() // default; will fail with clear error
 }
 
-async fn check_brain_health(namespace: &str) -> Check {
+async fn check_brain_health(namespace: &str, instance: Option<&str>) -> Check {
     let start = std::time::Instant::now();
-    let brain_deploy = find_brain_deploy(namespace).await;
+    let brain_deploy = find_brain_deploy(namespace, instance).await;
     // Use kubectl exec to check brain health from inside the cluster (30s timeout)
     let result = tokio::time::timeout(
         std::time::Duration::from_secs(30),
@@ -204,7 +210,7 @@ async fn check_brain_health(namespace: &str) -> Check {
                 "exec",
                 "-n",
                 namespace,
-                brain_deploy,
+                &brain_deploy,
                 "--",
                 "wget",
                 "-q",
@@ -246,8 +252,9 @@ async fn check_brain_health(namespace: &str) -> Check {
     }
 }
 
-async fn check_nats_health(namespace: &str) -> Check {
+async fn check_nats_health(namespace: &str, instance: Option<&str>) -> Check {
     let start = std::time::Instant::now();
+    let nats_deploy = format!("deploy/{}", crate::instance::prefixed(instance, "nats"));
     // Check NATS monitoring endpoint (30s timeout)
     let result = tokio::time::timeout(
         std::time::Duration::from_secs(30),
@@ -256,7 +263,7 @@ async fn check_nats_health(namespace: &str) -> Check {
                 "exec",
                 "-n",
                 namespace,
-                "deploy/nats",
+                &nats_deploy,
                 "--",
                 "wget",
                 "-q",
@@ -297,7 +304,7 @@ async fn check_test_prompt(namespace: &str, config: &InterviewResult) -> Check {
         .cloned()
         .unwrap_or_default();
 
-    let brain_deploy = find_brain_deploy(namespace).await;
+    let brain_deploy = find_brain_deploy(namespace, config.instance.as_deref()).await;
     // Use kubectl exec to send a test prompt through the brain API.
     // Pass auth token via env var to avoid shell injection.
     let result = tokio::time::timeout(
@@ -307,7 +314,7 @@ async fn check_test_prompt(namespace: &str, config: &InterviewResult) -> Check {
                 "exec",
                 "-n",
                 namespace,
-                brain_deploy,
+                &brain_deploy,
                 "--",
                 "sh",
                 "-c",
@@ -356,3 +363,104 @@ async fn check_test_prompt(namespace: &str, config: &InterviewResult) -> Check {
         },
     }
 }
+
+/// `--smoke-test`: request the app through the UI the way a browser would,
+/// so a bad secret that lets pods start but breaks the app (a wrong
+/// AUTH_TOKEN, a brain the UI can't reach) is caught. `/api/ping` needs no
+/// token; `/api/system/health` needs `auth_token`. Each is retried briefly,
+/// as a Service can lag its pods becoming ready.
+pub async fn smoke_test(base_url: &str, auth_token: &str) -> Vec<Check> {
+    let client = match reqwest::Client::builder().timeout(std::time::Duration::from_secs(10)).build() {
+        Ok(client) => client,
+        Err(e) => {
+            return vec![Check {
+                name: "smoke_test".into(),
+                passed: false,
+                message: format!("cannot create an HTTP client: {}", e),
+                duration_ms: 0,
+            }]
+        }
+    };
+    let base = base_url.trim_end_matches('/');
+    vec![
+        smoke_check(&client, "smoke_ping", &format!("{}/api/ping", base), None).await,
+        smoke_check(&client, "smoke_authenticated", &format!("{}/api/system/health", base), Some(auth_token)).await,
+    ]
+}
+
+const SMOKE_ATTEMPTS: u32 = 5;
+const SMOKE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+async fn smoke_check(client: &reqwest::Client, name: &str, url: &str, token: Option<&str>) -> Check {
+    let start = std::time::Instant::now();
+    let mut attempt = 1;
+    let message = loop {
+        let mut request = client.get(url);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let retry = match request.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                return Check {
+                    name: name.into(),
+                    passed: true,
+                    message: format!("GET {}: {}", url, resp.status()),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                };
+            }
+            // Retrying won't change the token
+            Ok(resp) if resp.status() == reqwest::StatusCode::UNAUTHORIZED => {
+                break format!("GET {}: 401 Unauthorized; the brain rejected the install's AUTH_TOKEN", url);
+            }
+            Ok(resp) => (resp.status().is_server_error(), format!("GET {}: {}", url, resp.status())),
+            Err(e) => (true, format!("GET {}: {}", url, e)),
+        };
+        match retry {
+            (true, _) if attempt < SMOKE_ATTEMPTS => {
+                attempt += 1;
+                tokio::time::sleep(SMOKE_RETRY_DELAY).await;
+            }
+            (_, message) => break message,
+        }
+    };
+    Check { name: name.into(), passed: false, message, duration_ms: start.elapsed().as_millis() as u64 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answers /api/ping, and /api/system/health only with `token`.
+    async fn serve(token: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let authorized = request.contains(&format!("authorization: bearer {}", token));
+                let status = match request.starts_with("get /api/ping ") || authorized {
+                    true => "200 OK",
+                    false => "401 Unauthorized",
+                };
+                let response = format!("HTTP/1.1 {}\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{{}}", status);
+                socket.write_all(response.as_bytes()).await.ok();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn smoke_test_passes_with_the_right_token_only() {
+        let url = serve("s3cret").await;
+        let checks = smoke_test(&url, "s3cret").await;
+        assert!(checks.iter().all(|c| c.passed), "{:?}", checks);
+
+        let checks = smoke_test(&format!("{}/", url), "wrong").await;
+        assert!(checks[0].passed);
+        assert!(!checks[1].passed);
+        assert!(checks[1].message.contains("AUTH_TOKEN"), "{}", checks[1].message);
+    }
+}
@@ -0,0 +1,23 @@
+//! Fixtures shared by the test crates.
+
+use bakerst_install::config_schema::ConfigSchema;
+use bakerst_install::interview::InterviewResult;
+
+/// The schema shipped in the install template.
+pub fn schema() -> ConfigSchema {
+    ConfigSchema::from_file(std::path::Path::new("../install-template/config-schema.json")).unwrap()
+}
+
+/// A default install into `bakerst` with `secrets` set and `features` enabled.
+pub fn config(secrets: &[(&str, &str)], features: &[&str]) -> InterviewResult {
+    InterviewResult {
+        secrets: secrets
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        enabled_features: features.iter().map(|f| f.to_string()).collect(),
+        namespace: "bakerst".into(),
+        agent_name: "Baker".into(),
+        ..Default::default()
+    }
+}
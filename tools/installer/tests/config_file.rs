@@ -183,3 +183,216 @@ verify:
     assert_eq!(verify.expected_pods.len(), 4);
     assert_eq!(verify.timeout_seconds, Some(180));
 }
+
+#[test]
+fn parse_toml_config() {
+    let toml = r#"
+namespace = "bakerst"
+
+[secrets]
+ANTHROPIC_API_KEY = "sk-ant-toml"
+
+[features]
+github = true
+
+[options]
+noWait = true
+"#;
+    let mut f = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+    write!(f, "{}", toml).unwrap();
+
+    let config = bakerst_install::config_file::load_config(f.path()).unwrap();
+    assert_eq!(config.secrets.get("ANTHROPIC_API_KEY").unwrap(), "sk-ant-toml");
+    assert_eq!(config.features.get("github"), Some(&true));
+    assert_eq!(config.options.no_wait, Some(true));
+}
+
+#[test]
+fn options_fill_in_unset_flags_only() {
+    let yaml = r#"
+version: "0.6.0"
+options:
+  manifest: /tmp/from-file.json
+  dryRun: true
+  applyStrategy: client
+  applyRetries: 5
+  pullConcurrency: 2
+  resourceProfile: small
+  ingressHost: bakerst.dev.example.com
+  set:
+    BRAIN_REPLICAS: "2"
+  namespaceLabels:
+    team: platform
+"#;
+    let mut f = NamedTempFile::new().unwrap();
+    write!(f, "{}", yaml).unwrap();
+    let config = bakerst_install::config_file::load_config(f.path()).unwrap();
+    assert!(config.unknown_keys().is_empty());
+
+    let mut args = bakerst_install::cli::InstallArgs {
+        manifest: Some("/tmp/from-flag.json".into()),
+        apply_retries: 1,
+        from_command_line: ["manifest".to_string(), "apply_retries".to_string()].into(),
+        ..Default::default()
+    };
+    config.apply_to(&mut args).unwrap();
+
+    // Flag wins over file, even one that has a default; file fills in
+    // what the flags left unset
+    assert_eq!(args.manifest, Some("/tmp/from-flag.json".into()));
+    assert_eq!(args.apply_retries, 1);
+    assert_eq!(args.version.as_deref(), Some("0.6.0"));
+    assert!(args.dry_run);
+    assert!(!args.no_wait);
+    assert_eq!(args.apply_strategy, Some(bakerst_install::k8s::ApplyStrategy::Client));
+    assert_eq!(args.pull_concurrency, 2);
+    assert_eq!(args.resource_profile, Some(bakerst_install::deploy::ResourceProfile::Small));
+    assert_eq!(args.ingress_host.as_deref(), Some("bakerst.dev.example.com"));
+    assert_eq!(args.set, [("BRAIN_REPLICAS".to_string(), "2".to_string())]);
+    assert_eq!(args.namespace_labels, [("team".to_string(), "platform".to_string())]);
+}
+
+#[test]
+fn default_install_args_match_a_parsed_install() {
+    use clap::Parser;
+    let cli = bakerst_install::cli::Cli::try_parse_from(["bakerst-install", "install"]).unwrap();
+    let Some(bakerst_install::cli::Commands::Install(parsed)) = cli.command else {
+        panic!("not an install");
+    };
+    let default = bakerst_install::cli::InstallArgs::default();
+    assert_eq!(default.apply_retries, parsed.apply_retries);
+    assert_eq!(default.apply_retries, bakerst_install::k8s::DEFAULT_APPLY_RETRIES);
+    assert_eq!(default.pull_concurrency, parsed.pull_concurrency);
+    assert_eq!(default.pull_concurrency, bakerst_install::images::DEFAULT_PULL_CONCURRENCY as u64);
+    assert_eq!(default.log, parsed.log);
+    assert_eq!(default.output, parsed.output);
+    assert_eq!(default.theme, parsed.theme);
+}
+
+#[test]
+fn option_values_are_checked_like_flags() {
+    for (yaml, key) in [
+        ("options:\n  ingressHost: Not_A_Host\n", "options.ingressHost"),
+        ("options:\n  uiNodePort: 8080\n", "options.uiNodePort"),
+        ("options:\n  pullConcurrency: 0\n", "options.pullConcurrency"),
+    ] {
+        let mut f = NamedTempFile::new().unwrap();
+        write!(f, "{}", yaml).unwrap();
+        let config = bakerst_install::config_file::load_config(f.path()).unwrap();
+        let err = config.apply_to(&mut Default::default()).unwrap_err();
+        assert!(err.to_string().starts_with(key), "{}", err);
+    }
+}
+
+#[test]
+fn file_only_options_are_merged_before_the_install_reads_them() {
+    let mut f = NamedTempFile::new().unwrap();
+    write!(f, "options:\n  metricsFile: /tmp/install-metrics.json\n  log: /tmp/from-file.log\n").unwrap();
+    let mut args = bakerst_install::cli::InstallArgs {
+        config: Some(f.path().into()),
+        ..Default::default()
+    };
+    bakerst_install::cmd_install::read_inputs(&mut args).unwrap();
+    assert_eq!(args.metrics_file, Some("/tmp/install-metrics.json".into()));
+    assert_eq!(args.log, std::path::PathBuf::from("/tmp/from-file.log"));
+}
+
+#[test]
+fn option_relations_are_checked_like_flags() {
+    let load = |yaml: &str| {
+        let mut f = NamedTempFile::new().unwrap();
+        write!(f, "{}", yaml).unwrap();
+        bakerst_install::config_file::load_config(f.path()).unwrap()
+    };
+
+    // Both from the file
+    let err = load("options:\n  dryRun: true\n  prune: true\n").apply_to(&mut Default::default()).unwrap_err();
+    assert_eq!(err.to_string(), "the argument '--dry-run' cannot be used with '--prune'");
+    let err = load("options:\n  output: json\n").apply_to(&mut Default::default()).unwrap_err();
+    assert!(err.to_string().ends_with("not provided: --non-interactive"), "{}", err);
+
+    // One from the command line, one from the file
+    let mut args = bakerst_install::cli::InstallArgs {
+        step: true,
+        from_command_line: ["step".to_string()].into(),
+        ..Default::default()
+    };
+    let err = load("options:\n  nonInteractive: true\n").apply_to(&mut args).unwrap_err();
+    assert_eq!(err.to_string(), "the argument '--non-interactive' cannot be used with '--step'");
+
+    // A switch the file turns off isn't given
+    load("options:\n  dryRun: false\n  prune: true\n").apply_to(&mut Default::default()).unwrap();
+}
+
+#[test]
+fn unknown_keys_are_reported() {
+    let yaml = r#"
+namespace: bakerst
+credentials: {}
+options:
+  dryRun: true
+  noWiat: true
+"#;
+    let mut f = NamedTempFile::new().unwrap();
+    write!(f, "{}", yaml).unwrap();
+
+    let config = bakerst_install::config_file::load_config(f.path()).unwrap();
+    assert_eq!(config.unknown_keys(), vec!["credentials", "options.noWiat"]);
+}
+
+#[test]
+fn saved_profile_loads_back_with_secrets_redacted() {
+    use bakerst_install::config_schema::ConfigSchema;
+    use bakerst_install::interview::{self, InterviewResult};
+
+    let schema = ConfigSchema::from_file("../install-template/config-schema.json".as_ref()).unwrap();
+    let result = InterviewResult {
+        secrets: [
+            ("ANTHROPIC_API_KEY", "sk-ant-typed"),
+            ("AUTH_TOKEN", "0123abcd"),
+            ("AGENT_NAME", "Sherlock"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect(),
+        enabled_features: vec!["voyage".into()],
+        namespace: "custom-ns".into(),
+        agent_name: "Sherlock".into(),
+        ..Default::default()
+    };
+
+    for name in ["profile.yaml", "profile.toml"] {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        result.save_profile(&path, &schema, false).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("sk-ant-typed"), "{}", saved);
+        assert!(!saved.contains("0123abcd"), "{}", saved);
+
+        std::env::set_var("ANTHROPIC_API_KEY", "sk-ant-from-env");
+        let config = bakerst_install::config_file::load_config(&path).unwrap();
+        let loaded = interview::from_config_file(&schema, &config).unwrap();
+        assert_eq!(loaded.namespace, "custom-ns");
+        assert_eq!(loaded.enabled_features, vec!["voyage".to_string()]);
+        assert_eq!(loaded.secrets["ANTHROPIC_API_KEY"], "sk-ant-from-env");
+        assert_eq!(loaded.secrets["AGENT_NAME"], "Sherlock");
+        // Redacted auto-generated secrets are generated afresh
+        assert_eq!(loaded.secrets["AUTH_TOKEN"].len(), 64);
+    }
+}
+
+#[test]
+fn features_missing_from_the_schema_are_reported() {
+    use bakerst_install::config_schema::ConfigSchema;
+
+    let schema = ConfigSchema::from_file("../install-template/config-schema.json".as_ref()).unwrap();
+    let mut f = NamedTempFile::new().unwrap();
+    write!(f, "features:\n  voyage: true\n  fax: true\n").unwrap();
+    let config = bakerst_install::config_file::load_config(f.path()).unwrap();
+    assert_eq!(config.unknown_features(&schema), vec!["fax"]);
+}
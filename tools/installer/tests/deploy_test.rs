@@ -0,0 +1,778 @@
+//! Tests for the deploy helpers that don't need a cluster.
+
+mod common;
+
+use bakerst_install::deploy;
+use common::{config, schema};
+
+#[test]
+fn route_secrets_fans_out_to_all_targets() {
+    let routes = deploy::route_secrets(&schema(), &config(&[("ANTHROPIC_API_KEY", "sk-ant")], &[]));
+    let targets = deploy::secret_key_targets(&routes);
+    assert_eq!(
+        targets["ANTHROPIC_API_KEY"],
+        vec!["bakerst-brain-secrets", "bakerst-worker-secrets"]
+    );
+}
+
+#[test]
+fn route_secrets_skips_disabled_feature_secrets() {
+    let cfg = config(&[("ANTHROPIC_API_KEY", "sk-ant"), ("TELEGRAM_BOT_TOKEN", "123:abc")], &[]);
+    let targets = deploy::secret_key_targets(&deploy::route_secrets(&schema(), &cfg));
+    assert!(!targets.contains_key("TELEGRAM_BOT_TOKEN"));
+
+    let cfg = config(
+        &[("ANTHROPIC_API_KEY", "sk-ant"), ("TELEGRAM_BOT_TOKEN", "123:abc")],
+        &["telegram"],
+    );
+    let targets = deploy::secret_key_targets(&deploy::route_secrets(&schema(), &cfg));
+    assert_eq!(targets["TELEGRAM_BOT_TOKEN"], vec!["bakerst-gateway-secrets"]);
+}
+
+#[test]
+fn route_secrets_drops_empty_values() {
+    let routes = deploy::route_secrets(&schema(), &config(&[("ANTHROPIC_API_KEY", "")], &[]));
+    assert!(routes.is_empty());
+}
+
+#[test]
+fn route_secrets_are_prefixed_for_an_instance() {
+    let mut cfg = config(&[("ANTHROPIC_API_KEY", "sk-ant")], &[]);
+    cfg.instance = Some("team-a".into());
+    let routes = deploy::route_secrets(&schema(), &cfg);
+    assert_eq!(
+        routes.keys().collect::<Vec<_>>(),
+        vec!["team-a-bakerst-brain-secrets", "team-a-bakerst-worker-secrets"]
+    );
+}
+
+#[test]
+fn instance_renames_resources_and_their_references() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\n\
+         spec:\n  selector:\n    matchLabels:\n      app: brain\n  template:\n    metadata:\n      labels:\n        app: brain\n\
+         \x20   spec:\n      containers:\n      - name: brain\n        envFrom:\n        - secretRef:\n            name: bakerst-brain-secrets\n\
+         \x20       env:\n        - name: NATS_URL\n          value: nats://nats:4222\n\
+         \x20     volumes:\n      - name: os\n        configMap:\n          name: bakerst-os\n\
+         ---\napiVersion: v1\nkind: Service\nmetadata:\n  name: nats\n\
+         spec:\n  selector:\n    app: nats\n  ports:\n  - port: 4222\n    nodePort: 30422\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions {
+        instance: Some("a".into()),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+
+    let (brain, nats) = (&resources[0], &resources[1]);
+    assert_eq!(brain["metadata"]["name"], "a-brain");
+    assert_eq!(brain["spec"]["selector"]["matchLabels"]["bakerst.io/instance"], "a");
+    assert_eq!(brain["spec"]["template"]["metadata"]["labels"]["bakerst.io/instance"], "a");
+    let pod = &brain["spec"]["template"]["spec"];
+    assert_eq!(pod["containers"][0]["envFrom"][0]["secretRef"]["name"], "a-bakerst-brain-secrets");
+    assert_eq!(pod["containers"][0]["env"][0]["value"], "nats://a-nats:4222");
+    assert_eq!(pod["volumes"][0]["configMap"]["name"], "a-bakerst-os");
+    assert!(pod["volumes"][0].get("secret").is_none());
+
+    assert_eq!(nats["metadata"]["name"], "a-nats");
+    assert_eq!(nats["spec"]["selector"]["bakerst.io/instance"], "a");
+    assert!(nats["spec"]["ports"][0].get("nodePort").is_none());
+}
+
+#[test]
+fn storage_class_is_set_on_claims_only() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: v1\nkind: PersistentVolumeClaim\nmetadata:\n  name: data\nspec:\n  accessModes: [ReadWriteOnce]\n\
+         ---\napiVersion: v1\nkind: Service\nmetadata:\n  name: ui\nspec: {}\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions {
+        storage_class: Some("local-path".into()),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    assert_eq!(resources[0]["spec"]["storageClassName"], "local-path");
+    assert!(resources[1]["spec"].get("storageClassName").is_none());
+}
+
+fn manifest_with_tags(tags: &[&str]) -> bakerst_install::manifest::Manifest {
+    let images: Vec<_> = tags
+        .iter()
+        .enumerate()
+        .map(|(i, tag)| {
+            serde_json::json!({
+                "name": format!("svc{}", i),
+                "image": format!("ghcr.io/example/bakerst-svc{}", i),
+                "tag": tag,
+                "required": true,
+                "architectures": ["linux/amd64"],
+            })
+        })
+        .collect();
+    let json = serde_json::json!({
+        "schemaVersion": 1,
+        "version": "0.6.0",
+        "releaseDate": "2026-01-01T00:00:00Z",
+        "templateUrl": "https://example.com/t.tar.gz",
+        "templateSha256": "",
+        "images": images,
+        "installers": [],
+    });
+    bakerst_install::manifest::Manifest::from_json(&json.to_string()).unwrap()
+}
+
+#[test]
+fn default_pull_policy_prefers_local_latest_images() {
+    use bakerst_install::k8s::PullPolicy;
+    let latest = manifest_with_tags(&["latest", "latest"]);
+    assert_eq!(deploy::default_pull_policy(&latest, true), PullPolicy::IfNotPresent);
+    assert_eq!(deploy::default_pull_policy(&latest, false), PullPolicy::Always);
+
+    let pinned = manifest_with_tags(&["0.6.0", "0.6.0"]);
+    assert_eq!(deploy::default_pull_policy(&pinned, true), PullPolicy::Always);
+}
+
+#[test]
+fn component_of_uses_app_label() {
+    let deploy = serde_json::json!({ "kind": "Deployment", "metadata": { "name": "brain-blue", "labels": { "app": "brain" } } });
+    let policy = serde_json::json!({ "kind": "NetworkPolicy", "metadata": { "name": "default-deny" } });
+    assert_eq!(deploy::component_of(&deploy), "brain");
+    assert_eq!(deploy::component_of(&policy), "shared");
+}
+
+#[test]
+fn components_without_templates_are_reported() {
+    let manifest = manifest_with_tags(&["0.6.0", "0.6.0"]);
+    let resources = vec![serde_json::json!({
+        "kind": "Deployment",
+        "metadata": { "name": "svc0" },
+        "spec": { "template": { "spec": { "containers": [{ "image": "bakerst-svc0:latest" }] } } }
+    })];
+    assert_eq!(deploy::components_without_templates(&manifest, &resources), vec!["svc1"]);
+}
+
+#[test]
+fn image_basename_strips_registry_tag_and_digest() {
+    assert_eq!(deploy::image_basename("ghcr.io/org/bakerst-brain:0.6.0"), "bakerst-brain");
+    assert_eq!(deploy::image_basename("bakerst-ui:latest"), "bakerst-ui");
+    assert_eq!(deploy::image_basename("localhost:5000/nats@sha256:abc"), "nats");
+}
+
+#[test]
+fn node_port_conflicts_ignore_our_own_service() {
+    use std::collections::BTreeMap;
+    let resources = vec![serde_json::json!({
+        "kind": "Service",
+        "metadata": { "name": "ui" },
+        "spec": { "type": "NodePort", "ports": [{ "port": 8080, "nodePort": 30080 }] }
+    })];
+    let requested = deploy::requested_node_ports(&resources);
+    assert_eq!(requested, vec![(30080, "ui".to_string())]);
+
+    let mut in_use = BTreeMap::new();
+    in_use.insert(30080, ("bakerst".to_string(), "ui".to_string()));
+    assert!(deploy::node_port_conflicts(&requested, &in_use, "bakerst").is_empty());
+
+    in_use.insert(30080, ("monitoring".to_string(), "grafana".to_string()));
+    let conflicts = deploy::node_port_conflicts(&requested, &in_use, "bakerst");
+    assert_eq!(conflicts[0].held_by, "monitoring/grafana");
+
+    in_use.insert(30000, ("other".to_string(), "x".to_string()));
+    assert_eq!(deploy::suggest_free_node_port(&in_use, &requested), Some(30001));
+}
+
+#[test]
+fn resource_diff_shows_only_real_changes() {
+    let live = serde_json::json!({
+        "apiVersion": "v1", "kind": "ConfigMap",
+        "metadata": {"name": "brain-config", "resourceVersion": "42", "uid": "abc"},
+        "data": {"LOG_LEVEL": "info"}
+    });
+    let mut desired = live.clone();
+    desired["metadata"].as_object_mut().unwrap().remove("resourceVersion");
+    assert!(deploy::resource_diff("ConfigMap/brain-config", Some(&live), &desired).is_none());
+
+    desired["data"]["LOG_LEVEL"] = "debug".into();
+    let diff = deploy::resource_diff("ConfigMap/brain-config", Some(&live), &desired).unwrap();
+    assert!(diff.contains("-  LOG_LEVEL: info"));
+    assert!(diff.contains("+  LOG_LEVEL: debug"));
+
+    let added = deploy::resource_diff("ConfigMap/brain-config", None, &desired).unwrap();
+    assert!(added.lines().all(|l| !l.starts_with('-') || l.starts_with("---")));
+}
+
+#[test]
+fn resource_diff_masks_secret_values() {
+    let live = serde_json::json!({
+        "apiVersion": "v1", "kind": "Secret",
+        "metadata": {"name": "bakerst-secrets"},
+        "data": {"API_KEY": "b2xk"}
+    });
+    let mut desired = live.clone();
+    desired["data"]["API_KEY"] = "bmV3".into();
+    let diff = deploy::resource_diff("Secret/bakerst-secrets", Some(&live), &desired).unwrap();
+    assert!(!diff.contains("b2xk") && !diff.contains("bmV3"));
+    assert!(diff.contains("API_KEY"));
+}
+
+#[test]
+fn feature_resource_hints_apply_only_when_enabled() {
+    let mut manifest = manifest_with_tags(&["0.6.0", "0.6.0"]);
+    let hint = |feature: &str, memory: &str| bakerst_install::manifest::ResourceHint {
+        feature: feature.into(),
+        requests: [("memory".to_string(), memory.to_string())].into(),
+        limits: Default::default(),
+    };
+    manifest.images[1].resource_hints = vec![hint("voice", "512Mi"), hint("browser", "1Gi")];
+
+    assert!(deploy::feature_resources(&manifest, &[]).is_empty());
+    let voice = deploy::feature_resources(&manifest, &["voice".into()]);
+    assert_eq!(voice["bakerst-svc1"].requests["memory"], "512Mi");
+    // Both enabled: the larger request wins
+    let both = deploy::feature_resources(&manifest, &["browser".into(), "voice".into()]);
+    assert_eq!(both["bakerst-svc1"].requests["memory"], "1Gi");
+
+    let mut resources = vec![serde_json::json!({
+        "kind": "Deployment",
+        "metadata": {"name": "svc1"},
+        "spec": {"template": {"spec": {"containers": [{
+            "name": "svc1",
+            "image": "ghcr.io/example/bakerst-svc1:0.6.0",
+            "resources": {"requests": {"cpu": "100m", "memory": "128Mi"}}
+        }]}}}
+    })];
+    let opts = deploy::DeployOptions { resources: voice, ..Default::default() };
+    deploy::customize_resources(&mut resources, &opts);
+    let requests = &resources[0]["spec"]["template"]["spec"]["containers"][0]["resources"]["requests"];
+    assert_eq!(requests["memory"], "512Mi");
+    assert_eq!(requests["cpu"], "100m");
+}
+
+#[test]
+fn prune_candidates_are_managed_resources_not_owned() {
+    let cfg = config(&[("ANTHROPIC_API_KEY", "sk-ant")], &[]);
+    let rendered = vec![
+        serde_json::json!({"kind": "Deployment", "metadata": {"name": "brain-blue"}}),
+        serde_json::json!({"kind": "Service", "metadata": {"name": "ui"}}),
+    ];
+    let owned = deploy::owned_resources(&rendered, &schema(), &cfg);
+    let live: Vec<_> = [
+        ("Namespace", "bakerst"),
+        ("Deployment", "brain-blue"),
+        ("Deployment", "voice"),
+        ("Service", "ui"),
+        ("Secret", "bakerst-brain-secrets"),
+        ("Secret", "bakerst-gateway-secrets"),
+        ("ConfigMap", "bakerst-os"),
+    ]
+    .iter()
+    .map(|(kind, name)| bakerst_install::k8s::ManagedResource {
+        kind: kind.to_string(),
+        name: name.to_string(),
+        created: None,
+        status: String::new(),
+    })
+    .collect();
+
+    assert_eq!(
+        deploy::prune_candidates(&live, &owned),
+        vec![
+            ("Deployment".to_string(), "voice".to_string()),
+            ("Secret".to_string(), "bakerst-gateway-secrets".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn dry_run_output_masks_secret_values() {
+    let mut objects = vec![
+        bakerst_install::k8s::secret_object(
+            "bakerst",
+            "bakerst-brain-secrets",
+            &[("ANTHROPIC_API_KEY".to_string(), "sk-ant-real".to_string())].into(),
+        ),
+        serde_json::json!({"apiVersion": "v1", "kind": "Service", "metadata": {"name": "ui"}}),
+    ];
+    deploy::mask_secret_values(&mut objects);
+    assert!(objects[0].get("data").is_none());
+    assert_eq!(objects[0]["stringData"]["ANTHROPIC_API_KEY"], "***");
+
+    let yaml = deploy::to_yaml_stream(&objects).unwrap();
+    assert!(!yaml.contains("sk-ant-real"));
+    assert_eq!(yaml.matches("---\n").count(), 2);
+    assert_eq!(bakerst_install::k8s::parse_yaml_docs(&yaml).unwrap().len(), 2);
+}
+
+#[test]
+fn applied_deployments_drive_the_health_watch() {
+    let applied = vec![
+        "Deployment/brain-blue".to_string(),
+        "Service/ui".to_string(),
+        "Deployment/ext-weather".to_string(),
+    ];
+    assert_eq!(deploy::applied_deployments(&applied), vec!["brain-blue", "ext-weather"]);
+}
+
+#[test]
+fn probe_overrides_retime_existing_probes_of_the_named_component() {
+    let mut manifest = manifest_with_tags(&["0.6.0", "0.6.0"]);
+    manifest.images[0].probes = Some(bakerst_install::manifest::ProbeTuning {
+        initial_delay_seconds: Some(30),
+        period_seconds: Some(20),
+    });
+    let probes = deploy::probe_tuning(&manifest, &[("svc0".into(), 90)], &[]).unwrap();
+
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: svc0\nspec:\n  template:\n    spec:\n      containers:\n\
+         \x20     - name: svc0\n        image: ghcr.io/example/bakerst-svc0:0.6.0\n        livenessProbe:\n          initialDelaySeconds: 5\n\
+         \x20     - name: sidecar\n        image: busybox\n        livenessProbe:\n          initialDelaySeconds: 5\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions { probes, ..Default::default() };
+    deploy::customize_resources(&mut resources, &opts);
+    let containers = &resources[0]["spec"]["template"]["spec"]["containers"];
+    // The flag wins over the manifest; the manifest's period still applies
+    assert_eq!(containers[0]["livenessProbe"]["initialDelaySeconds"], 90);
+    assert_eq!(containers[0]["livenessProbe"]["periodSeconds"], 20);
+    // No readiness probe in the template, so none is added
+    assert!(containers[0].get("readinessProbe").is_none());
+    assert_eq!(containers[1]["livenessProbe"]["initialDelaySeconds"], 5);
+}
+
+#[test]
+fn probe_overrides_must_name_a_manifest_component() {
+    let manifest = manifest_with_tags(&["0.6.0"]);
+    let err = deploy::probe_tuning(&manifest, &[], &[("brian".into(), 10)]).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown component 'brian' in --probe-period. Components: svc0");
+}
+
+#[test]
+fn manifest_probe_period_of_zero_is_rejected() {
+    let mut manifest = manifest_with_tags(&["0.6.0"]);
+    manifest.images[0].probes = Some(bakerst_install::manifest::ProbeTuning {
+        initial_delay_seconds: Some(0),
+        period_seconds: Some(0),
+    });
+    let err = deploy::probe_tuning(&manifest, &[], &[]).unwrap_err();
+    assert_eq!(err.to_string(), "Invalid manifest: component 'svc0' has a probe periodSeconds of 0");
+}
+
+#[test]
+fn resource_profile_sizes_known_components_and_feature_hints_still_win() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\n  labels:\n    app: brain\nspec:\n  replicas: 1\n\
+         \x20 template:\n    spec:\n      containers:\n      - name: brain\n        image: ghcr.io/example/bakerst-brain:0.6.0\n\
+         ---\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: qdrant\n  labels:\n    app: qdrant\nspec:\n  replicas: 1\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions {
+        resource_profile: deploy::ResourceProfile::Large,
+        resources: std::collections::BTreeMap::from([(
+            "bakerst-brain".to_string(),
+            deploy::ContainerResources {
+                limits: [("memory".to_string(), "4Gi".to_string())].into(),
+                ..Default::default()
+            },
+        )]),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    assert_eq!(resources[0]["spec"]["replicas"], 2);
+    let brain = &resources[0]["spec"]["template"]["spec"]["containers"][0]["resources"];
+    assert_eq!(brain["limits"]["cpu"], "2");
+    assert_eq!(brain["limits"]["memory"], "4Gi");
+    // Components the profile doesn't name keep the template's values
+    assert_eq!(resources[1]["spec"]["replicas"], 1);
+
+    assert_eq!(deploy::ResourceProfile::from_name("Small"), Some(deploy::ResourceProfile::Small));
+    assert!(deploy::ResourceProfile::Standard.describe().is_empty());
+}
+
+#[test]
+fn feature_hints_never_lower_the_template_quantities() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\nspec:\n  template:\n    spec:\n      containers:\n\
+         \x20     - name: brain\n        image: ghcr.io/example/bakerst-brain:0.6.0\n        resources:\n\
+         \x20         requests:\n            cpu: 1\n            memory: 256Mi\n          limits:\n            memory: 8Gi\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions {
+        resources: std::collections::BTreeMap::from([(
+            "bakerst-brain".to_string(),
+            deploy::ContainerResources {
+                requests: [("cpu".to_string(), "500m".to_string()), ("memory".to_string(), "1Gi".to_string())].into(),
+                limits: [("memory".to_string(), "4Gi".to_string()), ("cpu".to_string(), "2".to_string())].into(),
+            },
+        )]),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    let brain = &resources[0]["spec"]["template"]["spec"]["containers"][0]["resources"];
+    assert_eq!(brain["requests"]["cpu"], 1, "the template's larger value is kept");
+    assert_eq!(brain["requests"]["memory"], "1Gi");
+    assert_eq!(brain["limits"]["memory"], "8Gi");
+    assert_eq!(brain["limits"]["cpu"], "2", "missing quantities are added");
+}
+
+#[test]
+fn data_dir_swaps_claims_for_host_paths() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: v1\nkind: PersistentVolumeClaim\nmetadata:\n  name: qdrant-data\n\
+         ---\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: qdrant\n  labels:\n    app: qdrant\n\
+         spec:\n  template:\n    spec:\n      volumes:\n      - name: data\n        persistentVolumeClaim:\n\
+         \x20         claimName: qdrant-data\n      - name: config\n        configMap:\n          name: qdrant-config\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions {
+        data_dir: Some("/srv/bakerst".into()),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    assert_eq!(resources.len(), 1);
+    let volumes = &resources[0]["spec"]["template"]["spec"]["volumes"];
+    assert_eq!(
+        volumes[0],
+        serde_json::json!({"name": "data", "hostPath": {"path": "/srv/bakerst/qdrant", "type": "DirectoryOrCreate"}})
+    );
+    assert_eq!(volumes[1]["configMap"]["name"], "qdrant-config");
+}
+
+#[test]
+fn stateful_storage_turns_claims_into_volume_claim_templates() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: v1\nkind: PersistentVolumeClaim\nmetadata:\n  name: qdrant-data\n\
+         spec:\n  accessModes: [ReadWriteOnce]\n  resources:\n    requests:\n      storage: 5Gi\n\
+         ---\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: qdrant\n  labels:\n    app: qdrant\n\
+         spec:\n  strategy:\n    type: Recreate\n  template:\n    metadata:\n      labels:\n        app: qdrant\n\
+         \x20   spec:\n      volumes:\n      - name: data\n        persistentVolumeClaim:\n\
+         \x20         claimName: qdrant-data\n      - name: config\n        configMap:\n          name: qdrant-config\n\
+         ---\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\n  labels:\n    app: brain\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions {
+        stateful_storage: true,
+        storage_class: Some("fast".into()),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    assert_eq!(resources.len(), 2, "the standalone claim is dropped");
+    let qdrant = &resources[0];
+    assert_eq!(qdrant["kind"], "StatefulSet");
+    assert_eq!(qdrant["spec"]["serviceName"], "qdrant");
+    assert!(qdrant["spec"].get("strategy").is_none());
+    let template = &qdrant["spec"]["volumeClaimTemplates"][0];
+    assert_eq!(template["metadata"]["name"], "data");
+    assert_eq!(template["metadata"]["labels"]["app"], "qdrant");
+    assert_eq!(template["spec"]["resources"]["requests"]["storage"], "5Gi");
+    assert_eq!(template["spec"]["storageClassName"], "fast");
+    let volumes = qdrant["spec"]["template"]["spec"]["volumes"].as_array().unwrap();
+    assert_eq!(volumes.len(), 1);
+    assert_eq!(volumes[0]["name"], "config");
+    assert_eq!(resources[1]["kind"], "Deployment", "only the stateful components change");
+
+    let applied = ["StatefulSet/qdrant".to_string(), "Deployment/brain".to_string(), "Service/ui".to_string()];
+    assert_eq!(deploy::applied_deployments(&applied), ["qdrant", "brain"]);
+}
+
+#[test]
+fn rollback_undoes_only_what_this_run_created_newest_first() {
+    let applied: Vec<String> = ["ServiceAccount/brain", "Deployment/brain", "Service/ui", "Deployment/brain"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let preexisting = std::collections::BTreeSet::from([("Service".to_string(), "ui".to_string())]);
+    assert_eq!(
+        deploy::rollback_plan(&applied, &preexisting, &Default::default()),
+        vec![
+            ("Deployment".to_string(), "brain".to_string()),
+            ("ServiceAccount".to_string(), "brain".to_string()),
+        ]
+    );
+    // Kinds that couldn't be listed beforehand may have existed, so stay
+    let unlisted = std::collections::BTreeSet::from(["ServiceAccount".to_string()]);
+    assert_eq!(
+        deploy::rollback_plan(&applied, &preexisting, &unlisted),
+        vec![("Deployment".to_string(), "brain".to_string())]
+    );
+}
+
+#[test]
+fn ingress_routes_the_host_to_the_instance_ui_service() {
+    let opts = deploy::DeployOptions { instance: Some("team-a".into()), ..Default::default() };
+    let ingress = deploy::ingress_object("bakerst.dev.example.com", &opts).unwrap();
+    assert_eq!(ingress["kind"], "Ingress");
+    assert_eq!(ingress["metadata"]["name"], "team-a-ui");
+    assert_eq!(deploy::component_of(&ingress), "ui");
+    let rule = &ingress["spec"]["rules"][0];
+    assert_eq!(rule["host"], "bakerst.dev.example.com");
+    assert_eq!(rule["http"]["paths"][0]["backend"]["service"]["name"], "team-a-ui");
+    assert_eq!(rule["http"]["paths"][0]["backend"]["service"]["port"]["number"], 8080);
+    // Without a TLS Secret or class, the controller's defaults apply
+    assert!(ingress["spec"].get("tls").is_none());
+    assert!(ingress["spec"].get("ingressClassName").is_none());
+    assert_eq!(deploy::ingress_url("bakerst.dev.example.com", None), "http://bakerst.dev.example.com");
+
+    let opts = deploy::DeployOptions {
+        ingress_tls_secret: Some("bakerst-tls".into()),
+        ingress_class: Some("nginx".into()),
+        ..opts
+    };
+    let ingress = deploy::ingress_object("bakerst.dev.example.com", &opts).unwrap();
+    assert_eq!(ingress["spec"]["ingressClassName"], "nginx");
+    assert_eq!(ingress["spec"]["tls"][0]["hosts"][0], "bakerst.dev.example.com");
+    assert_eq!(ingress["spec"]["tls"][0]["secretName"], "bakerst-tls");
+    assert_eq!(
+        deploy::ingress_url("bakerst.dev.example.com", Some("bakerst-tls")),
+        "https://bakerst.dev.example.com"
+    );
+}
+
+#[test]
+fn upgrade_plan_compares_running_images_with_the_manifest() {
+    use bakerst_install::manifest::{Manifest, ManifestImage};
+    let image = |name: &str, tag: &str, required: bool| ManifestImage {
+        name: name.into(),
+        image: format!("ghcr.io/org/bakerst-{}", name),
+        tag: tag.into(),
+        required,
+        ..Default::default()
+    };
+    let manifest = Manifest {
+        images: vec![
+            image("brain", "0.6.0", true),
+            image("worker", "0.6.0", true),
+            image("ui", "0.6.0", true),
+            image("gateway", "0.6.0", true),
+            image("voice", "0.6.0", false),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(deploy::upgrade_plan(&manifest, &[]), None);
+
+    let live: Vec<String> = [
+        "ghcr.io/org/bakerst-brain:0.5.2",
+        "ghcr.io/org/bakerst-worker:0.6.0",
+        "ghcr.io/org/bakerst-ui:v0.7.0",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    let plan = deploy::upgrade_plan(&manifest, &live).unwrap();
+    let changes: Vec<_> = plan.iter().map(|c| (c.component.as_str(), c.change.clone())).collect();
+    assert_eq!(
+        changes,
+        [
+            ("brain", deploy::ImageChange::Upgrade { from: "0.5.2".into() }),
+            ("worker", deploy::ImageChange::Unchanged),
+            ("ui", deploy::ImageChange::Downgrade { from: "v0.7.0".into() }),
+            ("gateway", deploy::ImageChange::New),
+        ]
+    );
+    assert_eq!(deploy::compare_versions("0.10.0", "0.9.1"), Some(std::cmp::Ordering::Greater));
+    assert_eq!(deploy::compare_versions("latest", "0.9.1"), None);
+}
+
+#[test]
+fn registry_mirror_rewrites_every_container_image() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\nspec:\n  template:\n    spec:\n\
+         \x20     initContainers:\n      - name: migrate\n        image: ghcr.io/org/bakerst-brain:0.6.0\n\
+         \x20     containers:\n      - name: brain\n        image: ghcr.io/org/bakerst-brain:0.6.0\n",
+    )
+    .unwrap();
+    let opts = deploy::DeployOptions {
+        registry_mirror: Some("harbor.internal/baker".into()),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    let spec = &resources[0]["spec"]["template"]["spec"];
+    assert_eq!(spec["containers"][0]["image"], "harbor.internal/baker/bakerst-brain:0.6.0");
+    assert_eq!(spec["initContainers"][0]["image"], "harbor.internal/baker/bakerst-brain:0.6.0");
+}
+
+#[test]
+fn unchecked_images_are_pinned_to_their_digests_before_mirroring() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\nspec:\n  template:\n    spec:\n\
+         \x20     containers:\n      - name: brain\n        image: ghcr.io/org/bakerst-brain:0.6.0\n\
+         \x20     - name: sidecar\n        image: ghcr.io/org/sidecar:1.0\n",
+    )
+    .unwrap();
+    let manifest = bakerst_install::manifest::Manifest {
+        images: vec![bakerst_install::manifest::ManifestImage {
+            name: "brain".into(),
+            image: "ghcr.io/org/bakerst-brain".into(),
+            tag: "0.6.0".into(),
+            digest: Some("sha256:abc".into()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert!(deploy::image_digests(&manifest, None, &[]).is_empty(), "checked images are left alone");
+    let opts = deploy::DeployOptions {
+        image_digests: deploy::image_digests(&manifest, None, &["ghcr.io/org/bakerst-brain:0.6.0".into()]),
+        registry_mirror: Some("harbor.internal/baker".into()),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    let containers = &resources[0]["spec"]["template"]["spec"]["containers"];
+    assert_eq!(containers[0]["image"], "harbor.internal/baker/bakerst-brain:0.6.0@sha256:abc");
+    assert_eq!(containers[1]["image"], "harbor.internal/baker/sidecar:1.0", "no digest, not pinned");
+}
+
+#[test]
+fn images_pulled_through_a_mirror_are_still_pinned() {
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\nspec:\n  template:\n    spec:\n\
+         \x20     containers:\n      - name: brain\n        image: ghcr.io/org/bakerst-brain:0.6.0\n",
+    )
+    .unwrap();
+    let manifest = bakerst_install::manifest::Manifest {
+        images: vec![bakerst_install::manifest::ManifestImage {
+            name: "brain".into(),
+            image: "ghcr.io/org/bakerst-brain".into(),
+            tag: "0.6.0".into(),
+            digest: Some("sha256:abc".into()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    // The install pulls through the mirror, so the images left unchecked
+    // come back under their mirrored names
+    let mirror = Some("harbor.internal/baker");
+    let unchecked: Vec<String> = manifest.images.iter().map(|i| i.pull_reference(mirror)).collect();
+    assert_eq!(unchecked, ["harbor.internal/baker/bakerst-brain:0.6.0"]);
+    let opts = deploy::DeployOptions {
+        image_digests: deploy::image_digests(&manifest, mirror, &unchecked),
+        registry_mirror: mirror.map(String::from),
+        ..Default::default()
+    };
+    deploy::customize_resources(&mut resources, &opts);
+    assert_eq!(
+        resources[0]["spec"]["template"]["spec"]["containers"][0]["image"],
+        "harbor.internal/baker/bakerst-brain:0.6.0@sha256:abc"
+    );
+}
+
+#[test]
+fn ui_node_port_moves_the_ui_service_and_renders_as_a_variable() {
+    let yaml = "kind: Service\nmetadata:\n  name: ui\nspec:\n  type: NodePort\n  ports:\n  - port: 8080\n    nodePort: 30080\n---\n\
+                kind: Service\nmetadata:\n  name: brain\nspec:\n  ports:\n  - port: 3000\n";
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(yaml).unwrap();
+    let opts = deploy::DeployOptions { ui_node_port: Some(31080), ..Default::default() };
+    deploy::customize_resources(&mut resources, &opts);
+    assert_eq!(deploy::requested_node_ports(&resources), vec![(31080, "ui".to_string())]);
+
+    // A named instance drops the release's NodePort but keeps the one asked for
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(yaml).unwrap();
+    deploy::customize_resources(&mut resources, &deploy::DeployOptions { instance: Some("staging".into()), ..Default::default() });
+    assert!(deploy::requested_node_ports(&resources).is_empty());
+    let mut resources = bakerst_install::k8s::parse_yaml_docs(yaml).unwrap();
+    let opts = deploy::DeployOptions { instance: Some("staging".into()), ui_node_port: Some(31081), ..Default::default() };
+    deploy::customize_resources(&mut resources, &opts);
+    assert_eq!(deploy::requested_node_ports(&resources), vec![(31081, "staging-ui".to_string())]);
+
+    let vars = deploy::template_vars(&[("DOOR_POLICY".into(), "closed".into())], Some(31080));
+    assert_eq!(vars[1], ("UI_NODE_PORT".to_string(), "31080".to_string()));
+    let set = [("UI_NODE_PORT".to_string(), "30090".to_string())];
+    assert_eq!(deploy::template_vars(&set, Some(31080)), set);
+}
+
+#[test]
+fn deploy_options_carry_the_install_config() {
+    let mut cfg = config(&[], &[]);
+    cfg.instance = Some("staging".into());
+    cfg.data_dir = Some("/srv/bakerst".into());
+    cfg.registry_mirror = Some("harbor.internal/baker".into());
+    cfg.ui_node_port = Some(31080);
+    cfg.stateful_storage = true;
+    let opts = deploy::DeployOptions::from_config(&manifest_with_tags(&["latest"]), &cfg, Default::default());
+    assert_eq!(opts.pull_policy, Some(bakerst_install::k8s::PullPolicy::Always));
+    assert_eq!(opts.instance.as_deref(), Some("staging"));
+    assert_eq!(opts.data_dir.as_deref(), Some("/srv/bakerst"));
+    assert_eq!(opts.registry_mirror.as_deref(), Some("harbor.internal/baker"));
+    assert_eq!(opts.template_vars, [("UI_NODE_PORT".to_string(), "31080".to_string())]);
+    assert!(opts.stateful_storage);
+    // Left for the caller
+    assert!(opts.journal.is_none() && opts.image_digests.is_empty() && opts.ingress_host.is_none());
+}
+
+#[test]
+fn requests_are_summed_across_replicas_and_compared_with_free_capacity() {
+    use bakerst_install::k8s::ResourceUsage;
+    let resources = bakerst_install::k8s::parse_yaml_docs(
+        "kind: Deployment\nmetadata:\n  name: brain\nspec:\n  replicas: 2\n  template:\n    spec:\n      containers:\n\
+         \x20     - name: brain\n        resources:\n          requests: {cpu: 500m, memory: 512Mi}\n\
+         \x20     - name: sidecar\n---\n\
+         kind: Deployment\nmetadata:\n  name: nats\nspec:\n  template:\n    spec:\n      containers:\n\
+         \x20     - name: nats\n        resources:\n          requests: {cpu: 1, memory: 1Gi}\n---\n\
+         kind: Service\nmetadata:\n  name: ui\n",
+    )
+    .unwrap();
+    let needed = deploy::requested_resources(&resources);
+    assert_eq!(needed, ResourceUsage { cpu_millicores: 2000, memory_bytes: 2 << 30 });
+
+    let roomy = ResourceUsage { cpu_millicores: 4000, memory_bytes: 8 << 30 };
+    assert!(deploy::capacity_shortfall(needed, roomy).is_empty());
+    let tight = ResourceUsage { cpu_millicores: 1500, memory_bytes: 8 << 30 };
+    assert_eq!(
+        deploy::capacity_shortfall(needed, tight),
+        vec!["CPU: the install requests 2000m but only 1500m is free (short by 500m)"]
+    );
+}
+
+#[test]
+fn installed_version_comes_from_the_namespace_label_or_the_running_tags() {
+    use bakerst_install::manifest::{Manifest, ManifestImage};
+    let manifest = Manifest {
+        version: "0.2.0".into(),
+        images: vec![
+            ManifestImage { name: "brain".into(), image: "ghcr.io/org/bakerst-brain".into(), ..Default::default() },
+            ManifestImage { name: "worker".into(), image: "ghcr.io/org/bakerst-worker".into(), ..Default::default() },
+        ],
+        ..Default::default()
+    };
+    let live = |images: &[&str]| images.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+
+    let running = live(&["ghcr.io/org/bakerst-brain:0.1.0", "ghcr.io/org/bakerst-worker:0.1.0", "nats:2.10"]);
+    assert_eq!(deploy::installed_version(&manifest, &running, None).as_deref(), Some("0.1.0"));
+    assert_eq!(deploy::installed_version(&manifest, &running, Some("v0.1.1")).as_deref(), Some("0.1.1"));
+    // Mixed tags without a label: unknown rather than a guess
+    let mixed = live(&["ghcr.io/org/bakerst-brain:0.1.0", "ghcr.io/org/bakerst-worker:0.1.5"]);
+    assert_eq!(deploy::installed_version(&manifest, &mixed, None), None);
+    // Only unrelated workloads: nothing of ours is installed
+    assert_eq!(deploy::installed_version(&manifest, &live(&["nats:2.10"]), Some("0.1.0")), None);
+}
+
+#[test]
+fn set_values_fill_placeholders_in_the_release_manifests() {
+    let template = tempfile::tempdir().unwrap();
+    let k8s_dir = template.path().join("k8s");
+    std::fs::create_dir_all(k8s_dir.join("extensions/voice")).unwrap();
+    std::fs::write(
+        k8s_dir.join("brain.yaml"),
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\nspec:\n  replicas: {{BRAIN_REPLICAS}}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        k8s_dir.join("extensions/voice/voice.yaml"),
+        "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: voice\ndata:\n  {{#if VOICE_MODEL}}\n  model: {{VOICE_MODEL}}\n  {{/if}}\n",
+    )
+    .unwrap();
+
+    let keys = deploy::template_keys(&k8s_dir, &["voice".into()]).unwrap();
+    assert_eq!(keys.into_iter().collect::<Vec<_>>(), ["BRAIN_REPLICAS", "VOICE_MODEL"]);
+    assert_eq!(deploy::template_keys(&k8s_dir, &[]).unwrap().len(), 1);
+
+    let opts = deploy::DeployOptions {
+        template_vars: vec![("BRAIN_REPLICAS".into(), "3".into()), ("VOICE_MODEL".into(), "whisper".into())],
+        ..Default::default()
+    };
+    let objects = deploy::render_target(&k8s_dir, &["voice".into()], &opts).unwrap();
+    assert_eq!(objects[0]["spec"]["replicas"], 3);
+    assert_eq!(objects[1]["data"]["model"], "whisper");
+
+    // A placeholder no --set resolves fails the render instead of shipping "{{...}}"
+    let err = deploy::render_target(&k8s_dir, &[], &deploy::DeployOptions::default()).unwrap_err();
+    assert!(format!("{:#}", err).contains("BRAIN_REPLICAS"), "{:#}", err);
+}
@@ -0,0 +1,412 @@
+//! Drives the pull and deploy steps of an install against fake docker and
+//! cluster implementations, so no daemon or cluster is needed.
+
+mod common;
+
+use bakerst_install::images::{self, DockerOutput, ImageRuntime, PullEvent, PullProgress};
+use bakerst_install::{deploy, k8s};
+use common::{config, schema};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Pulls succeed unless the image is listed as private; local images exist.
+/// `inspect --format` reports the repo digests in `digests`, or the image
+/// ID in `ids` when asked for `{{.Id}}`.
+#[derive(Default)]
+struct FakeDocker {
+    private: Vec<&'static str>,
+    digests: HashMap<&'static str, &'static str>,
+    ids: HashMap<&'static str, &'static str>,
+    calls: Mutex<Vec<Vec<String>>>,
+}
+
+impl ImageRuntime for FakeDocker {
+    async fn docker(&self, args: Vec<String>) -> std::io::Result<DockerOutput> {
+        self.calls.lock().unwrap().push(args.clone());
+        let image = args.last().cloned().unwrap_or_default();
+        if self.private.contains(&image.as_str()) {
+            return Ok(DockerOutput {
+                success: false,
+                stderr: "Error response from daemon: unauthorized".into(),
+                ..Default::default()
+            });
+        }
+        let stdout = match self.digests.get(image.as_str()) {
+            _ if args.contains(&"{{.Id}}".to_string()) => {
+                format!("{}\n", self.ids.get(image.as_str()).copied().unwrap_or("sha256:other"))
+            }
+            Some(digest) if args.contains(&"--format".to_string()) => format!("[\"{}\"]\n", digest),
+            _ if args[0] == "pull" => format!(
+                "0.6.0: Pulling from {}\n4f4fb700ef54: Already exists\n9d3e8f7c1a2b: Pulling fs layer\n\
+                 9d3e8f7c1a2b: Download complete\n9d3e8f7c1a2b: Pull complete\nStatus: Downloaded newer image\n",
+                image
+            ),
+            _ => String::new(),
+        };
+        Ok(DockerOutput { success: true, stdout, stderr: String::new() })
+    }
+}
+
+/// Records applied objects; Secrets are kept so later reads see them.
+/// Dry-run applies are only counted, as the API server persists nothing.
+/// Objects named in `fail_once` are rejected the first time they're applied.
+#[derive(Default)]
+struct FakeCluster {
+    applied: Mutex<Vec<serde_json::Value>>,
+    fail_once: Mutex<Vec<&'static str>>,
+    dry_runs: Mutex<usize>,
+    secrets: Mutex<BTreeMap<String, BTreeMap<String, String>>>,
+}
+
+impl FakeCluster {
+    fn secret(&self, name: &str) -> BTreeMap<String, String> {
+        self.secrets.lock().unwrap().get(name).cloned().unwrap_or_default()
+    }
+
+    fn applied_names(&self, kind: &str) -> Vec<String> {
+        self.applied
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r["kind"] == kind)
+            .map(|r| r["metadata"]["name"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+}
+
+impl k8s::ClusterClient for FakeCluster {
+    async fn apply(
+        &self,
+        _namespace: &str,
+        resource: &serde_json::Value,
+        params: &k8s::ApplyParams,
+    ) -> anyhow::Result<()> {
+        let name = resource["metadata"]["name"].as_str().unwrap_or_default();
+        let mut fail_once = self.fail_once.lock().unwrap();
+        if let Some(i) = fail_once.iter().position(|n| *n == name) {
+            fail_once.remove(i);
+            anyhow::bail!("admission webhook denied {}", name);
+        }
+        drop(fail_once);
+        if params.dry_run {
+            *self.dry_runs.lock().unwrap() += 1;
+            return Ok(());
+        }
+        if resource["kind"] == "Secret" {
+            use base64::Engine;
+            let name = resource["metadata"]["name"].as_str().unwrap_or_default();
+            let data = resource["data"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(k, v)| {
+                    let raw = base64::engine::general_purpose::STANDARD
+                        .decode(v.as_str().unwrap_or_default())
+                        .unwrap();
+                    (k.clone(), String::from_utf8(raw).unwrap())
+                })
+                .collect();
+            self.secrets.lock().unwrap().insert(name.to_string(), data);
+        }
+        self.applied.lock().unwrap().push(resource.clone());
+        Ok(())
+    }
+
+    async fn read_secret(
+        &self,
+        _namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<BTreeMap<String, String>>> {
+        Ok(self.secrets.lock().unwrap().get(name).cloned())
+    }
+}
+
+#[tokio::test]
+async fn pulls_report_auth_failures_and_inspect_local_images() {
+    let docker = Arc::new(FakeDocker {
+        private: vec!["ghcr.io/org/bakerst-voice:0.6.0"],
+        ..Default::default()
+    });
+    assert!(images::docker_available(docker.as_ref()).await);
+
+    let refs = vec![
+        "ghcr.io/org/bakerst-brain:0.6.0".to_string(),
+        "ghcr.io/org/bakerst-voice:0.6.0".to_string(),
+        "bakerst-ui:dev".to_string(),
+    ];
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let results = images::pull_all(docker.clone(), refs.clone(), 1, tx).await;
+
+    let mut progress = PullProgress::new(refs.len());
+    let mut percents = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        match &event {
+            PullEvent::AuthRequired { registry, .. } => assert_eq!(registry, "ghcr.io"),
+            PullEvent::Progress { index: 0, percent } => percents.push(*percent),
+            _ => {}
+        }
+        progress.record(&event);
+    }
+    // Layer statuses from the pull's output, reported as they change
+    assert_eq!(percents, [100, 50, 75, 100]);
+    assert!(progress.is_done());
+    assert_eq!(progress.failed, vec!["ghcr.io/org/bakerst-voice:0.6.0".to_string()]);
+    assert!(results[0].is_ok() && results[2].is_ok());
+
+    // The local build is inspected, never pulled
+    let calls = docker.calls.lock().unwrap();
+    assert!(calls.iter().any(|c| c[..2] == ["image", "inspect"] && c[2] == "bakerst-ui:dev"));
+    assert!(!calls.iter().any(|c| c[0] == "pull" && c[1] == "bakerst-ui:dev"));
+}
+
+#[tokio::test]
+async fn bundled_images_are_only_inspected_never_pulled() {
+    // An image missing from the bundle fails inspection like a private one
+    let docker = Arc::new(FakeDocker {
+        private: vec!["ghcr.io/org/bakerst-voice:0.6.0"],
+        ..Default::default()
+    });
+    let refs = vec![
+        "ghcr.io/org/bakerst-brain:0.6.0".to_string(),
+        "ghcr.io/org/bakerst-voice:0.6.0".to_string(),
+    ];
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    images::inspect_all(docker.clone(), refs.clone(), tx).await;
+
+    let mut progress = PullProgress::new(refs.len());
+    while let Ok(event) = rx.try_recv() {
+        progress.record(&event);
+    }
+    assert!(progress.is_done());
+    assert_eq!(progress.failed, vec!["ghcr.io/org/bakerst-voice:0.6.0".to_string()]);
+    assert!(docker.calls.lock().unwrap().iter().all(|c| c[..2] == ["image", "inspect"]));
+}
+
+#[tokio::test]
+async fn pulled_image_digests_are_checked_against_the_manifest() {
+    let docker = FakeDocker {
+        digests: HashMap::from([(
+            "ghcr.io/org/bakerst-brain:0.6.0",
+            "ghcr.io/org/bakerst-brain@sha256:aaa",
+        )]),
+        ..Default::default()
+    };
+    let image = "ghcr.io/org/bakerst-brain:0.6.0";
+    assert!(images::verify_digest(&docker, image, "sha256:aaa").await.is_ok());
+    assert_eq!(
+        images::verify_digest(&docker, image, "sha256:bbb").await,
+        Err(images::PullError::DigestMismatch {
+            expected: "sha256:bbb".into(),
+            actual: "sha256:aaa".into(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn bundled_images_are_checked_against_the_ids_the_bundle_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bundle.tar");
+    let manifest = serde_json::json!([
+        { "Config": "blobs/sha256/aaa", "RepoTags": ["ghcr.io/org/bakerst-brain:0.6.0"], "Layers": [] },
+        { "Config": "bbb.json", "RepoTags": ["ghcr.io/org/bakerst-ui:0.6.0"], "Layers": [] },
+    ])
+    .to_string();
+    let mut bundle = tar::Builder::new(std::fs::File::create(&path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_cksum();
+    bundle.append_data(&mut header, "manifest.json", manifest.as_bytes()).unwrap();
+    bundle.finish().unwrap();
+
+    let ids = images::bundle_image_ids(&path).unwrap();
+    assert_eq!(ids["ghcr.io/org/bakerst-brain:0.6.0"], "sha256:aaa");
+    assert_eq!(ids["ghcr.io/org/bakerst-ui:0.6.0"], "sha256:bbb");
+
+    let docker = FakeDocker {
+        ids: HashMap::from([("ghcr.io/org/bakerst-brain:0.6.0", "sha256:aaa")]),
+        ..Default::default()
+    };
+    images::load_bundle(&docker, &path).await.unwrap();
+    // Loaded images have no repo digests, but the bundle's IDs still check out
+    let brain = "ghcr.io/org/bakerst-brain:0.6.0";
+    assert!(images::verify_digest(&docker, brain, "sha256:registry").await.is_err());
+    assert!(images::verify_image_id(&docker, brain, &ids[brain]).await.is_ok());
+    // A same-tagged image already in the cache isn't the bundle's
+    let ui = "ghcr.io/org/bakerst-ui:0.6.0";
+    assert_eq!(
+        images::verify_image_id(&docker, ui, &ids[ui]).await,
+        Err(images::PullError::DigestMismatch { expected: "sha256:bbb".into(), actual: "sha256:other".into() })
+    );
+}
+
+#[tokio::test]
+async fn images_that_cannot_be_inspected_are_not_present() {
+    let docker = FakeDocker {
+        private: vec!["ghcr.io/org/bakerst-voice:0.6.0"],
+        ..Default::default()
+    };
+    assert!(images::image_present(&docker, "ghcr.io/org/bakerst-brain:0.6.0").await);
+    assert!(!images::image_present(&docker, "ghcr.io/org/bakerst-voice:0.6.0").await);
+    assert_eq!(
+        docker.calls.lock().unwrap()[0],
+        vec!["image", "inspect", "ghcr.io/org/bakerst-brain:0.6.0"]
+    );
+}
+
+#[tokio::test]
+async fn secrets_fan_out_to_every_target_and_merge_on_update() {
+    let cluster = FakeCluster::default();
+    let params = k8s::ApplyParams::default();
+    let cfg = config(
+        &[("ANTHROPIC_API_KEY", "sk-ant"), ("TELEGRAM_BOT_TOKEN", "123:abc")],
+        &["telegram"],
+    );
+    deploy::apply_secrets(&cluster, &schema(), &cfg, k8s::SecretMode::Replace, true, &params)
+        .await
+        .unwrap();
+
+    assert_eq!(cluster.secret("bakerst-brain-secrets")["ANTHROPIC_API_KEY"], "sk-ant");
+    assert_eq!(cluster.secret("bakerst-worker-secrets")["ANTHROPIC_API_KEY"], "sk-ant");
+    assert_eq!(cluster.secret("bakerst-gateway-secrets")["TELEGRAM_BOT_TOKEN"], "123:abc");
+
+    // An update that only re-supplies the API key keeps the bot token
+    let cfg = config(&[("ANTHROPIC_API_KEY", "sk-new")], &["telegram"]);
+    deploy::apply_secrets(&cluster, &schema(), &cfg, k8s::SecretMode::Merge, true, &params)
+        .await
+        .unwrap();
+    assert_eq!(cluster.secret("bakerst-brain-secrets")["ANTHROPIC_API_KEY"], "sk-new");
+    assert_eq!(cluster.secret("bakerst-gateway-secrets")["TELEGRAM_BOT_TOKEN"], "123:abc");
+}
+
+#[tokio::test]
+async fn secret_values_are_trimmed_unless_disabled() {
+    let cluster = FakeCluster::default();
+    let params = k8s::ApplyParams::default();
+    let cfg = config(&[("ANTHROPIC_API_KEY", "sk-ant\n")], &[]);
+    deploy::apply_secrets(&cluster, &schema(), &cfg, k8s::SecretMode::Replace, true, &params)
+        .await
+        .unwrap();
+    assert_eq!(cluster.secret("bakerst-brain-secrets")["ANTHROPIC_API_KEY"], "sk-ant");
+
+    deploy::apply_secrets(&cluster, &schema(), &cfg, k8s::SecretMode::Replace, false, &params)
+        .await
+        .unwrap();
+    assert_eq!(cluster.secret("bakerst-brain-secrets")["ANTHROPIC_API_KEY"], "sk-ant\n");
+}
+
+#[tokio::test]
+async fn manifests_and_enabled_extensions_are_applied() {
+    let template = tempfile::tempdir().unwrap();
+    let k8s_dir = template.path().join("k8s");
+    let remote = k8s_dir.join("overlays/remote");
+    std::fs::create_dir_all(&remote).unwrap();
+    std::fs::write(
+        remote.join("all.yaml"),
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain-blue\n  labels:\n    app: brain\n\
+         spec:\n  template:\n    spec:\n      containers:\n      - name: brain\n        image: bakerst-brain:latest\n\
+         ---\napiVersion: v1\nkind: Service\nmetadata:\n  name: ui\n",
+    )
+    .unwrap();
+    for feature in ["voice", "browser"] {
+        let dir = k8s_dir.join("extensions").join(feature);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("deployment.yaml"),
+            format!("apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: {}\n", feature),
+        )
+        .unwrap();
+    }
+
+    let cluster = FakeCluster::default();
+    let opts = deploy::DeployOptions {
+        pull_policy: Some(k8s::PullPolicy::IfNotPresent),
+        ..Default::default()
+    };
+    deploy::apply_manifests_from_dir(&cluster, "bakerst", &deploy::manifest_dir(&k8s_dir), &opts)
+        .await
+        .unwrap();
+    deploy::apply_extensions(&cluster, "bakerst", &k8s_dir.join("extensions"), &["voice".into()], &opts)
+        .await
+        .unwrap();
+
+    assert_eq!(cluster.applied_names("Deployment"), vec!["brain-blue", "voice"]);
+    assert_eq!(cluster.applied_names("Service"), vec!["ui"]);
+    let applied = cluster.applied.lock().unwrap();
+    assert_eq!(
+        applied[0]["spec"]["template"]["spec"]["containers"][0]["imagePullPolicy"],
+        "IfNotPresent"
+    );
+}
+
+#[tokio::test]
+async fn a_failed_deploy_step_is_retried_without_reapplying_the_others() {
+    let template = tempfile::tempdir().unwrap();
+    let k8s_dir = template.path().join("k8s");
+    std::fs::create_dir_all(&k8s_dir).unwrap();
+    std::fs::write(k8s_dir.join("nats.yaml"), "apiVersion: v1\nkind: Service\nmetadata:\n  name: nats\n").unwrap();
+    std::fs::write(
+        k8s_dir.join("brain.yaml"),
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: brain\n",
+    )
+    .unwrap();
+    for feature in ["voice", "browser"] {
+        let dir = k8s_dir.join("extensions").join(feature);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("deployment.yaml"),
+            format!("apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: {}\n", feature),
+        )
+        .unwrap();
+    }
+
+    let cluster = FakeCluster { fail_once: Mutex::new(vec!["brain", "voice"]), ..Default::default() };
+    let opts = deploy::DeployOptions::default();
+    let features = vec!["voice".to_string(), "browser".to_string(), "github".to_string()];
+    let mut progress = deploy::DeployProgress::new(&k8s_dir, &features, &opts);
+    // No Ingress without --ingress-host, and no step for a feature without extensions
+    assert_eq!(progress.remaining.len(), 3);
+
+    let err = deploy::apply_steps(&cluster, "bakerst", &k8s_dir, &mut progress, &opts).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("admission webhook denied brain"));
+    assert_eq!(progress.failed(), Some(&deploy::DeployStep::Manifests));
+    assert_eq!(progress.applied, vec!["Service/nats"]);
+
+    let err = deploy::apply_steps(&cluster, "bakerst", &k8s_dir, &mut progress, &opts).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("admission webhook denied voice"));
+    assert_eq!(progress.failed(), Some(&deploy::DeployStep::Extension("voice".into())));
+    assert_eq!(progress.applied, vec!["Service/nats", "Deployment/brain"]);
+
+    deploy::apply_steps(&cluster, "bakerst", &k8s_dir, &mut progress, &opts).await.unwrap();
+    assert!(progress.failed().is_none());
+    assert_eq!(
+        progress.applied,
+        vec!["Service/nats", "Deployment/brain", "Deployment/voice", "Deployment/browser"]
+    );
+    // What had already gone in, step or resource, was applied once
+    assert_eq!(cluster.applied_names("Service"), vec!["nats"]);
+    assert_eq!(cluster.applied_names("Deployment"), vec!["brain", "voice", "browser"]);
+}
+
+#[tokio::test]
+async fn server_dry_run_reaches_every_apply_and_persists_nothing() {
+    let cluster = FakeCluster::default();
+    let params = k8s::ApplyParams { dry_run: true, ..Default::default() };
+    let cfg = config(&[("ANTHROPIC_API_KEY", "sk-ant")], &[]);
+    deploy::apply_secrets(&cluster, &schema(), &cfg, k8s::SecretMode::Replace, true, &params)
+        .await
+        .unwrap();
+    let labels = k8s::apply_resources(
+        &cluster,
+        "bakerst",
+        &k8s::parse_yaml_docs("apiVersion: v1\nkind: Service\nmetadata:\n  name: ui\n").unwrap(),
+        &params,
+        |_, _| {},
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(labels, vec!["Service/ui"]);
+    assert!(*cluster.dry_runs.lock().unwrap() >= 2);
+    assert!(cluster.applied.lock().unwrap().is_empty());
+    assert!(cluster.secret("bakerst-brain-secrets").is_empty());
+}
@@ -323,6 +323,42 @@ fn non_interactive_without_credentials_exits() {
         .failure();
 }
 
+/// Test `install --assume-cached-manifest` fails clearly when nothing is cached
+#[test]
+fn assume_cached_manifest_without_cache_exits_with_error() {
+    let home = tempfile::tempdir().unwrap();
+    Command::cargo_bin("bakerst-install")
+        .unwrap()
+        .env("HOME", home.path())
+        .args(["install", "--explain-secrets", "--assume-cached-manifest", "--version", "0.6.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No cached manifest for v0.6.0"));
+}
+
+/// Test `--kube-context` with an unknown context lists the available ones
+#[test]
+fn unknown_kube_context_lists_available_contexts() {
+    let dir = tempfile::tempdir().unwrap();
+    let kubeconfig = dir.path().join("config");
+    std::fs::write(
+        &kubeconfig,
+        "apiVersion: v1\nkind: Config\nclusters: []\nusers: []\ncurrent-context: prod\n\
+         contexts:\n- name: docker-desktop\n  context: {cluster: dd, user: dd}\n\
+         - name: prod\n  context: {cluster: prod, user: prod}\n",
+    )
+    .unwrap();
+    Command::cargo_bin("bakerst-install")
+        .unwrap()
+        .env("KUBECONFIG", &kubeconfig)
+        .args(["status", "--kube-context", "staging"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Kubernetes context 'staging' not found. Available contexts: docker-desktop, prod",
+        ));
+}
+
 /// Test `install --config` with missing file exits with error
 #[test]
 #[ignore = "requires running K8s cluster"]
@@ -393,3 +429,48 @@ async fn full_deploy_cycle() {
         .assert()
         .success();
 }
+
+/// Test `--output json` ends a failed install with a parseable error report
+#[test]
+fn json_output_reports_the_failed_phase() {
+    let home = tempfile::tempdir().unwrap();
+    let output = Command::cargo_bin("bakerst-install")
+        .unwrap()
+        .env("HOME", home.path())
+        .args([
+            "install", "--explain-secrets", "--assume-cached-manifest", "--version", "0.6.0",
+            "--non-interactive", "--output", "json",
+        ])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+    let report: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+    assert_eq!(report["result"], "error");
+    assert_eq!(report["phase"], "manifest");
+    assert!(report["error"].as_str().unwrap().contains("No cached manifest for v0.6.0"));
+}
+
+#[test]
+fn json_output_keeps_stdout_to_json_lines() {
+    let home = tempfile::tempdir().unwrap();
+    let output = Command::cargo_bin("bakerst-install")
+        .unwrap()
+        .env("HOME", home.path())
+        .args([
+            "install", "--explain-secrets", "--assume-cached-manifest", "--version", "0.6.0",
+            "--non-interactive", "--output", "json",
+        ])
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let events: Vec<serde_json::Value> = stdout.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(events[0], serde_json::json!({"event": "phase", "phase": "manifest"}));
+    assert_eq!(events.last().unwrap()["result"], "error");
+    // The human-readable progress moves to stderr
+    assert!(String::from_utf8(output.stderr).unwrap().contains("[2/10] Fetching manifest..."));
+}
@@ -66,3 +66,81 @@ fn test_from_json_validates_schema() {
     let json = r#"{"schemaVersion": 99, "version": "1.0", "templateUrl": "", "templateSha256": "", "images": []}"#;
     assert!(Manifest::from_json(json).is_err());
 }
+
+#[test]
+fn test_duplicate_image_names_rejected() {
+    let json = r#"{"schemaVersion": 1, "version": "1.0", "templateUrl": "", "templateSha256": "", "images": [
+        {"name": "brain", "image": "ghcr.io/org/brain", "tag": "1.0", "required": true, "architectures": []},
+        {"name": "worker", "image": "ghcr.io/org/worker", "tag": "1.0", "required": true, "architectures": []},
+        {"name": "brain", "image": "ghcr.io/org/brain2", "tag": "1.0", "required": false, "architectures": []}
+    ]}"#;
+    let err = Manifest::from_json(json).unwrap_err().to_string();
+    assert!(err.contains("duplicate image names: brain"), "got: {}", err);
+}
+
+#[test]
+fn test_signed_manifest_is_verified_before_parsing() {
+    use base64::Engine;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+    let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let public_key = b64.encode(key.public_key().as_ref());
+
+    let json = r#"{"schemaVersion": 1, "version": "0.6.0", "templateUrl": "", "templateSha256": "", "images": []}"#;
+    let signature = b64.encode(key.sign(json.as_bytes()).as_ref());
+    assert!(Manifest::from_signed_json(json, &signature, &public_key).is_ok());
+
+    let tampered = json.replace("0.6.0", "0.6.1");
+    let err = Manifest::from_signed_json(&tampered, &signature, &public_key).unwrap_err();
+    assert!(err.to_string().contains("signature verification failed"));
+}
+
+#[test]
+fn a_build_without_a_release_key_refuses_to_skip_verification() {
+    use bakerst_install::manifest::{release_public_key, RELEASE_PUBLIC_KEY};
+
+    let dir = tempfile::tempdir().unwrap();
+    let key_file = dir.path().join("release.pub");
+    std::fs::write(&key_file, "c29tZS1rZXk=\n").unwrap();
+    assert_eq!(release_public_key(Some(&key_file)).unwrap(), "c29tZS1rZXk=");
+
+    if RELEASE_PUBLIC_KEY.is_none_or(|key| key.trim().is_empty()) {
+        let err = release_public_key(None).unwrap_err().to_string();
+        assert!(err.contains("--manifest-pubkey"), "got: {}", err);
+    }
+}
+
+#[test]
+fn old_releases_get_a_staleness_note() {
+    use std::time::{Duration, UNIX_EPOCH};
+    // 2026-10-01T00:00:00Z
+    let now = UNIX_EPOCH + Duration::from_secs(1_790_812_800);
+    let release = |date: &str| Manifest {
+        version: "0.6.0".into(),
+        release_date: Some(date.into()),
+        ..Default::default()
+    };
+    assert_eq!(
+        release("2026-01-01T12:00:00+02:00").staleness_note(now).as_deref(),
+        Some("Release 0.6.0 is 9 months old; a newer one may be available.")
+    );
+    assert!(release("2026-02-01").staleness_note(now).is_some());
+    assert!(release("2026-09-01").staleness_note(now).is_none());
+    // Unparseable or future dates are skipped rather than guessed at
+    assert!(release("last spring").staleness_note(now).is_none());
+    assert!(release("2027-01-01").release_age(now).is_none());
+    assert!(Manifest::default().staleness_note(now).is_none());
+}
+
+#[test]
+fn newer_schema_is_rejected_before_its_fields_are_parsed() {
+    // A v999 manifest may drop or rename fields this installer requires
+    let err = Manifest::from_json(r#"{"schemaVersion": 999, "release": {"version": "9.0.0"}}"#).unwrap_err();
+    assert!(
+        err.to_string().starts_with("Manifest schema v999 is not supported by installer v"),
+        "{}",
+        err
+    );
+}